@@ -1,13 +1,18 @@
 use std::{
     env,
+    io::{self, BufReader, IsTerminal},
     path::PathBuf,
-    process::{exit, Command},
+    process::{exit, Command, Stdio},
 };
 
-use anyhow::Result;
-use flux_bin::utils::{
-    get_flux_driver_path, get_rust_toolchain, get_rustc_driver_lib_path, prepend_path_to_env_var,
-    EXIT_ERR, LIB_PATH,
+use anyhow::{anyhow, Result};
+use flux_bin::{
+    metadata::WorkspaceMetadata,
+    utils::{
+        get_flux_driver_path, get_rust_toolchain, get_rustc_driver_lib_path, get_std_specs_path,
+        prepend_path_to_env_var, EXIT_ERR, FLUX_PACKAGE_CONFIG_DIR, FLUX_STD_SPECS, LIB_PATH,
+    },
+    watch::{forward_messages, Results, Snapshot},
 };
 
 fn main() {
@@ -22,24 +27,136 @@ fn main() {
 }
 
 fn run() -> Result<i32> {
-    let flux_driver_path = get_flux_driver_path()?;
-    let rust_toolchain = get_rust_toolchain()?;
-    let ld_library_path = get_rustc_driver_lib_path(&rust_toolchain)?;
-    let extended_lib_path = prepend_path_to_env_var(LIB_PATH, ld_library_path)?;
-
     // Cargo can be called like `cargo [OPTIONS] flux`, so we skip all arguments until `flux` is
     // found.
-    let args = env::args()
+    let mut args = env::args()
         .skip_while(|arg| arg != "flux")
         .skip(1)
         .collect::<Vec<_>>();
 
+    if let Some(i) = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == "--watch")
+    {
+        args.remove(i);
+        watch(&args)
+    } else {
+        let (mut cmd, _) = cargo_check(&args)?;
+        Ok(cmd.status()?.code().unwrap_or(EXIT_ERR))
+    }
+}
+
+/// Runs flux every time a file in the workspace changes and prints the functions that started or
+/// stopped failing. Cargo checks again every crate affected by a change, and all the functions in
+/// it, but the query cache is enabled (unless explicitly disabled) so the constraints that didn't
+/// change are not sent to fixpoint again. A run that cannot be completed, e.g., because the
+/// manifest is malformed, is reported and we wait for the next change.
+fn watch(args: &[String]) -> Result<i32> {
+    // The messages tell us the crates in each run, see `forward_messages`.
+    let message_format =
+        if io::stderr().is_terminal() { "json-diagnostic-rendered-ansi" } else { "json" };
+    let mut args = args.to_vec();
+    let end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    args.insert(end, format!("--message-format={message_format}"));
+
+    let mut results = Results::default();
+    let mut watched = None;
+    loop {
+        if let Err(err) = watch_run(&args, &mut results, &mut watched) {
+            eprintln!("[flux] error: {err}");
+            println!("[flux] Waiting for changes...");
+        }
+        // Without a snapshot from a previous run, we watch the directory of the manifest given in
+        // the arguments or the one we were called from.
+        if watched.is_none() {
+            let cwd = env::current_dir()?;
+            let root = match manifest_path(&args).map(|path| cwd.join(path)) {
+                Some(manifest) => manifest.parent().unwrap_or(&cwd).to_path_buf(),
+                None => cwd.clone(),
+            };
+            let target_dir = match env::var("CARGO_TARGET_DIR") {
+                Ok(target_dir) => cwd.join(target_dir),
+                Err(_) => root.join("target"),
+            };
+            watched = Some(Watched::take(root, target_dir)?);
+        }
+        if let Some(watched) = &mut watched {
+            watched
+                .snapshot
+                .wait_for_changes(&watched.root, &watched.target_dir)?;
+        }
+    }
+}
+
+/// The files in a workspace as they were before a run
+struct Watched {
+    root: PathBuf,
+    target_dir: PathBuf,
+    snapshot: Snapshot,
+}
+
+impl Watched {
+    fn take(root: PathBuf, target_dir: PathBuf) -> io::Result<Watched> {
+        let snapshot = Snapshot::take(&root, &target_dir)?;
+        Ok(Watched { root, target_dir, snapshot })
+    }
+}
+
+/// Checks the workspace once in watch mode and reports the changes in the results. The snapshot in
+/// `watched` is replaced before running, so changes made while flux runs trigger a new run. It's
+/// kept if the run fails before that.
+fn watch_run(args: &[String], results: &mut Results, watched: &mut Option<Watched>) -> Result<()> {
+    let (mut cmd, metadata) = cargo_check(args)?;
+    // CODESYNC(save-results) Tell the flux-driver to save the status of each function.
+    cmd.env("FLUX_SAVE_RESULTS", "1");
+    if env::var_os("FLUX_CACHE").is_none() {
+        cmd.env("FLUX_CACHE", "1");
+    }
+
+    let root = PathBuf::from(&metadata.workspace_root);
+    let flux_target = flux_target_dir(&metadata)?;
+    let cargo_target = flux_target.parent().unwrap_or(&flux_target).to_path_buf();
+    *watched = Some(Watched::take(root, cargo_target)?);
+    results.record_baseline(&flux_target)?;
+
+    let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take().expect("stdout should be piped");
+    let artifacts = forward_messages(BufReader::new(stdout), &mut io::stderr())?;
+    child.wait()?;
+
+    let delta = results.update(&flux_target, &artifacts)?;
+    delta.print();
+    println!(
+        "[flux] {} newly failing, {} fixed, {} failing in total. Waiting for changes...",
+        delta.failing.len(),
+        delta.fixed.len(),
+        results.failing()
+    );
+    Ok(())
+}
+
+/// The command to check the workspace with flux. The config of each package is resolved from the
+/// workspace metadata, which is also returned.
+fn cargo_check(args: &[String]) -> Result<(Command, WorkspaceMetadata)> {
+    let flux_driver_path = get_flux_driver_path()?;
+    let rust_toolchain = get_rust_toolchain()?;
+    let ld_library_path = get_rustc_driver_lib_path(&rust_toolchain)?;
+    let extended_lib_path = prepend_path_to_env_var(LIB_PATH, ld_library_path)?;
+
     let cargo_path = env::var("CARGO_PATH").unwrap_or_else(|_| "cargo".to_string());
-    let cargo_target = env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
-    let cargo_target = PathBuf::from_iter([cargo_target, "flux".to_string()]);
+    let metadata = WorkspaceMetadata::read(&cargo_path, manifest_path(args))?;
+    let cargo_target = flux_target_dir(&metadata)?;
 
-    let exit_code = Command::new(cargo_path)
-        .arg("check")
+    // Each driver invocation picks the config of its package from this directory.
+    let package_config_dir = cargo_target.join("config");
+    metadata.write_configs(&package_config_dir)?;
+
+    let mut cmd = Command::new(cargo_path);
+    cmd.arg("check")
         .args(args)
         .env(LIB_PATH, extended_lib_path)
         // CODESYNC(build-sysroot, 5) Tell flux dependencies to build in flux mode.
@@ -50,8 +167,90 @@ fn run() -> Result<i32> {
         .env("RUSTUP_TOOLCHAIN", rust_toolchain)
         .env("RUSTC", flux_driver_path)
         .env("CARGO_TARGET_DIR", cargo_target)
-        .status()?
-        .code();
+        // CODESYNC(package-config) Tell the flux-driver where to find the config of each package.
+        .env(FLUX_PACKAGE_CONFIG_DIR, package_config_dir);
+    // Only check the members selected in the workspace metadata unless packages are given in the
+    // command line. Without any `--package` cargo would check the default members instead, so it's
+    // an error if none is selected.
+    if metadata.selects_members && !selects_packages(args) {
+        let mut selected = false;
+        for package in metadata.enabled_packages() {
+            cmd.args(["--package", &package.name]);
+            selected = true;
+        }
+        if !selected {
+            return Err(anyhow!("no package in `members` is enabled, there's nothing to check"));
+        }
+    }
+    if let Some(std_specs) = get_std_specs_path() {
+        // CODESYNC(std-specs-env) Tell the flux-driver where to find the bundled specs.
+        cmd.env(FLUX_STD_SPECS, std_specs);
+    }
+    Ok((cmd, metadata))
+}
+
+/// The target directory used by flux, which is kept separate from the one used by cargo.
+fn flux_target_dir(metadata: &WorkspaceMetadata) -> Result<PathBuf> {
+    let cargo_target =
+        env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| metadata.target_directory.clone());
+    Ok(env::current_dir()?.join(cargo_target).join("flux"))
+}
+
+/// The value of `--manifest-path` if given in the arguments.
+fn manifest_path(args: &[String]) -> Option<&str> {
+    let mut args = args.iter().take_while(|arg| *arg != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--manifest-path" {
+            return args.next().map(String::as_str);
+        }
+        if let Some(path) = arg.strip_prefix("--manifest-path=") {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// Whether the arguments select the packages to check.
+fn selects_packages(args: &[String]) -> bool {
+    args.iter().take_while(|arg| *arg != "--").any(|arg| {
+        matches!(arg.as_str(), "--package" | "--workspace" | "--all")
+            || arg.starts_with("-p")
+            || arg.starts_with("--package=")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|&arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_manifest_path() {
+        assert_eq!(
+            manifest_path(&args(&["--manifest-path", "a/Cargo.toml"])),
+            Some("a/Cargo.toml")
+        );
+        assert_eq!(
+            manifest_path(&args(&["-q", "--manifest-path=a/Cargo.toml"])),
+            Some("a/Cargo.toml")
+        );
+        assert_eq!(manifest_path(&args(&["--manifest-path"])), None);
+        assert_eq!(manifest_path(&args(&["--", "--manifest-path", "a/Cargo.toml"])), None);
+        assert_eq!(manifest_path(&args(&[])), None);
+    }
 
-    Ok(exit_code.unwrap_or(EXIT_ERR))
+    #[test]
+    fn finds_package_selection() {
+        assert!(selects_packages(&args(&["-p", "a"])));
+        assert!(selects_packages(&args(&["-pa"])));
+        assert!(selects_packages(&args(&["--package", "a"])));
+        assert!(selects_packages(&args(&["--package=a"])));
+        assert!(selects_packages(&args(&["--workspace"])));
+        assert!(selects_packages(&args(&["--all"])));
+        assert!(!selects_packages(&args(&["--release", "--", "-p"])));
+        assert!(!selects_packages(&args(&["--profile", "dev"])));
+    }
 }
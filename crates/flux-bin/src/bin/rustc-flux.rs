@@ -5,8 +5,8 @@ use std::{
 
 use anyhow::Result;
 use flux_bin::utils::{
-    get_flux_driver_path, get_rust_toolchain, get_rustc_driver_lib_path, prepend_path_to_env_var,
-    sysroot_dir, EXIT_ERR, LIB_PATH,
+    get_flux_driver_path, get_rust_toolchain, get_rustc_driver_lib_path, get_std_specs_path,
+    prepend_path_to_env_var, sysroot_dir, EXIT_ERR, FLUX_STD_SPECS, LIB_PATH,
 };
 
 fn main() {
@@ -26,16 +26,20 @@ fn run() -> Result<i32> {
     let ld_library_path = get_rustc_driver_lib_path(&rust_toolchain)?;
     let extended_lib_path = prepend_path_to_env_var(LIB_PATH, ld_library_path)?;
 
-    let exit_code = Command::new(flux_driver_path)
+    let mut cmd = Command::new(flux_driver_path);
+    cmd
         // Skip the invocation of rustc-flux itself
         .args(env::args().skip(1))
         .arg("-L")
         .arg(sysroot_dir())
         .arg("--extern")
         .arg("flux_rs")
-        .env(LIB_PATH, extended_lib_path)
-        .status()?
-        .code();
+        .env(LIB_PATH, extended_lib_path);
+    if let Some(std_specs) = get_std_specs_path() {
+        // CODESYNC(std-specs-env) Tell the flux-driver where to find the bundled specs.
+        cmd.env(FLUX_STD_SPECS, std_specs);
+    }
+    let exit_code = cmd.status()?.code();
 
     Ok(exit_code.unwrap_or(EXIT_ERR))
 }
@@ -1 +1,3 @@
+pub mod metadata;
 pub mod utils;
+pub mod watch;
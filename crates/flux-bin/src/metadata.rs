@@ -0,0 +1,327 @@
+use std::{
+    fs,
+    path::Path,
+    process::{self, Command},
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value as Json;
+use toml::{Table, Value};
+
+/// Key in `[workspace.metadata.flux]` listing the members to verify.
+const MEMBERS_KEY: &str = "members";
+
+/// Key in the flux metadata telling whether a package should be verified.
+const ENABLED_KEY: &str = "enabled";
+
+/// The flux configuration of the members of a workspace resolved from the `[workspace.metadata.flux]`
+/// and `[package.metadata.flux]` tables in the manifests.
+pub struct WorkspaceMetadata {
+    pub workspace_root: String,
+    pub target_directory: String,
+    pub packages: Vec<PackageMetadata>,
+    /// Whether the workspace explicitly selects the members to verify with `members = [...]`.
+    pub selects_members: bool,
+}
+
+pub struct PackageMetadata {
+    pub name: String,
+    /// The settings in `[workspace.metadata.flux]` overridden by the ones in
+    /// `[package.metadata.flux]`.
+    pub config: Table,
+}
+
+impl WorkspaceMetadata {
+    /// Reads the metadata of the workspace by calling `cargo metadata`. The `manifest_path` is
+    /// forwarded to cargo if given.
+    pub fn read(cargo_path: &str, manifest_path: Option<&str>) -> Result<WorkspaceMetadata> {
+        let mut cmd = Command::new(cargo_path);
+        cmd.args(["metadata", "--no-deps", "--format-version", "1"]);
+        if let Some(manifest_path) = manifest_path {
+            cmd.args(["--manifest-path", manifest_path]);
+        }
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "`cargo metadata` failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        WorkspaceMetadata::from_json(&serde_json::from_slice(&output.stdout)?)
+    }
+
+    /// Resolves the metadata of the workspace from the output of `cargo metadata`. The settings in
+    /// the workspace table come first, then `members` decides whether a package is enabled, and
+    /// the package table overrides both. It's an error for `members` to list a package that is not
+    /// a member of the workspace.
+    pub fn from_json(metadata: &Json) -> Result<WorkspaceMetadata> {
+        let workspace_root = metadata["workspace_root"]
+            .as_str()
+            .ok_or_else(|| anyhow!("`cargo metadata` did not report a workspace root"))?
+            .to_string();
+        let target_directory = metadata["target_directory"]
+            .as_str()
+            .ok_or_else(|| anyhow!("`cargo metadata` did not report a target directory"))?
+            .to_string();
+
+        let mut workspace_config = flux_table(&metadata["metadata"])?;
+        let members = workspace_config
+            .remove(MEMBERS_KEY)
+            .map(|members| {
+                members
+                    .try_into::<Vec<String>>()
+                    .map_err(|_| anyhow!("`{MEMBERS_KEY}` must be a list of package names"))
+            })
+            .transpose()?;
+
+        let workspace_members = metadata["workspace_members"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut packages = vec![];
+        for package in metadata["packages"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if !workspace_members.contains(&package["id"]) {
+                continue;
+            }
+            let name = package["name"]
+                .as_str()
+                .ok_or_else(|| anyhow!("`cargo metadata` reported a package without a name"))?
+                .to_string();
+
+            let package_config = flux_table(&package["metadata"])?;
+
+            // A package can opt in or out explicitly, otherwise it is enabled if the workspace
+            // lists it in its members or, when there's no such list, if the workspace enables it.
+            let mut config = workspace_config.clone();
+            if let Some(members) = &members {
+                let enabled = members.contains(&name);
+                config.insert(ENABLED_KEY.to_string(), Value::Boolean(enabled));
+            }
+            config.extend(package_config);
+
+            packages.push(PackageMetadata { name, config });
+        }
+
+        if let Some(members) = &members {
+            for member in members {
+                if !packages.iter().any(|package| &package.name == member) {
+                    return Err(anyhow!("`{member}` is not a member of the workspace"));
+                }
+            }
+        }
+
+        Ok(WorkspaceMetadata {
+            workspace_root,
+            target_directory,
+            packages,
+            selects_members: members.is_some(),
+        })
+    }
+
+    /// The packages that should be verified
+    pub fn enabled_packages(&self) -> impl Iterator<Item = &PackageMetadata> {
+        self.packages.iter().filter(|package| package.is_enabled())
+    }
+
+    /// Writes the resolved config of every package to `dir/<package name>.toml`. The driver adds
+    /// the config to the dep-info of the crate, so it is only written when it changes to avoid
+    /// checking the crate again. It's first written to a temporary file and then renamed, so
+    /// concurrent runs sharing the target directory never read a partially written config.
+    // CODESYNC(package-config) we must use the same layout in flux-config
+    pub fn write_configs(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        for package in &self.packages {
+            let path = dir.join(&package.name).with_extension("toml");
+            let contents = toml::to_string(&package.config)?;
+            if fs::read_to_string(&path).is_ok_and(|old| old == contents) {
+                continue;
+            }
+            let tmp = dir.join(format!("{}.{}.tmp", package.name, process::id()));
+            fs::write(&tmp, contents)?;
+            fs::rename(tmp, path)?;
+        }
+        Ok(())
+    }
+}
+
+impl PackageMetadata {
+    pub fn is_enabled(&self) -> bool {
+        self.config
+            .get(ENABLED_KEY)
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+}
+
+/// Returns the `flux` table inside a `metadata` object reported by `cargo metadata`.
+fn flux_table(metadata: &Json) -> Result<Table> {
+    match metadata.get("flux") {
+        Some(flux @ Json::Object(_)) => Ok(serde_json::from_value(flux.clone())?),
+        Some(Json::Null) | None => Ok(Table::new()),
+        Some(_) => Err(anyhow!("flux metadata must be a table")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs::File, time::SystemTime};
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn package(name: &str, flux: Json) -> Json {
+        json!({ "id": format!("{name} 0.1.0"), "name": name, "metadata": { "flux": flux } })
+    }
+
+    fn workspace(flux: Json, packages: Vec<Json>) -> Json {
+        let members: Vec<_> = packages
+            .iter()
+            .map(|package| package["id"].clone())
+            .collect();
+        json!({
+            "workspace_root": "/ws",
+            "target_directory": "/ws/target",
+            "metadata": { "flux": flux },
+            "workspace_members": members,
+            "packages": packages,
+        })
+    }
+
+    fn config<'a>(metadata: &'a WorkspaceMetadata, name: &str) -> &'a Table {
+        &metadata
+            .packages
+            .iter()
+            .find(|package| package.name == name)
+            .unwrap()
+            .config
+    }
+
+    fn enabled(metadata: &WorkspaceMetadata) -> Vec<&str> {
+        metadata
+            .enabled_packages()
+            .map(|package| package.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn package_overrides_workspace() {
+        let metadata = WorkspaceMetadata::from_json(&workspace(
+            json!({ "check_overflow": true, "solver_timeout": 5 }),
+            vec![
+                package("a", json!({ "enabled": true, "check_overflow": false })),
+                package("b", Json::Null),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(metadata.workspace_root, "/ws");
+        assert_eq!(metadata.target_directory, "/ws/target");
+        assert!(!metadata.selects_members);
+        assert_eq!(config(&metadata, "a")["check_overflow"], Value::Boolean(false));
+        assert_eq!(config(&metadata, "a")["solver_timeout"], Value::Integer(5));
+        assert_eq!(config(&metadata, "b")["check_overflow"], Value::Boolean(true));
+        assert_eq!(enabled(&metadata), ["a"]);
+    }
+
+    #[test]
+    fn members_enable_packages() {
+        let metadata = WorkspaceMetadata::from_json(&workspace(
+            json!({ "members": ["a", "b"] }),
+            vec![
+                package("a", Json::Null),
+                package("b", json!({ "enabled": false })),
+                package("c", Json::Null),
+                package("d", json!({ "enabled": true })),
+            ],
+        ))
+        .unwrap();
+
+        assert!(metadata.selects_members);
+        assert!(!config(&metadata, "a").contains_key(MEMBERS_KEY));
+        assert_eq!(enabled(&metadata), ["a", "d"]);
+    }
+
+    #[test]
+    fn ignores_packages_outside_the_workspace() {
+        let mut metadata = workspace(json!({ "enabled": true }), vec![package("a", Json::Null)]);
+        metadata["packages"]
+            .as_array_mut()
+            .unwrap()
+            .push(package("dep", Json::Null));
+
+        let metadata = WorkspaceMetadata::from_json(&metadata).unwrap();
+        assert_eq!(enabled(&metadata), ["a"]);
+    }
+
+    #[test]
+    fn unknown_member_is_an_error() {
+        let err = WorkspaceMetadata::from_json(&workspace(
+            json!({ "members": ["a", "b"] }),
+            vec![package("a", Json::Null)],
+        ))
+        .err()
+        .unwrap();
+        assert_eq!(err.to_string(), "`b` is not a member of the workspace");
+    }
+
+    #[test]
+    fn members_must_be_a_list() {
+        let err = WorkspaceMetadata::from_json(&workspace(json!({ "members": "a" }), vec![])).err();
+        assert_eq!(err.unwrap().to_string(), "`members` must be a list of package names");
+    }
+
+    #[test]
+    fn flux_metadata_must_be_a_table() {
+        let err = WorkspaceMetadata::from_json(&workspace(json!(true), vec![])).err();
+        assert_eq!(err.unwrap().to_string(), "flux metadata must be a table");
+    }
+
+    #[test]
+    fn write_configs() {
+        let json = |check_overflow| {
+            workspace(
+                json!({ "check_overflow": check_overflow }),
+                vec![package("a", json!({ "enabled": true })), package("b", Json::Null)],
+            )
+        };
+        let dir = env::temp_dir().join(format!("flux-bin-write-configs-{}", process::id()));
+        let read = |name: &str| {
+            let path = dir.join(name).with_extension("toml");
+            let modified = fs::metadata(&path).unwrap().modified().unwrap();
+            (fs::read_to_string(path).unwrap().parse::<Table>().unwrap(), modified)
+        };
+
+        let metadata = WorkspaceMetadata::from_json(&json(true)).unwrap();
+        metadata.write_configs(&dir).unwrap();
+        let (a, _) = read("a");
+        assert_eq!(&a, config(&metadata, "a"));
+        let (_, b_modified) = read("b");
+
+        // Only the configs that changed are written again
+        File::options()
+            .write(true)
+            .open(dir.join("a.toml"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let metadata = WorkspaceMetadata::from_json(&json(true)).unwrap();
+        metadata.write_configs(&dir).unwrap();
+        assert_eq!(read("a").1, SystemTime::UNIX_EPOCH);
+        assert_eq!(read("b").1, b_modified);
+
+        let metadata = WorkspaceMetadata::from_json(&json(false)).unwrap();
+        metadata.write_configs(&dir).unwrap();
+        let (a, modified) = read("a");
+        assert_eq!(&a, config(&metadata, "a"));
+        assert_ne!(modified, SystemTime::UNIX_EPOCH);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
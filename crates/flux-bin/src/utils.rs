@@ -14,6 +14,12 @@ pub const EXIT_ERR: i32 = -1;
 // CODESYNC(sysroot-env) we must use the same env var in flux-tests
 pub const FLUX_SYSROOT: &str = "FLUX_SYSROOT";
 
+// CODESYNC(std-specs-env) we must use the same env var in flux-driver
+pub const FLUX_STD_SPECS: &str = "FLUX_STD_SPECS";
+
+// CODESYNC(package-config) we must use the same env var in flux-config
+pub const FLUX_PACKAGE_CONFIG_DIR: &str = "FLUX_PACKAGE_CONFIG_DIR";
+
 /// The path of the flux sysroot lib containing precompiled libraries and the flux driver.
 pub fn sysroot_dir() -> PathBuf {
     env::var(FLUX_SYSROOT).map_or_else(|_| default_sysroot_dir(), PathBuf::from)
@@ -38,6 +44,12 @@ pub fn get_flux_driver_path() -> Result<PathBuf> {
     Ok(path)
 }
 
+/// Path to the precompiled library with specifications for the standard library, if it was built.
+pub fn get_std_specs_path() -> Option<PathBuf> {
+    let path = sysroot_dir().join("libflux_std_specs.rlib");
+    path.is_file().then_some(path)
+}
+
 pub fn get_rust_toolchain() -> Result<String> {
     let toolchain_str = include_str!("../../../rust-toolchain");
     let toolchain_file = rust_toolchain_file::toml::Parser::new(toolchain_str).parse()?;
@@ -0,0 +1,506 @@
+//! Support for `cargo flux --watch`, which re-runs flux every time a file in the workspace changes
+//! and reports the functions that started or stopped failing since the previous run.
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use serde_json::Value as Json;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// CODESYNC(results-ext) we must use the same extension in flux-driver
+const RESULTS_EXT: &str = "fluxresults";
+
+const FAILED: &str = "failed";
+
+// CODESYNC(results-format) status of a crate whose checking stopped before its functions
+const ERROR: &str = "error";
+
+/// Modification times of the files in a workspace that can affect verification, i.e., rust
+/// sources, manifests and flux config files. The target directory and hidden directories are
+/// skipped.
+#[derive(PartialEq, Eq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl Snapshot {
+    pub fn take(root: &Path, target_dir: &Path) -> io::Result<Snapshot> {
+        let mut files = BTreeMap::new();
+        collect_files(root, target_dir, &mut files)?;
+        Ok(Snapshot { files })
+    }
+
+    /// Blocks until a file is added, removed or modified and updates the snapshot. We poll the
+    /// file system and wait for a period without changes so a burst of writes (e.g., saving
+    /// several files at once) triggers a single run.
+    pub fn wait_for_changes(&mut self, root: &Path, target_dir: &Path) -> io::Result<()> {
+        let mut changed = false;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let snapshot = Snapshot::take(root, target_dir)?;
+            if snapshot != *self {
+                *self = snapshot;
+                changed = true;
+            } else if changed {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn collect_files(
+    dir: &Path,
+    target_dir: &Path,
+    files: &mut BTreeMap<PathBuf, SystemTime>,
+) -> io::Result<()> {
+    // Files can be removed while we scan, e.g., by an editor replacing a file when saving it. They
+    // are skipped, and the next snapshot sees the new version.
+    let Some(entries) = found(fs::read_dir(dir))? else { return Ok(()) };
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let Some(file_type) = found(entry.file_type())? else { continue };
+        if file_type.is_dir() {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden && path != target_dir {
+                collect_files(&path, target_dir, files)?;
+            }
+        } else if file_type.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext == "rs" || ext == "toml")
+        {
+            if let Some(modified) = found(entry.metadata().and_then(|m| m.modified()))? {
+                files.insert(path, modified);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Turns a [`io::ErrorKind::NotFound`] error into [`None`]
+fn found<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Status of the functions checked in the previous runs, indexed by the results file saved by the
+/// driver for each crate (see `FLUX_SAVE_RESULTS`).
+#[derive(Default)]
+pub struct Results {
+    /// Modification times of the results files before the first run. These files belong to older
+    /// builds (e.g., another profile or target) unless they are written again or cargo reports them
+    /// as part of a run.
+    baseline: Option<BTreeMap<PathBuf, SystemTime>>,
+    crates: BTreeMap<PathBuf, CrateResults>,
+}
+
+struct CrateResults {
+    /// Modification time of the results file when it was read
+    modified: SystemTime,
+    functions: BTreeMap<String, String>,
+}
+
+/// Functions whose status changed between two runs
+#[derive(Default)]
+pub struct Delta {
+    /// Functions that fail now but didn't fail (or weren't checked) in the previous run
+    pub failing: Vec<String>,
+    /// Functions that failed in the previous run and don't fail now
+    pub fixed: Vec<String>,
+    /// Crates whose checking stopped before their functions, e.g., because of an ill-formed spec
+    pub errors: Vec<String>,
+}
+
+impl Results {
+    /// Records the results files in `target_dir` before the first run, see [`Results::update`].
+    pub fn record_baseline(&mut self, target_dir: &Path) -> io::Result<()> {
+        if self.baseline.is_none() {
+            let mut baseline = BTreeMap::new();
+            for path in results_files(target_dir)? {
+                if let Some(metadata) = found(fs::metadata(&path))? {
+                    baseline.insert(path, metadata.modified()?);
+                }
+            }
+            self.baseline = Some(baseline);
+        }
+        Ok(())
+    }
+
+    /// Updates the results with the results files in `target_dir` written since they were last
+    /// read and returns the functions whose status changed. Crates that cargo didn't need to check
+    /// again keep their previous results. We compare with the modification time we saw before
+    /// rather than the time the run started, so a coarse file system clock can't make us miss a
+    /// crate. Files from before the first run are only read if they are in `artifacts`, the results
+    /// files of the crates in the run (see [`forward_messages`]). A crate that fails to compile
+    /// is not reported as an artifact, but its results are written again in the run.
+    pub fn update(&mut self, target_dir: &Path, artifacts: &[PathBuf]) -> io::Result<Delta> {
+        let mut delta = Delta::default();
+        for path in results_files(target_dir)? {
+            let Some(metadata) = found(fs::metadata(&path))? else { continue };
+            let modified = metadata.modified()?;
+            let old_build = self
+                .baseline
+                .as_ref()
+                .and_then(|baseline| baseline.get(&path))
+                .is_some_and(|before| *before == modified);
+            if old_build && !artifacts.contains(&path) {
+                continue;
+            }
+            if self
+                .crates
+                .get(&path)
+                .is_some_and(|old| old.modified == modified)
+            {
+                if let Some(name) = self.crates[&path].error() {
+                    delta.errors.push(name.to_string());
+                }
+                continue;
+            }
+            let functions = read_results(&path)?;
+            let old = self
+                .crates
+                .remove(&path)
+                .map(|old| old.functions)
+                .unwrap_or_default();
+            for (name, status) in &functions {
+                let failed_before = old.get(name).is_some_and(|status| status == FAILED);
+                if status == ERROR {
+                    delta.errors.push(name.clone());
+                } else if status == FAILED && !failed_before {
+                    delta.failing.push(name.clone());
+                } else if status != FAILED && failed_before {
+                    delta.fixed.push(name.clone());
+                }
+            }
+            self.crates
+                .insert(path, CrateResults { modified, functions });
+        }
+        Ok(delta)
+    }
+
+    pub fn failing(&self) -> usize {
+        self.crates
+            .values()
+            .flat_map(|results| results.functions.values())
+            .filter(|status| *status == FAILED)
+            .count()
+    }
+}
+
+impl CrateResults {
+    /// The name of the crate if its checking stopped with an error
+    fn error(&self) -> Option<&str> {
+        self.functions
+            .iter()
+            .find(|(_, status)| *status == ERROR)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+impl Delta {
+    pub fn print(&self) {
+        for name in &self.errors {
+            println!("  could not check: {name}");
+        }
+        for name in &self.failing {
+            println!("  newly failing: {name}");
+        }
+        for name in &self.fixed {
+            println!("  fixed: {name}");
+        }
+    }
+}
+
+fn results_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let Some(entries) = found(fs::read_dir(dir))? else { return Ok(files) };
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(results_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == RESULTS_EXT) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Forwards the diagnostics in the messages printed by cargo with `--message-format=json-*` to
+/// `diagnostics` and returns the results files of the crates compiled in the run, including the
+/// ones that didn't need to be checked again. Lines that are not a JSON message are forwarded as
+/// they are.
+pub fn forward_messages(
+    messages: impl BufRead,
+    diagnostics: &mut impl Write,
+) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for line in messages.lines() {
+        let line = line?;
+        let Ok(message) = serde_json::from_str::<Json>(&line) else {
+            writeln!(diagnostics, "{line}")?;
+            continue;
+        };
+        match message["reason"].as_str() {
+            Some("compiler-message") => {
+                if let Some(rendered) = message["message"]["rendered"].as_str() {
+                    write!(diagnostics, "{rendered}")?;
+                }
+            }
+            Some("compiler-artifact") => {
+                // The driver saves the results next to the flux metadata of the crate, which has
+                // the same name as the `.rmeta` file.
+                let rmeta = message["filenames"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Json::as_str)
+                    .map(Path::new)
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "rmeta"));
+                files.extend(rmeta.map(|path| path.with_extension(RESULTS_EXT)));
+            }
+            _ => {}
+        }
+    }
+    Ok(files)
+}
+
+// CODESYNC(results-format) we must use the same format in flux-driver
+fn read_results(path: &Path) -> io::Result<BTreeMap<String, String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(status, name)| (name.to_string(), status.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs::File,
+        process,
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    /// A fresh directory for a test, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = env::temp_dir().join(format!("flux-bin-{name}-{}", process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        /// Writes `contents` to `path` inside the directory with the given modification time
+        fn write(&self, path: &str, contents: &str, modified: u64) -> PathBuf {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            set_modified(&path, modified);
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn set_modified(path: &Path, secs: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn reads_results() {
+        let dir = TempDir::new("reads-results");
+        let path = dir.write("a.fluxresults", "verified a::f\nfailed a::m::g\n\n", 0);
+        let results = read_results(&path).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results["a::f"], "verified");
+        assert_eq!(results["a::m::g"], FAILED);
+    }
+
+    #[test]
+    fn finds_results_files() {
+        let dir = TempDir::new("finds-results-files");
+        let a = dir.write("debug/deps/liba-1.fluxresults", "", 0);
+        let b = dir.write("debug/build/b/libb-2.fluxresults", "", 0);
+        dir.write("debug/deps/liba-1.fluxmeta", "", 0);
+
+        let mut files = results_files(&dir.0).unwrap();
+        files.sort();
+        assert_eq!(files, [b, a]);
+        assert!(results_files(&dir.0.join("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn forwards_messages() {
+        let messages = [
+            r#"{"reason":"compiler-artifact","filenames":["/t/debug/deps/liba-1.rmeta"],"fresh":true}"#,
+            r#"{"reason":"compiler-message","message":{"rendered":"error: a\n"}}"#,
+            r#"{"reason":"compiler-artifact","filenames":["/t/debug/build/b-2/build_script_build-2"]}"#,
+            r#"{"reason":"build-finished","success":false}"#,
+            "not a message",
+        ];
+        let mut diagnostics = vec![];
+        let files = forward_messages(messages.join("\n").as_bytes(), &mut diagnostics).unwrap();
+        assert_eq!(files, [PathBuf::from("/t/debug/deps/liba-1.fluxresults")]);
+        assert_eq!(String::from_utf8(diagnostics).unwrap(), "error: a\nnot a message\n");
+    }
+
+    #[test]
+    fn updates_results() {
+        let dir = TempDir::new("updates-results");
+        let mut results = Results::default();
+
+        let a = dir.write("liba-1.fluxresults", "failed a::f\nverified a::g\n", 10);
+        dir.write("libb-1.fluxresults", "failed b::f\n", 10);
+        let delta = results.update(&dir.0, &[]).unwrap();
+        assert_eq!(delta.failing, ["a::f", "b::f"]);
+        assert!(delta.fixed.is_empty());
+        assert_eq!(results.failing(), 2);
+
+        // A results file that wasn't written again is skipped even if its contents differ
+        fs::write(&a, "verified a::f\n").unwrap();
+        set_modified(&a, 10);
+        let delta = results.update(&dir.0, &[]).unwrap();
+        assert!(delta.failing.is_empty() && delta.fixed.is_empty());
+        assert_eq!(results.failing(), 2);
+
+        // A file written again is read even if it's older than the previous one
+        dir.write("liba-1.fluxresults", "verified a::f\nfailed a::g\n", 5);
+        let delta = results.update(&dir.0, &[]).unwrap();
+        assert_eq!(delta.failing, ["a::g"]);
+        assert_eq!(delta.fixed, ["a::f"]);
+        assert_eq!(results.failing(), 2);
+    }
+
+    #[test]
+    fn skips_results_from_older_builds() {
+        let dir = TempDir::new("older-builds");
+        let mut results = Results::default();
+
+        let fresh = dir.write("debug/deps/liba-1.fluxresults", "failed a::f\n", 10);
+        dir.write("debug/deps/liba-0.fluxresults", "failed a::g\n", 10);
+        let release = dir.write("release/deps/liba-2.fluxresults", "failed a::h\n", 10);
+        results.record_baseline(&dir.0).unwrap();
+
+        // Only the file reported by cargo is read among the ones from before the first run
+        let delta = results.update(&dir.0, &[fresh.clone()]).unwrap();
+        assert_eq!(delta.failing, ["a::f"]);
+
+        // A file written during the session is read even if cargo doesn't report it
+        dir.write("debug/deps/libb-1.fluxresults", "failed b::f\n", 0);
+        let delta = results.update(&dir.0, &[fresh.clone()]).unwrap();
+        assert_eq!(delta.failing, ["b::f"]);
+
+        // The baseline is only recorded once
+        results.record_baseline(&dir.0).unwrap();
+        dir.write("release/deps/liba-2.fluxresults", "verified a::h\n", 20);
+        results.record_baseline(&dir.0).unwrap();
+        let delta = results.update(&dir.0, &[fresh]).unwrap();
+        assert!(delta.failing.is_empty() && delta.fixed.is_empty());
+        assert!(results.crates.contains_key(&release));
+        assert_eq!(results.failing(), 2);
+    }
+
+    #[test]
+    fn reports_crate_errors() {
+        let dir = TempDir::new("reports-crate-errors");
+        let mut results = Results::default();
+
+        dir.write("liba-1.fluxresults", "failed a::f\n", 10);
+        results.update(&dir.0, &[]).unwrap();
+
+        // The error is reported in every run until the crate can be checked again
+        dir.write("liba-1.fluxresults", "error a\n", 20);
+        for _ in 0..2 {
+            let delta = results.update(&dir.0, &[]).unwrap();
+            assert_eq!(delta.errors, ["a"]);
+            assert!(delta.failing.is_empty() && delta.fixed.is_empty());
+            assert_eq!(results.failing(), 0);
+        }
+
+        dir.write("liba-1.fluxresults", "failed a::f\n", 30);
+        let delta = results.update(&dir.0, &[]).unwrap();
+        assert!(delta.errors.is_empty());
+        assert_eq!(delta.failing, ["a::f"]);
+    }
+
+    #[test]
+    fn snapshot_tracks_sources_and_configs() {
+        let dir = TempDir::new("snapshot");
+        let target = dir.0.join("target");
+        let src = dir.write("src/lib.rs", "", 0);
+        dir.write("Cargo.toml", "", 0);
+        let notes = dir.write("notes.txt", "", 0);
+        let built = dir.write("target/debug/build/out.rs", "", 0);
+        let hidden = dir.write(".git/hook.rs", "", 0);
+
+        let snapshot = Snapshot::take(&dir.0, &target).unwrap();
+        let files: Vec<_> = snapshot.files.keys().cloned().collect();
+        assert_eq!(files, [dir.0.join("Cargo.toml"), src.clone()]);
+
+        for path in [&notes, &built, &hidden] {
+            set_modified(path, 1);
+        }
+        assert!(Snapshot::take(&dir.0, &target).unwrap() == snapshot);
+
+        set_modified(&src, 1);
+        assert!(Snapshot::take(&dir.0, &target).unwrap() != snapshot);
+
+        set_modified(&src, 0);
+        dir.write("flux.toml", "", 0);
+        assert!(Snapshot::take(&dir.0, &target).unwrap() != snapshot);
+    }
+
+    #[test]
+    fn snapshot_skips_removed_entries() {
+        let dir = TempDir::new("snapshot-removed");
+        let mut files = BTreeMap::new();
+        collect_files(&dir.0.join("missing"), &dir.0, &mut files).unwrap();
+        assert!(files.is_empty());
+
+        // Files and directories are removed and created again while the snapshots are taken
+        let src = dir.0.join("src");
+        let writes = AtomicUsize::new(0);
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    fs::create_dir_all(src.join("m")).unwrap();
+                    fs::write(src.join("m/lib.rs"), "").unwrap();
+                    fs::remove_dir_all(&src).unwrap();
+                    writes.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+            while writes.load(Ordering::Relaxed) < 1000 {
+                let snapshot = Snapshot::take(&dir.0, &dir.0.join("target"));
+                // Stop the writer before failing or the scope would wait for it forever
+                done.store(snapshot.is_err(), Ordering::Relaxed);
+                snapshot.unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+}
@@ -2,6 +2,7 @@
 use std::{
     fmt, fs,
     io::{self, Write},
+    path::PathBuf,
 };
 
 use flux_config as config;
@@ -14,12 +15,16 @@ pub fn writer_for_item(
     ext: impl AsRef<str>,
 ) -> io::Result<impl io::Write> {
     fs::create_dir_all(config::log_dir())?;
-    let path = config::log_dir().join(dump_base_name(tcx, def_id, ext));
-    let file = fs::File::create(path)?;
+    let file = fs::File::create(item_info_path(tcx, def_id, ext))?;
     let buf = std::io::BufWriter::new(file);
     Ok(buf)
 }
 
+/// The path of the file [`writer_for_item`] writes to.
+pub fn item_info_path(tcx: TyCtxt, def_id: DefId, ext: impl AsRef<str>) -> PathBuf {
+    config::log_dir().join(dump_base_name(tcx, def_id, ext))
+}
+
 pub fn dump_item_info<T: fmt::Debug>(
     tcx: TyCtxt,
     def_id: impl Into<DefId>,
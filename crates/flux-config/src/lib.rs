@@ -1,6 +1,13 @@
 #![feature(lazy_cell)]
 
-use std::{io::Read, path::PathBuf, sync::LazyLock};
+use std::{
+    io::Read,
+    iter,
+    path::PathBuf,
+    str::FromStr,
+    sync::{LazyLock, OnceLock},
+    time::Duration,
+};
 
 use config::{Environment, File};
 use serde::Deserialize;
@@ -8,6 +15,8 @@ pub use toml::Value;
 
 const FLUX_ENV_VAR_PREFIX: &str = "FLUX";
 const FLUX_CONFIG_ENV_VAR: &str = "FLUX_CONFIG";
+// CODESYNC(package-config) we must use the same env var in flux-bin
+const FLUX_PACKAGE_CONFIG_DIR_ENV_VAR: &str = "FLUX_PACKAGE_CONFIG_DIR";
 
 pub fn check_def() -> &'static str {
     &CONFIG.check_def
@@ -37,10 +46,59 @@ pub fn dump_rty() -> bool {
     CONFIG.dump_rty
 }
 
+pub fn dump_local_types() -> bool {
+    CONFIG.dump_local_types
+}
+
+pub fn dump_kvar_solutions() -> bool {
+    CONFIG.dump_kvar_solutions
+}
+
+pub fn dump_summary() -> bool {
+    CONFIG.dump_summary
+}
+
+/// Whether to save the status of each checked function next to the flux metadata of the crate.
+/// Used by `cargo flux --watch` to report which functions started or stopped failing.
+pub fn save_results() -> bool {
+    CONFIG.save_results
+}
+
+/// The environment variables the configuration depends on together with their current value,
+/// including the ones that are not set.
+pub fn env_vars() -> Vec<(String, Option<String>)> {
+    iter::once(FLUX_CONFIG_ENV_VAR.to_string())
+        .chain(
+            CONFIG_KEYS
+                .iter()
+                .map(|key| format!("{FLUX_ENV_VAR_PREFIX}_{}", key.to_uppercase())),
+        )
+        .map(|var| {
+            let val = std::env::var(&var).ok();
+            (var, val)
+        })
+        .collect()
+}
+
+/// Pointer width used for `usize` and `isize`. This is the pointer width of the compilation target
+/// (see [`set_target_pointer_width`]) unless it is overridden in the configuration.
 pub fn pointer_width() -> PointerWidth {
+    CONFIG
+        .pointer_width
+        .or_else(|| TARGET_POINTER_WIDTH.get().copied())
+        .unwrap_or(PointerWidth::W64)
+}
+
+/// Pointer width given in the configuration, if any.
+pub fn pointer_width_override() -> Option<PointerWidth> {
     CONFIG.pointer_width
 }
 
+/// Records the pointer width of the compilation target. Only the first call has an effect.
+pub fn set_target_pointer_width(pointer_width: PointerWidth) {
+    let _ = TARGET_POINTER_WIDTH.set(pointer_width);
+}
+
 pub fn log_dir() -> &'static PathBuf {
     &CONFIG.log_dir
 }
@@ -61,12 +119,128 @@ pub fn scrape_quals() -> bool {
     CONFIG.scrape_quals
 }
 
+pub fn skip_unsupported() -> bool {
+    CONFIG.skip_unsupported
+}
+
+pub fn check_vacuity() -> bool {
+    CONFIG.check_vacuity
+}
+
+pub fn suggest_preconditions() -> bool {
+    CONFIG.suggest_preconditions
+}
+
+pub fn infer_signatures() -> bool {
+    CONFIG.infer_signatures
+}
+
+pub fn check_raw_pointers() -> bool {
+    CONFIG.check_raw_pointers
+}
+
+/// Maximum time fixpoint can spend on the constraint of a single item.
+pub fn solver_timeout() -> Option<Duration> {
+    CONFIG.solver_timeout.map(Duration::from_secs)
+}
+
+/// Maximum time fixpoint can spend on all the items in a crate.
+pub fn solver_total_timeout() -> Option<Duration> {
+    CONFIG.solver_total_timeout.map(Duration::from_secs)
+}
+
 #[derive(Debug)]
 pub struct CrateConfig {
     pub check_overflow: bool,
     pub scrape_quals: bool,
+    pub skip_unsupported: bool,
+    pub check_vacuity: bool,
+    pub suggest_preconditions: bool,
+    pub infer_signatures: bool,
+    pub check_raw_pointers: bool,
+    /// Timeout in seconds for a single item, see [`solver_timeout`].
+    pub solver_timeout: Option<u64>,
+    pub kvar_encoding: KVarEncoding,
+    /// Dump the constraint and kvar solutions of each item as if [`dump_constraint`] and
+    /// [`dump_kvar_solutions`] were set.
+    pub verbose: bool,
+}
+
+/// Settings given with `#[flux::cfg(...)]` on a module, impl or function. A setting that is not
+/// given is inherited from the enclosing items and ultimately from the [`CrateConfig`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ItemConfig {
+    pub check_overflow: Option<bool>,
+    pub scrape_quals: Option<bool>,
+    pub solver_timeout: Option<u64>,
+    pub kvar_encoding: Option<KVarEncoding>,
+    pub verbose: Option<bool>,
 }
 
+impl ItemConfig {
+    /// Fills the settings missing in `self` with the ones given in `parent`.
+    pub fn inherit(&mut self, parent: &ItemConfig) {
+        self.check_overflow = self.check_overflow.or(parent.check_overflow);
+        self.scrape_quals = self.scrape_quals.or(parent.scrape_quals);
+        self.solver_timeout = self.solver_timeout.or(parent.solver_timeout);
+        self.kvar_encoding = self.kvar_encoding.or(parent.kvar_encoding);
+        self.verbose = self.verbose.or(parent.verbose);
+    }
+}
+
+/// How a kvar is encoded in the fixpoint constraint
+#[derive(Clone, Copy, Debug, Default)]
+pub enum KVarEncoding {
+    /// Generate a single kvar appending the self arguments and the scope, i.e.,
+    /// a kvar `$k(a0, ...)[b0, ...]` becomes `$k(a0, ..., b0, ...)` in the fixpoint constraint.
+    Single,
+    /// Generate a conjunction of kvars, one per self argument.
+    /// Concretely, a kvar `$k(a0, a1, ..., an)[b0, ...]` becomes
+    /// `$k0(a0, a1, ..., an, b0, ...) ∧ $k1(a1, ..., an, b0, ...) ∧ ... ∧ $kn(an, b0, ...)`
+    #[default]
+    Conj,
+}
+
+impl FromStr for KVarEncoding {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(KVarEncoding::Single),
+            "conj" => Ok(KVarEncoding::Conj),
+            _ => Err("kvar encoding must be `single` or `conj`"),
+        }
+    }
+}
+
+/// The keys of [`Config`], which can also be set with a `FLUX_<KEY>` environment variable.
+const CONFIG_KEYS: &[&str] = &[
+    "log_dir",
+    "dump_constraint",
+    "dump_checker_trace",
+    "dump_timings",
+    "dump_fhir",
+    "dump_rty",
+    "dump_mir",
+    "dump_local_types",
+    "dump_kvar_solutions",
+    "dump_summary",
+    "save_results",
+    "pointer_width",
+    "check_def",
+    "cache",
+    "cache_file",
+    "check_overflow",
+    "scrape_quals",
+    "skip_unsupported",
+    "check_vacuity",
+    "suggest_preconditions",
+    "infer_signatures",
+    "check_raw_pointers",
+    "solver_timeout",
+    "solver_total_timeout",
+];
+
 #[derive(Deserialize)]
 struct Config {
     log_dir: PathBuf,
@@ -76,17 +250,29 @@ struct Config {
     dump_fhir: bool,
     dump_rty: bool,
     dump_mir: bool,
-    pointer_width: PointerWidth,
+    dump_local_types: bool,
+    dump_kvar_solutions: bool,
+    dump_summary: bool,
+    save_results: bool,
+    pointer_width: Option<PointerWidth>,
     check_def: String,
     cache: bool,
     cache_file: String,
     check_overflow: bool,
     scrape_quals: bool,
+    skip_unsupported: bool,
+    check_vacuity: bool,
+    suggest_preconditions: bool,
+    infer_signatures: bool,
+    check_raw_pointers: bool,
+    solver_timeout: Option<u64>,
+    solver_total_timeout: Option<u64>,
 }
 
-#[derive(Copy, Clone, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
 #[serde(try_from = "u8")]
 pub enum PointerWidth {
+    W16,
     W32,
     W64,
 }
@@ -94,6 +280,7 @@ pub enum PointerWidth {
 impl PointerWidth {
     pub fn bits(self) -> u64 {
         match self {
+            PointerWidth::W16 => 16,
             PointerWidth::W32 => 32,
             PointerWidth::W64 => 64,
         }
@@ -105,13 +292,16 @@ impl TryFrom<u8> for PointerWidth {
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
+            16 => Ok(PointerWidth::W16),
             32 => Ok(PointerWidth::W32),
             64 => Ok(PointerWidth::W64),
-            _ => Err("pointer width must be 32 or 64"),
+            _ => Err("pointer width must be 16, 32 or 64"),
         }
     }
 }
 
+static TARGET_POINTER_WIDTH: OnceLock<PointerWidth> = OnceLock::new();
+
 static CONFIG: LazyLock<Config> = LazyLock::new(|| {
     fn build() -> Result<Config, config::ConfigError> {
         let mut config_builder = config::Config::builder()
@@ -123,17 +313,28 @@ static CONFIG: LazyLock<Config> = LazyLock::new(|| {
             .set_default("dump_mir", false)?
             .set_default("dump_fhir", false)?
             .set_default("dump_rty", false)?
+            .set_default("dump_local_types", false)?
+            .set_default("dump_kvar_solutions", false)?
+            .set_default("dump_summary", false)?
+            .set_default("save_results", false)?
             .set_default("check_asserts", "assume")?
-            .set_default("pointer_width", "64")?
             .set_default("check_def", "")?
             .set_default("cache", false)?
             .set_default("cache_file", "cache.json")?
             .set_default("check_overflow", false)?
-            .set_default("scrape_quals", false)?;
-        // Config comes first, enviroment settings override it.
+            .set_default("scrape_quals", false)?
+            .set_default("skip_unsupported", false)?
+            .set_default("check_vacuity", false)?
+            .set_default("suggest_preconditions", false)?
+            .set_default("infer_signatures", false)?
+            .set_default("check_raw_pointers", false)?;
+        // Config comes first, then the package config, enviroment settings override both.
         if let Some(config_path) = CONFIG_PATH.as_ref() {
             config_builder = config_builder.add_source(File::from(config_path.clone()));
         };
+        if let Some(config_path) = PACKAGE_CONFIG_PATH.as_ref() {
+            config_builder = config_builder.add_source(File::from(config_path.clone()));
+        };
         config_builder
             .add_source(Environment::with_prefix(FLUX_ENV_VAR_PREFIX).ignore_empty(true))
             .build()?
@@ -162,6 +363,16 @@ pub static CONFIG_PATH: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
     }
 });
 
+/// Path to the config of the package being compiled, resolved by `cargo-flux` from the
+/// `[workspace.metadata.flux]` and `[package.metadata.flux]` tables in the manifests.
+pub static PACKAGE_CONFIG_PATH: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+    let dir = std::env::var(FLUX_PACKAGE_CONFIG_DIR_ENV_VAR).ok()?;
+    // Cargo sets the name of the package when it calls the driver
+    let package = std::env::var("CARGO_PKG_NAME").ok()?;
+    let file = PathBuf::from(dir).join(package).with_extension("toml");
+    file.exists().then_some(file)
+});
+
 pub static CONFIG_FILE: LazyLock<Value> = LazyLock::new(|| {
     if let Some(path) = &*CONFIG_PATH {
         let mut file = std::fs::File::open(path).unwrap();
@@ -175,6 +386,17 @@ pub static CONFIG_FILE: LazyLock<Value> = LazyLock::new(|| {
 
 impl Default for CrateConfig {
     fn default() -> Self {
-        Self { check_overflow: check_overflow(), scrape_quals: scrape_quals() }
+        Self {
+            check_overflow: check_overflow(),
+            scrape_quals: scrape_quals(),
+            skip_unsupported: skip_unsupported(),
+            check_vacuity: check_vacuity(),
+            suggest_preconditions: suggest_preconditions(),
+            infer_signatures: infer_signatures(),
+            check_raw_pointers: check_raw_pointers(),
+            solver_timeout: CONFIG.solver_timeout,
+            kvar_encoding: KVarEncoding::default(),
+            verbose: false,
+        }
     }
 }
@@ -80,3 +80,7 @@ desugar_unresolved_path =
 desugar_unresolved_generic_param =
     cannot resolve generic param
     .note = generic parameters in refined signature must much rust signature
+
+desugar_unresolved_alias_reft =
+    cannot resolve associated refinement `<{$qself} as {$trait_name}>::{$name}`
+    .label = no matching trait bound in scope
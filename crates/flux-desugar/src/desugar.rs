@@ -17,7 +17,7 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use rustc_hir as hir;
 use rustc_hir::OwnerId;
 use rustc_span::{
-    def_id::LocalDefId,
+    def_id::{DefId, LocalDefId},
     sym::{self},
     symbol::kw,
     Span, Symbol,
@@ -224,6 +224,7 @@ enum FuncRes<'a> {
 enum QPathRes<'a> {
     Param(fhir::Ident),
     Const(&'a fhir::ConstInfo),
+    ConstParam(DefId),
     NumConst(i128),
 }
 
@@ -392,12 +393,21 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
         enum_def: &surface::EnumDef,
     ) -> Result<fhir::EnumDef> {
         let def_id = self.owner.def_id;
-        let ItemKind::Enum(hir_enum, _) = &self.genv.hir().expect_item(def_id).kind else {
-            bug!("expected enum");
+        let variants = match &self.genv.hir().expect_item(def_id).kind {
+            ItemKind::Enum(hir_enum, _) => {
+                iter::zip(&enum_def.variants, hir_enum.variants)
+                    .map(|(variant, hir_variant)| {
+                        self.desugar_enum_variant_def(variant, hir_variant)
+                    })
+                    .try_collect_exhaust()?
+            }
+            ItemKind::Union(hir_union, _) => {
+                iter::zip(&enum_def.variants, hir_union.fields())
+                    .map(|(variant, hir_field)| self.desugar_union_field_def(variant, hir_field))
+                    .try_collect_exhaust()?
+            }
+            _ => bug!("expected enum or union"),
         };
-        let variants = iter::zip(&enum_def.variants, hir_enum.variants)
-            .map(|(variant, hir_variant)| self.desugar_enum_variant_def(variant, hir_variant))
-            .try_collect_exhaust()?;
 
         let mut env = Env::from_params(
             self.genv,
@@ -429,35 +439,56 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
         hir_variant: &hir::Variant,
     ) -> Result<fhir::VariantDef> {
         if let Some(variant_def) = variant_def {
-            let mut env = self.gather_params_variant(variant_def)?;
-
-            let fields = iter::zip(&variant_def.fields, hir_variant.data.fields())
-                .map(|(ty, hir_field)| {
-                    Ok(fhir::FieldDef {
-                        ty: self.desugar_ty(None, ty, &mut env)?,
-                        def_id: hir_field.def_id,
-                        lifted: false,
-                    })
-                })
-                .try_collect_exhaust()?;
+            self.desugar_variant_def(variant_def, hir_variant.def_id, hir_variant.data.fields())
+        } else {
+            self.as_lift_cx().lift_enum_variant(hir_variant)
+        }
+    }
 
-            let ret = if let Some(ret) = &variant_def.ret {
-                self.desugar_variant_ret(ret, &mut env)?
-            } else {
-                self.as_lift_cx().lift_variant_ret()
-            };
+    fn desugar_union_field_def(
+        &mut self,
+        variant_def: &Option<surface::VariantDef>,
+        hir_field: &hir::FieldDef,
+    ) -> Result<fhir::VariantDef> {
+        if let Some(variant_def) = variant_def {
+            self.desugar_variant_def(variant_def, hir_field.def_id, std::slice::from_ref(hir_field))
+        } else {
+            self.as_lift_cx().lift_union_field(hir_field)
+        }
+    }
 
-            Ok(fhir::VariantDef {
-                def_id: hir_variant.def_id,
-                params: env.into_root().into_params(self),
-                fields,
-                ret,
-                span: variant_def.span,
-                lifted: false,
+    fn desugar_variant_def(
+        &mut self,
+        variant_def: &surface::VariantDef,
+        def_id: LocalDefId,
+        hir_fields: &[hir::FieldDef],
+    ) -> Result<fhir::VariantDef> {
+        let mut env = self.gather_params_variant(variant_def)?;
+
+        let fields = iter::zip(&variant_def.fields, hir_fields)
+            .map(|(ty, hir_field)| {
+                Ok(fhir::FieldDef {
+                    ty: self.desugar_ty(None, ty, &mut env)?,
+                    def_id: hir_field.def_id,
+                    lifted: false,
+                })
             })
+            .try_collect_exhaust()?;
+
+        let ret = if let Some(ret) = &variant_def.ret {
+            self.desugar_variant_ret(ret, &mut env)?
         } else {
-            self.as_lift_cx().lift_enum_variant(hir_variant)
-        }
+            self.as_lift_cx().lift_variant_ret()
+        };
+
+        Ok(fhir::VariantDef {
+            def_id,
+            params: env.into_root().into_params(self),
+            fields,
+            ret,
+            span: variant_def.span,
+            lifted: false,
+        })
     }
 
     pub(crate) fn desugar_type_alias(
@@ -482,14 +513,35 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
         })
     }
 
+    pub(crate) fn desugar_assoc_refinements(
+        &self,
+        assoc_refts: &[surface::AssocReft],
+    ) -> Result<Vec<fhir::AssocRefinement>> {
+        assoc_refts
+            .iter()
+            .map(|reft| self.desugar_assoc_refinement(reft))
+            .try_collect_exhaust()
+    }
+
+    fn desugar_assoc_refinement(&self, reft: &surface::AssocReft) -> Result<fhir::AssocRefinement> {
+        let mut env =
+            Env::from_params(self.genv, &self.sort_resolver, ScopeId::FluxItem, &reft.params)?;
+        let output = self.sort_resolver.resolve_sort(&reft.output)?;
+        let body = reft
+            .body
+            .as_ref()
+            .map(|body| self.desugar_expr(&mut env, body))
+            .transpose()?;
+        let params = env.into_root().into_params(self);
+        Ok(fhir::AssocRefinement { name: reft.name.name, params, output, body, span: reft.span })
+    }
+
     pub(crate) fn desugar_fn_sig(
         &mut self,
         fn_sig: &surface::FnSig,
     ) -> Result<(fhir::GenericPredicates, fhir::FnSig)> {
         let mut env = self.gather_params_fn_sig(fn_sig)?;
 
-        let mut requires = vec![];
-
         // Desugar predicates -- after we have gathered the input params
         let generic_preds = if let Some(predicates) = &fn_sig.predicates {
             self.desugar_predicates(predicates, &mut env)?
@@ -497,6 +549,23 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
             self.as_lift_cx().lift_predicates()?
         };
 
+        Ok((generic_preds, self.desugar_fn_sig_with_env(fn_sig, env)?))
+    }
+
+    /// Closures don't have generics nor predicates of their own, so we only desugar the
+    /// signature.
+    pub(crate) fn desugar_closure_sig(&mut self, fn_sig: &surface::FnSig) -> Result<fhir::FnSig> {
+        let env = self.gather_params_fn_sig(fn_sig)?;
+        self.desugar_fn_sig_with_env(fn_sig, env)
+    }
+
+    fn desugar_fn_sig_with_env(
+        &mut self,
+        fn_sig: &surface::FnSig,
+        mut env: Env,
+    ) -> Result<fhir::FnSig> {
+        let mut requires = vec![];
+
         if let Some(e) = &fn_sig.requires {
             let pred = self.desugar_expr(&mut env, e)?;
             requires.push(fhir::Constraint::Pred(pred));
@@ -529,7 +598,7 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
             span: fn_sig.span,
             lifted: false,
         };
-        Ok((generic_preds, fn_sig))
+        Ok(fn_sig)
     }
 
     fn desugar_constraint(
@@ -724,7 +793,15 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
             }
             surface::TyKind::Array(ty, len) => {
                 let ty = self.desugar_ty(None, ty, env)?;
-                fhir::TyKind::Array(Box::new(ty), fhir::ArrayLen { val: len.val, span: len.span })
+                fhir::TyKind::Array(Box::new(ty), self.desugar_array_len(len)?)
+            }
+            surface::TyKind::BareFn(inputs, output) => {
+                let inputs = inputs
+                    .iter()
+                    .map(|ty| self.desugar_ty(None, ty, env))
+                    .try_collect_exhaust()?;
+                let output = self.desugar_ty(None, output, env)?;
+                fhir::TyKind::BareFn(inputs, Box::new(output))
             }
             surface::TyKind::ImplTrait(node_id, bounds) => {
                 let item_id = self.resolver_output.impl_trait_res_map[&node_id];
@@ -838,6 +915,11 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
                 let kind = fhir::BaseTyKind::Slice(Box::new(self.desugar_ty(None, ty, env)?));
                 Ok(fhir::BaseTy { kind, span: bty.span })
             }
+            surface::BaseTyKind::RawPtr(ty, mutbl) => {
+                let ty = self.desugar_ty(None, ty, env)?;
+                let kind = fhir::BaseTyKind::RawPtr(Box::new(ty), *mutbl);
+                Ok(fhir::BaseTy { kind, span: bty.span })
+            }
         }
     }
 
@@ -865,7 +947,11 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
         let mut fhir_args = vec![];
         let mut bindings = vec![];
         if let Res::Def(
-            DefKind::TyAlias { .. } | DefKind::Struct | DefKind::Enum | DefKind::OpaqueTy,
+            DefKind::TyAlias { .. }
+            | DefKind::Struct
+            | DefKind::Enum
+            | DefKind::Union
+            | DefKind::OpaqueTy,
             def_id,
         ) = res
         {
@@ -879,7 +965,13 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
         for arg in args {
             match arg {
                 surface::GenericArg::Type(ty) => {
-                    fhir_args.push(fhir::GenericArg::Type(self.desugar_ty(None, ty, env)?));
+                    if let Some(def_id) = self.try_resolve_const_param_arg(ty) {
+                        let kind = fhir::ConstArgKind::Param(def_id);
+                        fhir_args
+                            .push(fhir::GenericArg::Const(fhir::ConstArg { kind, span: ty.span }));
+                    } else {
+                        fhir_args.push(fhir::GenericArg::Type(self.desugar_ty(None, ty, env)?));
+                    }
                 }
                 surface::GenericArg::Constraint(ident, ty) => {
                     bindings.push(fhir::TypeBinding {
@@ -892,6 +984,34 @@ impl<'a, 'tcx> RustItemCtxt<'a, 'tcx> {
         Ok((fhir_args, bindings))
     }
 
+    /// A const generic argument is parsed as a type so we look at the resolution of the path to
+    /// check whether it refers to a const generic parameter.
+    fn try_resolve_const_param_arg(&self, ty: &surface::Ty) -> Option<DefId> {
+        if let surface::TyKind::Base(surface::BaseTy {
+            kind: surface::BaseTyKind::Path(path), ..
+        }) = &ty.kind
+            && let Some(Res::Def(DefKind::ConstParam, def_id)) =
+                self.resolver_output.path_res_map.get(&path.node_id)
+        {
+            Some(*def_id)
+        } else {
+            None
+        }
+    }
+
+    fn desugar_array_len(&self, len: &surface::ArrayLen) -> Result<fhir::ConstArg> {
+        let kind = match len.kind {
+            surface::ArrayLenKind::Lit(val) => fhir::ConstArgKind::Lit(val),
+            surface::ArrayLenKind::Param(ident) => {
+                let def_id = self
+                    .resolve_const_param(ident.name)
+                    .ok_or_else(|| self.emit_err(errors::UnresolvedVar::from_ident(ident)))?;
+                fhir::ConstArgKind::Param(def_id)
+            }
+        };
+        Ok(fhir::ConstArg { kind, span: len.span })
+    }
+
     fn desugar_bty_bind(
         &mut self,
         bind: Option<surface::Ident>,
@@ -1025,6 +1145,12 @@ trait DesugarCtxt<'a, 'tcx: 'a> {
     fn genv(&self) -> &'a GlobalEnv<'a, 'tcx>;
     fn next_fhir_id(&self) -> FhirId;
 
+    /// Resolves the name of a const generic parameter in scope
+    fn resolve_const_param(&self, name: Symbol) -> Option<DefId>;
+
+    /// Resolves an associated refinement `<T as Trait>::name` against the trait bounds in scope
+    fn resolve_alias_reft(&self, alias: &surface::AliasReft) -> Result<fhir::AliasReft>;
+
     fn desugar_expr(&self, env: &mut Env, expr: &surface::Expr) -> Result<fhir::Expr> {
         let kind = match &expr.kind {
             surface::ExprKind::QPath(qpath) => {
@@ -1033,6 +1159,7 @@ trait DesugarCtxt<'a, 'tcx: 'a> {
                     QPathRes::Const(const_info) => {
                         fhir::ExprKind::Const(const_info.def_id, qpath.span)
                     }
+                    QPathRes::ConstParam(def_id) => fhir::ExprKind::Const(def_id, qpath.span),
                     QPathRes::NumConst(i) => fhir::ExprKind::Literal(fhir::Lit::Int(i)),
                 }
             }
@@ -1074,6 +1201,11 @@ trait DesugarCtxt<'a, 'tcx: 'a> {
                     }
                 }
             }
+            surface::ExprKind::Alias(alias, args) => {
+                let alias = self.resolve_alias_reft(alias)?;
+                let args = self.desugar_exprs(env, args)?;
+                fhir::ExprKind::Alias(alias, args)
+            }
             surface::ExprKind::IfThenElse(box [p, e1, e2]) => {
                 let p = self.desugar_expr(env, p);
                 let e1 = self.desugar_expr(env, e1);
@@ -1127,6 +1259,9 @@ trait DesugarCtxt<'a, 'tcx: 'a> {
                 if let Some(param) = env.get(*var) {
                     return Ok(QPathRes::Param(fhir::Ident::new(param.name, *var)));
                 }
+                if let Some(def_id) = self.resolve_const_param(var.name) {
+                    return Ok(QPathRes::ConstParam(def_id));
+                }
                 if let Some(const_info) = self.genv().const_by_name(var.name) {
                     return Ok(QPathRes::Const(const_info));
                 }
@@ -1161,6 +1296,68 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> for RustItemCtxt<'a, 'tcx> {
     fn genv(&self) -> &'a GlobalEnv<'a, 'tcx> {
         self.genv
     }
+
+    fn resolve_const_param(&self, name: Symbol) -> Option<DefId> {
+        let tcx = self.genv.tcx;
+        let mut generics = tcx.generics_of(self.owner);
+        loop {
+            for param in &generics.params {
+                if let rustc_middle::ty::GenericParamDefKind::Const { .. } = param.kind
+                    && param.name == name
+                {
+                    return Some(param.def_id);
+                }
+            }
+            generics = tcx.generics_of(generics.parent?);
+        }
+    }
+
+    fn resolve_alias_reft(&self, alias: &surface::AliasReft) -> Result<fhir::AliasReft> {
+        let tcx = self.genv.tcx;
+        let qself = alias.qself.name;
+        let Some(trait_name) = alias.trait_path.segments.last() else {
+            return Err(self.emit_err(errors::UnresolvedAliasReft::new(alias)));
+        };
+
+        // Find the type parameter named `qself`
+        let mut generics = tcx.generics_of(self.owner);
+        let qself_res = loop {
+            let param = generics.params.iter().find(|param| {
+                matches!(param.kind, rustc_middle::ty::GenericParamDefKind::Type { .. })
+                    && param.name == qself
+            });
+            if let Some(param) = param {
+                break if let DefKind::Trait = tcx.def_kind(param.def_id) {
+                    Res::SelfTyParam { trait_: param.def_id }
+                } else {
+                    Res::Def(DefKind::TyParam, param.def_id)
+                };
+            }
+            let Some(parent) = generics.parent else {
+                return Err(self.emit_err(errors::UnresolvedAliasReft::new(alias)));
+            };
+            generics = tcx.generics_of(parent);
+        };
+
+        // Find a bound `qself: Trait` where the name of the trait matches
+        let trait_id = tcx
+            .predicates_of(self.owner)
+            .instantiate_identity(tcx)
+            .predicates
+            .into_iter()
+            .filter_map(|clause| clause.as_trait_clause())
+            .map(|pred| pred.skip_binder())
+            .find(|pred| {
+                matches!(pred.self_ty().kind(), rustc_middle::ty::Param(param) if param.name == qself)
+                    && tcx.item_name(pred.def_id()) == trait_name.name
+            })
+            .map(|pred| pred.def_id());
+        let Some(trait_id) = trait_id else {
+            return Err(self.emit_err(errors::UnresolvedAliasReft::new(alias)));
+        };
+
+        Ok(fhir::AliasReft { qself: qself_res, trait_id, name: alias.name.name, span: alias.span })
+    }
 }
 
 impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> for FluxItemCtxt<'a, 'tcx> {
@@ -1171,6 +1368,14 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> for FluxItemCtxt<'a, 'tcx> {
     fn genv(&self) -> &'a GlobalEnv<'a, 'tcx> {
         self.genv
     }
+
+    fn resolve_const_param(&self, _name: Symbol) -> Option<DefId> {
+        None
+    }
+
+    fn resolve_alias_reft(&self, alias: &surface::AliasReft) -> Result<fhir::AliasReft> {
+        Err(self.emit_err(errors::UnresolvedAliasReft::new(alias)))
+    }
 }
 
 macro_rules! define_resolve_num_const {
@@ -1186,10 +1391,28 @@ macro_rules! define_resolve_num_const {
                         }
                     },
                 )*
+                "isize" | "usize" => resolve_pointer_sized_num_const(typ, name),
                 _ => None
             }
         }
     };
 }
 
-define_resolve_num_const!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+define_resolve_num_const!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+/// The bounds of `isize` and `usize` depend on the pointer width of the target, which may differ
+/// from the one of the host running flux.
+fn resolve_pointer_sized_num_const(
+    typ: surface::Ident,
+    name: surface::Ident,
+) -> Option<QPathRes<'static>> {
+    let bits = flux_config::pointer_width().bits();
+    let n = match (typ.name.as_str(), name.name.as_str()) {
+        ("isize", "MAX") => i128::MAX >> (128 - bits),
+        ("isize", "MIN") => i128::MIN >> (128 - bits),
+        ("usize", "MAX") => (1 << bits) - 1,
+        ("usize", "MIN") => 0,
+        _ => return None,
+    };
+    Some(QPathRes::NumConst(n))
+}
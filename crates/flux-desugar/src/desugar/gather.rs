@@ -281,6 +281,15 @@ impl RustItemCtxt<'_, '_> {
                 }
                 self.gather_params_ty(None, ty, TypePos::Other, env)
             }
+            surface::TyKind::BareFn(inputs, output) => {
+                if let Some(bind) = bind {
+                    env.insert(self.sess(), bind, Param::SyntaxError)?;
+                }
+                inputs
+                    .iter()
+                    .chain([&**output])
+                    .try_for_each_exhaust(|ty| self.gather_params_ty(None, ty, TypePos::Other, env))
+            }
             surface::TyKind::Exists { bind: ex_bind, bty, .. } => {
                 if let Some(bind) = bind {
                     env.insert(self.sess(), bind, Param::SyntaxError)?;
@@ -384,7 +393,7 @@ impl RustItemCtxt<'_, '_> {
     fn gather_params_bty(&self, bty: &surface::BaseTy, pos: TypePos, params: &mut Env) -> Result {
         match &bty.kind {
             surface::BaseTyKind::Path(path) => self.gather_params_path(path, pos, params),
-            surface::BaseTyKind::Slice(ty) => {
+            surface::BaseTyKind::Slice(ty) | surface::BaseTyKind::RawPtr(ty, _) => {
                 self.gather_params_ty(None, ty, TypePos::Other, params)
             }
         }
@@ -182,3 +182,32 @@ impl IllegalBinder {
         Self { span, kind: kind.token_str() }
     }
 }
+
+#[derive(Diagnostic)]
+#[diag(desugar_unresolved_alias_reft, code = "FLUX")]
+pub(super) struct UnresolvedAliasReft {
+    #[primary_span]
+    #[label]
+    span: Span,
+    qself: Symbol,
+    trait_name: String,
+    name: Symbol,
+}
+
+impl UnresolvedAliasReft {
+    pub(super) fn new(alias: &surface::AliasReft) -> Self {
+        Self {
+            span: alias.span,
+            qself: alias.qself.name,
+            trait_name: format!(
+                "{}",
+                alias
+                    .trait_path
+                    .segments
+                    .iter()
+                    .format_with("::", |s, f| f(&s.name))
+            ),
+            name: alias.name.name,
+        }
+    }
+}
@@ -42,7 +42,7 @@ use flux_middle::{
 };
 use flux_syntax::surface;
 use rustc_errors::ErrorGuaranteed;
-use rustc_hir::OwnerId;
+use rustc_hir::{def_id::LocalDefId, OwnerId};
 
 pub fn desugar_struct_def(
     genv: &mut GlobalEnv,
@@ -170,6 +170,39 @@ pub fn desugar_fn_sig(
     Ok(())
 }
 
+pub fn desugar_assoc_refinements(
+    genv: &mut GlobalEnv,
+    owner_id: OwnerId,
+    assoc_refts: &[surface::AssocReft],
+    resolver_output: &ResolverOutput,
+) -> Result<(), ErrorGuaranteed> {
+    let cx = RustItemCtxt::new(genv, owner_id, resolver_output, None);
+    let assoc_refts = cx.desugar_assoc_refinements(assoc_refts)?;
+    genv.map_mut()
+        .insert_assoc_refinements(owner_id.def_id, assoc_refts);
+    Ok(())
+}
+
+pub fn desugar_closure_sig(
+    genv: &mut GlobalEnv,
+    def_id: LocalDefId,
+    fn_sig: &surface::FnSig,
+    resolver_output: &ResolverOutput,
+) -> Result<(), ErrorGuaranteed> {
+    // Closures are not owners, but they have their own signature so we use them as the owner of
+    // the fhir nodes in it.
+    let owner_id = OwnerId { def_id };
+    let fn_sig =
+        RustItemCtxt::new(genv, owner_id, resolver_output, None).desugar_closure_sig(fn_sig)?;
+
+    if config::dump_fhir() {
+        dbg::dump_item_info(genv.tcx, def_id, "fhir", &fn_sig).unwrap();
+    }
+
+    genv.map_mut().insert_fn_sig(def_id, fn_sig);
+    Ok(())
+}
+
 /// HACK(nilehmann) this is a bit of a hack. We use it to properly register generics and predicates
 /// for items that don't have surface syntax (impl blocks, traits, ...), or for `impl` blocks with
 /// explicit `generics` annotations. In the former case, we use `desugar`; in the latter cases we
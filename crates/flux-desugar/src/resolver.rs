@@ -2,7 +2,7 @@ use flux_common::{bug, iter::IterExt};
 use flux_errors::FluxSession;
 use flux_middle::fhir::Res;
 use flux_syntax::surface::{self, BaseTy, BaseTyKind, Ident, Path, Ty};
-use hir::{def::DefKind, ItemId, ItemKind, OwnerId, PathSegment};
+use hir::{def::DefKind, def_id::LocalDefId, ItemId, ItemKind, OwnerId, PathSegment};
 use itertools::Itertools;
 use rustc_data_structures::unord::UnordMap;
 use rustc_errors::ErrorGuaranteed;
@@ -107,6 +107,30 @@ impl<'a, 'tcx> Resolver<'a, 'tcx> {
 
         Ok(())
     }
+
+    pub fn resolve_closure_sig(&mut self, def_id: LocalDefId, fn_sig: &surface::FnSig) -> Result {
+        let table = NameResTable::from_closure(self.tcx, self.sess, def_id)?;
+        let mut item_resolver =
+            ItemLikeResolver { sess: self.sess, table, output: &mut self.output };
+
+        let args = fn_sig
+            .args
+            .iter()
+            .try_for_each_exhaust(|arg| item_resolver.resolve_arg(arg));
+
+        let ensures = fn_sig
+            .ensures
+            .iter()
+            .try_for_each_exhaust(|cstr| item_resolver.resolve_constraint(cstr));
+
+        let returns = item_resolver.resolve_fn_ret_ty(&fn_sig.returns);
+
+        args?;
+        returns?;
+        ensures?;
+
+        Ok(())
+    }
 }
 
 struct ItemLikeResolver<'a> {
@@ -138,19 +162,7 @@ impl<'a> ItemLikeResolver<'a> {
         owner_id: OwnerId,
         output: &'a mut ResolverOutput,
     ) -> Result<Self> {
-        let table = match tcx.hir().owner(owner_id) {
-            hir::OwnerNode::Item(item) => NameResTable::from_item(tcx, sess, item)?,
-            hir::OwnerNode::ImplItem(impl_item) => {
-                NameResTable::from_impl_item(tcx, sess, impl_item)?
-            }
-            hir::OwnerNode::TraitItem(trait_item) => {
-                NameResTable::from_trait_item(tcx, sess, trait_item)?
-            }
-            node @ (hir::OwnerNode::ForeignItem(_) | hir::OwnerNode::Crate(_)) => {
-                bug!("unsupported node {node:?}")
-            }
-        };
-
+        let table = NameResTable::from_owner(tcx, sess, owner_id)?;
         Ok(Self { sess, table, output })
     }
 
@@ -239,6 +251,12 @@ impl<'a> ItemLikeResolver<'a> {
                 tys.iter().try_for_each_exhaust(|ty| self.resolve_ty(ty))
             }
             surface::TyKind::Array(ty, _) => self.resolve_ty(ty),
+            surface::TyKind::BareFn(inputs, output) => {
+                inputs
+                    .iter()
+                    .chain([&**output])
+                    .try_for_each_exhaust(|ty| self.resolve_ty(ty))
+            }
             surface::TyKind::ImplTrait(node_id, bounds) => {
                 self.resolve_bounds(bounds)?;
                 self.resolve_opaque_impl(*node_id, ty.span)
@@ -260,7 +278,7 @@ impl<'a> ItemLikeResolver<'a> {
     fn resolve_bty(&mut self, bty: &BaseTy) -> Result {
         match &bty.kind {
             BaseTyKind::Path(path) => self.resolve_path(path),
-            BaseTyKind::Slice(ty) => self.resolve_ty(ty),
+            BaseTyKind::Slice(ty) | BaseTyKind::RawPtr(ty, _) => self.resolve_ty(ty),
         }
     }
 
@@ -296,6 +314,49 @@ impl<'sess> NameResTable<'sess> {
         NameResTable { sess, opaque: None, res: UnordMap::default() }
     }
 
+    fn from_owner(tcx: TyCtxt, sess: &'sess FluxSession, owner_id: OwnerId) -> Result<Self> {
+        match tcx.hir().owner(owner_id) {
+            hir::OwnerNode::Item(item) => NameResTable::from_item(tcx, sess, item),
+            hir::OwnerNode::ImplItem(impl_item) => {
+                NameResTable::from_impl_item(tcx, sess, impl_item)
+            }
+            hir::OwnerNode::TraitItem(trait_item) => {
+                NameResTable::from_trait_item(tcx, sess, trait_item)
+            }
+            node @ (hir::OwnerNode::ForeignItem(_) | hir::OwnerNode::Crate(_)) => {
+                bug!("unsupported node {node:?}")
+            }
+        }
+    }
+
+    /// Closure parameters are usually not annotated, so besides the names in the closure's
+    /// declaration we also make available the names in scope for the enclosing item and all
+    /// primitive types.
+    fn from_closure(tcx: TyCtxt, sess: &'sess FluxSession, def_id: LocalDefId) -> Result<Self> {
+        let hir_id = tcx.hir().local_def_id_to_hir_id(def_id);
+        let mut table = Self::from_owner(tcx, sess, hir_id.owner)?;
+
+        if let hir::ExprKind::Closure(closure) = tcx.hir().expect_expr(hir_id).kind {
+            closure
+                .fn_decl
+                .inputs
+                .iter()
+                .try_for_each_exhaust(|ty| table.collect_from_ty(ty))?;
+            if let hir::FnRetTy::Return(ty) = closure.fn_decl.output {
+                table.collect_from_ty(ty)?;
+            }
+        }
+
+        for prim_ty in hir::PrimTy::ALL {
+            let key = ResKey { s: prim_ty.name_str().to_string() };
+            if table.get(&key).is_none() {
+                table.insert(key, Res::PrimTy(prim_ty));
+            }
+        }
+
+        Ok(table)
+    }
+
     fn from_item(tcx: TyCtxt, sess: &'sess FluxSession, item: &hir::Item) -> Result<Self> {
         let mut table = Self::new(sess);
         let def_id = item.owner_id.def_id;
@@ -303,7 +364,8 @@ impl<'sess> NameResTable<'sess> {
             ItemKind::TyAlias(ty, _) => {
                 table.collect_from_ty(ty)?;
             }
-            ItemKind::Struct(data, _) => {
+            ItemKind::Struct(data, generics) => {
+                table.collect_const_params(generics);
                 table.insert(
                     ResKey::from_ident(item.ident),
                     Res::Def(DefKind::Struct, def_id.to_def_id()),
@@ -313,7 +375,19 @@ impl<'sess> NameResTable<'sess> {
                     table.collect_from_ty(field.ty)?;
                 }
             }
-            ItemKind::Enum(data, _) => {
+            ItemKind::Union(data, generics) => {
+                table.collect_const_params(generics);
+                table.insert(
+                    ResKey::from_ident(item.ident),
+                    Res::Def(DefKind::Union, def_id.to_def_id()),
+                );
+
+                for field in data.fields() {
+                    table.collect_from_ty(field.ty)?;
+                }
+            }
+            ItemKind::Enum(data, generics) => {
+                table.collect_const_params(generics);
                 table.insert(
                     ResKey::from_ident(item.ident),
                     Res::Def(DefKind::Enum, def_id.to_def_id()),
@@ -326,6 +400,7 @@ impl<'sess> NameResTable<'sess> {
                 }
             }
             ItemKind::Fn(fn_sig, generics, ..) => {
+                table.collect_const_params(generics);
                 table.collect_from_fn_sig(fn_sig)?;
                 table.collect_from_generics(generics)?;
                 table.collect_from_opaque_impls(tcx)?;
@@ -348,9 +423,11 @@ impl<'sess> NameResTable<'sess> {
         if let Some(parent_impl_did) = tcx.impl_of_method(def_id.to_def_id()) {
             let parent_impl_item = tcx.hir().expect_item(parent_impl_did.expect_local());
             if let ItemKind::Impl(parent) = &parent_impl_item.kind {
+                table.collect_const_params(parent.generics);
                 table.collect_from_ty(parent.self_ty)?;
             }
         }
+        table.collect_const_params(impl_item.generics);
 
         match &impl_item.kind {
             rustc_hir::ImplItemKind::Fn(fn_sig, _) => {
@@ -380,6 +457,8 @@ impl<'sess> NameResTable<'sess> {
             }
         }
 
+        table.collect_const_params(trait_item.generics);
+
         match &trait_item.kind {
             rustc_hir::TraitItemKind::Fn(fn_sig, _) => {
                 table.collect_from_fn_sig(fn_sig)?;
@@ -406,6 +485,17 @@ impl<'sess> NameResTable<'sess> {
             .try_for_each_exhaust(|pred| self.collect_from_where_predicate(pred))
     }
 
+    fn collect_const_params(&mut self, generics: &hir::Generics<'_>) {
+        for param in generics.params {
+            if let hir::GenericParamKind::Const { .. } = param.kind {
+                self.insert(
+                    ResKey { s: param.name.ident().name.to_string() },
+                    Res::Def(DefKind::ConstParam, param.def_id.to_def_id()),
+                );
+            }
+        }
+    }
+
     fn collect_from_where_predicate(&mut self, clause: &hir::WherePredicate) -> Result {
         if let hir::WherePredicate::BoundPredicate(bound) = clause {
             self.collect_from_ty(bound.bounded_ty)?;
@@ -547,12 +637,8 @@ impl<'sess> NameResTable<'sess> {
         match arg {
             hir::GenericArg::Type(ty) => self.collect_from_ty(ty),
             hir::GenericArg::Lifetime(_) => Ok(()),
-            hir::GenericArg::Const(_) => {
-                Err(self.sess.emit_err(errors::UnsupportedSignature::new(
-                    arg.span(),
-                    "const generics are not supported yet",
-                )))
-            }
+            // Const params used as arguments are made available through `collect_const_params`
+            hir::GenericArg::Const(_) => Ok(()),
 
             hir::GenericArg::Infer(_) => unreachable!(),
         }
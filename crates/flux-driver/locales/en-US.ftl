@@ -25,8 +25,23 @@ driver_missing_variant =
     .label = this variant doesn't have a refinement annotation
     .note = all variants in a refined enum must be annotated
 
+driver_opaque_union =
+    unions can't be opaque
+    .note = the type of each field of a union is needed to check accesses to it
+
 driver_malformed_extern_spec =
     malformed extern_spec, this should never happen if you are using the extern_spec macro. Did you accidentally use the internal flux::extern_spec attribute?
 
 driver_missing_fn_sig_for_extern_spec =
     missing flux::sig attribute (functions declared as flux::extern_spec require a flux::sig)
+
+driver_unsupported_summary =
+    {$count ->
+        [one] 1 function was
+        *[other] {$count} functions were
+    } not checked because of unsupported features
+    .note = skipped: {$names}
+
+driver_pointer_width_mismatch =
+    the configured pointer width ({$pointer_width}) differs from the pointer width of the target `{$target}` ({$target_pointer_width})
+    .note = `usize` and `isize` are checked assuming a pointer width of {$pointer_width}
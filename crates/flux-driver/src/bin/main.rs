@@ -7,11 +7,11 @@ use std::{
     io::{self, Read as _},
     ops::Deref,
     path::{Path, PathBuf},
-    process::exit,
+    process::{self, exit},
 };
 
 use flux_driver::callbacks::FluxCallbacks;
-use rustc_driver::{catch_with_exit_code, RunCompiler};
+use rustc_driver::{catch_with_exit_code, RunCompiler, EXIT_FAILURE};
 
 mod logger;
 
@@ -45,6 +45,21 @@ fn main() -> io::Result<()> {
         }
     }
 
+    let print_metadata = match take_print_metadata(&mut args)
+        .as_deref()
+        .map(PrintMetadata::new)
+        .transpose()
+    {
+        Ok(print_metadata) => print_metadata,
+        Err(err) => {
+            eprintln!("error: {err}");
+            exit(EXIT_FAILURE)
+        }
+    };
+    if let Some(print_metadata) = &print_metadata {
+        args.extend(print_metadata.args.iter().cloned());
+    }
+
     args.push("--sysroot".into());
     args.push(sysroot().expect("Flux Rust requires rustup to be built."));
     args.push("-Coverflow-checks=off".to_string());
@@ -52,12 +67,23 @@ fn main() -> io::Result<()> {
     args.push("-Zcrate-attr=register_tool(flux)".to_string());
     args.push("-Zcrate-attr=register_tool(flux_tool)".to_string());
     args.push("--cfg=flux".to_string());
+    let verify = context.verify() && print_metadata.is_none();
+    if verify {
+        args.extend(std_specs_args(&original_args));
+    }
 
-    let mut callbacks =
-        FluxCallbacks { full_compilation: context.full_compilation(), verify: context.verify() };
+    let mut callbacks = FluxCallbacks {
+        full_compilation: context.full_compilation(),
+        verify,
+        print_metadata: print_metadata
+            .as_ref()
+            .map(|print_metadata| print_metadata.crate_name.clone()),
+    };
 
     let exit_code = catch_with_exit_code(move || RunCompiler::new(&args, &mut callbacks).run());
     resolve_logs()?;
+    // `exit` doesn't run destructors
+    drop(print_metadata);
     exit(exit_code)
 }
 
@@ -69,6 +95,109 @@ fn sysroot() -> Option<String> {
     Some(format!("{home}/toolchains/{toolchain}"))
 }
 
+const STD_SPECS_CRATE: &str = "flux_std_specs";
+
+/// Arguments to force loading the precompiled specs for the standard library when they are
+/// available. We don't load them when compiling the specs themselves.
+fn std_specs_args(args: &[String]) -> Vec<String> {
+    // CODESYNC(std-specs-env) we must use the same env var in flux-bin
+    let Ok(path) = env::var("FLUX_STD_SPECS") else { return vec![] };
+    if arg_value(args, "--crate-name", |name| name == STD_SPECS_CRATE).is_some() {
+        return vec![];
+    }
+    let path = PathBuf::from(path);
+    let mut args = vec![
+        "-Zunstable-options".to_string(),
+        format!("--extern=force:{STD_SPECS_CRATE}={}", path.display()),
+    ];
+    if let Some(dir) = path.parent() {
+        args.push(format!("-Ldependency={}", dir.display()));
+    }
+    args
+}
+
+/// Removes `--print-metadata <file>` from the arguments returning the file if present.
+fn take_print_metadata(args: &mut Vec<String>) -> Option<PathBuf> {
+    let i = args.iter().position(|arg| arg == "--print-metadata")?;
+    args.remove(i);
+    (i < args.len()).then(|| PathBuf::from(args.remove(i)))
+}
+
+/// The name of the crate a flux metadata file belongs to. Metadata files are named like the
+/// `.rmeta` file of the crate, i.e., `lib<crate name>-<hash>.fluxmeta`.
+fn crate_name_for_metadata(path: &Path) -> io::Result<&str> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("lib"))
+        .and_then(|name| name.strip_suffix(".fluxmeta"))
+        .map(|name| name.split_once('-').map_or(name, |(name, _)| name))
+        .filter(|name| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected a file named `lib<crate name>-<hash>.fluxmeta`, found `{}`",
+                    path.display()
+                ),
+            )
+        })
+}
+
+/// A dummy crate depending on the crate of a metadata file, such that its flux metadata is loaded
+/// and can be printed. The crate is written to a temporary directory which is removed when this is
+/// dropped.
+struct PrintMetadata {
+    crate_name: String,
+    dir: PathBuf,
+    /// Arguments to compile the dummy crate
+    args: Vec<String>,
+}
+
+impl PrintMetadata {
+    fn new(path: &Path) -> io::Result<PrintMetadata> {
+        let crate_name = crate_name_for_metadata(path)?.to_string();
+        let Some(rmeta) = ["rmeta", "rlib"]
+            .into_iter()
+            .map(|ext| path.with_extension(ext))
+            .find(|rmeta| rmeta.is_file())
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no `.rmeta` or `.rlib` file next to `{}`", path.display()),
+            ));
+        };
+
+        let dir = env::temp_dir().join(format!("flux-print-metadata-{}", process::id()));
+        fs::create_dir_all(&dir)?;
+        let mut print_metadata = PrintMetadata { crate_name, dir, args: vec![] };
+        let src = print_metadata.dir.join("lib.rs");
+        fs::write(&src, format!("extern crate {};\n", print_metadata.crate_name))?;
+
+        print_metadata.args = vec![
+            src.display().to_string(),
+            "--crate-type=lib".to_string(),
+            "--crate-name=flux_print_metadata".to_string(),
+            "--edition=2021".to_string(),
+            format!("--extern={}={}", print_metadata.crate_name, rmeta.display()),
+            format!("--out-dir={}", print_metadata.dir.display()),
+        ];
+        if let Some(deps) = path.parent() {
+            print_metadata
+                .args
+                .push(format!("-Ldependency={}", deps.display()));
+        }
+        Ok(print_metadata)
+    }
+}
+
+impl Drop for PrintMetadata {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
 /// If a command-line option matches `find_arg`, then apply the predicate `pred` on its value. If
 /// true, then return it. The parameter is assumed to be either `--arg=value` or `--arg value`.
 pub fn arg_value<'a, T: Deref<Target = str>>(
@@ -100,22 +229,28 @@ impl FluxMetadata {
         let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") else {
             return None;
         };
-        let manifest_dir = PathBuf::from(manifest_dir);
-        let manifest = FluxMetadata::read_manifest(&manifest_dir);
-        let enabled = manifest
-            .get("package")
-            .and_then(|package| package.get("metadata"))
-            .and_then(|metadata| metadata.get("flux"))
-            .and_then(|flux| flux.get("enabled"))
-            .and_then(toml::Value::as_bool)
-            .unwrap_or(false);
-        Some(FluxMetadata { enabled })
-    }
-
-    fn read_manifest(manifest_dir: &Path) -> toml::Value {
-        let manifest_path = manifest_dir.join("Cargo.toml");
+        // When called from cargo-flux, the package config already merges the workspace and
+        // package metadata. Otherwise (e.g., for packages outside the workspace) we read the
+        // manifest directly.
+        let enabled = if let Some(config_path) = &*flux_config::PACKAGE_CONFIG_PATH {
+            FluxMetadata::read_toml(config_path)
+                .get("enabled")
+                .and_then(toml::Value::as_bool)
+        } else {
+            let manifest = FluxMetadata::read_toml(&PathBuf::from(manifest_dir).join("Cargo.toml"));
+            manifest
+                .get("package")
+                .and_then(|package| package.get("metadata"))
+                .and_then(|metadata| metadata.get("flux"))
+                .and_then(|flux| flux.get("enabled"))
+                .and_then(toml::Value::as_bool)
+        };
+        Some(FluxMetadata { enabled: enabled.unwrap_or(false) })
+    }
+
+    fn read_toml(path: &Path) -> toml::Value {
         let mut contents = String::new();
-        let mut file = fs::File::open(manifest_path).unwrap();
+        let mut file = fs::File::open(path).unwrap();
         file.read_to_string(&mut contents).unwrap();
         toml::from_str(&contents).unwrap()
     }
@@ -164,3 +299,21 @@ impl Context {
         matches!(self, Context::CargoFlux { .. })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crate_name_from_metadata_file() {
+        fn crate_name(path: &str) -> Option<&str> {
+            crate_name_for_metadata(Path::new(path)).ok()
+        }
+        assert_eq!(crate_name("target/debug/deps/libmy_lib-0123abcd.fluxmeta"), Some("my_lib"));
+        assert_eq!(crate_name("libmy_lib.fluxmeta"), Some("my_lib"));
+        assert_eq!(crate_name("my_lib-0123abcd.fluxmeta"), None);
+        assert_eq!(crate_name("libmy_lib-0123abcd.rmeta"), None);
+        assert_eq!(crate_name("lib-0123abcd.fluxmeta"), None);
+        assert_eq!(crate_name("libmy.lib.fluxmeta"), None);
+    }
+}
@@ -1,6 +1,11 @@
-use config::CrateConfig;
+use std::{
+    iter,
+    time::{Duration, Instant},
+};
+
+use config::{CrateConfig, ItemConfig, PointerWidth};
 use desugar::resolver::{Resolver, ResolverOutput};
-use flux_common::{cache::QueryCache, dbg, iter::IterExt};
+use flux_common::{bug, cache::QueryCache, dbg, iter::IterExt};
 use flux_config as config;
 use flux_desugar as desugar;
 use flux_errors::{FluxSession, ResultExt};
@@ -10,17 +15,21 @@ use flux_middle::{
     global_env::GlobalEnv,
 };
 use flux_refineck as refineck;
-use refineck::CheckerConfig;
+use itertools::Itertools;
+use refineck::{CheckOutcome, CheckerConfig};
 use rustc_borrowck::consumers::ConsumerOptions;
 use rustc_driver::{Callbacks, Compilation};
 use rustc_errors::ErrorGuaranteed;
+use rustc_hash::FxHashMap;
 use rustc_hir::{self as hir, def::DefKind, def_id::LocalDefId, OwnerId};
 use rustc_interface::{interface::Compiler, Queries};
 use rustc_middle::{query, ty::TyCtxt};
 use rustc_session::config::OutputType;
+use rustc_span::Symbol;
 
 use crate::{
     collector::{IgnoreKey, Ignores, SpecCollector, Specs},
+    summary::Summary,
     DEFAULT_LOCALE_RESOURCES,
 };
 
@@ -28,6 +37,8 @@ use crate::{
 pub struct FluxCallbacks {
     pub full_compilation: bool,
     pub verify: bool,
+    /// Print the flux metadata of this extern crate instead of verifying
+    pub print_metadata: Option<String>,
 }
 
 impl Callbacks for FluxCallbacks {
@@ -37,6 +48,26 @@ impl Callbacks for FluxCallbacks {
         config.override_queries = Some(|_, local| {
             local.mir_borrowck = mir_borrowck;
         });
+
+        // Cargo doesn't know about the flux configuration, so we add it to the dep-info of the
+        // crate for cargo to check the crate again when the configuration changes. The config of
+        // the package is tracked even if the crate is not verified because it may enable it.
+        let verify = self.verify;
+        config.parse_sess_created = Some(Box::new(move |parse_sess| {
+            let file_depinfo = parse_sess.file_depinfo.get_mut();
+            if let Some(path) = &*config::PACKAGE_CONFIG_PATH {
+                file_depinfo.insert(Symbol::intern(&path.to_string_lossy()));
+            }
+            if verify {
+                if let Some(path) = &*config::CONFIG_PATH {
+                    file_depinfo.insert(Symbol::intern(&path.to_string_lossy()));
+                }
+                let env_depinfo = parse_sess.env_depinfo.get_mut();
+                for (var, val) in config::env_vars() {
+                    env_depinfo.insert((Symbol::intern(&var), val.as_deref().map(Symbol::intern)));
+                }
+            }
+        }));
     }
 
     fn after_analysis<'tcx>(
@@ -44,6 +75,11 @@ impl Callbacks for FluxCallbacks {
         compiler: &Compiler,
         queries: &'tcx Queries<'tcx>,
     ) -> Compilation {
+        if let Some(crate_name) = &self.print_metadata {
+            Self::print_metadata(queries, crate_name);
+            return Compilation::Stop;
+        }
+
         if self.verify {
             self.verify(compiler, queries);
         }
@@ -68,24 +104,42 @@ impl FluxCallbacks {
         }
 
         queries.global_ctxt().unwrap().enter(|tcx| {
-            let sess = FluxSession::new(
-                &tcx.sess.opts,
-                tcx.sess.parse_sess.clone_source_map(),
-                rustc_errors::fallback_fluent_bundle(DEFAULT_LOCALE_RESOURCES.to_vec(), false),
-            );
+            let sess = flux_session(tcx);
             let _ = check_crate(tcx, &sess);
             sess.finish_diagnostics();
         });
     }
+
+    fn print_metadata<'tcx>(queries: &'tcx Queries<'tcx>, crate_name: &str) {
+        queries.global_ctxt().unwrap().enter(|tcx| {
+            let sess = flux_session(tcx);
+            flux_metadata::print_metadata(tcx, &sess, Symbol::intern(crate_name));
+            sess.finish_diagnostics();
+        });
+    }
+}
+
+fn flux_session(tcx: TyCtxt) -> FluxSession {
+    FluxSession::new(
+        &tcx.sess.opts,
+        tcx.sess.parse_sess.clone_source_map(),
+        rustc_errors::fallback_fluent_bundle(DEFAULT_LOCALE_RESOURCES.to_vec(), false),
+    )
 }
 
 fn check_crate(tcx: TyCtxt, sess: &FluxSession) -> Result<(), ErrorGuaranteed> {
     tracing::info_span!("check_crate").in_scope(|| {
+        Summary::save_crate_error(tcx).unwrap_or(());
+        set_pointer_width(tcx, sess);
         let cstore = CStore::load(tcx, sess);
         let mut specs = SpecCollector::collect(tcx, sess)?;
 
         // Ignore everything and go home
         if specs.ignores.contains(&IgnoreKey::Crate) {
+            // Clear the results of a previous run
+            Summary::new(Default::default(), Default::default())
+                .save(tcx)
+                .unwrap_or(());
             return Ok(());
         }
 
@@ -95,13 +149,22 @@ fn check_crate(tcx: TyCtxt, sess: &FluxSession) -> Result<(), ErrorGuaranteed> {
 
         stage1_desugar(&mut genv, &specs)?;
         let resolver_output = resolve_crate(tcx, sess, &specs)?;
+        // Computed before `stage2_desugar` which consumes the function specs
+        let with_sig = specs
+            .fn_sigs
+            .items()
+            .filter_map(|(owner_id, spec)| spec.fn_sig.as_ref().map(|_| owner_id.def_id))
+            .collect();
+        let extern_specs = specs.extern_specs.values().copied().collect();
+        let summary = Summary::new(with_sig, extern_specs);
         stage2_desugar(&mut genv, &mut specs, &resolver_output)?;
 
         flux_fhir_analysis::check_crate_wf(&genv)?;
 
         tracing::info!("Callbacks::check_wf");
 
-        let mut ck = CrateChecker::new(&genv, specs.ignores, specs.crate_config);
+        let mut ck =
+            CrateChecker::new(&genv, specs.ignores, specs.crate_config, specs.configs, summary);
 
         let crate_items = tcx.hir_crate_items(());
         let items = crate_items.items().map(|item| item.owner_id.def_id);
@@ -109,11 +172,18 @@ fn check_crate(tcx: TyCtxt, sess: &FluxSession) -> Result<(), ErrorGuaranteed> {
             .impl_items()
             .map(|impl_item| impl_item.owner_id.def_id);
 
-        let result = items
-            .chain(impl_items)
+        let def_ids = items.chain(impl_items).collect_vec();
+        if ck.checker_config.infer_signatures {
+            ck.infer_fn_sigs(&def_ids);
+        }
+
+        let result = def_ids
+            .into_iter()
             .try_for_each_exhaust(|def_id| ck.check_def(def_id));
 
         ck.cache.save().unwrap_or(());
+        ck.report_unsupported();
+        ck.summary.save(tcx).unwrap_or(());
 
         tracing::info!("Callbacks::check_crate");
 
@@ -198,7 +268,9 @@ fn resolve_crate(
         .try_for_each_exhaust(|id| {
             match tcx.def_kind(id) {
                 DefKind::Struct => resolver.resolve_struct_def(id, &specs.structs[&id])?,
-                DefKind::Enum => resolver.resolve_enum_def(id, &specs.enums[&id])?,
+                DefKind::Enum | DefKind::Union => {
+                    resolver.resolve_enum_def(id, &specs.enums[&id])?;
+                }
                 DefKind::TyAlias { .. } => {
                     if let Some(type_alias) = &specs.ty_aliases[&id] {
                         resolver.resolve_type_alias(id, type_alias)?;
@@ -214,6 +286,11 @@ fn resolve_crate(
             Ok(())
         })?;
 
+    specs
+        .closure_sigs
+        .iter()
+        .try_for_each_exhaust(|(def_id, fn_sig)| resolver.resolve_closure_sig(*def_id, fn_sig))?;
+
     Ok(resolver.into_output())
 }
 
@@ -257,6 +334,15 @@ fn stage2_desugar(
         .err()
         .or(err);
 
+    // Closures
+    err = std::mem::take(&mut specs.closure_sigs)
+        .into_iter()
+        .try_for_each_exhaust(|(def_id, fn_sig)| {
+            desugar::desugar_closure_sig(genv, def_id, &fn_sig, resolver_output)
+        })
+        .err()
+        .or(err);
+
     if let Some(err) = err {
         Err(err)
     } else {
@@ -283,7 +369,7 @@ fn desugar_item(
         hir::ItemKind::OpaqueTy(_) => {
             desugar::desugar_generics_and_predicates(genv, owner_id, resolver_output, None)?;
         }
-        hir::ItemKind::Enum(..) => {
+        hir::ItemKind::Enum(..) | hir::ItemKind::Union(..) => {
             let enum_def = &specs.enums[&owner_id];
             desugar::desugar_enum_def(genv, owner_id, enum_def, resolver_output)?;
         }
@@ -293,6 +379,9 @@ fn desugar_item(
         }
         hir::ItemKind::Trait(.., items) => {
             desugar::desugar_generics_and_predicates(genv, owner_id, resolver_output, None)?;
+            if let Some(assoc_refts) = specs.assoc_refts.get(&owner_id) {
+                desugar::desugar_assoc_refinements(genv, owner_id, assoc_refts, resolver_output)?;
+            }
             items.iter().try_for_each_exhaust(|trait_item| {
                 desugar_assoc_item(
                     genv,
@@ -306,6 +395,9 @@ fn desugar_item(
         hir::ItemKind::Impl(impl_) => {
             let generics = specs.impls.get(&owner_id);
             desugar::desugar_generics_and_predicates(genv, owner_id, resolver_output, generics)?;
+            if let Some(assoc_refts) = specs.assoc_refts.get(&owner_id) {
+                desugar::desugar_assoc_refinements(genv, owner_id, assoc_refts, resolver_output)?;
+            }
             impl_.items.iter().try_for_each_exhaust(|impl_item| {
                 desugar_assoc_item(
                     genv,
@@ -358,6 +450,28 @@ fn desugar_fn_sig(
     Ok(())
 }
 
+/// Sets the pointer width used for `usize` and `isize` to the one of the compilation target, warning
+/// if the configuration overrides it with a different one.
+fn set_pointer_width(tcx: TyCtxt, sess: &FluxSession) {
+    let target = &tcx.sess.target;
+    let target_pointer_width = match target.pointer_width {
+        16 => PointerWidth::W16,
+        32 => PointerWidth::W32,
+        64 => PointerWidth::W64,
+        bits => bug!("unsupported pointer width `{bits}`"),
+    };
+    if let Some(pointer_width) = config::pointer_width_override()
+        && pointer_width != target_pointer_width
+    {
+        sess.emit_warning(errors::PointerWidthMismatch {
+            pointer_width: pointer_width.bits(),
+            target: target.llvm_target.to_string(),
+            target_pointer_width: target_pointer_width.bits(),
+        });
+    }
+    config::set_target_pointer_width(target_pointer_width);
+}
+
 fn save_metadata(genv: &GlobalEnv) {
     let tcx = genv.tcx;
     if tcx
@@ -375,7 +489,16 @@ struct CrateChecker<'a, 'genv, 'tcx> {
     genv: &'a GlobalEnv<'genv, 'tcx>,
     ignores: Ignores,
     cache: QueryCache,
+    /// Configuration for the entire crate. Items use it overridden by [`CrateChecker::configs`],
+    /// see [`CrateChecker::checker_config`].
     checker_config: CheckerConfig,
+    /// Configuration given with `#[flux::cfg]` on modules, impls and functions
+    configs: FxHashMap<LocalDefId, ItemConfig>,
+    /// Functions skipped because they use unsupported features
+    unsupported: Vec<LocalDefId>,
+    /// Outcome of the functions already checked while inferring signatures
+    inferred: FxHashMap<LocalDefId, Result<CheckOutcome, ErrorGuaranteed>>,
+    summary: Summary,
 }
 
 impl<'a, 'genv, 'tcx> CrateChecker<'a, 'genv, 'tcx> {
@@ -383,13 +506,47 @@ impl<'a, 'genv, 'tcx> CrateChecker<'a, 'genv, 'tcx> {
         genv: &'a GlobalEnv<'genv, 'tcx>,
         ignores: Ignores,
         crate_config: Option<CrateConfig>,
+        configs: FxHashMap<LocalDefId, ItemConfig>,
+        summary: Summary,
     ) -> Self {
         let crate_config = crate_config.unwrap_or_default();
         let checker_config = CheckerConfig {
             check_overflow: crate_config.check_overflow,
             scrape_quals: crate_config.scrape_quals,
+            skip_unsupported: crate_config.skip_unsupported,
+            check_vacuity: crate_config.check_vacuity,
+            suggest_preconditions: crate_config.suggest_preconditions,
+            infer_signatures: crate_config.infer_signatures,
+            check_raw_pointers: crate_config.check_raw_pointers,
+            solver_deadline: config::solver_total_timeout().map(|timeout| Instant::now() + timeout),
+            solver_timeout: crate_config.solver_timeout.map(Duration::from_secs),
+            kvar_encoding: crate_config.kvar_encoding,
+            verbose: crate_config.verbose,
         };
-        CrateChecker { genv, ignores, cache: QueryCache::load(), checker_config }
+        CrateChecker {
+            genv,
+            ignores,
+            cache: QueryCache::load(),
+            checker_config,
+            configs,
+            unsupported: vec![],
+            inferred: FxHashMap::default(),
+            summary,
+        }
+    }
+
+    fn report_unsupported(&self) {
+        if self.unsupported.is_empty() {
+            return;
+        }
+        let names = self
+            .unsupported
+            .iter()
+            .map(|def_id| format!("`{}`", self.genv.tcx.def_path_str(def_id.to_def_id())))
+            .join(", ");
+        self.genv
+            .sess
+            .emit_warning(errors::UnsupportedSummary { count: self.unsupported.len(), names });
     }
 
     /// `is_ignored` transitively follows the `def_id`'s parent-chain to check if
@@ -408,21 +565,97 @@ impl<'a, 'genv, 'tcx> CrateChecker<'a, 'genv, 'tcx> {
         }
     }
 
+    /// Returns the configuration used to check `def_id`. Settings given with `#[flux::cfg]` are
+    /// inherited following the chain of parents of `def_id`, i.e., a function takes the settings
+    /// it doesn't set from its enclosing impl (if any), then from the enclosing modules, and
+    /// finally from the crate. Unlike [`CrateChecker::is_ignored`] we cannot use the module chain
+    /// directly because it skips impls.
+    fn checker_config(&self, def_id: LocalDefId) -> CheckerConfig {
+        let mut item_config = ItemConfig::default();
+        let mut next = Some(def_id);
+        while let Some(def_id) = next {
+            if let Some(parent_config) = self.configs.get(&def_id) {
+                item_config.inherit(parent_config);
+            }
+            next = self.genv.tcx.opt_local_parent(def_id);
+        }
+
+        let mut checker_config = self.checker_config;
+        if let Some(check_overflow) = item_config.check_overflow {
+            checker_config.check_overflow = check_overflow;
+        }
+        if let Some(scrape_quals) = item_config.scrape_quals {
+            checker_config.scrape_quals = scrape_quals;
+        }
+        if let Some(solver_timeout) = item_config.solver_timeout {
+            checker_config.solver_timeout = Some(Duration::from_secs(solver_timeout));
+        }
+        if let Some(kvar_encoding) = item_config.kvar_encoding {
+            checker_config.kvar_encoding = kvar_encoding;
+        }
+        if let Some(verbose) = item_config.verbose {
+            checker_config.verbose = verbose;
+        }
+        checker_config
+    }
+
+    /// Whether `def_id` or any of its parents has a `#[flux::cfg]`
+    fn has_item_config(&self, def_id: LocalDefId) -> bool {
+        iter::successors(Some(def_id), |def_id| self.genv.tcx.opt_local_parent(*def_id))
+            .any(|def_id| self.configs.contains_key(&def_id))
+    }
+
     fn matches_check_def(&self, def_id: LocalDefId) -> bool {
         let def_path = self.genv.tcx.def_path_str(def_id.to_def_id());
         def_path.contains(config::check_def())
     }
 
+    /// Infers signatures for the functions in `def_ids` that are checked, see
+    /// [`refineck::inference`]. The functions are solved together with the configuration of the
+    /// crate, so functions affected by a `#[flux::cfg]` are left out and checked on their own
+    /// with their configuration. In particular, their callees don't get an inferred signature.
+    fn infer_fn_sigs(&mut self, def_ids: &[LocalDefId]) {
+        let fns = def_ids
+            .iter()
+            .copied()
+            .filter(|def_id| {
+                matches!(self.genv.tcx.def_kind(*def_id), DefKind::Fn | DefKind::AssocFn)
+                    && self.matches_check_def(*def_id)
+                    && !self.is_ignored(*def_id)
+                    && !self.has_item_config(*def_id)
+            })
+            .collect_vec();
+        self.inferred = refineck::inference::infer_fn_sigs(self.genv, &fns, self.checker_config)
+            .into_iter()
+            .collect();
+    }
+
     fn check_def(&mut self, def_id: LocalDefId) -> Result<(), ErrorGuaranteed> {
-        if self.is_ignored(def_id) || !self.matches_check_def(def_id) {
+        if !self.matches_check_def(def_id) {
+            return Ok(());
+        }
+        let def_kind = self.genv.tcx.def_kind(def_id);
+        if self.is_ignored(def_id) {
+            if let DefKind::Fn | DefKind::AssocFn = def_kind {
+                self.summary.record_ignored(self.genv.tcx, def_id);
+            }
             return Ok(());
         }
 
-        match self.genv.tcx.def_kind(def_id) {
+        let checker_config = self.checker_config(def_id);
+        match def_kind {
             DefKind::Fn | DefKind::AssocFn => {
-                refineck::check_fn(self.genv, &mut self.cache, def_id, self.checker_config)
+                let result = match self.inferred.remove(&def_id) {
+                    Some(result) => result,
+                    None => refineck::check_fn(self.genv, &mut self.cache, def_id, checker_config),
+                };
+                self.summary.record_checked(self.genv.tcx, def_id, &result);
+                if let CheckOutcome::Unsupported { .. } = result? {
+                    self.unsupported.push(def_id);
+                }
+                Ok(())
             }
-            DefKind::Enum => {
+            DefKind::Enum | DefKind::Union => {
                 let adt_def = self.genv.adt_def(def_id.to_def_id()).emit(self.genv.sess)?;
                 let enum_def = self.genv.map().get_enum(def_id);
                 refineck::invariants::check_invariants(
@@ -431,7 +664,7 @@ impl<'a, 'genv, 'tcx> CrateChecker<'a, 'genv, 'tcx> {
                     def_id,
                     &enum_def.invariants,
                     &adt_def,
-                    self.checker_config,
+                    checker_config,
                 )
             }
             DefKind::Struct => {
@@ -446,7 +679,7 @@ impl<'a, 'genv, 'tcx> CrateChecker<'a, 'genv, 'tcx> {
                     def_id,
                     &struct_def.invariants,
                     &adt_def,
-                    self.checker_config,
+                    checker_config,
                 )
             }
             _ => Ok(()),
@@ -454,7 +687,7 @@ impl<'a, 'genv, 'tcx> CrateChecker<'a, 'genv, 'tcx> {
     }
 }
 
-fn def_id_symbol(tcx: TyCtxt, def_id: LocalDefId) -> rustc_span::Symbol {
+fn def_id_symbol(tcx: TyCtxt, def_id: LocalDefId) -> Symbol {
     let did = def_id.to_def_id();
     // TODO(RJ) use fully qualified names: Symbol::intern(&tcx.def_path_str(did))
     let def_path = tcx.def_path(did);
@@ -497,3 +730,24 @@ fn mir_borrowck<'tcx>(
     let original_mir_borrowck = providers.mir_borrowck;
     original_mir_borrowck(tcx, def_id)
 }
+
+mod errors {
+    use flux_macros::Diagnostic;
+
+    #[derive(Diagnostic)]
+    #[diag(driver_unsupported_summary, code = "FLUX")]
+    #[note]
+    pub struct UnsupportedSummary {
+        pub count: usize,
+        pub names: String,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(driver_pointer_width_mismatch, code = "FLUX")]
+    #[note]
+    pub struct PointerWidthMismatch {
+        pub pointer_width: u64,
+        pub target: String,
+        pub target_pointer_width: u64,
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use flux_common::iter::IterExt;
-use flux_config::{self as config, CrateConfig};
+use flux_config::{self as config, CrateConfig, ItemConfig, KVarEncoding};
 use flux_errors::{FluxSession, ResultExt};
 use flux_middle::{const_eval::scalar_int_to_rty_constant, rty::Constant};
 use flux_syntax::{surface, ParseResult, ParseSess};
@@ -13,11 +13,15 @@ use rustc_data_structures::unord::{UnordMap, UnordSet};
 use rustc_errors::{ErrorGuaranteed, IntoDiagnostic};
 use rustc_hash::FxHashMap;
 use rustc_hir::{
+    def::DefKind,
     def_id::{DefId, LocalDefId},
-    EnumDef, ImplItemKind, Item, ItemKind, OwnerId, VariantData,
+    EnumDef, ImplItemKind, Item, ItemKind, Node, OwnerId, VariantData,
 };
 use rustc_middle::ty::{ScalarInt, TyCtxt};
-use rustc_span::{Span, Symbol, SyntaxContext};
+use rustc_span::{
+    hygiene::{ExpnKind, MacroKind},
+    Span, Symbol, SyntaxContext,
+};
 
 pub(crate) struct SpecCollector<'tcx, 'a> {
     tcx: TyCtxt<'tcx>,
@@ -25,6 +29,9 @@ pub(crate) struct SpecCollector<'tcx, 'a> {
     specs: Specs,
     sess: &'a FluxSession,
     error_guaranteed: Option<ErrorGuaranteed>,
+    /// Types annotated with `#[flux::trusted_derives]` mapped to the derives that should be
+    /// trusted, or [`None`] if all of them should.
+    trusted_derives: FxHashMap<LocalDefId, Option<Vec<Symbol>>>,
 }
 
 #[derive(PartialEq, Eq, Hash)]
@@ -40,8 +47,11 @@ pub type Ignores = UnordSet<IgnoreKey>;
 
 pub(crate) struct Specs {
     pub fn_sigs: UnordMap<OwnerId, FnSpec>,
+    pub closure_sigs: FxHashMap<LocalDefId, surface::FnSig>,
     pub structs: FxHashMap<OwnerId, surface::StructDef>,
     pub impls: FxHashMap<OwnerId, surface::Generics>,
+    /// Associated refinements declared in traits or defined in impls
+    pub assoc_refts: FxHashMap<OwnerId, Vec<surface::AssocReft>>,
     pub enums: FxHashMap<OwnerId, surface::EnumDef>,
     pub qualifs: Vec<surface::Qualifier>,
     pub func_defs: Vec<surface::FuncDef>,
@@ -50,6 +60,8 @@ pub(crate) struct Specs {
     pub ignores: Ignores,
     pub consts: FxHashMap<LocalDefId, ConstSig>,
     pub crate_config: Option<config::CrateConfig>,
+    /// Configuration given with `#[flux::cfg]` on modules, impls and functions
+    pub configs: FxHashMap<LocalDefId, config::ItemConfig>,
     pub extern_specs: FxHashMap<DefId, LocalDefId>,
 }
 
@@ -84,6 +96,7 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
             sess,
             specs: Specs::new(),
             error_guaranteed: None,
+            trusted_derives: FxHashMap::default(),
         };
 
         collector.parse_crate_spec(tcx.hir().krate_attrs())?;
@@ -99,9 +112,11 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
                 ItemKind::Fn(..) => collector.parse_fn_spec(owner_id, attrs),
                 ItemKind::Struct(data, ..) => collector.parse_struct_def(owner_id, attrs, data),
                 ItemKind::Enum(def, ..) => collector.parse_enum_def(owner_id, attrs, def),
+                ItemKind::Union(data, ..) => collector.parse_union_def(owner_id, attrs, data),
                 ItemKind::Mod(..) => collector.parse_mod_spec(owner_id.def_id, attrs),
                 ItemKind::TyAlias(..) => collector.parse_tyalias_spec(owner_id, attrs),
                 ItemKind::Const(..) => collector.parse_const_spec(item, attrs),
+                ItemKind::Trait(..) => collector.parse_trait_spec(owner_id, attrs),
                 ItemKind::Impl(_) => collector.parse_impl_spec(owner_id, attrs),
                 _ => Ok(()),
             };
@@ -121,6 +136,17 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
                 let hir_id = impl_item.hir_id();
                 let attrs = tcx.hir().attrs(hir_id);
                 let _ = collector.parse_fn_spec(owner_id, attrs);
+                if collector.is_trusted_derive(owner_id.def_id)
+                    && let Some(fn_spec) = collector.specs.fn_sigs.get_mut(&owner_id)
+                {
+                    fn_spec.trusted = true;
+                }
+            }
+        }
+
+        for def_id in tcx.hir().body_owners() {
+            if let DefKind::Closure = tcx.def_kind(def_id) {
+                let _ = collector.parse_closure_spec(def_id);
             }
         }
 
@@ -142,8 +168,9 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
 
         self.specs.extend_items(attrs.items());
 
-        let crate_config = attrs.crate_config();
-        self.specs.crate_config = crate_config;
+        if let Some(mut cfg) = attrs.cfg() {
+            self.specs.crate_config = Some(cfg.try_into_crate_cfg().emit(self.sess)?);
+        }
         Ok(())
     }
 
@@ -157,6 +184,18 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         if attrs.ignore() {
             self.specs.ignores.insert(IgnoreKey::Module(def_id));
         }
+        self.collect_item_cfg(def_id, &mut attrs)
+    }
+
+    fn collect_item_cfg(
+        &mut self,
+        def_id: LocalDefId,
+        attrs: &mut FluxAttrs,
+    ) -> Result<(), ErrorGuaranteed> {
+        if let Some(mut cfg) = attrs.cfg() {
+            let item_config = cfg.try_into_item_cfg().emit(self.sess)?;
+            self.specs.configs.insert(def_id, item_config);
+        }
         Ok(())
     }
 
@@ -187,6 +226,22 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         }
     }
 
+    fn parse_trait_spec(
+        &mut self,
+        owner_id: OwnerId,
+        attrs: &[Attribute],
+    ) -> Result<(), ErrorGuaranteed> {
+        let mut attrs = self.parse_flux_attrs(attrs)?;
+        self.report_dups(&attrs)?;
+
+        let assoc_refts = attrs.assoc_refts();
+        if !assoc_refts.is_empty() {
+            self.specs.assoc_refts.insert(owner_id, assoc_refts);
+        }
+
+        Ok(())
+    }
+
     fn parse_impl_spec(
         &mut self,
         owner_id: OwnerId,
@@ -199,7 +254,12 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
             self.specs.impls.insert(owner_id, generics);
         }
 
-        Ok(())
+        let assoc_refts = attrs.assoc_refts();
+        if !assoc_refts.is_empty() {
+            self.specs.assoc_refts.insert(owner_id, assoc_refts);
+        }
+
+        self.collect_item_cfg(owner_id.def_id, &mut attrs)
     }
 
     fn parse_tyalias_spec(
@@ -224,6 +284,8 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
 
         let mut opaque = attrs.opaque();
 
+        self.collect_trusted_derives(owner_id.def_id, &mut attrs);
+
         let refined_by = attrs.refined_by();
 
         let generics = attrs.generics();
@@ -292,6 +354,7 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
     ) -> Result<(), ErrorGuaranteed> {
         let mut attrs = self.parse_flux_attrs(attrs)?;
         self.report_dups(&attrs)?;
+        self.collect_trusted_derives(owner_id.def_id, &mut attrs);
         let refined_by = attrs.refined_by();
         let variants = enum_def
             .variants
@@ -313,6 +376,59 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         Ok(())
     }
 
+    /// Unions are collected as enums with one variant per field. The return index of a field's
+    /// variant describes the union values for which that field is the active one.
+    fn parse_union_def(
+        &mut self,
+        owner_id: OwnerId,
+        attrs: &[Attribute],
+        data: &VariantData,
+    ) -> Result<(), ErrorGuaranteed> {
+        let mut attrs = self.parse_flux_attrs(attrs)?;
+        self.report_dups(&attrs)?;
+        if attrs.opaque() {
+            return Err(self.emit_err(errors::OpaqueUnion::new(self.tcx.def_span(owner_id))));
+        }
+        self.collect_trusted_derives(owner_id.def_id, &mut attrs);
+        let refined_by = attrs.refined_by();
+        let variants = data
+            .fields()
+            .iter()
+            .map(|field| self.parse_union_field(field, refined_by.is_some()))
+            .try_collect_exhaust()?;
+
+        let invariants = attrs.invariants();
+
+        self.specs.enums.insert(
+            owner_id,
+            surface::EnumDef {
+                refined_by,
+                variants,
+                invariants,
+                node_id: self.parse_sess.next_node_id(),
+            },
+        );
+        Ok(())
+    }
+
+    fn parse_union_field(
+        &mut self,
+        field: &rustc_hir::FieldDef,
+        has_refined_by: bool,
+    ) -> Result<Option<surface::VariantDef>, ErrorGuaranteed> {
+        let attrs = self.tcx.hir().attrs(field.hir_id);
+        let mut attrs = self.parse_flux_attrs(attrs)?;
+        self.report_dups(&attrs)?;
+
+        let variant = attrs.variant();
+
+        if variant.is_none() && has_refined_by {
+            return Err(self.emit_err(errors::MissingVariant::new(field.span)));
+        }
+
+        Ok(variant)
+    }
+
     fn parse_variant(
         &mut self,
         hir_variant: &rustc_hir::Variant,
@@ -331,6 +447,29 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         Ok(variant)
     }
 
+    fn collect_trusted_derives(&mut self, def_id: LocalDefId, attrs: &mut FluxAttrs) {
+        if let Some(names) = attrs.trusted_derives() {
+            let names = names.map(|names| names.names.iter().map(|name| name.name).collect());
+            self.trusted_derives.insert(def_id, names);
+        }
+    }
+
+    /// Whether `def_id` is a method generated by a `#[derive]` on a type that opted out of checking
+    /// it with `#[flux::trusted_derives]`.
+    fn is_trusted_derive(&self, def_id: LocalDefId) -> bool {
+        let tcx = self.tcx;
+        let expn_data = tcx.def_span(def_id).ctxt().outer_expn_data();
+        let ExpnKind::Macro(MacroKind::Derive, derive) = expn_data.kind else { return false };
+        let Some(impl_id) = tcx.impl_of_method(def_id.to_def_id()) else { return false };
+        let Some(adt_def) = tcx.type_of(impl_id).skip_binder().ty_adt_def() else { return false };
+        let Some(adt_id) = adt_def.did().as_local() else { return false };
+        match self.trusted_derives.get(&adt_id) {
+            Some(Some(names)) => names.contains(&derive),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
     fn parse_fn_spec(
         &mut self,
         owner_id: OwnerId,
@@ -340,6 +479,8 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         self.report_dups(&attrs)?;
         // TODO(nilehmann) error if it has non-fun attrs
 
+        self.collect_item_cfg(owner_id.def_id, &mut attrs)?;
+
         let mut trusted = attrs.trusted();
         let fn_sig = attrs.fn_sig();
         let qual_names = attrs.qual_names();
@@ -362,6 +503,32 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         Ok(())
     }
 
+    /// A closure can be annotated either directly on the closure expression (which requires
+    /// `#![feature(stmt_expr_attributes)]`) or on the `let` statement binding the closure, e.g.,
+    ///
+    /// ```ignore
+    /// #[flux::sig(fn(x: i32{x > 0}) -> i32{v: v > 1})]
+    /// let f = |x| x + 1;
+    /// ```
+    fn parse_closure_spec(&mut self, def_id: LocalDefId) -> Result<(), ErrorGuaranteed> {
+        let hir = self.tcx.hir();
+        let hir_id = hir.local_def_id_to_hir_id(def_id);
+
+        let mut attrs = self.parse_flux_attrs(hir.attrs(hir_id))?;
+        if let Node::Local(local) = hir.get_parent(hir_id)
+            && let Some(init) = local.init
+            && init.hir_id == hir_id
+        {
+            attrs.extend(self.parse_flux_attrs(hir.attrs(local.hir_id))?);
+        }
+        self.report_dups(&attrs)?;
+
+        if let Some(fn_sig) = attrs.fn_sig() {
+            self.specs.closure_sigs.insert(def_id, fn_sig);
+        }
+        Ok(())
+    }
+
     fn parse_flux_attrs(&mut self, attrs: &[Attribute]) -> Result<FluxAttrs, ErrorGuaranteed> {
         let attrs: Vec<_> = attrs
             .iter()
@@ -409,6 +576,9 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
             ("refined_by", AttrArgs::Delimited(dargs)) => {
                 self.parse(dargs, ParseSess::parse_refined_by, FluxAttrKind::RefinedBy)?
             }
+            ("assoc", AttrArgs::Delimited(dargs)) => {
+                self.parse(dargs, ParseSess::parse_assoc_refts, FluxAttrKind::AssocRefts)?
+            }
             ("generics", AttrArgs::Delimited(dargs)) => {
                 self.parse(dargs, ParseSess::parse_generics, FluxAttrKind::Generics)?
             }
@@ -422,11 +592,7 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
                 self.parse(dargs, ParseSess::parse_expr, FluxAttrKind::Invariant)?
             }
             ("cfg", AttrArgs::Delimited(..)) => {
-                let crate_cfg = FluxAttrCFG::parse_cfg(attr_item)
-                    .emit(self.sess)?
-                    .try_into_crate_cfg()
-                    .emit(self.sess)?;
-                FluxAttrKind::CrateConfig(crate_cfg)
+                FluxAttrKind::Config(FluxAttrCFG::parse_cfg(attr_item).emit(self.sess)?)
             }
             ("constant", AttrArgs::Empty) => {
                 FluxAttrKind::ConstSig(surface::ConstSig { span: attr_item.span() })
@@ -434,6 +600,12 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
             ("ignore", AttrArgs::Empty) => FluxAttrKind::Ignore,
             ("opaque", AttrArgs::Empty) => FluxAttrKind::Opaque,
             ("trusted", AttrArgs::Empty) => FluxAttrKind::Trusted,
+            ("trusted_derives", AttrArgs::Empty) => FluxAttrKind::TrustedDerives(None),
+            ("trusted_derives", AttrArgs::Delimited(dargs)) => {
+                self.parse(dargs, ParseSess::parse_qual_names, |names| {
+                    FluxAttrKind::TrustedDerives(Some(names))
+                })?
+            }
             ("extern_spec", AttrArgs::Empty) => FluxAttrKind::ExternSpec,
             _ => return Err(self.emit_err(errors::InvalidAttr { span: attr_item.span() })),
         };
@@ -446,7 +618,7 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         &mut self,
         def_id: LocalDefId,
     ) -> Result<DefId, ErrorGuaranteed> {
-        use rustc_hir::{def, ExprKind, Node};
+        use rustc_hir::{def, ExprKind};
         // Regular functions
         if let Node::Item(i) = self.tcx.hir().find_by_def_id(def_id).unwrap()
             && let ItemKind::Fn(_, _, body_id) = &i.kind
@@ -499,7 +671,7 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         ctor: impl FnOnce(T) -> FluxAttrKind,
     ) -> Result<FluxAttrKind, ErrorGuaranteed> {
         let entire = dargs.dspan.entire().with_ctxt(SyntaxContext::root());
-        parser(&mut self.parse_sess, &dargs.tokens, entire)
+        parser(&mut self.parse_sess, &dargs.tokens.flattened(), entire)
             .map(ctor)
             .map_err(|err| self.emit_err(errors::SyntaxErr::from(err)))
     }
@@ -541,7 +713,9 @@ impl Specs {
     fn new() -> Specs {
         Specs {
             fn_sigs: Default::default(),
+            closure_sigs: Default::default(),
             impls: Default::default(),
+            assoc_refts: Default::default(),
             structs: Default::default(),
             enums: Default::default(),
             qualifs: Vec::default(),
@@ -551,6 +725,7 @@ impl Specs {
             ignores: Default::default(),
             consts: Default::default(),
             crate_config: None,
+            configs: Default::default(),
             extern_specs: Default::default(),
         }
     }
@@ -596,17 +771,21 @@ struct FluxAttr {
 #[derive(Debug)]
 enum FluxAttrKind {
     Trusted,
+    TrustedDerives(Option<surface::QualNames>),
     Opaque,
     FnSig(surface::FnSig),
     RefinedBy(surface::RefinedBy),
     Generics(surface::Generics),
     QualNames(surface::QualNames),
     Items(Vec<surface::Item>),
+    AssocRefts(Vec<surface::AssocReft>),
     TypeAlias(surface::TyAlias),
     Field(surface::Ty),
     Variant(surface::VariantDef),
     ConstSig(surface::ConstSig),
-    CrateConfig(config::CrateConfig),
+    /// A `#[flux::cfg]` attribute. Which settings are valid depends on whether it is attached to
+    /// the crate or to an item, so we only validate them once we know where it is.
+    Config(FluxAttrCFG),
     Invariant(surface::Expr),
     Ignore,
     ExternSpec,
@@ -638,7 +817,7 @@ macro_rules! read_attr {
 
 impl FluxAttr {
     pub fn allow_dups(&self) -> bool {
-        matches!(&self.kind, FluxAttrKind::Invariant(..))
+        matches!(&self.kind, FluxAttrKind::Invariant(..) | FluxAttrKind::AssocRefts(..))
     }
 }
 
@@ -647,6 +826,12 @@ impl FluxAttrs {
         FluxAttrs { map: attrs.into_iter().into_group_map_by(|attr| attr.kind.name()) }
     }
 
+    fn extend(&mut self, other: FluxAttrs) {
+        for (name, attrs) in other.map {
+            self.map.entry(name).or_default().extend(attrs);
+        }
+    }
+
     fn dups(&self) -> impl Iterator<Item = (&'static str, &[FluxAttr])> {
         self.map
             .iter()
@@ -662,6 +847,10 @@ impl FluxAttrs {
         read_flag!(self, Ignore)
     }
 
+    fn trusted_derives(&mut self) -> Option<Option<surface::QualNames>> {
+        read_attr!(self, TrustedDerives)
+    }
+
     fn opaque(&mut self) -> bool {
         read_flag!(self, Opaque)
     }
@@ -670,6 +859,13 @@ impl FluxAttrs {
         read_attrs!(self, Items).into_iter().flatten()
     }
 
+    fn assoc_refts(&mut self) -> Vec<surface::AssocReft> {
+        read_attrs!(self, AssocRefts)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
     fn fn_sig(&mut self) -> Option<surface::FnSig> {
         read_attr!(self, FnSig)
     }
@@ -702,8 +898,8 @@ impl FluxAttrs {
         read_attr!(self, Variant)
     }
 
-    fn crate_config(&mut self) -> Option<config::CrateConfig> {
-        read_attr!(self, CrateConfig)
+    fn cfg(&mut self) -> Option<FluxAttrCFG> {
+        read_attr!(self, Config)
     }
 
     fn invariants(&mut self) -> Vec<surface::Expr> {
@@ -719,17 +915,19 @@ impl FluxAttrKind {
     fn name(&self) -> &'static str {
         match self {
             FluxAttrKind::Trusted => attr_name!(Trusted),
+            FluxAttrKind::TrustedDerives(_) => attr_name!(TrustedDerives),
             FluxAttrKind::Opaque => attr_name!(Opaque),
             FluxAttrKind::FnSig(_) => attr_name!(FnSig),
             FluxAttrKind::ConstSig(_) => attr_name!(ConstSig),
             FluxAttrKind::RefinedBy(_) => attr_name!(RefinedBy),
             FluxAttrKind::Generics(_) => attr_name!(Generics),
             FluxAttrKind::Items(_) => attr_name!(Items),
+            FluxAttrKind::AssocRefts(_) => attr_name!(AssocRefts),
             FluxAttrKind::QualNames(_) => attr_name!(QualNames),
             FluxAttrKind::Field(_) => attr_name!(Field),
             FluxAttrKind::Variant(_) => attr_name!(Variant),
             FluxAttrKind::TypeAlias(_) => attr_name!(TypeAlias),
-            FluxAttrKind::CrateConfig(_) => attr_name!(CrateConfig),
+            FluxAttrKind::Config(_) => attr_name!(Config),
             FluxAttrKind::Ignore => attr_name!(Ignore),
             FluxAttrKind::Invariant(_) => attr_name!(Invariant),
             FluxAttrKind::ExternSpec => attr_name!(ExternSpec),
@@ -753,7 +951,7 @@ macro_rules! try_read_setting {
         if let Some(CFGSetting { setting, span }) = $self.map.remove(stringify!($setting)) {
             let parse_result = setting.as_str().parse::<$type>();
             if let Ok(val) = parse_result {
-                $cfg.$setting = val;
+                $cfg.$setting = val.into();
             } else {
                 return Err(errors::CFGError {
                     span,
@@ -828,6 +1026,14 @@ impl FluxAttrCFG {
         let mut crate_config = CrateConfig::default();
         try_read_setting!(self, check_overflow, bool, crate_config);
         try_read_setting!(self, scrape_quals, bool, crate_config);
+        try_read_setting!(self, skip_unsupported, bool, crate_config);
+        try_read_setting!(self, check_vacuity, bool, crate_config);
+        try_read_setting!(self, suggest_preconditions, bool, crate_config);
+        try_read_setting!(self, infer_signatures, bool, crate_config);
+        try_read_setting!(self, check_raw_pointers, bool, crate_config);
+        try_read_setting!(self, solver_timeout, u64, crate_config);
+        try_read_setting!(self, kvar_encoding, KVarEncoding, crate_config);
+        try_read_setting!(self, verbose, bool, crate_config);
 
         if let Some((name, setting)) = self.map.iter().next() {
             return Err(errors::CFGError {
@@ -838,6 +1044,24 @@ impl FluxAttrCFG {
 
         Ok(crate_config)
     }
+
+    fn try_into_item_cfg(&mut self) -> Result<config::ItemConfig, errors::CFGError> {
+        let mut item_config = ItemConfig::default();
+        try_read_setting!(self, check_overflow, bool, item_config);
+        try_read_setting!(self, scrape_quals, bool, item_config);
+        try_read_setting!(self, solver_timeout, u64, item_config);
+        try_read_setting!(self, kvar_encoding, KVarEncoding, item_config);
+        try_read_setting!(self, verbose, bool, item_config);
+
+        if let Some((name, setting)) = self.map.iter().next() {
+            return Err(errors::CFGError {
+                span: setting.span,
+                message: format!("invalid item cfg keyword `{name}`"),
+            });
+        }
+
+        Ok(item_config)
+    }
 }
 
 mod errors {
@@ -927,6 +1151,20 @@ mod errors {
         }
     }
 
+    #[derive(Diagnostic)]
+    #[diag(driver_opaque_union, code = "FLUX")]
+    #[note]
+    pub(super) struct OpaqueUnion {
+        #[primary_span]
+        span: Span,
+    }
+
+    impl OpaqueUnion {
+        pub(super) fn new(span: Span) -> Self {
+            Self { span }
+        }
+    }
+
     impl From<flux_syntax::ParseError> for SyntaxErr {
         fn from(err: flux_syntax::ParseError) -> Self {
             use flux_syntax::ParseErrorKind;
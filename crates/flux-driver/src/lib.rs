@@ -14,6 +14,7 @@ extern crate rustc_span;
 
 pub mod callbacks;
 mod collector;
+mod summary;
 
 use flux_macros::fluent_messages;
 use rustc_errors::{DiagnosticMessage, SubdiagnosticMessage};
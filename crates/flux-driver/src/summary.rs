@@ -0,0 +1,352 @@
+//! A per module report of how many functions were verified, saved in the log directory when
+//! `FLUX_DUMP_SUMMARY` is set. The status of each function can also be saved next to the flux
+//! metadata of the crate when `FLUX_SAVE_RESULTS` is set.
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use flux_config as config;
+use flux_refineck::CheckOutcome;
+use rustc_data_structures::unord::UnordSet;
+use rustc_errors::ErrorGuaranteed;
+use rustc_hash::FxHashSet;
+use rustc_hir::def_id::{LocalDefId, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+use rustc_session::config::OutFileName;
+
+const SUMMARY_FILE: &str = "summary";
+
+// CODESYNC(results-ext) we must use the same extension in flux-bin
+const RESULTS_EXT: &str = "fluxresults";
+
+// CODESYNC(results-format) status of a crate whose checking stopped before its functions
+const CRATE_ERROR: &str = "error";
+
+pub(crate) struct Summary {
+    /// Functions annotated with a `#[flux::sig]`
+    with_sig: UnordSet<LocalDefId>,
+    /// Functions declaring an extern spec
+    extern_specs: FxHashSet<LocalDefId>,
+    modules: BTreeMap<String, ModuleSummary>,
+    /// Status of each function indexed by its path (including the crate name)
+    results: BTreeMap<String, FnStatus>,
+    fixpoint_time: Duration,
+}
+
+#[derive(Default)]
+struct ModuleSummary {
+    fns: usize,
+    with_sig: usize,
+    verified: usize,
+    verified_with_sig: usize,
+    failed: usize,
+    trusted: usize,
+    ignored: usize,
+    extern_specs: usize,
+    unsupported: usize,
+}
+
+#[derive(Clone, Copy)]
+enum FnStatus {
+    Verified,
+    Failed,
+    Trusted,
+    Ignored,
+    ExternSpec,
+    Unsupported,
+}
+
+impl Summary {
+    pub(crate) fn new(with_sig: UnordSet<LocalDefId>, extern_specs: FxHashSet<LocalDefId>) -> Self {
+        Summary {
+            with_sig,
+            extern_specs,
+            modules: BTreeMap::default(),
+            results: BTreeMap::default(),
+            fixpoint_time: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn record_ignored(&mut self, tcx: TyCtxt, def_id: LocalDefId) {
+        self.record(tcx, def_id, FnStatus::Ignored);
+    }
+
+    pub(crate) fn record_checked(
+        &mut self,
+        tcx: TyCtxt,
+        def_id: LocalDefId,
+        result: &Result<CheckOutcome, ErrorGuaranteed>,
+    ) {
+        let status = match result {
+            Ok(CheckOutcome::Checked { fixpoint_time }) => {
+                self.fixpoint_time += *fixpoint_time;
+                FnStatus::Verified
+            }
+            Ok(CheckOutcome::Trusted) if self.extern_specs.contains(&def_id) => {
+                FnStatus::ExternSpec
+            }
+            Ok(CheckOutcome::Trusted) => FnStatus::Trusted,
+            Ok(CheckOutcome::Unsupported { .. }) => FnStatus::Unsupported,
+            Err(_) => FnStatus::Failed,
+        };
+        self.record(tcx, def_id, status);
+    }
+
+    fn record(&mut self, tcx: TyCtxt, def_id: LocalDefId, status: FnStatus) {
+        let module = tcx.parent_module_from_def_id(def_id).to_def_id();
+        let module = if module.is_crate_root() {
+            "crate".to_string()
+        } else {
+            format!("crate::{}", tcx.def_path_str(module))
+        };
+        let summary = self.modules.entry(module).or_default();
+        summary.fns += 1;
+        if self.with_sig.contains(&def_id) {
+            summary.with_sig += 1;
+            if let FnStatus::Verified = status {
+                summary.verified_with_sig += 1;
+            }
+        }
+        let count = match status {
+            FnStatus::Verified => &mut summary.verified,
+            FnStatus::Failed => &mut summary.failed,
+            FnStatus::Trusted => &mut summary.trusted,
+            FnStatus::Ignored => &mut summary.ignored,
+            FnStatus::ExternSpec => &mut summary.extern_specs,
+            FnStatus::Unsupported => &mut summary.unsupported,
+        };
+        *count += 1;
+        let path =
+            format!("{}::{}", tcx.crate_name(LOCAL_CRATE), tcx.def_path_str(def_id.to_def_id()));
+        self.results.insert(path, status);
+    }
+
+    pub(crate) fn save(&self, tcx: TyCtxt) -> io::Result<()> {
+        if let Some(path) = results_path(tcx) {
+            let mut file = fs::File::create(path)?;
+            self.write_results(&mut file)?;
+        }
+        if !config::dump_summary() {
+            return Ok(());
+        }
+        fs::create_dir_all(config::log_dir())?;
+        let mut file = fs::File::create(config::log_dir().join(SUMMARY_FILE))?;
+        self.write(&mut file)
+    }
+
+    /// Saves an error as the results of the crate, which are overwritten by [`Summary::save`] once
+    /// the crate is checked. If checking stops before, e.g., because a spec is ill-formed, the crate
+    /// is reported as such instead of keeping the results of a previous run.
+    pub(crate) fn save_crate_error(tcx: TyCtxt) -> io::Result<()> {
+        if let Some(path) = results_path(tcx) {
+            fs::write(path, format!("{CRATE_ERROR} {}\n", tcx.crate_name(LOCAL_CRATE)))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the status of each function, one per line, followed by its path.
+    // CODESYNC(results-format) we must use the same format in flux-bin
+    fn write_results(&self, w: &mut impl Write) -> io::Result<()> {
+        for (path, status) in &self.results {
+            writeln!(w, "{} {path}", status.as_str())?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        let mut total = ModuleSummary::default();
+        for summary in self.modules.values() {
+            total.add(summary);
+        }
+        let width = self
+            .modules
+            .keys()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max("module".len());
+
+        writeln!(
+            w,
+            "{:width$}  {:>5}  {:>5}  {:>8}  {:>6}  {:>7}  {:>7}  {:>6}  {:>11}",
+            "module",
+            "fns",
+            "specs",
+            "verified",
+            "failed",
+            "trusted",
+            "ignored",
+            "extern",
+            "unsupported",
+        )?;
+        for (module, summary) in &self.modules {
+            summary.write_row(w, module, width)?;
+        }
+        total.write_row(w, "total", width)?;
+        writeln!(w)?;
+        writeln!(
+            w,
+            "verified {} of {} functions ({}) and {} of {} functions with specs ({})",
+            total.verified,
+            total.fns,
+            percentage(total.verified, total.fns),
+            total.verified_with_sig,
+            total.with_sig,
+            percentage(total.verified_with_sig, total.with_sig),
+        )?;
+        writeln!(w, "fixpoint time: {:.2}ms", self.fixpoint_time.as_secs_f64() * 1000.0)
+    }
+}
+
+impl FnStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            FnStatus::Verified => "verified",
+            FnStatus::Failed => "failed",
+            FnStatus::Trusted => "trusted",
+            FnStatus::Ignored => "ignored",
+            FnStatus::ExternSpec => "extern",
+            FnStatus::Unsupported => "unsupported",
+        }
+    }
+}
+
+impl ModuleSummary {
+    fn add(&mut self, other: &ModuleSummary) {
+        self.fns += other.fns;
+        self.with_sig += other.with_sig;
+        self.verified += other.verified;
+        self.verified_with_sig += other.verified_with_sig;
+        self.failed += other.failed;
+        self.trusted += other.trusted;
+        self.ignored += other.ignored;
+        self.extern_specs += other.extern_specs;
+        self.unsupported += other.unsupported;
+    }
+
+    fn write_row(&self, w: &mut impl Write, name: &str, width: usize) -> io::Result<()> {
+        writeln!(
+            w,
+            "{name:width$}  {:>5}  {:>5}  {:>8}  {:>6}  {:>7}  {:>7}  {:>6}  {:>11}",
+            self.fns,
+            self.with_sig,
+            self.verified,
+            self.failed,
+            self.trusted,
+            self.ignored,
+            self.extern_specs,
+            self.unsupported,
+        )
+    }
+}
+
+fn percentage(n: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", (n as f64) * 100.0 / (total as f64))
+    }
+}
+
+/// The file where the status of each function is saved if `FLUX_SAVE_RESULTS` is set
+fn results_path(tcx: TyCtxt) -> Option<PathBuf> {
+    if config::save_results()
+        && let OutFileName::Real(path) = flux_metadata::filename_for_metadata(tcx)
+    {
+        Some(path.with_extension(RESULTS_EXT))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> Summary {
+        let mut summary = Summary::new(UnordSet::default(), FxHashSet::default());
+        summary.modules.insert(
+            "crate".to_string(),
+            ModuleSummary {
+                fns: 3,
+                with_sig: 2,
+                verified: 2,
+                verified_with_sig: 1,
+                failed: 1,
+                ..Default::default()
+            },
+        );
+        summary.modules.insert(
+            "crate::vec".to_string(),
+            ModuleSummary {
+                fns: 4,
+                with_sig: 2,
+                verified: 1,
+                verified_with_sig: 1,
+                trusted: 1,
+                ignored: 1,
+                extern_specs: 1,
+                ..Default::default()
+            },
+        );
+        summary
+            .results
+            .insert("foo::bar".to_string(), FnStatus::Failed);
+        summary
+            .results
+            .insert("foo::baz".to_string(), FnStatus::Verified);
+        summary
+            .results
+            .insert("foo::vec::RVec::new".to_string(), FnStatus::Trusted);
+        summary.fixpoint_time = Duration::from_micros(12345);
+        summary
+    }
+
+    #[test]
+    fn write_summary() {
+        let mut buf = vec![];
+        summary().write(&mut buf).unwrap();
+        let expected = "\
+module        fns  specs  verified  failed  trusted  ignored  extern  unsupported
+crate           3      2         2       1        0        0       0            0
+crate::vec      4      2         1       0        1        1       1            0
+total           7      4         3       1        1        1       1            0
+
+verified 3 of 7 functions (42.9%) and 2 of 4 functions with specs (50.0%)
+fixpoint time: 12.35ms
+";
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_empty_summary() {
+        let mut buf = vec![];
+        Summary::new(UnordSet::default(), FxHashSet::default())
+            .write(&mut buf)
+            .unwrap();
+        let expected = "\
+module    fns  specs  verified  failed  trusted  ignored  extern  unsupported
+total       0      0         0       0        0        0       0            0
+
+verified 0 of 0 functions (-) and 0 of 0 functions with specs (-)
+fixpoint time: 0.00ms
+";
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_results() {
+        let mut buf = vec![];
+        summary().write_results(&mut buf).unwrap();
+        let expected = "\
+failed foo::bar
+verified foo::baz
+trusted foo::vec::RVec::new
+";
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+}
@@ -46,6 +46,11 @@ impl FluxSession {
         self.parse_sess.emit_err(err)
     }
 
+    #[track_caller]
+    pub fn emit_warning<'a>(&'a self, warning: impl IntoDiagnostic<'a, ()>) {
+        self.parse_sess.emit_warning(warning);
+    }
+
     #[track_caller]
     pub fn emit_fatal<'a>(&'a self, fatal: impl IntoDiagnostic<'a, !>) -> ! {
         self.parse_sess.emit_fatal(fatal)
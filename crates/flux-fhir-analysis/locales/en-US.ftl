@@ -97,6 +97,19 @@ fhir_analysis_cannot_infer_sort =
     .label = cannot infer sort
     .note = sort must be known at this point
 
+fhir_analysis_unknown_assoc_refinement =
+    associated refinement `{$name}` is not declared in trait `{$trait_name}`
+    .label = not a member of `{$trait_name}`
+
+fhir_analysis_incompatible_assoc_refinement =
+    associated refinement `{$name}` is incompatible with its declaration in the trait
+    .label = expected `{$expected}`
+    .decl_label = declared here
+
+fhir_analysis_missing_assoc_refinement =
+    missing definition for associated refinement `{$name}`
+    .label = missing `{$name}` in implementation
+
 # Annot check
 
 fhir_analysis_invalid_refinement =
@@ -16,7 +16,7 @@ use flux_middle::fhir::{
 };
 use rustc_data_structures::unord::UnordMap;
 use rustc_errors::IntoDiagnostic;
-use rustc_hir::OwnerId;
+use rustc_hir::{def_id::LocalDefId, OwnerId};
 use rustc_middle::ty::TyCtxt;
 
 pub fn check_fn_sig(
@@ -34,6 +34,31 @@ pub fn check_fn_sig(
     Zipper::new(sess, wfckresults, self_ty.as_ref()).zip_fn_sig(fn_sig, expected_fn_sig)
 }
 
+/// Closure arguments are usually not annotated in Rust, so there's no declaration to zip the
+/// signature with. We only check the number of arguments match.
+pub fn check_closure_sig(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    def_id: LocalDefId,
+    fn_sig: &fhir::FnSig,
+) -> Result<(), ErrorGuaranteed> {
+    let closure = tcx
+        .hir()
+        .expect_expr(tcx.hir().local_def_id_to_hir_id(def_id));
+    let rustc_hir::ExprKind::Closure(closure) = closure.kind else {
+        bug!("expected closure expression");
+    };
+    let inputs = closure.fn_decl.inputs.len();
+    if fn_sig.args.len() != inputs {
+        return Err(sess.emit_err(errors::FunArgCountMismatch::for_closure(
+            fn_sig,
+            closure.fn_decl_span,
+            inputs,
+        )));
+    }
+    Ok(())
+}
+
 pub fn check_alias(
     tcx: TyCtxt,
     sess: &FluxSession,
@@ -201,22 +226,23 @@ impl<'zip> Zipper<'zip> {
                 self.zip_tys(tys, expected_tys)
             }
             (fhir::TyKind::Array(ty, len), fhir::TyKind::Array(expected_ty, expected_len)) => {
-                if len.val != expected_len.val {
+                if len.kind != expected_len.kind {
                     return Err(self.emit_err(errors::ArrayLenMismatch::new(len, expected_len)));
                 }
                 self.zip_ty(ty, expected_ty)
             }
             (
-                fhir::TyKind::RawPtr(ty, mutbl),
-                fhir::TyKind::RawPtr(expected_ty, expected_mutbl),
+                fhir::TyKind::BareFn(inputs, output),
+                fhir::TyKind::BareFn(expected_inputs, expected_output),
             ) => {
-                if mutbl != expected_mutbl {
+                if inputs.len() != expected_inputs.len() {
                     return Err(self.emit_err(
                         errors::InvalidRefinement::from_tys(ty, expected_ty)
-                            .with_note("types differ in mutability"),
+                            .with_note("function pointers have different number of arguments"),
                     ));
                 }
-                self.zip_ty(ty, expected_ty)
+                self.zip_tys(inputs, expected_inputs)?;
+                self.zip_ty(output, expected_output)
             }
             (fhir::TyKind::Never, fhir::TyKind::Never) => Ok(()),
             (fhir::TyKind::Hole(fhir_id), _) => {
@@ -262,6 +288,12 @@ impl<'zip> Zipper<'zip> {
                 self.zip_lifetime(*lft1, *lft2);
                 Ok(())
             }
+            (fhir::GenericArg::Const(c1), fhir::GenericArg::Const(c2)) => {
+                if c1.kind != c2.kind {
+                    return Err(self.emit_err(errors::ArrayLenMismatch::new(c1, c2)));
+                }
+                Ok(())
+            }
             _ => bug!(),
         }
     }
@@ -293,6 +325,18 @@ impl<'zip> Zipper<'zip> {
             (fhir::BaseTyKind::Slice(ty), fhir::BaseTyKind::Slice(expected_ty)) => {
                 self.zip_ty(ty, expected_ty)
             }
+            (
+                fhir::BaseTyKind::RawPtr(ty, mutbl),
+                fhir::BaseTyKind::RawPtr(expected_ty, expected_mutbl),
+            ) => {
+                if mutbl != expected_mutbl {
+                    return Err(self.emit_err(
+                        errors::InvalidRefinement::from_btys(bty, expected_bty)
+                            .with_note("types differ in mutability"),
+                    ));
+                }
+                self.zip_ty(ty, expected_ty)
+            }
             _ => Err(self.emit_err(errors::InvalidRefinement::from_btys(bty, expected_bty))),
         }
     }
@@ -432,6 +476,15 @@ mod errors {
                 expected_args: expected_fn_sig.args.len(),
             }
         }
+
+        pub(super) fn for_closure(fn_sig: &fhir::FnSig, decl_span: Span, inputs: usize) -> Self {
+            Self {
+                span: fn_sig.span,
+                args: fn_sig.args.len(),
+                expected_span: decl_span,
+                expected_args: inputs,
+            }
+        }
     }
 
     #[derive(Diagnostic)]
@@ -465,19 +518,19 @@ mod errors {
         #[primary_span]
         #[label]
         span: Span,
-        len: usize,
+        len: String,
         #[label(fhir_analysis_expected_label)]
         expected_span: Span,
-        expected_len: usize,
+        expected_len: String,
     }
 
     impl ArrayLenMismatch {
-        pub(super) fn new(len: &fhir::ArrayLen, expected_len: &fhir::ArrayLen) -> Self {
+        pub(super) fn new(len: &fhir::ConstArg, expected_len: &fhir::ConstArg) -> Self {
             Self {
                 span: len.span,
-                len: len.val,
+                len: format!("{len:?}"),
                 expected_span: expected_len.span,
-                expected_len: expected_len.val,
+                expected_len: format!("{expected_len:?}"),
             }
         }
     }
@@ -27,7 +27,7 @@ use rustc_hir::{
 };
 use rustc_middle::{
     middle::resolve_bound_vars::ResolvedArg,
-    ty::{AssocItem, AssocKind, BoundVar, TyCtxt},
+    ty::{AssocItem, AssocKind, BoundVar, ParamConst, TyCtxt},
 };
 use rustc_span::symbol::kw;
 use rustc_type_ir::DebruijnIndex;
@@ -165,6 +165,13 @@ pub(crate) fn conv_generics(
                 fhir::GenericParamKind::SplTy => rty::GenericParamDefKind::SplTy,
                 fhir::GenericParamKind::BaseTy => rty::GenericParamDefKind::BaseTy,
                 fhir::GenericParamKind::Lifetime => rty::GenericParamDefKind::Lifetime,
+                fhir::GenericParamKind::Const => {
+                    let rustc::ty::GenericParamDefKind::Const { has_default } = rust_param.kind
+                    else {
+                        bug!("expected const generic param");
+                    };
+                    rty::GenericParamDefKind::Const { has_default }
+                }
             };
             let def_id = param.def_id.to_def_id();
             Some(rty::GenericParamDef {
@@ -253,6 +260,19 @@ pub(crate) fn conv_defn(
     rty::Defn { name: defn.name, expr }
 }
 
+pub(crate) fn conv_assoc_refinement(
+    genv: &GlobalEnv,
+    reft: &fhir::AssocRefinement,
+    body: &fhir::Expr,
+    wfckresults: &fhir::WfckResults,
+) -> rty::Binder<rty::Expr> {
+    let cx = ConvCtxt::new(genv, wfckresults);
+    let mut env = Env::new(&[]);
+    env.push_layer(Layer::list(&cx, 0, &reft.params, false));
+    let expr = cx.conv_expr(&env, body);
+    rty::Binder::new(expr, env.pop_layer().into_bound_vars())
+}
+
 pub(crate) fn conv_qualifier(
     genv: &GlobalEnv,
     qualifier: &fhir::Qualifier,
@@ -276,8 +296,15 @@ pub(crate) fn conv_fn_sig(
 
     let late_bound_regions = refining::refine_bound_variables(&genv.lower_late_bound_vars(def_id)?);
 
-    let mut env = Env::new(&fn_sig.params);
-    env.push_layer(Layer::list(&cx, late_bound_regions.len() as u32, &[], true));
+    // Closures are not generic over refinement parameters (they cannot be instantiated
+    // explicitly at a call site) so we bind their parameters late.
+    let (early_bound, late_bound) = if genv.tcx.is_closure(def_id.to_def_id()) {
+        (&[][..], &fn_sig.params[..])
+    } else {
+        (&fn_sig.params[..], &[][..])
+    };
+    let mut env = Env::new(early_bound);
+    env.push_layer(Layer::list(&cx, late_bound_regions.len() as u32, late_bound, true));
 
     let mut requires = vec![];
     for constr in &fn_sig.requires {
@@ -369,7 +396,9 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
         clauses: &mut Vec<rty::Clause>,
     ) -> QueryResult<()> {
         let mut into = vec![rty::GenericArg::Ty(bounded_ty.clone())];
+        env.push_layer(Layer::list(self, params.len() as u32, &[], true));
         self.conv_generic_args_into(env, args, &mut into)?;
+        env.pop_layer();
         self.fill_generic_args_defaults(trait_id, &mut into)?;
         let trait_ref = rty::TraitRef { def_id: trait_id, args: into.into() };
         let pred = rty::TraitPredicate { trait_ref };
@@ -398,7 +427,8 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
             }
             fhir::GenericParamKind::Type { default: _ }
             | fhir::GenericParamKind::BaseTy
-            | fhir::GenericParamKind::SplTy => bug!("unexpected!"),
+            | fhir::GenericParamKind::SplTy
+            | fhir::GenericParamKind::Const => bug!("unexpected!"),
         }
     }
 
@@ -420,6 +450,7 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
             output: self.conv_ty(env, &path.bindings[0].term)?,
             kind,
         };
+        env.pop_layer();
         // FIXME(nilehmann) We should use `tcx.late_bound_vars` here instead of trusting our lowering
         let vars = trait_ref
             .bound_generic_params
@@ -618,19 +649,29 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
                 Ok(rty::Ty::tuple(tys))
             }
             fhir::TyKind::Array(ty, len) => {
-                Ok(rty::Ty::array(self.conv_ty(env, ty)?, rty::Const::from(len.val)))
+                Ok(rty::Ty::array(self.conv_ty(env, ty)?, self.conv_const_arg(*len)))
+            }
+            fhir::TyKind::BareFn(inputs, output) => {
+                // Function pointers with late bound lifetimes are rejected during lifting, so the
+                // binders of the signature and its output are empty.
+                env.push_layer(Layer::empty());
+                let inputs = inputs
+                    .iter()
+                    .map(|ty| self.conv_ty(env, ty))
+                    .try_collect_vec()?;
+                env.push_layer(Layer::empty());
+                let ret = self.conv_ty(env, output)?;
+                env.pop_layer();
+                env.pop_layer();
+                let output = rty::Binder::new(rty::FnOutput::new(ret, vec![]), List::empty());
+                let fn_sig = rty::FnSig::new(vec![], inputs, output);
+                Ok(rty::Ty::fn_ptr(rty::PolyFnSig::new(fn_sig, List::empty())))
             }
             fhir::TyKind::Never => Ok(rty::Ty::never()),
             fhir::TyKind::Constr(pred, ty) => {
                 let pred = self.conv_expr(env, pred);
                 Ok(rty::Ty::constr(pred, self.conv_ty(env, ty)?))
             }
-            fhir::TyKind::RawPtr(ty, mutability) => {
-                Ok(rty::Ty::indexed(
-                    rty::BaseTy::RawPtr(self.conv_ty(env, ty)?, *mutability),
-                    rty::Expr::unit(),
-                ))
-            }
             fhir::TyKind::Hole(fhir_id) => {
                 let ty = self
                     .wfckresults
@@ -773,6 +814,10 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
                 let slice = rty::BaseTy::slice(self.conv_ty(env, ty)?);
                 Ok(rty::Ty::indexed(slice, idx))
             }
+            fhir::BaseTyKind::RawPtr(ty, mutability) => {
+                let ptr = rty::BaseTy::RawPtr(self.conv_ty(env, ty)?, *mutability);
+                Ok(rty::Ty::indexed(ptr, idx))
+            }
         }
     }
 
@@ -795,7 +840,7 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
             fhir::Res::PrimTy(PrimTy::Float(float_ty)) => {
                 rty::BaseTy::Float(rustc_middle::ty::float_ty(*float_ty))
             }
-            fhir::Res::Def(DefKind::Struct | DefKind::Enum, did) => {
+            fhir::Res::Def(DefKind::Struct | DefKind::Enum | DefKind::Union, did) => {
                 let adt_def = self.genv.adt_def(*did)?;
                 let args = self.conv_generic_args(env, *did, &path.args)?;
                 rty::BaseTy::adt(adt_def, args)
@@ -856,11 +901,23 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
                 fhir::GenericArg::Type(ty) => {
                     into.push(rty::GenericArg::Ty(self.conv_ty(env, ty)?));
                 }
+                fhir::GenericArg::Const(c) => {
+                    into.push(rty::GenericArg::Const(self.conv_const_arg(*c)));
+                }
             }
         }
         Ok(())
     }
 
+    fn conv_const_arg(&self, c: fhir::ConstArg) -> rty::Const {
+        match c.kind {
+            fhir::ConstArgKind::Lit(val) => rty::Const::from(val),
+            fhir::ConstArgKind::Param(def_id) => {
+                rty::Const::Param(def_id_to_param_const(self.genv.tcx, def_id.expect_local()))
+            }
+        }
+    }
+
     fn fill_generic_args_defaults(
         &self,
         def_id: DefId,
@@ -873,7 +930,7 @@ impl<'a, 'tcx> ConvCtxt<'a, 'tcx> {
                 let ty = self
                     .genv
                     .type_of(param.def_id)?
-                    .instantiate(&[], &[])
+                    .instantiate(into, &[])
                     .into_ty();
                 into.push(rty::GenericArg::Ty(ty));
             } else {
@@ -947,7 +1004,14 @@ impl ConvCtxt<'_, '_> {
         let fhir_id = expr.fhir_id;
         let espan = Some(ESpan::new(expr.span));
         let expr = match &expr.kind {
-            fhir::ExprKind::Const(did, _) => rty::Expr::const_def_id(*did, espan),
+            fhir::ExprKind::Const(did, _) => {
+                if let DefKind::ConstParam = self.genv.tcx.def_kind(*did) {
+                    let param = def_id_to_param_const(self.genv.tcx, did.expect_local());
+                    rty::Expr::const_param(param, espan)
+                } else {
+                    rty::Expr::const_def_id(*did, espan)
+                }
+            }
             fhir::ExprKind::Var(var) => env.lookup(*var).to_expr(),
             fhir::ExprKind::Literal(lit) => rty::Expr::constant_at(conv_lit(*lit), espan),
             fhir::ExprKind::BinaryOp(op, box [e1, e2]) => {
@@ -959,6 +1023,9 @@ impl ConvCtxt<'_, '_> {
             fhir::ExprKind::App(func, args) => {
                 rty::Expr::app(self.conv_func(env, func), self.conv_exprs(env, args), espan)
             }
+            fhir::ExprKind::Alias(alias, args) => {
+                rty::Expr::alias(self.conv_alias_reft(alias), self.conv_exprs(env, args), espan)
+            }
             fhir::ExprKind::IfThenElse(box [p, e1, e2]) => {
                 rty::Expr::ite(
                     self.conv_expr(env, p),
@@ -972,6 +1039,23 @@ impl ConvCtxt<'_, '_> {
         self.add_coercions(expr, fhir_id)
     }
 
+    fn conv_alias_reft(&self, alias: &fhir::AliasReft) -> rty::AliasReft {
+        let self_ty = match alias.qself {
+            fhir::Res::SelfTyParam { .. } => {
+                rty::Ty::param(rty::ParamTy { index: 0, name: kw::SelfUpper })
+            }
+            fhir::Res::Def(DefKind::TyParam, def_id) => {
+                rty::Ty::param(def_id_to_param_ty(self.genv.tcx, def_id.expect_local()))
+            }
+            res => bug!("unexpected res for associated refinement `{res:?}`"),
+        };
+        rty::AliasReft {
+            trait_id: alias.trait_id,
+            name: alias.name,
+            args: List::singleton(rty::GenericArg::Ty(self_ty)),
+        }
+    }
+
     fn conv_func(&self, env: &Env, func: &fhir::Func) -> rty::Expr {
         let expr = match func {
             fhir::Func::Var(ident, _) => env.lookup(*ident).to_expr(),
@@ -1155,6 +1239,14 @@ pub fn conv_func_decl(genv: &GlobalEnv, uif: &fhir::FuncDecl) -> rty::FuncDecl {
     rty::FuncDecl { name: uif.name, sort: conv_func_sort(genv, &uif.sort), kind: uif.kind }
 }
 
+pub(crate) fn conv_assoc_reft_sort(
+    genv: &GlobalEnv,
+    decl: &fhir::AssocRefinement,
+) -> rty::FuncSort {
+    let inputs = conv_sorts(genv, decl.params.iter().map(|param| &param.sort));
+    rty::FuncSort::new(inputs, conv_sort(genv, &decl.output))
+}
+
 fn conv_sorts<'a>(
     genv: &GlobalEnv,
     sorts: impl IntoIterator<Item = &'a fhir::Sort>,
@@ -1228,6 +1320,13 @@ fn def_id_to_param_ty(tcx: TyCtxt, def_id: LocalDefId) -> rty::ParamTy {
     }
 }
 
+fn def_id_to_param_const(tcx: TyCtxt, def_id: LocalDefId) -> ParamConst {
+    ParamConst {
+        index: def_id_to_param_index(tcx, def_id),
+        name: tcx.item_name(def_id.to_def_id()),
+    }
+}
+
 fn def_id_to_param_index(tcx: TyCtxt, def_id: LocalDefId) -> u32 {
     let item_def_id = tcx.hir().ty_param_owner(def_id);
     let generics = tcx.generics_of(item_def_id);
@@ -19,7 +19,7 @@ use flux_config as config;
 use flux_errors::ResultExt;
 use flux_macros::fluent_messages;
 use flux_middle::{
-    fhir::{self, FluxLocalDefId, WfckResults},
+    fhir::{self, FluxLocalDefId},
     global_env::GlobalEnv,
     intern::List,
     queries::{Providers, QueryErr, QueryResult},
@@ -47,6 +47,8 @@ pub fn provide(providers: &mut Providers) {
         generics_of,
         predicates_of,
         item_bounds,
+        assoc_refinement_def,
+        sort_of_assoc_reft,
     };
 }
 
@@ -76,6 +78,31 @@ fn defns(genv: &GlobalEnv) -> QueryResult<rty::Defns> {
     Ok(defns)
 }
 
+fn assoc_refinement_def(
+    genv: &GlobalEnv,
+    owner_id: LocalDefId,
+    name: Symbol,
+) -> QueryResult<Option<rty::AssocRefinementDef>> {
+    let Some(reft) = genv.map().find_assoc_refinement(owner_id, name) else {
+        return Ok(None);
+    };
+    let Some(body) = &reft.body else {
+        return Ok(None);
+    };
+    let wfckresults = genv.check_wf(owner_id)?;
+    let body = conv::conv_assoc_refinement(genv, reft, body, &wfckresults);
+    Ok(Some(rty::EarlyBinder(normalize(genv, body)?)))
+}
+
+fn sort_of_assoc_reft(
+    genv: &GlobalEnv,
+    trait_id: LocalDefId,
+    name: Symbol,
+) -> Option<rty::FuncSort> {
+    let decl = genv.map().find_assoc_refinement(trait_id, name)?;
+    Some(conv::conv_assoc_reft_sort(genv, decl))
+}
+
 fn qualifiers(genv: &GlobalEnv) -> QueryResult<Vec<rty::Qualifier>> {
     genv.map()
         .qualifiers()
@@ -88,7 +115,7 @@ fn qualifiers(genv: &GlobalEnv) -> QueryResult<Vec<rty::Qualifier>> {
 
 fn invariants_of(genv: &GlobalEnv, def_id: LocalDefId) -> QueryResult<Vec<rty::Invariant>> {
     let (params, invariants) = match genv.tcx.def_kind(def_id) {
-        DefKind::Enum => {
+        DefKind::Enum | DefKind::Union => {
             let enum_def = genv.map().get_enum(def_id);
             (&enum_def.params, &enum_def.invariants)
         }
@@ -96,7 +123,7 @@ fn invariants_of(genv: &GlobalEnv, def_id: LocalDefId) -> QueryResult<Vec<rty::I
             let struct_def = genv.map().get_struct(def_id);
             (&struct_def.params, &struct_def.invariants)
         }
-        kind => bug!("expected struct or enum found `{kind:?}`"),
+        kind => bug!("expected struct, enum or union found `{kind:?}`"),
     };
     let wfckresults = genv.check_wf(def_id)?;
     conv::conv_invariants(genv, params, invariants, &wfckresults)
@@ -108,11 +135,13 @@ fn invariants_of(genv: &GlobalEnv, def_id: LocalDefId) -> QueryResult<Vec<rty::I
 fn adt_def(genv: &GlobalEnv, def_id: LocalDefId) -> QueryResult<rty::AdtDef> {
     let invariants = invariants_of(genv, def_id)?;
     match genv.tcx.def_kind(def_id) {
-        DefKind::Enum => Ok(conv::adt_def_for_enum(genv, invariants, genv.map().get_enum(def_id))),
+        DefKind::Enum | DefKind::Union => {
+            Ok(conv::adt_def_for_enum(genv, invariants, genv.map().get_enum(def_id)))
+        }
         DefKind::Struct => {
             Ok(conv::adt_def_for_struct(genv, invariants, genv.map().get_struct(def_id)))
         }
-        kind => bug!("expected struct or enum found `{kind:?}`"),
+        kind => bug!("expected struct, enum or union found `{kind:?}`"),
     }
 }
 
@@ -151,6 +180,7 @@ fn generics_of(genv: &GlobalEnv, local_id: LocalDefId) -> QueryResult<rty::Gener
         DefKind::Impl { .. }
         | DefKind::Struct
         | DefKind::Enum
+        | DefKind::Union
         | DefKind::TyAlias { .. }
         | DefKind::OpaqueTy
         | DefKind::AssocFn
@@ -201,7 +231,11 @@ fn type_of(genv: &GlobalEnv, def_id: LocalDefId) -> QueryResult<rty::EarlyBinder
                 _ => bug!("non-type def"),
             }
         }
-        DefKind::Impl { .. } | DefKind::Struct | DefKind::Enum | DefKind::AssocTy => {
+        DefKind::Impl { .. }
+        | DefKind::Struct
+        | DefKind::Enum
+        | DefKind::Union
+        | DefKind::AssocTy => {
             let generics = genv.generics_of(def_id)?;
             let ty = genv.lower_type_of(def_id)?.skip_binder();
             Refiner::default(genv, &generics).refine_poly_ty(&ty)?
@@ -218,7 +252,7 @@ fn variants_of(
     def_id: LocalDefId,
 ) -> QueryResult<rty::Opaqueness<rty::EarlyBinder<rty::PolyVariants>>> {
     let variants = match genv.tcx.def_kind(def_id) {
-        DefKind::Enum => {
+        DefKind::Enum | DefKind::Union => {
             let enum_def = genv.map().get_enum(def_id);
             let wfckresults = genv.check_wf(def_id)?;
             let variants = conv::ConvCtxt::conv_enum_def_variants(genv, enum_def, &wfckresults)?
@@ -235,7 +269,7 @@ fn variants_of(
                 .map(|variant| rty::EarlyBinder(List::singleton(variant)))
         }
         kind => {
-            bug!("expected struct or enum found `{kind:?}`")
+            bug!("expected struct, enum or union found `{kind:?}`")
         }
     };
     if config::dump_rty() {
@@ -286,7 +320,7 @@ fn check_wf_rust_item(genv: &GlobalEnv, def_id: LocalDefId) -> QueryResult<Rc<fh
             annot_check::check_struct_def(genv.tcx, genv.sess, &mut wfckresults, struct_def)?;
             wfckresults
         }
-        DefKind::Enum => {
+        DefKind::Enum | DefKind::Union => {
             let enum_def = genv.map().get_enum(def_id);
             let mut wfckresults = wf::check_enum_def(genv, enum_def)?;
             annot_check::check_enum_def(genv.tcx, genv.sess, &mut wfckresults, enum_def)?;
@@ -304,9 +338,15 @@ fn check_wf_rust_item(genv: &GlobalEnv, def_id: LocalDefId) -> QueryResult<Rc<fh
             let opaque_ty = genv.map().get_opaque_ty(def_id).unwrap();
             wf::check_opaque_ty(genv, opaque_ty, owner_id)?
         }
-        DefKind::Impl { .. } => {
-            // We currently dont support refinements on an impl item, so there's nothing to check here.
-            WfckResults::new(OwnerId { def_id })
+        DefKind::Impl { .. } | DefKind::Trait => {
+            wf::check_assoc_refinements(genv, OwnerId { def_id })?
+        }
+        DefKind::Closure if genv.map().has_fn_sig(def_id) => {
+            let owner_id = OwnerId { def_id };
+            let fn_sig = genv.map().get_fn_sig(def_id);
+            let wfckresults = wf::check_fn_sig(genv, fn_sig, owner_id)?;
+            annot_check::check_closure_sig(genv.tcx, genv.sess, def_id, fn_sig)?;
+            wfckresults
         }
         DefKind::Closure | DefKind::Coroutine | DefKind::TyParam => {
             let parent = genv.tcx.local_parent(def_id);
@@ -325,8 +365,11 @@ pub fn check_crate_wf(genv: &GlobalEnv) -> Result<(), ErrorGuaranteed> {
             DefKind::TyAlias { .. }
             | DefKind::Struct
             | DefKind::Enum
+            | DefKind::Union
             | DefKind::Fn
             | DefKind::AssocFn
+            | DefKind::Trait
+            | DefKind::Impl { .. }
             | DefKind::OpaqueTy => {
                 err = genv.check_wf(def_id).emit(genv.sess).err().or(err);
             }
@@ -334,6 +377,12 @@ pub fn check_crate_wf(genv: &GlobalEnv) -> Result<(), ErrorGuaranteed> {
         }
     }
 
+    for def_id in genv.hir().body_owners() {
+        if genv.tcx.def_kind(def_id) == DefKind::Closure && genv.map().has_fn_sig(def_id) {
+            err = genv.check_wf(def_id).emit(genv.sess).err().or(err);
+        }
+    }
+
     for defn in genv.map().defns() {
         err = genv
             .check_wf(FluxLocalDefId::Flux(defn.name))
@@ -78,6 +78,61 @@ impl UnknownQualifier {
     }
 }
 
+#[derive(Diagnostic)]
+#[diag(fhir_analysis_unknown_assoc_refinement, code = "FLUX")]
+pub(super) struct UnknownAssocRefinement {
+    #[primary_span]
+    #[label]
+    span: Span,
+    name: Symbol,
+    trait_name: String,
+}
+
+impl UnknownAssocRefinement {
+    pub(super) fn new(span: Span, name: Symbol, trait_name: String) -> Self {
+        Self { span, name, trait_name }
+    }
+}
+
+#[derive(Diagnostic)]
+#[diag(fhir_analysis_incompatible_assoc_refinement, code = "FLUX")]
+pub(super) struct IncompatibleAssocRefinement {
+    #[primary_span]
+    #[label]
+    span: Span,
+    name: Symbol,
+    expected: String,
+    #[label(fhir_analysis_decl_label)]
+    decl_span: Span,
+}
+
+impl IncompatibleAssocRefinement {
+    pub(super) fn new(impl_reft: &fhir::AssocRefinement, decl: &fhir::AssocRefinement) -> Self {
+        let inputs = decl.params.iter().map(|param| format!("{:?}", param.sort));
+        Self {
+            span: impl_reft.span,
+            name: decl.name,
+            expected: format!("({}) -> {:?}", inputs.collect::<Vec<_>>().join(", "), decl.output),
+            decl_span: decl.span,
+        }
+    }
+}
+
+#[derive(Diagnostic)]
+#[diag(fhir_analysis_missing_assoc_refinement, code = "FLUX")]
+pub(super) struct MissingAssocRefinement {
+    #[primary_span]
+    #[label]
+    span: Span,
+    name: Symbol,
+}
+
+impl MissingAssocRefinement {
+    pub(super) fn new(span: Span, name: Symbol) -> Self {
+        Self { span, name }
+    }
+}
+
 #[derive(Diagnostic)]
 #[diag(fhir_analysis_missing_ensures, code = "FLUX")]
 pub(super) struct MissingEnsures {
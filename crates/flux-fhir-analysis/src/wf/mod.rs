@@ -60,6 +60,71 @@ pub(crate) fn check_defn(
     Ok(infcx.into_results())
 }
 
+/// Checks the associated refinements of a trait or an impl. For an impl we additionally check that
+/// its definitions match the declarations in the implemented trait.
+pub(crate) fn check_assoc_refinements(
+    genv: &GlobalEnv,
+    owner_id: OwnerId,
+) -> Result<WfckResults, ErrorGuaranteed> {
+    let mut infcx = InferCtxt::new(genv, owner_id.into());
+    let assoc_refts = genv.map().assoc_refinements_of(owner_id.def_id);
+
+    assoc_refts.iter().try_for_each_exhaust(|reft| {
+        if let Some(body) = &reft.body {
+            infcx.push_layer(&reft.params);
+            infcx.check_expr(body, &reft.output)?;
+        }
+        Ok(())
+    })?;
+
+    if let DefKind::Impl { of_trait: true } = genv.tcx.def_kind(owner_id)
+        && let Some(trait_ref) = genv.tcx.impl_trait_ref(owner_id)
+    {
+        let trait_id = trait_ref.skip_binder().def_id;
+        let decls = trait_id
+            .as_local()
+            .map(|trait_id| genv.map().assoc_refinements_of(trait_id))
+            .unwrap_or_default();
+
+        assoc_refts.iter().try_for_each_exhaust(|reft| {
+            let Some(decl) = decls.iter().find(|decl| decl.name == reft.name) else {
+                return Err(genv.sess.emit_err(errors::UnknownAssocRefinement::new(
+                    reft.span,
+                    reft.name,
+                    genv.tcx.def_path_str(trait_id),
+                )));
+            };
+            let compatible = reft.output == decl.output
+                && reft.params.len() == decl.params.len()
+                && iter::zip(&reft.params, &decl.params).all(|(p1, p2)| p1.sort == p2.sort);
+            if !compatible {
+                return Err(genv
+                    .sess
+                    .emit_err(errors::IncompatibleAssocRefinement::new(reft, decl)));
+            }
+            if reft.body.is_none() {
+                return Err(genv
+                    .sess
+                    .emit_err(errors::MissingAssocRefinement::new(reft.span, reft.name)));
+            }
+            Ok(())
+        })?;
+
+        // Declarations in the trait with a default body don't need to be defined in the impl
+        decls.iter().try_for_each_exhaust(|decl| {
+            if decl.body.is_none() && !assoc_refts.iter().any(|reft| reft.name == decl.name) {
+                let span = genv.tcx.def_span(owner_id);
+                return Err(genv
+                    .sess
+                    .emit_err(errors::MissingAssocRefinement::new(span, decl.name)));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(infcx.into_results())
+}
+
 pub(crate) fn check_fn_quals(
     sess: &FluxSession,
     qualifiers: &FxHashSet<Symbol>,
@@ -169,8 +234,10 @@ pub(crate) fn check_fn_sig(
         .iter()
         .try_for_each_exhaust(|constr| wf.check_constraint(&mut infcx, constr));
 
-    let predicates = genv.map().get_generic_predicates(owner_id.def_id).unwrap();
-    wf.check_generic_predicates(&mut infcx, predicates)?;
+    // Closures don't have predicates of their own
+    if let Some(predicates) = genv.map().get_generic_predicates(owner_id.def_id) {
+        wf.check_generic_predicates(&mut infcx, predicates)?;
+    }
 
     let output = wf.check_fn_output(&mut infcx, &fn_sig.output);
 
@@ -348,6 +415,12 @@ impl<'a, 'tcx> Wf<'a, 'tcx> {
             fhir::TyKind::Ref(_, fhir::MutTy { ty, .. }) | fhir::TyKind::Array(ty, _) => {
                 self.check_type(infcx, ty)
             }
+            fhir::TyKind::BareFn(inputs, output) => {
+                inputs
+                    .iter()
+                    .chain([&**output])
+                    .try_for_each_exhaust(|ty| self.check_type(infcx, ty))
+            }
             fhir::TyKind::Constr(pred, ty) => {
                 self.check_type(infcx, ty)?;
                 self.check_pred(infcx, pred)
@@ -357,7 +430,6 @@ impl<'a, 'tcx> Wf<'a, 'tcx> {
                 let def_id = item_id.owner_id.to_def_id();
                 self.check_generic_args(infcx, def_id, args)
             }
-            fhir::TyKind::RawPtr(ty, _) => self.check_type(infcx, ty),
             fhir::TyKind::Hole(_) | fhir::TyKind::Never => Ok(()),
         }
     }
@@ -389,7 +461,7 @@ impl<'a, 'tcx> Wf<'a, 'tcx> {
             fhir::TyKind::Ptr(_, _)
             | fhir::TyKind::Ref(_, _)
             | fhir::TyKind::Array(_, _)
-            | fhir::TyKind::RawPtr(_, _)
+            | fhir::TyKind::BareFn(_, _)
             | fhir::TyKind::OpaqueDef(_, _, _, _)
             | fhir::TyKind::Never
             | fhir::TyKind::Hole(_) => self.emit_err(errors::InvalidBaseInstance::new(ty)),
@@ -459,7 +531,9 @@ impl<'a, 'tcx> Wf<'a, 'tcx> {
                 }
                 self.check_path(infcx, path)
             }
-            fhir::BaseTyKind::Slice(ty) => self.check_type(infcx, ty),
+            fhir::BaseTyKind::Slice(ty) | fhir::BaseTyKind::RawPtr(ty, _) => {
+                self.check_type(infcx, ty)
+            }
         }
     }
 
@@ -575,6 +649,10 @@ impl<'a, 'tcx> Wf<'a, 'tcx> {
                 }
                 Ok(())
             }
+            fhir::ExprKind::Alias(_, args) => {
+                args.iter()
+                    .try_for_each_exhaust(|arg| self.check_param_uses_expr(infcx, arg, false))
+            }
             fhir::ExprKind::IfThenElse(exprs) => {
                 exprs
                     .iter()
@@ -127,6 +127,7 @@ impl<'a, 'tcx> InferCtxt<'a, 'tcx> {
             fhir::ExprKind::UnaryOp(_, _)
             | fhir::ExprKind::Dot(_, _)
             | fhir::ExprKind::App(_, _)
+            | fhir::ExprKind::Alias(_, _)
             | fhir::ExprKind::Const(_, _)
             | fhir::ExprKind::Var(_)
             | fhir::ExprKind::Literal(_) => {
@@ -200,6 +201,7 @@ impl<'a, 'tcx> InferCtxt<'a, 'tcx> {
             fhir::ExprKind::UnaryOp(op, e) => self.synth_unary_op(*op, e),
             fhir::ExprKind::Const(_, _) => Ok(fhir::Sort::Int), // TODO: generalize const sorts
             fhir::ExprKind::App(f, es) => self.synth_app(f, es, expr.span),
+            fhir::ExprKind::Alias(alias, es) => self.synth_alias(alias, es, expr.span),
             fhir::ExprKind::IfThenElse(box [p, e1, e2]) => {
                 self.check_expr(p, &fhir::Sort::Bool)?;
                 let sort = self.synth_expr(e1)?;
@@ -309,6 +311,39 @@ impl<'a, 'tcx> InferCtxt<'a, 'tcx> {
         Ok(fsort.output().clone())
     }
 
+    fn synth_alias(
+        &mut self,
+        alias: &fhir::AliasReft,
+        args: &[fhir::Expr],
+        span: Span,
+    ) -> Result<fhir::Sort, ErrorGuaranteed> {
+        let genv = self.genv;
+        let Some(decl) = alias
+            .trait_id
+            .as_local()
+            .and_then(|trait_id| genv.map().find_assoc_refinement(trait_id, alias.name))
+        else {
+            return Err(self.emit_err(errors::UnknownAssocRefinement::new(
+                alias.span,
+                alias.name,
+                genv.tcx.def_path_str(alias.trait_id),
+            )));
+        };
+        if args.len() != decl.params.len() {
+            return Err(self.emit_err(errors::ArgCountMismatch::new(
+                Some(span),
+                String::from("associated refinement"),
+                decl.params.len(),
+                args.len(),
+            )));
+        }
+
+        iter::zip(args, &decl.params)
+            .try_for_each_exhaust(|(arg, param)| self.check_expr(arg, &param.sort))?;
+
+        Ok(decl.output.clone())
+    }
+
     fn synth_func(&mut self, func: &fhir::Func) -> Result<fhir::FuncSort, ErrorGuaranteed> {
         let func_sort = match func {
             fhir::Func::Var(var, fhir_id) => {
@@ -44,6 +44,14 @@ impl BigInt {
     pub fn uint_max(bit_width: u32) -> BigInt {
         (u128::MAX >> (128 - bit_width)).into()
     }
+
+    /// Returns the value as a `u32` if it is non-negative and fits in one.
+    pub fn to_u32(&self) -> Option<u32> {
+        match self.sign {
+            Sign::NonNegative => u32::try_from(self.val).ok(),
+            Sign::Negative => None,
+        }
+    }
 }
 
 impl From<usize> for BigInt {
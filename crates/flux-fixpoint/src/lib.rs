@@ -1,19 +1,26 @@
 #![feature(rustc_private, min_specialization, lazy_cell, box_patterns, let_chains)]
 
+// Tests are linked as executables so they need the compiler's shared library
+#[cfg(test)]
+extern crate rustc_driver as _;
 extern crate rustc_macros;
 extern crate rustc_serialize;
 extern crate rustc_span;
 
 pub mod big_int;
 mod constraint;
+mod parser;
 
 use std::{
     collections::hash_map::DefaultHasher,
     fmt::{self, Write as FmtWrite},
     hash::{Hash, Hasher},
-    io::{self, BufWriter, Write as IOWrite},
-    process::{Command, Stdio},
+    io::{self, BufWriter, Read, Write as IOWrite},
+    process::{Child, Command, Stdio},
     str::FromStr,
+    sync::mpsc,
+    thread,
+    time::Duration,
 };
 
 pub use constraint::{
@@ -24,6 +31,7 @@ use derive_where::derive_where;
 use flux_common::{cache::QueryCache, format::PadAdapter};
 use flux_config as config;
 use itertools::Itertools;
+pub use parser::{parse_expr, ParseError};
 use serde::{de, Deserialize};
 
 use crate::constraint::DEFAULT_QUALIFIERS;
@@ -115,6 +123,22 @@ pub struct Stats {
 #[derive(Deserialize, Debug)]
 pub struct CrashInfo(Vec<serde_json::Value>);
 
+/// The solution found by fixpoint for a kvar. The solution is an expression over the kvar's
+/// parameters in the syntax accepted by [`parse_expr`].
+#[derive(Deserialize, Debug)]
+pub struct KVarBind {
+    pub kvar: String,
+    pub params: Vec<String>,
+    pub val: String,
+}
+
+/// The output printed by fixpoint with `--json`. The solution is reported next to the result in
+/// a `solution` field, which is missing in versions of fixpoint that don't report solutions.
+#[derive(Deserialize)]
+struct Output {
+    solution: Option<Vec<KVarBind>>,
+}
+
 #[derive_where(Hash)]
 pub struct KVar<T: Types> {
     kvid: T::KVar,
@@ -141,10 +165,14 @@ impl<T: Types> Task<T> {
         hasher.finish()
     }
 
+    /// Checks the task, returning early if it is already in the `cache`. If fixpoint doesn't
+    /// finish within `timeout` the process, together with the smt solver it spawned, is killed
+    /// and an error of kind [`io::ErrorKind::TimedOut`] is returned.
     pub fn check_with_cache(
         &self,
         key: String,
         cache: &mut QueryCache,
+        timeout: Option<Duration>,
     ) -> io::Result<FixpointResult<T::Tag>> {
         let hash = self.hash_with_default();
 
@@ -152,7 +180,7 @@ impl<T: Types> Task<T> {
             return Ok(FixpointResult::Safe(Default::default()));
         }
 
-        let result = self.check();
+        let result = self.check(false, timeout).map(|(result, _)| result);
 
         if config::is_cache_enabled()
             && let Ok(FixpointResult::Safe(_)) = result
@@ -162,27 +190,115 @@ impl<T: Types> Task<T> {
         result
     }
 
-    fn check(&self) -> io::Result<FixpointResult<T::Tag>> {
-        let mut child = Command::new("fixpoint")
-            .arg("-q")
-            .arg("--stdin")
-            .arg("--json")
+    /// Like [`Task::check_with_cache`] but it neither reads nor updates the cache.
+    pub fn check_without_cache(
+        &self,
+        timeout: Option<Duration>,
+    ) -> io::Result<FixpointResult<T::Tag>> {
+        self.check(false, timeout).map(|(result, _)| result)
+    }
+
+    /// Like [`Task::check_with_cache`] but also returns the solutions fixpoint found for the
+    /// kvars. The cache is not used because cached results don't record solutions. Kvars are not
+    /// eliminated so every kvar in the task gets a solution.
+    pub fn check_with_solution(
+        &self,
+        timeout: Option<Duration>,
+    ) -> io::Result<(FixpointResult<T::Tag>, Vec<KVarBind>)> {
+        self.check(true, timeout)
+    }
+
+    fn check(
+        &self,
+        with_solution: bool,
+        timeout: Option<Duration>,
+    ) -> io::Result<(FixpointResult<T::Tag>, Vec<KVarBind>)> {
+        let mut cmd = Command::new("fixpoint");
+        cmd.arg("-q").arg("--stdin").arg("--json");
+        if with_solution {
+            cmd.arg("--eliminate=none");
+        }
+        // Run fixpoint in its own process group so we can also kill the smt solver on timeout.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+        let mut child = cmd
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
-            .spawn()
-            .unwrap();
-        let mut stdin = None;
-        std::mem::swap(&mut stdin, &mut child.stdin);
-        {
-            let mut w = BufWriter::new(stdin.unwrap());
-            writeln!(w, "{self}")?;
-        }
-        let out = child.wait_with_output()?;
+            .spawn()?;
+
+        // Both ends of the pipe are handled in their own thread such that we can stop waiting if
+        // fixpoint hangs while reading the input or while solving.
+        let input = self.to_string();
+        let stdin = child.stdin.take().unwrap();
+        thread::spawn(move || {
+            let mut w = BufWriter::new(stdin);
+            // If fixpoint exits early the error is reported when parsing its output
+            let _ = writeln!(w, "{input}");
+        });
+        let mut stdout = child.stdout.take().unwrap();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = vec![];
+            let _ = tx.send(stdout.read_to_end(&mut buf).map(|_| buf));
+        });
+
+        let stdout = match timeout {
+            Some(timeout) => {
+                match rx.recv_timeout(timeout) {
+                    Ok(stdout) => stdout?,
+                    Err(_) => {
+                        kill_process_group(&mut child)?;
+                        child.wait()?;
+                        return Err(io::ErrorKind::TimedOut.into());
+                    }
+                }
+            }
+            None => {
+                rx.recv()
+                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))??
+            }
+        };
+        child.wait()?;
+
+        let result = serde_json::from_slice(&stdout)?;
+        let solution = if with_solution { parse_solution(&result, &stdout)? } else { vec![] };
+
+        Ok((result, solution))
+    }
+}
 
-        let result = serde_json::from_slice(&out.stdout)?;
+/// Kills a child spawned as the leader of its own process group and all the processes in the group.
+fn kill_process_group(child: &mut Child) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let pgid = libc::pid_t::try_from(child.id())
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        // SAFETY: `kill` has no memory safety requirements. A negative pid sends the signal to
+        // every process in the group.
+        if unsafe { libc::kill(-pgid, libc::SIGKILL) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    child.kill()
+}
 
-        Ok(result)
+/// Extracts the solution from the output of fixpoint. The solution is only meaningful when the
+/// constraint is safe, so it is an error if it is missing in that case.
+fn parse_solution<Tag>(result: &FixpointResult<Tag>, stdout: &[u8]) -> io::Result<Vec<KVarBind>> {
+    let output = serde_json::from_slice::<Output>(stdout)?;
+    match (result, output.solution) {
+        (_, Some(solution)) => Ok(solution),
+        (FixpointResult::Safe(_), None) => {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "fixpoint did not report a solution, make sure your version of fixpoint \
+                 supports reporting solutions with `--json`",
+            ))
+        }
+        (_, None) => Ok(vec![]),
     }
 }
 
@@ -269,3 +385,37 @@ impl<'de, Tag: FromStr> Deserialize<'de> for Error<Tag> {
         Ok(Error { id, tag })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_solution_from_output() {
+        let stdout = br#"{"contents":{"numChck":2,"numCstr":2,"numIter":3,"numVald":2},"solution":[{"kvar":"$k0","params":["lq_karg$nnf_arg$##k0##0","lq_karg$nnf_arg$##k0##1"],"val":"((lq_karg$nnf_arg$##k0##0 >= 0) && (lq_karg$nnf_arg$##k0##1 = 1))"}],"tag":"Safe"}"#;
+        let result = serde_json::from_slice::<FixpointResult<String>>(stdout).unwrap();
+        let solution = parse_solution(&result, stdout).unwrap();
+        let [bind] = &solution[..] else { panic!("expected a single kvar in the solution") };
+        assert_eq!(bind.kvar, "$k0");
+        assert_eq!(bind.params, ["lq_karg$nnf_arg$##k0##0", "lq_karg$nnf_arg$##k0##1"]);
+        assert!(parse_expr::<StringTypes>(&bind.val, |var| {
+            ["lq_karg$nnf_arg$##k0##0", "lq_karg$nnf_arg$##k0##1"]
+                .into_iter()
+                .find(|param| *param == var)
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn missing_solution_is_an_error_if_safe() {
+        let stdout =
+            br#"{"contents":{"numChck":2,"numCstr":2,"numIter":3,"numVald":2},"tag":"Safe"}"#;
+        let result = serde_json::from_slice::<FixpointResult<String>>(stdout).unwrap();
+        let err = parse_solution(&result, stdout).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let stdout = br#"{"contents":[{"numChck":2,"numCstr":2,"numIter":3,"numVald":1},[[1,"0"]]],"tag":"Unsafe"}"#;
+        let result = serde_json::from_slice::<FixpointResult<String>>(stdout).unwrap();
+        assert!(parse_solution(&result, stdout).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+//! A parser for the expression syntax fixpoint uses to report kvar solutions.
+//!
+//! The grammar is the one we use to print [`Expr`] (see its `Display` implementation) with the
+//! usual precedence and associativity for infix operators, so parentheses are optional.
+use std::{iter::Peekable, marker::PhantomData, str::CharIndices};
+
+use rustc_span::Symbol;
+
+use crate::{big_int::BigInt, BinOp, Constant, Expr, Func, Proj, Types, UnOp};
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub msg: String,
+    pub pos: usize,
+}
+
+/// Parses an expression. Identifiers are resolved as variables with `resolve_var`. Identifiers
+/// that fail to resolve are only accepted in function position where they are interpreted as
+/// theory functions.
+pub fn parse_expr<T: Types>(
+    src: &str,
+    resolve_var: impl FnMut(&str) -> Option<T::Var>,
+) -> Result<Expr<T>, ParseError> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser { tokens, pos: 0, resolve_var, _types: PhantomData };
+    let expr = parser.expr()?;
+    if let Some((tok, pos)) = parser.tokens.get(parser.pos) {
+        return Err(ParseError { msg: format!("unexpected token `{tok:?}`"), pos: *pos });
+    }
+    Ok(expr)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(u128),
+    Real(i128),
+    Op(&'static str),
+    OpenParen,
+    CloseParen,
+}
+
+/// Operators sorted such that no operator is a prefix of an operator appearing earlier.
+const OPERATORS: &[&str] = &[
+    "<=>", "==>", "=>", "==", "/=", "!=", "<=", ">=", "&&", "||", "<", ">", "=", "+", "-", "*",
+    "/", "~", "!",
+];
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars: Peekable<CharIndices> = src.char_indices().peekable();
+    while let Some(&(pos, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' {
+            chars.next();
+            tokens.push((Token::OpenParen, pos));
+        } else if c == ')' {
+            chars.next();
+            tokens.push((Token::CloseParen, pos));
+        } else if c.is_ascii_digit() {
+            let mut end = pos;
+            while let Some(&(i, c)) = chars.peek()
+                && (c.is_ascii_digit() || c == '.')
+            {
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let lit = &src[pos..end];
+            let tok = if let Some((int, frac)) = lit.split_once('.') {
+                if frac.chars().any(|c| c != '0') {
+                    return Err(ParseError { msg: format!("unsupported real `{lit}`"), pos });
+                }
+                int.parse().map(Token::Real)
+            } else {
+                lit.parse().map(Token::Int)
+            };
+            let tok =
+                tok.map_err(|_| ParseError { msg: format!("invalid literal `{lit}`"), pos })?;
+            tokens.push((tok, pos));
+        } else if is_ident_start(c) {
+            let mut end = pos;
+            while let Some(&(i, c)) = chars.peek()
+                && is_ident_char(c)
+            {
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push((Token::Ident(src[pos..end].to_string()), pos));
+        } else if let Some(op) = OPERATORS.iter().find(|op| src[pos..].starts_with(**op)) {
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push((Token::Op(op), pos));
+        } else {
+            return Err(ParseError { msg: format!("unexpected character `{c}`"), pos });
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '$' | '#' | '\'' | '.')
+}
+
+struct Parser<T, F> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    resolve_var: F,
+    _types: PhantomData<fn() -> T>,
+}
+
+/// Binary operators grouped by precedence, from lowest to highest.
+const PRECEDENCE: &[&[(&str, BinOp)]] = &[
+    &[("<=>", BinOp::Iff)],
+    &[("=>", BinOp::Imp), ("==>", BinOp::Imp)],
+    &[("||", BinOp::Or), ("or", BinOp::Or)],
+    &[("&&", BinOp::And), ("and", BinOp::And)],
+    &[
+        ("=", BinOp::Eq),
+        ("==", BinOp::Eq),
+        ("/=", BinOp::Ne),
+        ("!=", BinOp::Ne),
+        ("<", BinOp::Lt),
+        ("<=", BinOp::Le),
+        (">", BinOp::Gt),
+        (">=", BinOp::Ge),
+    ],
+    &[("+", BinOp::Add), ("-", BinOp::Sub)],
+    &[("*", BinOp::Mul), ("/", BinOp::Div), ("mod", BinOp::Mod)],
+];
+
+impl<T: Types, F: FnMut(&str) -> Option<T::Var>> Parser<T, F> {
+    fn expr(&mut self) -> Result<Expr<T>, ParseError> {
+        if self.eat_ident("if") {
+            let p = self.expr()?;
+            self.expect_ident("then")?;
+            let e1 = self.expr()?;
+            self.expect_ident("else")?;
+            let e2 = self.expr()?;
+            return Ok(Expr::IfThenElse(Box::new([p, e1, e2])));
+        }
+        self.binary(0)
+    }
+
+    /// Parses a sequence of binary operators of precedence `level` or higher. Implications are
+    /// right associative and everything else is left associative.
+    fn binary(&mut self, level: usize) -> Result<Expr<T>, ParseError> {
+        let Some(ops) = PRECEDENCE.get(level) else { return self.unary() };
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(op) = self.eat_binop(ops) {
+            if op == BinOp::Imp {
+                let rhs = self.binary(level)?;
+                return Ok(Expr::BinaryOp(op, Box::new([lhs, rhs])));
+            }
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::BinaryOp(op, Box::new([lhs, rhs]));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr<T>, ParseError> {
+        if self.eat(&Token::Op("~")) || self.eat(&Token::Op("!")) || self.eat_ident("not") {
+            return Ok(Expr::UnaryOp(UnOp::Not, Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Op("-")) {
+            return Ok(Expr::UnaryOp(UnOp::Neg, Box::new(self.unary()?)));
+        }
+        self.app()
+    }
+
+    fn app(&mut self) -> Result<Expr<T>, ParseError> {
+        let Some((Token::Ident(name), pos)) = self.tokens.get(self.pos).cloned() else {
+            return self.atom();
+        };
+        if is_keyword(&name) {
+            return self.atom();
+        }
+        self.pos += 1;
+        let mut args = vec![];
+        while self.at_atom_start() {
+            args.push(self.atom()?);
+        }
+        let var = (self.resolve_var)(&name);
+        match (var, &name[..], &mut args[..]) {
+            (Some(var), _, []) => Ok(Expr::Var(var)),
+            (Some(var), _, _) => Ok(Expr::App(Func::Var(var), args)),
+            (None, "fst", [_]) => Ok(Expr::Proj(Box::new(args.remove(0)), Proj::Fst)),
+            (None, "snd", [_]) => Ok(Expr::Proj(Box::new(args.remove(0)), Proj::Snd)),
+            (None, "Pair", [_, _]) => {
+                let e2 = args.remove(1);
+                let e1 = args.remove(0);
+                Ok(Expr::Pair(Box::new([e1, e2])))
+            }
+            (None, "Unit", []) => Ok(Expr::Unit),
+            (None, _, [_, ..]) => Ok(Expr::App(Func::Itf(Symbol::intern(&name)), args)),
+            (None, _, []) => Err(ParseError { msg: format!("unknown variable `{name}`"), pos }),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr<T>, ParseError> {
+        let Some((tok, pos)) = self.tokens.get(self.pos).cloned() else {
+            return Err(ParseError { msg: "unexpected end of input".to_string(), pos: usize::MAX });
+        };
+        self.pos += 1;
+        match tok {
+            Token::Int(n) => Ok(Expr::Constant(Constant::Int(BigInt::from(n)))),
+            Token::Real(r) => Ok(Expr::Constant(Constant::Real(r))),
+            Token::Ident(name) if name == "true" => Ok(Expr::Constant(Constant::Bool(true))),
+            Token::Ident(name) if name == "false" => Ok(Expr::Constant(Constant::Bool(false))),
+            Token::Ident(_) => {
+                // Identifiers in argument position are not applied to anything.
+                self.pos -= 1;
+                let Some((Token::Ident(name), pos)) = self.tokens.get(self.pos).cloned() else {
+                    unreachable!()
+                };
+                self.pos += 1;
+                match (self.resolve_var)(&name) {
+                    Some(var) => Ok(Expr::Var(var)),
+                    None if name == "Unit" => Ok(Expr::Unit),
+                    None => Err(ParseError { msg: format!("unknown variable `{name}`"), pos }),
+                }
+            }
+            Token::OpenParen => {
+                let e = self.expr()?;
+                if !self.eat(&Token::CloseParen) {
+                    return Err(ParseError { msg: "expected `)`".to_string(), pos });
+                }
+                Ok(e)
+            }
+            tok => Err(ParseError { msg: format!("unexpected token `{tok:?}`"), pos }),
+        }
+    }
+
+    fn at_atom_start(&self) -> bool {
+        match self.tokens.get(self.pos) {
+            Some((Token::Ident(name), _)) => !is_keyword(name),
+            Some((Token::Int(_) | Token::Real(_) | Token::OpenParen, _)) => true,
+            _ => false,
+        }
+    }
+
+    fn eat_binop(&mut self, ops: &[(&str, BinOp)]) -> Option<BinOp> {
+        let (tok, _) = self.tokens.get(self.pos)?;
+        let s = match tok {
+            Token::Op(op) => *op,
+            Token::Ident(name) => name.as_str(),
+            _ => return None,
+        };
+        let (_, op) = ops.iter().find(|(op, _)| *op == s)?;
+        self.pos += 1;
+        Some(*op)
+    }
+
+    fn eat(&mut self, tok: &Token) -> bool {
+        if self.tokens.get(self.pos).is_some_and(|(t, _)| t == tok) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_ident(&mut self, kw: &str) -> bool {
+        self.eat(&Token::Ident(kw.to_string()))
+    }
+
+    fn expect_ident(&mut self, kw: &str) -> Result<(), ParseError> {
+        if self.eat_ident(kw) {
+            Ok(())
+        } else {
+            let pos = self
+                .tokens
+                .get(self.pos)
+                .map_or(usize::MAX, |(_, pos)| *pos);
+            Err(ParseError { msg: format!("expected `{kw}`"), pos })
+        }
+    }
+}
+
+fn is_keyword(s: &str) -> bool {
+    matches!(s, "if" | "then" | "else" | "not" | "and" | "or" | "mod" | "true" | "false")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StringTypes;
+
+    /// Parameters of the kvars in the solutions below as named by fixpoint
+    const PARAMS: &[&str] =
+        &["lq_karg$nnf_arg$##k0##0", "lq_karg$nnf_arg$##k0##1", "lq_karg$nnf_arg$##k0##2"];
+
+    fn parse(src: &str) -> Result<String, ParseError> {
+        let expr = parse_expr::<StringTypes>(src, |var| {
+            PARAMS.iter().copied().find(|param| *param == var)
+        })?;
+        Ok(expr.to_string().replace("lq_karg$nnf_arg$##k0##", "a"))
+    }
+
+    #[test]
+    fn parses_fixpoint_solutions() {
+        let cases = [
+            ("true", "true"),
+            (
+                "((lq_karg$nnf_arg$##k0##0 >= 0) && (lq_karg$nnf_arg$##k0##0 <= lq_karg$nnf_arg$##k0##1))",
+                "(a0 >= 0) && (a0 <= a1)",
+            ),
+            (
+                "(lq_karg$nnf_arg$##k0##1 = (lq_karg$nnf_arg$##k0##0 + 1))",
+                "a1 = (a0 + 1)",
+            ),
+            ("(lq_karg$nnf_arg$##k0##2 <=> (lq_karg$nnf_arg$##k0##0 > 0))", "a2 <=> (a0 > 0)"),
+            ("(not lq_karg$nnf_arg$##k0##2)", "~a2"),
+            ("((lq_karg$nnf_arg$##k0##0 mod 2) = 0)", "(a0 mod 2) = 0"),
+            ("(lq_karg$nnf_arg$##k0##0 != lq_karg$nnf_arg$##k0##1)", "a0 /= a1"),
+            ("(lq_karg$nnf_arg$##k0##0 >= (-1))", "a0 >= -1"),
+            (
+                "(if (lq_karg$nnf_arg$##k0##0 > 0) then lq_karg$nnf_arg$##k0##0 else 0)",
+                "if a0 > 0 then a0 else 0",
+            ),
+            ("((fst lq_karg$nnf_arg$##k0##0) = 1)", "(fst a0) = 1"),
+        ];
+        for (src, expected) in cases {
+            assert_eq!(parse(src).unwrap(), expected, "while parsing `{src}`");
+        }
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        let cases = [
+            ("lq_karg$nnf_arg$##k0##0 + 1 * 2 = 2", "(a0 + (1 * 2)) = 2"),
+            ("1 - 2 - 3 = 0", "((1 - 2) - 3) = 0"),
+            ("true && false || true", "(true && false) || true"),
+            ("true => false => true", "true => (false => true)"),
+        ];
+        for (src, expected) in cases {
+            assert_eq!(parse(src).unwrap(), expected, "while parsing `{src}`");
+        }
+    }
+
+    #[test]
+    fn reports_errors() {
+        let cases = [
+            ("(lq_karg$nnf_arg$##k0##0 > 0", "expected `)`"),
+            ("unknown > 0", "unknown variable `unknown`"),
+            ("lq_karg$nnf_arg$##k0##0 > 0.5", "unsupported real `0.5`"),
+            ("lq_karg$nnf_arg$##k0##0 > 0)", "unexpected token `CloseParen`"),
+            ("lq_karg$nnf_arg$##k0##0 >", "unexpected end of input"),
+            ("lq_karg$nnf_arg$##k0##0 ? 0", "unexpected character `?`"),
+        ];
+        for (src, expected) in cases {
+            let err = parse(src).unwrap_err();
+            assert_eq!(err.msg, expected, "while parsing `{src}`");
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Running flux and translating the diagnostics it reports in rustc's json format.
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    fmt::Write as _,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::Result;
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Position, Range, Url,
+};
+use serde::Deserialize;
+
+use crate::resolve_path;
+
+/// An invocation of flux on the crate containing a file.
+pub(crate) struct FluxRun {
+    kind: RunKind,
+    /// Directory where the command is executed.
+    cwd: PathBuf,
+    /// Directory where flux dumps the types of locals.
+    log_dir: PathBuf,
+}
+
+enum RunKind {
+    /// Check the package in `cwd` with `cargo flux`.
+    Cargo,
+    /// Check a single file as a library with `rustc-flux`.
+    File(PathBuf),
+}
+
+impl FluxRun {
+    pub(crate) fn new(file: &Path) -> FluxRun {
+        let manifest_dir = file
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join("Cargo.toml").is_file());
+        let (kind, cwd) = match manifest_dir {
+            Some(dir) => (RunKind::Cargo, dir.to_path_buf()),
+            None => {
+                let dir = file.parent().unwrap_or(Path::new("."));
+                (RunKind::File(file.to_path_buf()), dir.to_path_buf())
+            }
+        };
+        let mut hasher = DefaultHasher::new();
+        cwd.hash(&mut hasher);
+        let log_dir = env::temp_dir()
+            .join("flux-lsp")
+            .join(format!("{:x}", hasher.finish()));
+        FluxRun { kind, cwd, log_dir }
+    }
+
+    pub(crate) fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    pub(crate) fn log_dir(&self) -> &Path {
+        &self.log_dir
+    }
+
+    /// Runs flux returning the reported diagnostics together with the file they belong to.
+    pub(crate) fn run(&self) -> Result<Vec<(PathBuf, Diagnostic)>> {
+        // Discard the dumps of the previous run so items that were removed or renamed don't
+        // linger. Cargo doesn't check crates that didn't change again, so in that case hovering
+        // only works on the crates checked by this run, which include the one of the saved file.
+        if self.log_dir.exists() {
+            fs::remove_dir_all(&self.log_dir)?;
+        }
+        let mut cmd = match &self.kind {
+            RunKind::Cargo => {
+                let mut cmd = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+                cmd.args(["flux", "--message-format=json"]);
+                cmd
+            }
+            RunKind::File(file) => {
+                let mut cmd = Command::new("rustc-flux");
+                cmd.args(["--crate-type=lib", "--error-format=json", "--out-dir"])
+                    .arg(&self.log_dir)
+                    .arg(file);
+                cmd
+            }
+        };
+        let output = cmd
+            .current_dir(&self.cwd)
+            .env("FLUX_LOG_DIR", &self.log_dir)
+            .env("FLUX_DUMP_LOCAL_TYPES", "1")
+            .stdin(Stdio::null())
+            .output()?;
+
+        // Cargo prints its messages to stdout while rustc prints diagnostics to stderr.
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let diagnostics = stdout
+            .lines()
+            .chain(stderr.lines())
+            .filter_map(|line| {
+                match serde_json::from_str(line).ok()? {
+                    JsonMessage::Cargo { reason, message } if reason == "compiler-message" => {
+                        message
+                    }
+                    JsonMessage::Cargo { .. } => None,
+                    JsonMessage::Rustc(diagnostic) => Some(diagnostic),
+                }
+            })
+            .filter_map(|diagnostic| self.to_lsp(diagnostic))
+            .collect();
+        Ok(diagnostics)
+    }
+
+    fn to_lsp(&self, diagnostic: RustcDiagnostic) -> Option<(PathBuf, Diagnostic)> {
+        let primary = diagnostic.spans.iter().find(|span| span.is_primary)?;
+        let mut message = diagnostic.message.clone();
+        let mut related = vec![];
+        for span in &diagnostic.spans {
+            if let Some(label) = &span.label {
+                related.extend(self.related_information(span, label.clone()));
+            }
+        }
+        for child in &diagnostic.children {
+            let child_message = format!("{}: {}", child.level, child.message);
+            if let Some(span) = child.spans.first() {
+                related.extend(self.related_information(span, child_message));
+            } else {
+                write!(message, "\n{child_message}").unwrap();
+            }
+        }
+        let lsp_diagnostic = Diagnostic {
+            range: primary.range(),
+            severity: Some(severity(&diagnostic.level)),
+            code: diagnostic
+                .code
+                .map(|code| NumberOrString::String(code.code)),
+            source: Some("flux".to_string()),
+            message,
+            related_information: Some(related).filter(|related| !related.is_empty()),
+            ..Default::default()
+        };
+        Some((resolve_path(&self.cwd, &primary.file_name), lsp_diagnostic))
+    }
+
+    fn related_information(
+        &self,
+        span: &RustcSpan,
+        message: String,
+    ) -> Option<DiagnosticRelatedInformation> {
+        let uri = Url::from_file_path(resolve_path(&self.cwd, &span.file_name)).ok()?;
+        Some(DiagnosticRelatedInformation {
+            location: Location { uri, range: span.range() },
+            message,
+        })
+    }
+}
+
+fn severity(level: &str) -> DiagnosticSeverity {
+    match level {
+        "error" | "error: internal compiler error" => DiagnosticSeverity::ERROR,
+        "warning" => DiagnosticSeverity::WARNING,
+        "help" => DiagnosticSeverity::HINT,
+        _ => DiagnosticSeverity::INFORMATION,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonMessage {
+    /// A message printed by cargo when using `--message-format=json`.
+    Cargo { reason: String, message: Option<RustcDiagnostic> },
+    /// A diagnostic printed by rustc when using `--error-format=json`.
+    Rustc(RustcDiagnostic),
+}
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    spans: Vec<RustcSpan>,
+    children: Vec<RustcDiagnostic>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+    label: Option<String>,
+}
+
+impl RustcSpan {
+    /// Rustc's lines and columns are 1-based while lsp's are 0-based.
+    fn range(&self) -> Range {
+        Range {
+            start: Position {
+                line: self.line_start.saturating_sub(1),
+                character: self.column_start.saturating_sub(1),
+            },
+            end: Position {
+                line: self.line_end.saturating_sub(1),
+                character: self.column_end.saturating_sub(1),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIAGNOSTIC: &str = r#"{
+        "message": "refinement type error",
+        "code": { "code": "FLUX", "explanation": null },
+        "level": "error",
+        "spans": [
+            {
+                "file_name": "/src/lib.rs",
+                "line_start": 3,
+                "line_end": 3,
+                "column_start": 5,
+                "column_end": 10,
+                "is_primary": true,
+                "label": "a postcondition cannot be proved"
+            },
+            {
+                "file_name": "/src/lib.rs",
+                "line_start": 1,
+                "line_end": 1,
+                "column_start": 25,
+                "column_end": 30,
+                "is_primary": false,
+                "label": null
+            }
+        ],
+        "children": [
+            {
+                "message": "this is the condition that cannot be proved",
+                "code": null,
+                "level": "note",
+                "spans": [
+                    {
+                        "file_name": "/src/lib.rs",
+                        "line_start": 1,
+                        "line_end": 1,
+                        "column_start": 25,
+                        "column_end": 30,
+                        "is_primary": true,
+                        "label": null
+                    }
+                ],
+                "children": []
+            },
+            {
+                "message": "see the flux book",
+                "code": null,
+                "level": "help",
+                "spans": [],
+                "children": []
+            }
+        ]
+    }"#;
+
+    fn run() -> FluxRun {
+        FluxRun::new(Path::new("/src/lib.rs"))
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range {
+            start: Position { line: start.0, character: start.1 },
+            end: Position { line: end.0, character: end.1 },
+        }
+    }
+
+    #[test]
+    fn translates_rustc_diagnostic() {
+        let diagnostic = serde_json::from_str(DIAGNOSTIC).unwrap();
+        let (file, diagnostic) = run().to_lsp(diagnostic).unwrap();
+
+        assert_eq!(file, Path::new("/src/lib.rs"));
+        assert_eq!(diagnostic.range, range((2, 4), (2, 9)));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostic.code, Some(NumberOrString::String("FLUX".to_string())));
+        assert_eq!(diagnostic.message, "refinement type error\nhelp: see the flux book");
+
+        let related = diagnostic.related_information.unwrap();
+        let related: Vec<_> = related
+            .iter()
+            .map(|info| (info.location.range, info.message.as_str()))
+            .collect();
+        assert_eq!(
+            related,
+            [
+                (range((2, 4), (2, 9)), "a postcondition cannot be proved"),
+                (range((0, 24), (0, 29)), "note: this is the condition that cannot be proved"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_diagnostics_without_primary_span() {
+        let diagnostic = serde_json::from_str(
+            r#"{ "message": "aborting due to previous error", "code": null, "level": "error", "spans": [], "children": [] }"#,
+        )
+        .unwrap();
+        assert!(run().to_lsp(diagnostic).is_none());
+    }
+
+    #[test]
+    fn parses_cargo_messages() {
+        let message = format!(r#"{{ "reason": "compiler-message", "message": {DIAGNOSTIC} }}"#);
+        let message = serde_json::from_str(&message).unwrap();
+        assert!(matches!(
+            message,
+            JsonMessage::Cargo { reason, message: Some(_) } if reason == "compiler-message"
+        ));
+
+        let message =
+            serde_json::from_str(r#"{ "reason": "build-finished", "success": true }"#).unwrap();
+        assert!(matches!(message, JsonMessage::Cargo { message: None, .. }));
+    }
+}
@@ -0,0 +1,194 @@
+//! Answering hover requests with the types of locals dumped by flux.
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+use serde::Deserialize;
+
+use crate::resolve_path;
+
+/// The types of locals dumped by the last run, grouped by the file of the item they belong to.
+#[derive(Default)]
+pub(crate) struct LocalTypes {
+    items: HashMap<PathBuf, Vec<LocalTypesDump>>,
+}
+
+// CODESYNC(local-types) the format must be kept in sync with `flux-refineck`
+#[derive(Deserialize)]
+struct LocalTypesDump {
+    body_span: Option<SpanData>,
+    snapshots: Vec<TypeSnapshot>,
+}
+
+#[derive(Deserialize)]
+struct TypeSnapshot {
+    span: SpanData,
+    ctxt: String,
+    locals: Vec<LocalType>,
+}
+
+#[derive(Deserialize)]
+struct LocalType {
+    name: String,
+    ty: String,
+}
+
+#[derive(Deserialize)]
+struct SpanData {
+    file: String,
+    lo_line: usize,
+    lo_col: usize,
+    hi_line: usize,
+    hi_col: usize,
+}
+
+impl LocalTypes {
+    /// Loads all the dumps in `log_dir`. File names in the dumps are resolved relative to `cwd`.
+    pub(crate) fn load(log_dir: &Path, cwd: &Path) -> LocalTypes {
+        let mut items: HashMap<_, Vec<_>> = HashMap::new();
+        let Ok(entries) = fs::read_dir(log_dir) else { return LocalTypes::default() };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.to_string_lossy().ends_with(".locals.json") {
+                continue;
+            }
+            let Ok(contents) = fs::read_to_string(&path) else { continue };
+            let Ok(dump) = serde_json::from_str::<LocalTypesDump>(&contents) else { continue };
+            if let Some(body_span) = &dump.body_span {
+                let file = resolve_path(cwd, &body_span.file);
+                items.entry(file).or_default().push(dump);
+            }
+        }
+        LocalTypes { items }
+    }
+
+    pub(crate) fn hover(&self, file: &Path, text: &str, position: Position) -> Option<Hover> {
+        let name = ident_at(text, position)?;
+        // Dumps use 1-based lines.
+        let cursor = (position.line as usize + 1, position.character as usize);
+
+        // The innermost item containing the cursor.
+        let item = self
+            .items
+            .get(file)?
+            .iter()
+            .filter(|item| {
+                item.body_span
+                    .as_ref()
+                    .is_some_and(|span| span.contains(cursor))
+            })
+            .min_by_key(|item| item.body_span.as_ref().map(SpanData::len))?;
+
+        let snapshots = item
+            .snapshots
+            .iter()
+            .filter_map(|snapshot| Some((snapshot, snapshot.local(name)?)));
+        // Pick the last snapshot starting before the cursor. If there is none, e.g., when hovering
+        // over an argument in the signature, pick the first one.
+        let (snapshot, local) = snapshots
+            .clone()
+            .filter(|(snapshot, _)| snapshot.span.lo() <= cursor)
+            .max_by_key(|(snapshot, _)| snapshot.span.lo())
+            .or_else(|| {
+                snapshots
+                    .clone()
+                    .min_by_key(|(snapshot, _)| snapshot.span.lo())
+            })?;
+
+        let value = format!("```flux\n{}: {}\n```\n\n`{}`", local.name, local.ty, snapshot.ctxt);
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+            range: None,
+        })
+    }
+}
+
+impl TypeSnapshot {
+    fn local(&self, name: &str) -> Option<&LocalType> {
+        self.locals.iter().find(|local| local.name == name)
+    }
+}
+
+impl SpanData {
+    fn lo(&self) -> (usize, usize) {
+        (self.lo_line, self.lo_col)
+    }
+
+    fn hi(&self) -> (usize, usize) {
+        (self.hi_line, self.hi_col)
+    }
+
+    fn contains(&self, pos: (usize, usize)) -> bool {
+        self.lo() <= pos && pos <= self.hi()
+    }
+
+    /// An approximation of the length used to compare nested spans.
+    fn len(&self) -> (usize, usize) {
+        (self.hi_line - self.lo_line, self.hi_col.abs_diff(self.lo_col))
+    }
+}
+
+/// Returns the identifier at `position`. Columns are assumed to be counted in chars.
+fn ident_at(text: &str, position: Position) -> Option<&str> {
+    let line = text.lines().nth(position.line as usize)?;
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let offset = line
+        .char_indices()
+        .nth(position.character as usize)
+        .map_or(line.len(), |(i, _)| i);
+    let start = line[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_ident(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = line[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_ident(*c))
+        .map_or(line.len(), |(i, _)| offset + i);
+    let ident = &line[start..end];
+    (!ident.is_empty()).then_some(ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(text: &str, line: u32, character: u32) -> Option<&str> {
+        ident_at(text, Position { line, character })
+    }
+
+    #[test]
+    fn ident_at_cursor() {
+        let text = "fn foo(x: i32) {\n    let my_var = x + 1;\n}";
+        assert_eq!(ident(text, 0, 3), Some("foo"));
+        assert_eq!(ident(text, 0, 4), Some("foo"));
+        // The cursor is placed before the character, so it is also on an ident right after it.
+        assert_eq!(ident(text, 0, 6), Some("foo"));
+        assert_eq!(ident(text, 1, 8), Some("my_var"));
+        assert_eq!(ident(text, 1, 13), Some("my_var"));
+        assert_eq!(ident(text, 1, 17), Some("x"));
+        // End of the line
+        assert_eq!(ident(text, 1, 100), None);
+    }
+
+    #[test]
+    fn ident_at_no_ident() {
+        let text = "fn foo(x: i32) {\n    let my_var = x + 1;\n}";
+        assert_eq!(ident(text, 1, 0), None);
+        assert_eq!(ident(text, 1, 16), None);
+        assert_eq!(ident(text, 2, 0), None);
+        assert_eq!(ident(text, 3, 0), None);
+    }
+
+    #[test]
+    fn ident_at_counts_chars() {
+        let text = "let s = \"λ\"; let é = 1;";
+        assert_eq!(ident(text, 0, 9), Some("λ"));
+        assert_eq!(ident(text, 0, 12), None);
+        assert_eq!(ident(text, 0, 17), Some("é"));
+    }
+}
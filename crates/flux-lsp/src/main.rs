@@ -0,0 +1,192 @@
+//! A language server for Flux.
+//!
+//! The server runs flux every time a file is opened or saved and publishes the resulting
+//! diagnostics. Checking is done with `cargo flux` when the file belongs to a package and with
+//! `rustc-flux` otherwise, so both binaries need to be installed. Checks run with
+//! `FLUX_DUMP_LOCAL_TYPES` set and the dumped types are used to answer hover requests with the
+//! refined type of the local under the cursor.
+mod diagnostics;
+mod hover;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Notification as _, PublishDiagnostics,
+    },
+    request::{HoverRequest, Request as _},
+    Diagnostic, HoverParams, HoverProviderCapability, InitializeParams, PublishDiagnosticsParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, Url,
+};
+
+use crate::{diagnostics::FluxRun, hover::LocalTypes};
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+            open_close: Some(true),
+            change: Some(TextDocumentSyncKind::FULL),
+            save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+            ..Default::default()
+        })),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    })?;
+    let _: InitializeParams = serde_json::from_value(connection.initialize(capabilities)?)?;
+
+    Server::default().main_loop(&connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Server {
+    /// Contents of the open documents, used to find the identifier under the cursor.
+    documents: HashMap<Url, String>,
+    /// Files for which we have published diagnostics in the last run.
+    published: HashSet<Url>,
+    local_types: LocalTypes,
+}
+
+impl Server {
+    fn main_loop(&mut self, connection: &Connection) -> Result<()> {
+        for msg in &connection.receiver {
+            match msg {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    self.handle_request(connection, req)?;
+                }
+                Message::Notification(not) => self.handle_notification(connection, not)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, connection: &Connection, req: Request) -> Result<()> {
+        let resp = if req.method == HoverRequest::METHOD {
+            match serde_json::from_value::<HoverParams>(req.params) {
+                Ok(params) => Response::new_ok(req.id, self.hover(&params)),
+                Err(err) => {
+                    Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string())
+                }
+            }
+        } else {
+            let msg = format!("unsupported request `{}`", req.method);
+            Response::new_err(req.id, ErrorCode::MethodNotFound as i32, msg)
+        };
+        connection.sender.send(Message::Response(resp))?;
+        Ok(())
+    }
+
+    fn handle_notification(&mut self, connection: &Connection, not: Notification) -> Result<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = cast_notification::<DidOpenTextDocument>(not)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.check(connection, &uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = cast_notification::<DidChangeTextDocument>(not)?;
+                // We only advertise full synchronization, so the last change is the whole text.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri, change.text);
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                let params = cast_notification::<DidSaveTextDocument>(not)?;
+                self.check(connection, &params.text_document.uri)?;
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = cast_notification::<DidCloseTextDocument>(not)?;
+                self.documents.remove(&params.text_document.uri);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Runs flux on the crate containing `uri` and publishes the diagnostics it reports.
+    fn check(&mut self, connection: &Connection, uri: &Url) -> Result<()> {
+        let Ok(file) = uri.to_file_path() else { return Ok(()) };
+        let run = FluxRun::new(&file);
+        let diagnostics = match run.run() {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                // Errors are logged to stderr which clients show in the server's output.
+                eprintln!("failed to run flux on {}: {err}", file.display());
+                return Ok(());
+            }
+        };
+        self.local_types = LocalTypes::load(run.log_dir(), run.cwd());
+
+        let mut by_file: HashMap<Url, Vec<Diagnostic>> = HashMap::new();
+        for (path, diagnostic) in diagnostics {
+            if let Ok(uri) = Url::from_file_path(&path) {
+                by_file.entry(uri).or_default().push(diagnostic);
+            }
+        }
+        // Clear diagnostics of the checked file and of files that no longer have errors.
+        by_file.entry(uri.clone()).or_default();
+        for uri in self.published.drain() {
+            by_file.entry(uri).or_default();
+        }
+        for (uri, diagnostics) in by_file {
+            if !diagnostics.is_empty() {
+                self.published.insert(uri.clone());
+            }
+            let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+            let not = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+            connection.sender.send(Message::Notification(not))?;
+        }
+        Ok(())
+    }
+
+    fn hover(&self, params: &HoverParams) -> Option<lsp_types::Hover> {
+        let position = &params.text_document_position_params;
+        let uri = &position.text_document.uri;
+        let text = self.documents.get(uri)?;
+        let file = uri.to_file_path().ok()?;
+        self.local_types
+            .hover(&canonicalize(&file), text, position.position)
+    }
+}
+
+fn cast_notification<N>(not: Notification) -> Result<N::Params>
+where
+    N: lsp_types::notification::Notification,
+{
+    not.extract(N::METHOD)
+        .map_err(|err| anyhow!("malformed `{}` notification: {err:?}", N::METHOD))
+}
+
+/// Resolves a file name reported by the compiler. Relative names are relative to the directory
+/// where the compiler was invoked which, for packages in a workspace, is the workspace root. We
+/// thus try `cwd` and all its ancestors.
+fn resolve_path(cwd: &Path, file_name: &str) -> PathBuf {
+    let path = Path::new(file_name);
+    if path.is_absolute() {
+        return canonicalize(path);
+    }
+    cwd.ancestors()
+        .map(|dir| dir.join(path))
+        .find(|path| path.exists())
+        .map_or_else(|| cwd.join(path), |path| canonicalize(&path))
+}
+
+fn canonicalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,89 @@
+//! Computes the hash of the definitions of the types stored in the metadata, see `src/header.rs`.
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use proc_macro2::{token_stream, Delimiter, Spacing, TokenStream, TokenTree};
+
+/// Directories with the definitions of the types stored in the metadata. We hash every file in them
+/// rather than the ones known to define encoded types so new modules are never missed.
+const SOURCE_DIRS: &[&str] = &["src", "../flux-middle/src"];
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let mut files = vec![];
+    for dir in SOURCE_DIRS {
+        let dir = manifest_dir.join(dir);
+        println!("cargo:rerun-if-changed={}", dir.display());
+        collect_rust_files(&dir, &mut files);
+    }
+    files.sort();
+
+    let mut hasher = DefaultHasher::new();
+    for file in &files {
+        let src = fs::read_to_string(file).unwrap();
+        let tokens = TokenStream::from_str(&src)
+            .unwrap_or_else(|err| panic!("failed to tokenize `{}`: {err}", file.display()));
+        hash_tokens(tokens, &mut hasher);
+    }
+    println!("cargo:rustc-env=FLUX_METADATA_TYPES_HASH={}", hasher.finish());
+}
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+/// Hashes the tokens of a file, so changes to formatting or comments, including doc comments, don't
+/// change the hash.
+fn hash_tokens(tokens: TokenStream, hasher: &mut DefaultHasher) {
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '#' && is_doc_attr(&mut tokens) => {}
+            TokenTree::Group(group) => {
+                let delimiter = match group.delimiter() {
+                    Delimiter::Parenthesis => '(',
+                    Delimiter::Brace => '{',
+                    Delimiter::Bracket => '[',
+                    Delimiter::None => ' ',
+                };
+                delimiter.hash(hasher);
+                hash_tokens(group.stream(), hasher);
+            }
+            TokenTree::Ident(ident) => ident.to_string().hash(hasher),
+            TokenTree::Punct(punct) => {
+                punct.as_char().hash(hasher);
+                matches!(punct.spacing(), Spacing::Joint).hash(hasher);
+            }
+            TokenTree::Literal(lit) => lit.to_string().hash(hasher),
+        }
+    }
+}
+
+/// Whether the tokens after a `#` are a doc attribute, i.e., `[doc = ...]` or `![doc = ...]`, in
+/// which case they are skipped.
+fn is_doc_attr(tokens: &mut token_stream::IntoIter) -> bool {
+    let mut lookahead = tokens.clone();
+    let mut next = lookahead.next();
+    if matches!(&next, Some(TokenTree::Punct(punct)) if punct.as_char() == '!') {
+        next = lookahead.next();
+    }
+    let Some(TokenTree::Group(group)) = next else { return false };
+    let is_doc = group.delimiter() == Delimiter::Bracket
+        && matches!(group.stream().into_iter().next(), Some(TokenTree::Ident(ident)) if ident == "doc");
+    if is_doc {
+        *tokens = lookahead;
+    }
+    is_doc
+}
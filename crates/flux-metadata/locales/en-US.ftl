@@ -1 +1,10 @@
 metadata_decode_file_error = "error when decoding flux metadata file {$path}: {$err}"
+
+metadata_incompatible_metadata =
+    flux metadata for crate `{$crate_name}` was produced by an incompatible version of flux
+    .note = `{$path}` was produced by {$found}, but this is {$expected}
+    .help = rebuild `{$crate_name}` with this version of flux, e.g., run `cargo clean` before `cargo flux` or reinstall flux if the crate is part of its sysroot
+
+metadata_not_flux_metadata = `{$path}` is not a flux metadata file
+
+metadata_missing_metadata = no flux metadata found for crate `{$crate_name}`
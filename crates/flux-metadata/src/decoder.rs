@@ -13,10 +13,16 @@ use rustc_middle::{
     ty::{self, TyCtxt},
 };
 use rustc_serialize::{opaque::MemDecoder, Decodable, Decoder};
-use rustc_span::def_id::{CrateNum, DefIndex, StableCrateId};
+use rustc_span::{
+    def_id::{CrateNum, DefIndex, StableCrateId},
+    Symbol,
+};
 use rustc_type_ir::TyDecoder;
 
-use crate::{CrateMetadata, METADATA_HEADER};
+use crate::{
+    header::{HeaderError, MetadataHeader},
+    CrateMetadata,
+};
 
 struct DecodeContext<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
@@ -26,6 +32,7 @@ struct DecodeContext<'a, 'tcx> {
 pub(super) fn decode_crate_metadata(
     tcx: TyCtxt,
     sess: &FluxSession,
+    crate_name: Symbol,
     path: &Path,
 ) -> Option<CrateMetadata> {
     let mut file = match fs::File::open(path) {
@@ -37,11 +44,22 @@ pub(super) fn decode_crate_metadata(
     file.read_to_end(&mut buf)
         .unwrap_or_else(|err| sess.emit_fatal(errors::DecodeFileError::new(path, err)));
 
-    if !buf.starts_with(METADATA_HEADER) {
-        panic!("incompatible metadata version")
-    }
+    let header_len = match MetadataHeader::decode(&buf) {
+        Ok((header, len)) if &header == MetadataHeader::current() => len,
+        Ok((header, _)) => {
+            sess.emit_fatal(errors::IncompatibleMetadata::new(crate_name, path, header.to_string()))
+        }
+        Err(HeaderError::UnknownVersion(version)) => {
+            sess.emit_fatal(errors::IncompatibleMetadata::new(
+                crate_name,
+                path,
+                format!("a version of flux with metadata format {version}"),
+            ))
+        }
+        Err(HeaderError::NotFluxMetadata) => sess.emit_fatal(errors::NotFluxMetadata { path }),
+    };
 
-    let mut decoder = DecodeContext { tcx, opaque: MemDecoder::new(&buf, METADATA_HEADER.len()) };
+    let mut decoder = DecodeContext { tcx, opaque: MemDecoder::new(&buf, header_len) };
     Some(CrateMetadata::decode(&mut decoder))
 }
 
@@ -109,6 +127,9 @@ mod errors {
     use std::{io, path::Path};
 
     use flux_macros::Diagnostic;
+    use rustc_span::Symbol;
+
+    use crate::header::MetadataHeader;
 
     #[derive(Diagnostic)]
     #[diag(metadata_decode_file_error, code = "FLUX")]
@@ -122,4 +143,28 @@ mod errors {
             Self { path, err }
         }
     }
+
+    #[derive(Diagnostic)]
+    #[diag(metadata_incompatible_metadata, code = "FLUX")]
+    #[note]
+    #[help]
+    pub(super) struct IncompatibleMetadata<'a> {
+        crate_name: Symbol,
+        path: &'a Path,
+        found: String,
+        expected: String,
+    }
+
+    impl<'a> IncompatibleMetadata<'a> {
+        pub(super) fn new(crate_name: Symbol, path: &'a Path, found: String) -> Self {
+            let expected = MetadataHeader::current().to_string();
+            Self { crate_name, path, found, expected }
+        }
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(metadata_not_flux_metadata, code = "FLUX")]
+    pub(super) struct NotFluxMetadata<'a> {
+        pub(super) path: &'a Path,
+    }
 }
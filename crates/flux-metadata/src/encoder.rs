@@ -9,7 +9,7 @@ use rustc_serialize::{opaque, Encodable, Encoder};
 use rustc_span::def_id::{CrateNum, DefIndex};
 use rustc_type_ir::TyEncoder;
 
-use crate::{CrateMetadata, METADATA_HEADER};
+use crate::{header::MetadataHeader, CrateMetadata};
 
 struct EncodeContext<'tcx> {
     tcx: TyCtxt<'tcx>,
@@ -23,7 +23,7 @@ pub fn encode_metadata(genv: &GlobalEnv, path: &std::path::Path) {
     let mut encoder = opaque::FileEncoder::new(path)
         .unwrap_or_else(|err| genv.tcx.sess.emit_fatal(FailCreateFileEncoder { err }));
 
-    encoder.emit_raw_bytes(METADATA_HEADER);
+    encoder.emit_raw_bytes(&MetadataHeader::current().encode());
 
     let crate_root = CrateMetadata::new(genv);
 
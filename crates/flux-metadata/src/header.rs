@@ -0,0 +1,128 @@
+//! The header of a `.fluxmeta` file, which identifies the build of flux that produced it. Metadata
+//! can only be decoded by the same build that encoded it, so the header records the flux version
+//! and a hash of the definitions of the encoded types.
+use std::{fmt, sync::OnceLock};
+
+const METADATA_MAGIC: &[u8] = b"flux";
+
+/// Version of the layout of the header. It must be incremented if the header changes.
+const METADATA_VERSION: u8 = 1;
+
+const FLUX_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Hash of the definitions of the types stored in the metadata. Changing them may change how the
+/// metadata is encoded, so the build script hashes the tokens of every file in `flux-middle` and
+/// this crate. Comments and formatting don't affect it, but any other change to those files does,
+/// even if the encoding stays the same.
+const TYPES_HASH: &str = env!("FLUX_METADATA_TYPES_HASH");
+
+#[derive(PartialEq, Eq)]
+pub struct MetadataHeader {
+    pub flux_version: String,
+    /// Hash of the [definitions](TYPES_HASH) of the encoded types
+    pub types_hash: u64,
+}
+
+pub enum HeaderError {
+    /// The file doesn't start with the flux magic bytes
+    NotFluxMetadata,
+    /// The header was written with a different layout, i.e., by an older or newer version of flux
+    UnknownVersion(u8),
+}
+
+impl MetadataHeader {
+    /// The header of the metadata produced by this build of flux
+    pub fn current() -> &'static MetadataHeader {
+        static HEADER: OnceLock<MetadataHeader> = OnceLock::new();
+        HEADER.get_or_init(|| {
+            MetadataHeader {
+                flux_version: FLUX_VERSION.to_string(),
+                types_hash: TYPES_HASH.parse().unwrap(),
+            }
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = METADATA_MAGIC.to_vec();
+        bytes.push(METADATA_VERSION);
+        bytes.push(self.flux_version.len() as u8);
+        bytes.extend(self.flux_version.as_bytes());
+        bytes.extend(self.types_hash.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes the header at the start of `buf` returning it together with its length in bytes
+    pub fn decode(buf: &[u8]) -> Result<(MetadataHeader, usize), HeaderError> {
+        let Some(buf) = buf.strip_prefix(METADATA_MAGIC) else {
+            return Err(HeaderError::NotFluxMetadata);
+        };
+        // Headers before version 1 had three zero bytes before the version.
+        let version = match buf {
+            [version, ..] if *version != 0 => *version,
+            [_, _, _, version, ..] => *version,
+            _ => return Err(HeaderError::NotFluxMetadata),
+        };
+        if version != METADATA_VERSION {
+            return Err(HeaderError::UnknownVersion(version));
+        }
+        let [_, len, buf @ ..] = buf else {
+            return Err(HeaderError::NotFluxMetadata);
+        };
+        let len = *len as usize;
+        if buf.len() < len + 8 {
+            return Err(HeaderError::NotFluxMetadata);
+        }
+        let flux_version = String::from_utf8_lossy(&buf[..len]).into_owned();
+        let types_hash = u64::from_le_bytes(buf[len..len + 8].try_into().unwrap());
+        let header_len = METADATA_MAGIC.len() + 2 + len + 8;
+        Ok((MetadataHeader { flux_version, types_hash }, header_len))
+    }
+}
+
+impl fmt::Display for MetadataHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "flux {} (types hash {:016x})", self.flux_version, self.types_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let header = MetadataHeader { flux_version: "0.1.0".to_string(), types_hash: 0xf1e2 };
+        let mut bytes = header.encode();
+        let len = bytes.len();
+        bytes.extend(b"rest of the metadata");
+
+        let Ok((decoded, decoded_len)) = MetadataHeader::decode(&bytes) else { panic!() };
+        assert!(decoded == header);
+        assert_eq!(decoded_len, len);
+        assert_eq!(&bytes[decoded_len..], b"rest of the metadata");
+    }
+
+    #[test]
+    fn v0_header_is_unknown_version() {
+        let bytes = b"flux\0\0\0\0rest of the metadata";
+        assert!(matches!(MetadataHeader::decode(bytes), Err(HeaderError::UnknownVersion(0))));
+    }
+
+    #[test]
+    fn truncated_header() {
+        let bytes = MetadataHeader::current().encode();
+        for len in 0..bytes.len() {
+            assert!(matches!(
+                MetadataHeader::decode(&bytes[..len]),
+                Err(HeaderError::NotFluxMetadata)
+            ));
+        }
+    }
+
+    #[test]
+    fn wrong_magic() {
+        let mut bytes = MetadataHeader::current().encode();
+        bytes[0] = b'r';
+        assert!(matches!(MetadataHeader::decode(&bytes), Err(HeaderError::NotFluxMetadata)));
+    }
+}
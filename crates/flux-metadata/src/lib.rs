@@ -1,7 +1,10 @@
 #![allow(incomplete_features)]
-#![feature(rustc_private, specialization, if_let_guard)]
+#![feature(rustc_private, specialization, if_let_guard, let_chains)]
 
 extern crate rustc_data_structures;
+// Tests are linked as executables so they need the compiler's shared library
+#[cfg(test)]
+extern crate rustc_driver as _;
 extern crate rustc_errors;
 extern crate rustc_hash;
 extern crate rustc_hir;
@@ -15,6 +18,7 @@ extern crate rustc_type_ir;
 
 mod decoder;
 mod encoder;
+mod header;
 
 use std::path::PathBuf;
 
@@ -22,6 +26,9 @@ use decoder::decode_crate_metadata;
 use flux_errors::FluxSession;
 use flux_macros::fluent_messages;
 use flux_middle::{cstore::CrateStore, fhir, global_env::GlobalEnv, intern::List, rty};
+use header::MetadataHeader;
+use itertools::Itertools;
+use rustc_data_structures::unord::{ExtendUnord, UnordMap};
 use rustc_errors::{DiagnosticMessage, SubdiagnosticMessage};
 use rustc_hash::FxHashMap;
 use rustc_hir::def::DefKind;
@@ -31,26 +38,33 @@ use rustc_session::{
     config::{OutFileName, OutputType},
     utils::CanonicalizedPath,
 };
-use rustc_span::def_id::{CrateNum, DefId, DefIndex};
+use rustc_span::{
+    def_id::{CrateNum, DefId, DefIndex},
+    Symbol,
+};
 
 pub use crate::encoder::encode_metadata;
 
 fluent_messages! { "../locales/en-US.ftl" }
 
-const METADATA_VERSION: u8 = 0;
-const METADATA_HEADER: &[u8] = &[b'f', b'l', b'u', b'x', 0, 0, 0, METADATA_VERSION];
-
 pub struct CStore {
     meta: FxHashMap<CrateNum, CrateMetadata>,
+    /// Extern specs exported by all loaded crates, mapping the external item to the dummy item
+    /// holding its specification.
+    externs: UnordMap<DefId, DefId>,
 }
 
 #[derive(TyEncodable, TyDecodable)]
 pub struct CrateMetadata {
     fn_sigs: FxHashMap<DefIndex, rty::EarlyBinder<rty::PolyFnSig>>,
+    generics_of: FxHashMap<DefIndex, rty::Generics>,
     refined_bys: FxHashMap<DefIndex, fhir::RefinedBy>,
     adts: FxHashMap<DefIndex, AdtMetadata>,
     /// For now it only store type of aliases
     type_of: FxHashMap<DefIndex, rty::EarlyBinder<rty::PolyTy>>,
+    /// Items in other crates with an extern spec in this crate, mapped to the dummy item
+    /// containing the spec.
+    extern_specs: UnordMap<DefId, DefIndex>,
 }
 
 #[derive(TyEncodable, TyDecodable)]
@@ -61,16 +75,23 @@ struct AdtMetadata {
 
 impl CStore {
     pub fn load(tcx: TyCtxt, sess: &FluxSession) -> Self {
-        let meta = tcx
+        let meta: FxHashMap<_, _> = tcx
             .crates(())
             .iter()
             .filter_map(|crate_num| {
                 let path = flux_metadata_extern_location(tcx, *crate_num)?;
-                let meta = decode_crate_metadata(tcx, sess, path.as_path())?;
+                let crate_name = tcx.crate_name(*crate_num);
+                let meta = decode_crate_metadata(tcx, sess, crate_name, path.as_path())?;
                 Some((*crate_num, meta))
             })
             .collect();
-        Self { meta }
+        let mut externs = UnordMap::default();
+        for (crate_num, meta) in &meta {
+            externs.extend_unord(meta.extern_specs.items().map(|(extern_id, index)| {
+                (*extern_id, DefId { krate: *crate_num, index: *index })
+            }));
+        }
+        Self { meta, externs }
     }
 
     fn adt(&self, def_id: DefId) -> Option<&AdtMetadata> {
@@ -87,6 +108,10 @@ impl CrateStore for CStore {
             .cloned()
     }
 
+    fn generics_of(&self, def_id: DefId) -> Option<&rty::Generics> {
+        self.meta.get(&def_id.krate)?.generics_of.get(&def_id.index)
+    }
+
     fn refined_by(&self, def_id: DefId) -> Option<&fhir::RefinedBy> {
         self.meta.get(&def_id.krate)?.refined_bys.get(&def_id.index)
     }
@@ -106,12 +131,17 @@ impl CrateStore for CStore {
     fn type_of(&self, def_id: DefId) -> Option<&rty::EarlyBinder<rty::PolyTy>> {
         self.meta.get(&def_id.krate)?.type_of.get(&def_id.index)
     }
+
+    fn lookup_extern(&self, def_id: DefId) -> Option<DefId> {
+        self.externs.get(&def_id).copied()
+    }
 }
 
 impl CrateMetadata {
     fn new(genv: &GlobalEnv) -> Self {
         let tcx = genv.tcx;
         let mut fn_sigs = FxHashMap::default();
+        let mut generics_of = FxHashMap::default();
         let mut adts = FxHashMap::default();
         let mut refined_bys = FxHashMap::default();
         let mut type_of = FxHashMap::default();
@@ -120,11 +150,22 @@ impl CrateMetadata {
             let def_id = local_id.to_def_id();
             let def_kind = tcx.def_kind(local_id);
 
+            if let DefKind::Fn
+            | DefKind::AssocFn
+            | DefKind::Impl { .. }
+            | DefKind::Enum
+            | DefKind::Struct
+            | DefKind::Union = def_kind
+                && let Ok(generics) = genv.generics_of(def_id)
+            {
+                generics_of.insert(def_id.index, generics);
+            }
+
             match def_kind {
                 DefKind::Fn | DefKind::AssocFn => {
                     fn_sigs.insert(def_id.index, genv.fn_sig(def_id).unwrap());
                 }
-                DefKind::Enum | DefKind::Struct => {
+                DefKind::Enum | DefKind::Struct | DefKind::Union => {
                     let adt_def = genv.adt_def(def_id).unwrap();
                     let variants = genv.variants_of(def_id).unwrap();
                     let meta = AdtMetadata { adt_def, variants };
@@ -139,10 +180,80 @@ impl CrateMetadata {
                 _ => {}
             }
         }
-        Self { fn_sigs, refined_bys, adts, type_of }
+        let extern_specs = genv
+            .map()
+            .externs()
+            .items()
+            .map(|(extern_id, local_id)| (*extern_id, local_id.local_def_index))
+            .collect();
+        Self { fn_sigs, generics_of, refined_bys, adts, type_of, extern_specs }
     }
 }
 
+/// Prints the refined signatures and definitions stored in the flux metadata of `crate_name`, which
+/// must be an extern crate of the current crate.
+pub fn print_metadata(tcx: TyCtxt, sess: &FluxSession, crate_name: Symbol) {
+    let Some(crate_num) = tcx
+        .crates(())
+        .iter()
+        .copied()
+        .find(|crate_num| tcx.crate_name(*crate_num) == crate_name)
+    else {
+        sess.emit_fatal(errors::MissingMetadata { crate_name });
+    };
+    let Some(path) = flux_metadata_extern_location(tcx, crate_num) else {
+        sess.emit_fatal(errors::MissingMetadata { crate_name });
+    };
+    let Some(meta) = decode_crate_metadata(tcx, sess, crate_name, path.as_path()) else {
+        sess.emit_fatal(errors::MissingMetadata { crate_name });
+    };
+    let def_path = |index: DefIndex| tcx.def_path_str(DefId { krate: crate_num, index });
+
+    println!("// flux metadata of `{crate_name}` produced by {}", MetadataHeader::current());
+    for (path, sig) in sorted_by_path(&meta.fn_sigs, def_path) {
+        println!("fn {path}: {:?}", sig.0);
+    }
+    for (path, adt) in sorted_by_path(&meta.adts, def_path) {
+        match &adt.variants {
+            rty::Opaqueness::Opaque => println!("opaque {path}"),
+            rty::Opaqueness::Transparent(variants) => {
+                for variant in &variants.0 {
+                    println!("variant {path}: {variant:?}");
+                }
+            }
+        }
+    }
+    for (path, ty) in sorted_by_path(&meta.type_of, def_path) {
+        println!("type {path} = {:?}", ty.0);
+    }
+    for (path, refined_by) in sorted_by_path(&meta.refined_bys, def_path) {
+        let params = refined_by
+            .index_params()
+            .format_with(", ", |(name, sort), f| f(&format_args!("{name}: {sort:?}")));
+        println!("refined_by {path}: {{{params}}}");
+    }
+    let extern_specs = meta
+        .extern_specs
+        .items()
+        .map(|(extern_id, index)| (tcx.def_path_str(*extern_id), def_path(*index)))
+        .into_sorted_stable_ord();
+    for (extern_path, path) in extern_specs {
+        println!("extern spec {extern_path}: {path}");
+    }
+}
+
+fn sorted_by_path<T>(
+    map: &FxHashMap<DefIndex, T>,
+    def_path: impl Fn(DefIndex) -> String,
+) -> Vec<(String, &T)> {
+    let mut items = map
+        .iter()
+        .map(|(index, value)| (def_path(*index), value))
+        .collect::<Vec<_>>();
+    items.sort_by(|(path1, _), (path2, _)| path1.cmp(path2));
+    items
+}
+
 pub fn filename_for_metadata(tcx: TyCtxt) -> OutFileName {
     match rustc_session::output::filename_for_metadata(tcx.sess, tcx.output_filenames(())) {
         OutFileName::Real(path) => OutFileName::Real(path.with_extension("fluxmeta")),
@@ -161,6 +272,20 @@ fn flux_metadata_extern_location(tcx: TyCtxt, crate_num: CrateNum) -> Option<Pat
         .into_iter()
         .flatten()
         .map(CanonicalizedPath::canonicalized)
-        .find(|path| path.extension().unwrap_or_default() == OutputType::Metadata.extension())?;
+        .find(|path| {
+            let extension = path.extension().unwrap_or_default();
+            extension == OutputType::Metadata.extension() || extension == "rlib"
+        })?;
     Some(path.with_extension("fluxmeta"))
 }
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_span::Symbol;
+
+    #[derive(Diagnostic)]
+    #[diag(metadata_missing_metadata, code = "FLUX")]
+    pub(super) struct MissingMetadata {
+        pub(super) crate_name: Symbol,
+    }
+}
@@ -4,6 +4,7 @@ use crate::{fhir, rty};
 
 pub trait CrateStore {
     fn fn_sig(&self, def_id: DefId) -> Option<rty::EarlyBinder<rty::PolyFnSig>>;
+    fn generics_of(&self, def_id: DefId) -> Option<&rty::Generics>;
     fn refined_by(&self, def_id: DefId) -> Option<&fhir::RefinedBy>;
     fn adt_def(&self, def_id: DefId) -> Option<&rty::AdtDef>;
     fn variants(
@@ -11,6 +12,8 @@ pub trait CrateStore {
         def_id: DefId,
     ) -> Option<rty::Opaqueness<rty::EarlyBinder<&[rty::PolyVariant]>>>;
     fn type_of(&self, def_id: DefId) -> Option<&rty::EarlyBinder<rty::PolyTy>>;
+    /// If there's an extern spec for `def_id` in a dependency, the id of the item holding it.
+    fn lookup_extern(&self, def_id: DefId) -> Option<DefId>;
 }
 
 pub type CrateStoreDyn = dyn CrateStore;
@@ -68,6 +68,7 @@ pub enum GenericParamKind {
     SplTy,
     BaseTy,
     Lifetime,
+    Const,
 }
 
 #[derive(Debug, Clone)]
@@ -160,6 +161,7 @@ pub struct Map {
     fn_quals: FxHashMap<LocalDefId, Vec<SurfaceIdent>>,
     trusted: UnordSet<LocalDefId>,
     externs: UnordMap<DefId, LocalDefId>,
+    assoc_refinements: UnordMap<LocalDefId, Vec<AssocRefinement>>,
 }
 
 #[derive(Debug)]
@@ -287,8 +289,9 @@ pub enum TyKind {
     Ptr(Lifetime, Ident),
     Ref(Lifetime, MutTy),
     Tuple(Vec<Ty>),
-    Array(Box<Ty>, ArrayLen),
-    RawPtr(Box<Ty>, Mutability),
+    Array(Box<Ty>, ConstArg),
+    /// A function pointer type. Refinements in it are local to the signature.
+    BareFn(Vec<Ty>, Box<Ty>),
     OpaqueDef(ItemId, Vec<GenericArg>, Vec<RefineArg>, bool),
     Never,
     Hole(FhirId),
@@ -310,12 +313,19 @@ pub enum Lifetime {
     Resolved(ResolvedArg),
 }
 
-#[derive(Clone)]
-pub struct ArrayLen {
-    pub val: usize,
+#[derive(Clone, Copy)]
+pub struct ConstArg {
+    pub kind: ConstArgKind,
     pub span: Span,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConstArgKind {
+    Lit(usize),
+    /// A const generic parameter, e.g., the `N` in `[i32; N]`
+    Param(DefId),
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub enum WeakKind {
     Shr,
@@ -415,6 +425,7 @@ pub struct BaseTy {
 pub enum BaseTyKind {
     Path(QPath),
     Slice(Box<Ty>),
+    RawPtr(Box<Ty>, Mutability),
 }
 
 #[derive(Clone)]
@@ -441,6 +452,7 @@ pub struct TypeBinding {
 pub enum GenericArg {
     Lifetime(Lifetime),
     Type(Ty),
+    Const(ConstArg),
 }
 
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
@@ -604,9 +616,21 @@ pub enum ExprKind {
     BinaryOp(BinOp, Box<[Expr; 2]>),
     UnaryOp(UnOp, Box<Expr>),
     App(Func, Vec<Expr>),
+    Alias(AliasReft, Vec<Expr>),
     IfThenElse(Box<[Expr; 3]>),
 }
 
+/// A reference to an associated refinement, e.g., `<T as Trait>::name`
+#[derive(Clone, Copy)]
+pub struct AliasReft {
+    /// The type parameter the refinement is projected from. Either a generic type parameter or
+    /// `Self` inside a trait.
+    pub qself: Res,
+    pub trait_id: DefId,
+    pub name: Symbol,
+    pub span: Span,
+}
+
 #[derive(Clone)]
 pub enum Func {
     /// A function coming from a refinement parameter.
@@ -817,6 +841,17 @@ pub enum FuncKind {
     Def,
 }
 
+/// An associated refinement declared in a trait or defined in an impl. Declarations in traits
+/// may omit the `body`.
+#[derive(Debug)]
+pub struct AssocRefinement {
+    pub name: Symbol,
+    pub params: Vec<RefineParam>,
+    pub output: Sort,
+    pub body: Option<Expr>,
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub struct Defn {
     pub name: Symbol,
@@ -873,6 +908,11 @@ impl RefinedBy {
         }
     }
 
+    /// Index parameters and their sorts in the order they appear in the definition
+    pub fn index_params(&self) -> impl Iterator<Item = (Symbol, &Sort)> {
+        self.index_params.iter().map(|(name, sort)| (*name, sort))
+    }
+
     pub fn field_index(&self, fld: Symbol) -> Option<usize> {
         self.index_params.get_index_of(&fld)
     }
@@ -1122,6 +1162,12 @@ impl Map {
             .unwrap_or_else(|| bug!("no fn_sig found for `{def_id:?}`"))
     }
 
+    /// Whether there's a signature for `def_id`. This is always the case for functions (the
+    /// signature is lifted if not annotated), but only closures with a `#[flux::sig]` have one.
+    pub fn has_fn_sig(&self, def_id: LocalDefId) -> bool {
+        self.fns.contains_key(&def_id)
+    }
+
     pub fn fn_quals(&self) -> impl Iterator<Item = (LocalDefId, &Vec<SurfaceIdent>)> {
         self.fn_quals.iter().map(|(def_id, quals)| (*def_id, quals))
     }
@@ -1146,6 +1192,10 @@ impl Map {
         self.externs.get(&extern_def_id).copied()
     }
 
+    pub fn externs(&self) -> &UnordMap<DefId, LocalDefId> {
+        &self.externs
+    }
+
     // ADT
 
     pub fn insert_refined_by(&mut self, def_id: LocalDefId, refined_by: RefinedBy) {
@@ -1156,6 +1206,29 @@ impl Map {
         &self.refined_by[&def_id]
     }
 
+    // Associated refinements
+
+    pub fn insert_assoc_refinements(&mut self, def_id: LocalDefId, refts: Vec<AssocRefinement>) {
+        self.assoc_refinements.insert(def_id, refts);
+    }
+
+    /// The associated refinements declared in a trait or defined in an impl
+    pub fn assoc_refinements_of(&self, def_id: LocalDefId) -> &[AssocRefinement] {
+        self.assoc_refinements
+            .get(&def_id)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn find_assoc_refinement(
+        &self,
+        def_id: LocalDefId,
+        name: Symbol,
+    ) -> Option<&AssocRefinement> {
+        self.assoc_refinements_of(def_id)
+            .iter()
+            .find(|reft| reft.name == name)
+    }
+
     // Aliases
 
     pub fn insert_type_alias(&mut self, def_id: LocalDefId, alias: TyAlias) {
@@ -1256,6 +1329,27 @@ impl Map {
             vec![Sort::BitVec(32), Sort::BitVec(32)],
             Sort::BitVec(32),
         );
+        self.insert_theory_func(
+            Symbol::intern("bv_or"),
+            Symbol::intern("bvor"),
+            0,
+            vec![Sort::BitVec(32), Sort::BitVec(32)],
+            Sort::BitVec(32),
+        );
+        self.insert_theory_func(
+            Symbol::intern("bv_shl"),
+            Symbol::intern("bvshl"),
+            0,
+            vec![Sort::BitVec(32), Sort::BitVec(32)],
+            Sort::BitVec(32),
+        );
+        self.insert_theory_func(
+            Symbol::intern("bv_lshr"),
+            Symbol::intern("bvlshr"),
+            0,
+            vec![Sort::BitVec(32), Sort::BitVec(32)],
+            Sort::BitVec(32),
+        );
 
         // Set operations
         self.insert_theory_func(
@@ -1514,10 +1608,11 @@ impl fmt::Debug for Ty {
             }
             TyKind::Tuple(tys) => write!(f, "({:?})", tys.iter().format(", ")),
             TyKind::Array(ty, len) => write!(f, "[{ty:?}; {len:?}]"),
+            TyKind::BareFn(inputs, output) => {
+                write!(f, "fn({:?}) -> {output:?}", inputs.iter().format(", "))
+            }
             TyKind::Never => write!(f, "!"),
             TyKind::Constr(pred, ty) => write!(f, "{{{ty:?} | {pred:?}}}"),
-            TyKind::RawPtr(ty, Mutability::Not) => write!(f, "*const {ty:?}"),
-            TyKind::RawPtr(ty, Mutability::Mut) => write!(f, "*mut {ty:?}"),
             TyKind::Hole(_) => write!(f, "_"),
             TyKind::OpaqueDef(def_id, args, refine_args, _) => {
                 write!(
@@ -1538,9 +1633,12 @@ impl fmt::Debug for Lifetime {
     }
 }
 
-impl fmt::Debug for ArrayLen {
+impl fmt::Debug for ConstArg {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "_")
+        match self.kind {
+            ConstArgKind::Lit(val) => write!(f, "{val}"),
+            ConstArgKind::Param(def_id) => write!(f, "{}", pretty::def_id_to_string(def_id)),
+        }
     }
 }
 
@@ -1549,6 +1647,8 @@ impl fmt::Debug for BaseTy {
         match &self.kind {
             BaseTyKind::Path(qpath) => write!(f, "{qpath:?}"),
             BaseTyKind::Slice(ty) => write!(f, "[{ty:?}]"),
+            BaseTyKind::RawPtr(ty, Mutability::Not) => write!(f, "*const {ty:?}"),
+            BaseTyKind::RawPtr(ty, Mutability::Mut) => write!(f, "*mut {ty:?}"),
         }
     }
 }
@@ -1604,6 +1704,7 @@ impl fmt::Debug for GenericArg {
         match self {
             GenericArg::Type(ty) => write!(f, "{ty:?}"),
             GenericArg::Lifetime(lft) => write!(f, "{lft:?}"),
+            GenericArg::Const(cst) => write!(f, "{cst:?}"),
         }
     }
 }
@@ -1653,6 +1754,16 @@ impl fmt::Debug for Expr {
             ExprKind::Literal(lit) => write!(f, "{lit:?}"),
             ExprKind::Const(x, _) => write!(f, "{}", pretty::def_id_to_string(*x)),
             ExprKind::App(uf, es) => write!(f, "{uf:?}({:?})", es.iter().format(", ")),
+            ExprKind::Alias(alias, es) => {
+                write!(
+                    f,
+                    "<{:?} as {}>::{}({:?})",
+                    alias.qself,
+                    pretty::def_id_to_string(alias.trait_id),
+                    alias.name,
+                    es.iter().format(", ")
+                )
+            }
             ExprKind::IfThenElse(box [p, e1, e2]) => {
                 write!(f, "(if {p:?} {{ {e1:?} }} else {{ {e2:?} }})")
             }
@@ -24,11 +24,12 @@ pub fn lift_refined_by(tcx: TyCtxt, owner_id: OwnerId) -> fhir::RefinedBy {
     let def_id = owner_id.def_id;
     let item = tcx.hir().expect_item(def_id);
     match item.kind {
-        hir::ItemKind::TyAlias(..) | hir::ItemKind::Struct(..) | hir::ItemKind::Enum(..) => {
-            fhir::RefinedBy::trivial(def_id, item.ident.span)
-        }
+        hir::ItemKind::TyAlias(..)
+        | hir::ItemKind::Struct(..)
+        | hir::ItemKind::Enum(..)
+        | hir::ItemKind::Union(..) => fhir::RefinedBy::trivial(def_id, item.ident.span),
         _ => {
-            bug!("expected struct, enum or type alias");
+            bug!("expected struct, enum, union or type alias");
         }
     }
 }
@@ -113,7 +114,9 @@ pub fn lift_self_ty(
         };
         let self_ty = cx.lift_ty(impl_.self_ty)?;
         Ok(Some(self_ty))
-    } else if let def_kind @ (DefKind::Struct | DefKind::Enum) = tcx.def_kind(owner_id) {
+    } else if let def_kind @ (DefKind::Struct | DefKind::Enum | DefKind::Union) =
+        tcx.def_kind(owner_id)
+    {
         let generics = tcx.hir().get_generics(owner_id.def_id).unwrap();
         let item = tcx.hir().expect_item(owner_id.def_id);
         let cx = LiftCtxt::new(tcx, sess, owner_id, None);
@@ -183,11 +186,19 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
                 ))
             }
             hir::GenericParamKind::Const { .. } => {
-                return self.emit_err(errors::UnsupportedHir::new(
-                    self.tcx,
-                    param.def_id,
-                    "const generics are not supported",
-                ))
+                if !self
+                    .tcx
+                    .type_of(param.def_id)
+                    .instantiate_identity()
+                    .is_integral()
+                {
+                    return self.emit_err(errors::UnsupportedHir::new(
+                        self.tcx,
+                        param.def_id,
+                        "only const generics of integer type are supported",
+                    ));
+                }
+                fhir::GenericParamKind::Const
             }
         };
         Ok(fhir::GenericParam { def_id: param.def_id, kind })
@@ -222,15 +233,27 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
         pred: &hir::WherePredicate,
     ) -> Result<fhir::WhereBoundPredicate, ErrorGuaranteed> {
         if let hir::WherePredicate::BoundPredicate(bound) = pred {
-            if !bound.bound_generic_params.is_empty() {
-                return self.emit_unsupported("higher-rank trait bounds are not supported");
-            }
             let bounded_ty = self.lift_ty(bound.bounded_ty)?;
-            let bounds = bound
+            if !bound.bound_generic_params.is_empty() && has_late_bound_lifetimes(&bounded_ty) {
+                return self.emit_unsupported(
+                    "higher-rank trait bounds on types with bound lifetimes are not supported",
+                );
+            }
+            let mut bounds: fhir::GenericBounds = bound
                 .bounds
                 .iter()
                 .map(|bound| self.lift_generic_bound(bound))
                 .try_collect()?;
+            // A binder on the predicate, e.g., `for<'a> F: Fn(&'a i32)`, binds the same lifetimes
+            // as a binder on each of its trait bounds, e.g., `F: for<'a> Fn(&'a i32)`.
+            for generic_bound in &mut bounds {
+                if let fhir::GenericBound::Trait(poly_trait_ref, _) = generic_bound {
+                    for param in bound.bound_generic_params {
+                        let param = self.lift_generic_param(param)?;
+                        poly_trait_ref.bound_generic_params.push(param);
+                    }
+                }
+            }
 
             Ok(fhir::WhereBoundPredicate { bounded_ty, bounds, span: bound.span })
         } else {
@@ -354,9 +377,11 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
         def_id: LocalDefId,
     ) -> Result<fhir::VariantDef, ErrorGuaranteed> {
         let hir_id = self.tcx.hir().local_def_id_to_hir_id(def_id);
-        let node = self.tcx.hir().get(hir_id);
-        let hir::Node::Variant(variant) = node else { bug!("expected a variant") };
-        self.lift_enum_variant(variant)
+        match self.tcx.hir().get(hir_id) {
+            hir::Node::Variant(variant) => self.lift_enum_variant(variant),
+            hir::Node::Field(field) => self.lift_union_field(field),
+            _ => bug!("expected a variant or a union field"),
+        }
     }
 
     pub fn lift_enum_variant(
@@ -385,9 +410,35 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
         })
     }
 
+    /// Lifts a field of a union as a variant with a single field. Unions are treated as enums with
+    /// one variant per field.
+    pub fn lift_union_field(
+        &mut self,
+        field: &hir::FieldDef,
+    ) -> Result<fhir::VariantDef, ErrorGuaranteed> {
+        let item = self.tcx.hir().expect_item(self.owner.def_id);
+        let hir::ItemKind::Union(_, generics) = &item.kind else { bug!("expected a union") };
+
+        let fields = vec![self.lift_field_def(field)?];
+
+        let ret = self.lift_variant_ret_inner(item, generics);
+
+        Ok(fhir::VariantDef {
+            def_id: field.def_id,
+            params: vec![],
+            fields,
+            ret,
+            span: field.span,
+            lifted: true,
+        })
+    }
+
     pub fn lift_variant_ret(&mut self) -> fhir::VariantRet {
         let item = self.tcx.hir().expect_item(self.owner.def_id);
-        let hir::ItemKind::Enum(_, generics) = &item.kind else { bug!("expected an enum") };
+        let (hir::ItemKind::Enum(_, generics) | hir::ItemKind::Union(_, generics)) = &item.kind
+        else {
+            bug!("expected an enum or a union")
+        };
         self.lift_variant_ret_inner(item, generics)
     }
 
@@ -435,7 +486,20 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
             }
             hir::TyKind::Path(qpath) => return self.lift_qpath(qpath),
             hir::TyKind::Ptr(mut_ty) => {
-                fhir::TyKind::RawPtr(Box::new(self.lift_ty(mut_ty.ty)?), mut_ty.mutbl)
+                let kind =
+                    fhir::BaseTyKind::RawPtr(Box::new(self.lift_ty(mut_ty.ty)?), mut_ty.mutbl);
+                let bty = fhir::BaseTy { kind, span: ty.span };
+                return Ok(fhir::Ty { kind: fhir::TyKind::BaseTy(bty), span: ty.span });
+            }
+            hir::TyKind::BareFn(bare_fn) if bare_fn.generic_params.is_empty() => {
+                let inputs = bare_fn
+                    .decl
+                    .inputs
+                    .iter()
+                    .map(|ty| self.lift_ty(ty))
+                    .try_collect_exhaust()?;
+                let output = self.lift_fn_ret_ty(&bare_fn.decl.output)?;
+                fhir::TyKind::BareFn(inputs, Box::new(output))
             }
             hir::TyKind::OpaqueDef(item_id, args, in_trait_def) => {
                 let opaque_ty = self
@@ -527,8 +591,9 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
                     let ty = self.lift_ty(ty)?;
                     lifted.push(fhir::GenericArg::Type(ty));
                 }
-                hir::GenericArg::Const(_) => {
-                    return self.emit_unsupported("const generics are not supported")
+                hir::GenericArg::Const(const_arg) => {
+                    let const_arg = self.lift_anon_const(&const_arg.value)?;
+                    lifted.push(fhir::GenericArg::Const(const_arg));
                 }
                 hir::GenericArg::Infer(_) => {
                     bug!("unexpected inference generic argument");
@@ -556,17 +621,30 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
         Ok(lifted)
     }
 
-    fn lift_array_len(&self, len: hir::ArrayLen) -> Result<fhir::ArrayLen, ErrorGuaranteed> {
-        let body = match len {
-            hir::ArrayLen::Body(anon_const) => self.tcx.hir().body(anon_const.body),
+    fn lift_array_len(&self, len: hir::ArrayLen) -> Result<fhir::ConstArg, ErrorGuaranteed> {
+        match len {
+            hir::ArrayLen::Body(anon_const) => self.lift_anon_const(&anon_const),
             hir::ArrayLen::Infer(_, _) => bug!("unexpected `ArrayLen::Infer`"),
-        };
-        if let hir::ExprKind::Lit(lit) = &body.value.kind
-            && let LitKind::Int(array_len, _) = lit.node
-        {
-            Ok(fhir::ArrayLen { val: array_len as usize, span: lit.span })
-        } else {
-            self.emit_unsupported("only interger literals are supported for array lengths")
+        }
+    }
+
+    fn lift_anon_const(
+        &self,
+        anon_const: &hir::AnonConst,
+    ) -> Result<fhir::ConstArg, ErrorGuaranteed> {
+        let body = self.tcx.hir().body(anon_const.body);
+        match &body.value.kind {
+            hir::ExprKind::Lit(lit) if let LitKind::Int(val, _) = lit.node => {
+                Ok(fhir::ConstArg { kind: fhir::ConstArgKind::Lit(val as usize), span: lit.span })
+            }
+            hir::ExprKind::Path(hir::QPath::Resolved(None, path))
+                if let hir::def::Res::Def(DefKind::ConstParam, def_id) = path.res =>
+            {
+                Ok(fhir::ConstArg { kind: fhir::ConstArgKind::Param(def_id), span: path.span })
+            }
+            _ => self.emit_unsupported(
+                "only interger literals and const generic parameters are supported as constants",
+            ),
         }
     }
 
@@ -597,7 +675,8 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
                     args.push(fhir::GenericArg::Lifetime(lft));
                 }
                 hir::GenericParamKind::Const { .. } => {
-                    return self.emit_unsupported("const generics are not supported");
+                    let kind = fhir::ConstArgKind::Param(param.def_id.to_def_id());
+                    args.push(fhir::GenericArg::Const(fhir::ConstArg { kind, span: param.span }));
                 }
             }
         }
@@ -622,6 +701,22 @@ impl<'a, 'tcx> LiftCtxt<'a, 'tcx> {
     }
 }
 
+fn has_late_bound_lifetimes(ty: &fhir::Ty) -> bool {
+    struct LateBoundVisitor(bool);
+
+    impl fhir::visit::Visitor for LateBoundVisitor {
+        fn visit_lifetime(&mut self, lft: &fhir::Lifetime) {
+            if let fhir::Lifetime::Resolved(ResolvedArg::LateBound(..)) = lft {
+                self.0 = true;
+            }
+        }
+    }
+
+    let mut visitor = LateBoundVisitor(false);
+    fhir::visit::Visitor::visit_ty(&mut visitor, ty);
+    visitor.0
+}
+
 pub mod errors {
     use flux_macros::Diagnostic;
     use rustc_hir::def_id::DefId;
@@ -166,6 +166,7 @@ pub fn walk_generic_arg<V: Visitor>(vis: &mut V, arg: &GenericArg) {
     match arg {
         GenericArg::Lifetime(lft) => vis.visit_lifetime(lft),
         GenericArg::Type(ty) => vis.visit_ty(ty),
+        GenericArg::Const(_) => {}
     }
 }
 
@@ -198,8 +199,9 @@ pub fn walk_ty<V: Visitor>(vis: &mut V, ty: &Ty) {
         TyKind::Array(ty, _len) => {
             vis.visit_ty(ty);
         }
-        TyKind::RawPtr(ty, _mtblt) => {
-            vis.visit_ty(ty);
+        TyKind::BareFn(inputs, output) => {
+            walk_list!(vis, visit_ty, inputs);
+            vis.visit_ty(output);
         }
         TyKind::OpaqueDef(_item_id, generics, refine, _bool) => {
             walk_list!(vis, visit_generic_arg, generics);
@@ -213,7 +215,7 @@ pub fn walk_ty<V: Visitor>(vis: &mut V, ty: &Ty) {
 pub fn walk_bty<V: Visitor>(vis: &mut V, bty: &BaseTy) {
     match &bty.kind {
         BaseTyKind::Path(path) => vis.visit_qpath(path),
-        BaseTyKind::Slice(ty) => vis.visit_ty(ty),
+        BaseTyKind::Slice(ty) | BaseTyKind::RawPtr(ty, _) => vis.visit_ty(ty),
     }
 }
 
@@ -296,7 +298,7 @@ pub fn walk_expr<V: Visitor>(vis: &mut V, expr: &Expr) {
             vis.visit_expr(e2);
         }
         ExprKind::UnaryOp(_op, e) => vis.visit_expr(e),
-        ExprKind::App(_func, args) => {
+        ExprKind::App(_, args) | ExprKind::Alias(_, args) => {
             walk_list!(vis, visit_expr, args);
         }
         ExprKind::IfThenElse(box [e1, e2, e3]) => {
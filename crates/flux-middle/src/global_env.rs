@@ -107,6 +107,18 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
         self.queries.check_wf(self, flux_id.into())
     }
 
+    pub fn assoc_refinement_def(
+        &self,
+        owner_id: DefId,
+        name: Symbol,
+    ) -> QueryResult<Option<rty::AssocRefinementDef>> {
+        self.queries.assoc_refinement_def(self, owner_id, name)
+    }
+
+    pub fn sort_of_assoc_reft(&self, trait_id: DefId, name: Symbol) -> Option<rty::FuncSort> {
+        self.queries.sort_of_assoc_reft(self, trait_id, name)
+    }
+
     pub fn generics_of(&self, def_id: impl Into<DefId>) -> QueryResult<rty::Generics> {
         self.queries.generics_of(self, def_id.into())
     }
@@ -181,7 +193,7 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
     pub fn sort_of_bty(&self, bty: &fhir::BaseTy) -> Option<fhir::Sort> {
         match &bty.kind {
             fhir::BaseTyKind::Path(fhir::QPath::Resolved(_, path)) => self.sort_of_path(path),
-            fhir::BaseTyKind::Slice(_) => Some(fhir::Sort::Int),
+            fhir::BaseTyKind::Slice(_) | fhir::BaseTyKind::RawPtr(..) => Some(fhir::Sort::Int),
         }
     }
 
@@ -191,7 +203,8 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
         sort_args: &[fhir::Sort],
     ) -> Vec<fhir::Sort> {
         let def_id = def_id.into();
-        if let Some(local_id) = def_id.as_local().or_else(|| self.map().get_extern(def_id)) {
+        let def_id = self.lookup_extern(def_id).unwrap_or(def_id);
+        if let Some(local_id) = def_id.as_local() {
             self.map().refined_by(local_id).index_sorts(sort_args)
         } else {
             self.cstore()
@@ -209,7 +222,10 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
             fhir::Res::PrimTy(PrimTy::Float(..) | PrimTy::Str | PrimTy::Char) => {
                 Some(fhir::Sort::Unit)
             }
-            fhir::Res::Def(DefKind::TyAlias { .. } | DefKind::Enum | DefKind::Struct, def_id) => {
+            fhir::Res::Def(
+                DefKind::TyAlias { .. } | DefKind::Enum | DefKind::Struct | DefKind::Union,
+                def_id,
+            ) => {
                 let mut sort_args = vec![];
                 if let Ok(generics) = self.generics_of(def_id) {
                     for (param, arg) in generics.params.iter().zip(&path.args) {
@@ -241,20 +257,25 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
             fhir::GenericParamKind::BaseTy | fhir::GenericParamKind::SplTy => {
                 Some(fhir::Sort::Param(def_id))
             }
-            fhir::GenericParamKind::Type { .. } | fhir::GenericParamKind::Lifetime => None,
+            fhir::GenericParamKind::Type { .. }
+            | fhir::GenericParamKind::Lifetime
+            | fhir::GenericParamKind::Const => None,
         }
     }
 
     fn sort_of_ty(&self, ty: &fhir::Ty) -> Option<fhir::Sort> {
         match &ty.kind {
             fhir::TyKind::BaseTy(bty) | fhir::TyKind::Indexed(bty, _) => {
+                if let fhir::BaseTyKind::RawPtr(..) = bty.kind {
+                    return Some(fhir::Sort::Int);
+                }
                 self.sort_of_path(bty.as_path()?)
             }
             fhir::TyKind::Exists(_, ty) | fhir::TyKind::Constr(_, ty) => self.sort_of_ty(ty),
-            fhir::TyKind::RawPtr(_, _)
-            | fhir::TyKind::Ref(_, _)
+            fhir::TyKind::Ref(_, _)
             | fhir::TyKind::Tuple(_)
             | fhir::TyKind::Array(_, _)
+            | fhir::TyKind::BareFn(_, _)
             | fhir::TyKind::Never => Some(fhir::Sort::Unit),
             fhir::TyKind::Hole(_) => Some(fhir::Sort::Wildcard),
             fhir::TyKind::Ptr(_, _) => None,
@@ -267,9 +288,10 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
         // CODESYNC(sort-of, 3) sorts should be given consistently
         match ty.kind() {
             ty::TyKind::Bool => Some(fhir::Sort::Bool),
-            ty::TyKind::Slice(_) | ty::TyKind::Int(_) | ty::TyKind::Uint(_) => {
-                Some(fhir::Sort::Int)
-            }
+            ty::TyKind::Slice(_)
+            | ty::TyKind::Int(_)
+            | ty::TyKind::Uint(_)
+            | ty::TyKind::RawPtr(_) => Some(fhir::Sort::Int),
             ty::TyKind::Adt(adt_def, args) => {
                 let mut sort_args = vec![];
                 for arg in *args {
@@ -291,16 +313,17 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
             ty::TyKind::Float(_)
             | ty::TyKind::Str
             | ty::TyKind::Char
-            | ty::TyKind::RawPtr(_)
             | ty::TyKind::Ref(..)
             | ty::TyKind::Tuple(_)
             | ty::TyKind::Array(..)
+            | ty::TyKind::FnPtr(..)
             | ty::TyKind::Never => Some(fhir::Sort::Unit),
             _ => bug!("unexpected self ty {ty:?}"),
         }
     }
 
     pub fn early_bound_sorts_of(&self, def_id: DefId, sort_args: &[fhir::Sort]) -> Vec<fhir::Sort> {
+        let def_id = self.lookup_extern(def_id).unwrap_or(def_id);
         if let Some(local_id) = def_id.as_local() {
             self.map().refined_by(local_id).early_bound_sorts(sort_args)
         } else {
@@ -347,6 +370,7 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
         sort_args: List<fhir::Sort>,
         fld: Symbol,
     ) -> Option<fhir::Sort> {
+        let def_id = self.lookup_extern(def_id).unwrap_or(def_id);
         let poly_sort = if let Some(local_id) = def_id.as_local() {
             self.map().refined_by(local_id).field_sort(fld, &sort_args)
         } else {
@@ -358,6 +382,7 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
     }
 
     pub fn field_index(&self, def_id: DefId, fld: Symbol) -> Option<usize> {
+        let def_id = self.lookup_extern(def_id).unwrap_or(def_id);
         if let Some(local_id) = def_id.as_local() {
             self.map().refined_by(local_id).field_index(fld)
         } else {
@@ -430,8 +455,13 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
         self.tcx.hir()
     }
 
+    /// Resolves an item to the one holding its extern spec, either in the current crate or in a
+    /// dependency. Local extern specs take precedence.
     pub(crate) fn lookup_extern(&self, def_id: DefId) -> Option<DefId> {
-        self.map().get_extern(def_id).map(LocalDefId::to_def_id)
+        self.map()
+            .get_extern(def_id)
+            .map(LocalDefId::to_def_id)
+            .or_else(|| self.cstore().lookup_extern(def_id))
     }
 
     pub(crate) fn is_fn_once_output(&self, def_id: DefId) -> bool {
@@ -439,4 +469,10 @@ impl<'sess, 'tcx> GlobalEnv<'sess, 'tcx> {
             .require_lang_item(rustc_hir::LangItem::FnOnceOutput, None)
             == def_id
     }
+
+    pub(crate) fn is_discriminant_ty(&self, def_id: DefId) -> bool {
+        self.tcx
+            .require_lang_item(rustc_hir::LangItem::Discriminant, None)
+            == def_id
+    }
 }
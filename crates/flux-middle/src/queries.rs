@@ -9,7 +9,10 @@ use itertools::Itertools;
 use rustc_data_structures::unord::UnordMap;
 use rustc_errors::IntoDiagnostic;
 use rustc_hash::FxHashMap;
-use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_hir::{
+    def::DefKind,
+    def_id::{DefId, LocalDefId},
+};
 use rustc_infer::infer::TyCtxtInferExt;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::{Span, Symbol};
@@ -25,7 +28,7 @@ use crate::{
     },
     rustc::{
         self,
-        lowering::{self, UnsupportedReason},
+        lowering::{self, UnsupportedMirErr, UnsupportedReason},
         ty,
     },
 };
@@ -36,7 +39,16 @@ pub type QueryResult<T = ()> = Result<T, QueryErr>;
 
 #[derive(Debug, Clone)]
 pub enum QueryErr {
-    UnsupportedType { def_id: DefId, def_span: Span, reason: UnsupportedReason },
+    UnsupportedType {
+        def_id: DefId,
+        def_span: Span,
+        reason: UnsupportedReason,
+    },
+    /// The body of `def_id` contains MIR we don't support.
+    UnsupportedMir {
+        def_id: DefId,
+        err: UnsupportedMirErr,
+    },
     Emitted(ErrorGuaranteed),
 }
 
@@ -56,6 +68,9 @@ pub struct Providers {
     pub predicates_of:
         fn(&GlobalEnv, LocalDefId) -> QueryResult<rty::EarlyBinder<rty::GenericPredicates>>,
     pub item_bounds: fn(&GlobalEnv, LocalDefId) -> QueryResult<rty::EarlyBinder<List<rty::Clause>>>,
+    pub assoc_refinement_def:
+        fn(&GlobalEnv, LocalDefId, Symbol) -> QueryResult<Option<rty::AssocRefinementDef>>,
+    pub sort_of_assoc_reft: fn(&GlobalEnv, LocalDefId, Symbol) -> Option<rty::FuncSort>,
 }
 
 macro_rules! empty_query {
@@ -78,6 +93,8 @@ impl Default for Providers {
             generics_of: |_, _| empty_query!(),
             predicates_of: |_, _| empty_query!(),
             item_bounds: |_, _| empty_query!(),
+            assoc_refinement_def: |_, _, _| empty_query!(),
+            sort_of_assoc_reft: |_, _, _| empty_query!(),
         }
     }
 }
@@ -100,6 +117,8 @@ pub struct Queries<'tcx> {
     variants_of: Cache<DefId, QueryResult<rty::Opaqueness<rty::EarlyBinder<rty::PolyVariants>>>>,
     fn_sig: Cache<DefId, QueryResult<rty::EarlyBinder<rty::PolyFnSig>>>,
     lower_late_bound_vars: Cache<LocalDefId, QueryResult<List<rustc::ty::BoundVariableKind>>>,
+    assoc_refinement_def: Cache<(DefId, Symbol), QueryResult<Option<rty::AssocRefinementDef>>>,
+    sort_of_assoc_reft: Cache<(DefId, Symbol), Option<rty::FuncSort>>,
 }
 
 impl<'tcx> Queries<'tcx> {
@@ -110,7 +129,8 @@ impl<'tcx> Queries<'tcx> {
     ) -> QueryResult<Rc<rustc::mir::Body<'tcx>>> {
         run_with_cache(&self.mir, def_id, || {
             let mir = unsafe { flux_common::mir_storage::retrieve_mir_body(genv.tcx, def_id) };
-            let mir = rustc::lowering::LoweringCtxt::lower_mir_body(genv.tcx, genv.sess, mir)?;
+            let mir = rustc::lowering::LoweringCtxt::lower_mir_body(genv.tcx, mir)
+                .map_err(|err| QueryErr::UnsupportedMir { def_id: def_id.to_def_id(), err })?;
             Ok(Rc::new(mir))
         })
     }
@@ -200,6 +220,8 @@ impl<'tcx> Queries<'tcx> {
             let def_id = genv.lookup_extern(def_id).unwrap_or(def_id);
             if let Some(local_id) = def_id.as_local() {
                 (self.providers.generics_of)(genv, local_id)
+            } else if let Some(generics) = genv.cstore().generics_of(def_id) {
+                Ok(generics.clone())
             } else {
                 let generics = genv.tcx.generics_of(def_id);
                 let generics = lowering::lower_generics(generics)
@@ -266,7 +288,13 @@ impl<'tcx> Queries<'tcx> {
             } else if let Some(ty) = genv.cstore().type_of(def_id) {
                 Ok(ty.clone())
             } else {
-                let generics = genv.generics_of(def_id)?;
+                // The default of a type parameter can mention the other parameters of its parent,
+                // e.g., `Rhs = Self` in `PartialEq`.
+                let generics_def_id = match genv.tcx.def_kind(def_id) {
+                    DefKind::TyParam => genv.tcx.parent(def_id),
+                    _ => def_id,
+                };
+                let generics = genv.generics_of(generics_def_id)?;
                 let ty = genv.lower_type_of(def_id)?.skip_binder();
                 let ty = Refiner::default(genv, &generics).refine_ty(&ty)?;
                 Ok(rty::EarlyBinder(rty::Binder::with_sort(ty, rty::Sort::unit())))
@@ -285,21 +313,32 @@ impl<'tcx> Queries<'tcx> {
             } else if let Some(variants) = genv.cstore().variants(def_id) {
                 Ok(variants.map(|variants| variants.map(List::from)))
             } else {
-                let variants = genv
-                    .tcx
-                    .adt_def(def_id)
-                    .variants()
-                    .iter()
-                    .map(|variant_def| {
-                        let fields = variant_def
-                            .fields
-                            .iter()
-                            .map(|field| Ok(genv.lower_type_of(field.did)?.skip_binder()))
-                            .try_collect_vec::<_, QueryErr>()?;
-                        Refiner::default(genv, &genv.generics_of(def_id)?)
-                            .refine_variant_def(def_id, &fields)
-                    })
-                    .try_collect()?;
+                let adt_def = genv.tcx.adt_def(def_id);
+                let generics = genv.generics_of(def_id)?;
+                let refiner = Refiner::default(genv, &generics);
+                let variants = if adt_def.is_union() {
+                    // Unions are refined with one variant per field
+                    adt_def
+                        .all_fields()
+                        .map(|field| {
+                            let ty = genv.lower_type_of(field.did)?.skip_binder();
+                            refiner.refine_variant_def(def_id, &[ty])
+                        })
+                        .try_collect()?
+                } else {
+                    adt_def
+                        .variants()
+                        .iter()
+                        .map(|variant_def| {
+                            let fields = variant_def
+                                .fields
+                                .iter()
+                                .map(|field| Ok(genv.lower_type_of(field.did)?.skip_binder()))
+                                .try_collect_vec::<_, QueryErr>()?;
+                            refiner.refine_variant_def(def_id, &fields)
+                        })
+                        .try_collect()?
+                };
                 Ok(rty::Opaqueness::Transparent(rty::EarlyBinder(variants)))
             }
         })
@@ -327,6 +366,35 @@ impl<'tcx> Queries<'tcx> {
         })
     }
 
+    /// The definition of an associated refinement in an impl, or the default definition if
+    /// `owner_id` is a trait. Returns [`None`] if the item doesn't define the refinement.
+    pub(crate) fn assoc_refinement_def(
+        &self,
+        genv: &GlobalEnv,
+        owner_id: DefId,
+        name: Symbol,
+    ) -> QueryResult<Option<rty::AssocRefinementDef>> {
+        run_with_cache(&self.assoc_refinement_def, (owner_id, name), || {
+            if let Some(local_id) = owner_id.as_local() {
+                (self.providers.assoc_refinement_def)(genv, local_id, name)
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    pub(crate) fn sort_of_assoc_reft(
+        &self,
+        genv: &GlobalEnv,
+        trait_id: DefId,
+        name: Symbol,
+    ) -> Option<rty::FuncSort> {
+        run_with_cache(&self.sort_of_assoc_reft, (trait_id, name), || {
+            let local_id = trait_id.as_local()?;
+            (self.providers.sort_of_assoc_reft)(genv, local_id, name)
+        })
+    }
+
     pub(crate) fn lower_late_bound_vars(
         &self,
         genv: &GlobalEnv,
@@ -358,6 +426,18 @@ impl QueryErr {
     pub fn unsupported(tcx: TyCtxt, def_id: DefId, reason: UnsupportedReason) -> Self {
         QueryErr::UnsupportedType { def_id, def_span: tcx.def_span(def_id), reason }
     }
+
+    /// If the error was caused by an unsupported feature, returns the span where the feature
+    /// was found together with a description of it.
+    pub fn unsupported_reason(&self) -> Option<(Span, String)> {
+        match self {
+            QueryErr::UnsupportedType { def_span, reason, .. } => {
+                Some((*def_span, reason.descr.clone()))
+            }
+            QueryErr::UnsupportedMir { err, .. } => Some((err.span(), err.reason())),
+            QueryErr::Emitted(_) => None,
+        }
+    }
 }
 
 impl<'a> IntoDiagnostic<'a> for QueryErr {
@@ -375,6 +455,7 @@ impl<'a> IntoDiagnostic<'a> for QueryErr {
                 builder.note(reason.descr);
                 builder
             }
+            QueryErr::UnsupportedMir { err, .. } => err.into_diagnostic(handler),
             QueryErr::Emitted(_) => {
                 let mut builder = handler.struct_err("QueryErr::Emitted should be emitted");
                 builder.downgrade_to_delayed_bug();
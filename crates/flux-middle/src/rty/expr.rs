@@ -6,27 +6,45 @@ use itertools::Itertools;
 use rustc_hir::def_id::DefId;
 use rustc_index::newtype_index;
 use rustc_macros::{Decodable, Encodable, TyDecodable, TyEncodable};
-use rustc_middle::mir::Local;
+use rustc_middle::{mir::Local, ty::ParamConst};
+use rustc_serialize::{Decodable, Encodable};
 use rustc_span::{BytePos, Span, Symbol, SyntaxContext};
 use rustc_target::abi::FieldIdx;
-use rustc_type_ir::{DebruijnIndex, INNERMOST};
+use rustc_type_ir::{DebruijnIndex, TyDecoder, TyEncoder, INNERMOST};
 
-use super::{evars::EVar, BaseTy, Binder, IntTy, Sort, UintTy};
+use super::{evars::EVar, BaseTy, Binder, GenericArgs, IntTy, Sort, UintTy};
 use crate::{
     fhir::FuncKind,
     intern::{impl_internable, impl_slice_internable, Interned, List},
     rty::fold::{TypeFoldable, TypeFolder, TypeSuperFoldable},
-    rustc::mir::{Place, PlaceElem},
+    rustc::{
+        mir::{Place, PlaceElem},
+        ty::Const,
+    },
 };
 
 pub type Expr = Interned<ExprS>;
 
-#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct ExprS {
     kind: ExprKind,
     espan: Option<ESpan>,
 }
 
+// Spans are only meaningful in the crate where they were created so we don't export them
+// through metadata.
+impl<E: TyEncoder> Encodable<E> for ExprS {
+    fn encode(&self, e: &mut E) {
+        self.kind.encode(e);
+    }
+}
+
+impl<D: TyDecoder> Decodable<D> for ExprS {
+    fn decode(d: &mut D) -> Self {
+        ExprS { kind: ExprKind::decode(d), espan: None }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, TyEncodable, TyDecodable, Debug)]
 pub struct ESpan {
     /// The top-level span information
@@ -78,8 +96,14 @@ pub enum ExprKind {
     Local(Local),
     Constant(Constant),
     ConstDefId(DefId),
+    /// A const generic parameter, e.g., the `N` in `fn foo<const N: usize>()`. Const generics are
+    /// substituted with their value at instantiation, so these are treated as uninterpreted
+    /// constants of sort `int` inside the item declaring them.
+    ConstParam(ParamConst),
     BinaryOp(BinOp, Expr, Expr),
     App(Expr, List<Expr>),
+    /// An associated refinement applied to arguments, e.g., `<T as Trait>::name(e1, ..., en)`
+    Alias(AliasReft, List<Expr>),
     GlobalFunc(Symbol, FuncKind),
     UnaryOp(UnOp, Expr),
     TupleProj(Expr, u32),
@@ -110,6 +134,15 @@ pub enum ExprKind {
     Hole(HoleKind),
 }
 
+/// A reference to an associated refinement of a trait. The only generic argument is the `Self`
+/// type of the trait, which determines the impl providing the definition.
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
+pub struct AliasReft {
+    pub trait_id: DefId,
+    pub name: Symbol,
+    pub args: GenericArgs,
+}
+
 /// The position where a hole appears. This determines how it will be inferred. This is related but not
 /// quite the same as the [`InferMode`].
 ///
@@ -316,6 +349,17 @@ impl Expr {
         ExprKind::ConstDefId(c).intern_at(espan)
     }
 
+    pub fn const_param(param: ParamConst, espan: Option<ESpan>) -> Expr {
+        ExprKind::ConstParam(param).intern_at(espan)
+    }
+
+    pub fn from_const(c: &Const) -> Expr {
+        match c {
+            Const::Param(param) => Expr::const_param(*param, None),
+            Const::Value(value) => Expr::constant(Constant::from(value.val)),
+        }
+    }
+
     pub fn tuple(exprs: impl Into<List<Expr>>) -> Expr {
         ExprKind::Tuple(exprs.into()).intern()
     }
@@ -367,6 +411,10 @@ impl Expr {
         ExprKind::App(func.into(), args.into()).intern_at(espan)
     }
 
+    pub fn alias(alias: AliasReft, args: impl Into<List<Expr>>, espan: Option<ESpan>) -> Expr {
+        ExprKind::Alias(alias, args.into()).intern_at(espan)
+    }
+
     pub fn global_func(func: Symbol, kind: FuncKind) -> Expr {
         ExprKind::GlobalFunc(func, kind).intern()
     }
@@ -800,6 +848,7 @@ mod pretty {
                 ExprKind::Var(var) => w!("{:?}", var),
                 ExprKind::Local(local) => w!("{:?}", ^local),
                 ExprKind::ConstDefId(did) => w!("{}", ^pretty::def_id_to_string(*did)),
+                ExprKind::ConstParam(param) => w!("{}", ^param.name),
                 ExprKind::Constant(c) => w!("{}", ^c),
                 ExprKind::BinaryOp(op, e1, e2) => {
                     if should_parenthesize(op, e1) {
@@ -855,6 +904,14 @@ mod pretty {
                             .format_with(", ", |arg, f| f(&format_args_cx!("{:?}", arg)))
                     )
                 }
+                ExprKind::Alias(alias, args) => {
+                    w!("{:?}({})",
+                        alias,
+                        ^args
+                            .iter()
+                            .format_with(", ", |arg, f| f(&format_args_cx!("{:?}", arg)))
+                    )
+                }
                 ExprKind::IfThenElse(p, e1, e2) => {
                     w!("if {:?} {{ {:?} }} else {{ {:?} }}", p, e1, e2)
                 }
@@ -872,6 +929,13 @@ mod pretty {
         }
     }
 
+    impl Pretty for AliasReft {
+        fn fmt(&self, cx: &PPrintCx, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            define_scoped!(cx, f);
+            w!("<{:?} as {:?}>::{}", &self.args[0], self.trait_id, ^self.name)
+        }
+    }
+
     impl Pretty for Var {
         fn fmt(&self, cx: &PPrintCx, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             define_scoped!(cx, f);
@@ -953,5 +1017,5 @@ mod pretty {
         }
     }
 
-    impl_debug_with_default_cx!(Expr, Loc, Path, Var, KVar);
+    impl_debug_with_default_cx!(Expr, Loc, Path, Var, KVar, AliasReft);
 }
@@ -14,9 +14,9 @@ use super::{
     normalize::{Defns, Normalizer},
     projections,
     subst::EVarSubstFolder,
-    AliasTy, BaseTy, Binder, BoundVariableKind, Clause, ClauseKind, Constraint, Expr, ExprKind,
-    FnOutput, FnSig, FnTraitPredicate, FuncSort, GeneratorObligPredicate, GenericArg, Index,
-    Invariant, KVar, Name, OpaqueArgsMap, Opaqueness, OutlivesPredicate, PolyFuncSort,
+    AliasReft, AliasTy, BaseTy, Binder, BoundVariableKind, Clause, ClauseKind, Constraint, Expr,
+    ExprKind, FnOutput, FnSig, FnTraitPredicate, FuncSort, GeneratorObligPredicate, GenericArg,
+    Index, Invariant, KVar, Name, OpaqueArgsMap, Opaqueness, OutlivesPredicate, PolyFuncSort,
     ProjectionPredicate, PtrKind, Qualifier, ReLateBound, Region, Sort, TraitPredicate, TraitRef,
     Ty, TyKind,
 };
@@ -870,6 +870,7 @@ impl TypeSuperVisitable for BaseTy {
             BaseTy::Ref(_, ty, _) => ty.visit_with(visitor),
             BaseTy::Tuple(tys) => tys.visit_with(visitor),
             BaseTy::Array(ty, _) => ty.visit_with(visitor),
+            BaseTy::FnPtr(fn_sig) => fn_sig.visit_with(visitor),
             BaseTy::Int(_)
             | BaseTy::Uint(_)
             | BaseTy::Bool
@@ -911,6 +912,7 @@ impl TypeSuperFoldable for BaseTy {
             | BaseTy::Char
             | BaseTy::Never => self.clone(),
             BaseTy::Closure(did, args) => BaseTy::Closure(*did, args.try_fold_with(folder)?),
+            BaseTy::FnPtr(fn_sig) => BaseTy::FnPtr(fn_sig.try_fold_with(folder)?),
             BaseTy::Coroutine(did, args) => BaseTy::Coroutine(*did, args.try_fold_with(folder)?),
             BaseTy::CoroutineWitness(did, args) => {
                 BaseTy::CoroutineWitness(*did, args.try_fold_with(folder)?)
@@ -997,6 +999,10 @@ impl TypeSuperVisitable for Expr {
                 func.visit_with(visitor)?;
                 arg.visit_with(visitor)
             }
+            ExprKind::Alias(alias, args) => {
+                alias.args.visit_with(visitor)?;
+                args.visit_with(visitor)
+            }
             ExprKind::IfThenElse(p, e1, e2) => {
                 p.visit_with(visitor)?;
                 e1.visit_with(visitor)?;
@@ -1008,7 +1014,8 @@ impl TypeSuperVisitable for Expr {
             | ExprKind::Hole(_)
             | ExprKind::Local(_)
             | ExprKind::GlobalFunc(..)
-            | ExprKind::ConstDefId(_) => ControlFlow::Continue(()),
+            | ExprKind::ConstDefId(_)
+            | ExprKind::ConstParam(_) => ControlFlow::Continue(()),
         }
     }
 }
@@ -1036,6 +1043,7 @@ impl TypeSuperFoldable for Expr {
             ExprKind::Local(local) => Expr::local(*local, span),
             ExprKind::Constant(c) => Expr::constant_at(*c, span),
             ExprKind::ConstDefId(did) => Expr::const_def_id(*did, span),
+            ExprKind::ConstParam(param) => Expr::const_param(*param, span),
             ExprKind::BinaryOp(op, e1, e2) => {
                 Expr::binary_op(*op, e1.try_fold_with(folder)?, e2.try_fold_with(folder)?, span)
             }
@@ -1052,6 +1060,14 @@ impl TypeSuperFoldable for Expr {
             ExprKind::App(func, arg) => {
                 Expr::app(func.try_fold_with(folder)?, arg.try_fold_with(folder)?, span)
             }
+            ExprKind::Alias(alias, args) => {
+                let alias = AliasReft {
+                    trait_id: alias.trait_id,
+                    name: alias.name,
+                    args: alias.args.try_fold_with(folder)?,
+                };
+                Expr::alias(alias, args.try_fold_with(folder)?, span)
+            }
             ExprKind::IfThenElse(p, e1, e2) => {
                 Expr::ite(
                     p.try_fold_with(folder)?,
@@ -15,7 +15,7 @@ pub mod subst;
 use std::{fmt, hash::Hash, iter, slice, sync::LazyLock};
 
 pub use evars::{EVar, EVarGen};
-pub use expr::{ESpan, Expr, ExprKind, HoleKind, KVar, KVid, Loc, Name, Path, Var};
+pub use expr::{AliasReft, ESpan, Expr, ExprKind, HoleKind, KVar, KVid, Loc, Name, Path, Var};
 use flux_common::bug;
 pub use flux_fixpoint::{BinOp, Constant, UnOp};
 use itertools::Itertools;
@@ -57,7 +57,7 @@ pub use crate::{
     },
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, TyEncodable, TyDecodable)]
 pub struct Generics {
     pub params: List<GenericParamDef>,
     pub refine_params: List<RefineParam>,
@@ -66,13 +66,13 @@ pub struct Generics {
     pub parent_refine_count: usize,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+#[derive(PartialEq, Eq, Debug, Clone, Hash, TyEncodable, TyDecodable)]
 pub struct RefineParam {
     pub sort: Sort,
     pub mode: InferMode,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct GenericParamDef {
     pub kind: GenericParamDefKind,
     pub def_id: DefId,
@@ -80,7 +80,7 @@ pub struct GenericParamDef {
     pub name: Symbol,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum GenericParamDefKind {
     Type { has_default: bool },
     SplTy,
@@ -269,14 +269,14 @@ where
 
 pub type PolyFnSig = Binder<FnSig>;
 
-#[derive(Clone, TyEncodable, TyDecodable)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct FnSig {
     requires: List<Constraint>,
     args: List<Ty>,
     output: Binder<FnOutput>,
 }
 
-#[derive(Clone, Debug, TyEncodable, TyDecodable)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, TyEncodable, TyDecodable)]
 pub struct FnOutput {
     pub ret: Ty,
     pub ensures: List<Constraint>,
@@ -316,6 +316,8 @@ pub struct ClosureOblig {
 }
 
 pub type PolyTy = Binder<Ty>;
+/// The definition of an associated refinement, abstracted over its refinement parameters
+pub type AssocRefinementDef = EarlyBinder<Binder<Expr>>;
 pub type Ty = Interned<TyS>;
 
 #[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
@@ -366,12 +368,15 @@ pub enum BaseTy {
     Slice(Ty),
     Adt(AdtDef, GenericArgs),
     Float(FloatTy),
+    /// A raw pointer indexed by the number of valid elements it points to, i.e., the pointer can
+    /// be dereferenced if its index is positive.
     RawPtr(Ty, Mutability),
     Ref(Region, Ty, Mutability),
     Tuple(List<Ty>),
     Array(Ty, Const),
     Never,
     Closure(DefId, List<Ty>),
+    FnPtr(PolyFnSig),
     Coroutine(DefId, GenericArgs),
     CoroutineWitness(DefId, GenericArgs),
     Param(ParamTy),
@@ -483,6 +488,11 @@ impl Clause {
         // FIXME(nilehmann) we should deal with the binder in all the places this is used instead of blindly skipping it here
         self.kind.clone().skip_binder()
     }
+
+    /// The kind of the clause together with its (possibly empty) list of late bound regions.
+    pub fn bound_kind(&self) -> &Binder<ClauseKind> {
+        &self.kind
+    }
 }
 
 pub struct GeneratorArgs {
@@ -540,11 +550,60 @@ impl GenericArgs {
 }
 
 impl FnTraitPredicate {
-    pub fn to_closure_sig(&self, closure_id: DefId, tys: List<Ty>) -> PolyFnSig {
-        let mut vars = vec![];
+    /// Builds the signature of a closure from the predicate. The late bound regions of a
+    /// higher-ranked predicate (given in `bound_vars`) become the first variables bound by the
+    /// signature.
+    pub fn to_closure_sig(
+        &self,
+        closure_id: DefId,
+        tys: List<Ty>,
+        bound_vars: &[BoundVariableKind],
+    ) -> PolyFnSig {
+        let mut vars = bound_vars.to_vec();
+
+        let env_ty = self.closure_env_ty(closure_id, tys, &mut vars);
+        let inputs = std::iter::once(env_ty)
+            .chain(self.tupled_args.expect_tuple().iter().cloned())
+            .collect_vec();
+
+        let fn_sig = FnSig::new(
+            vec![],
+            inputs,
+            Binder::new(FnOutput::new(self.output.shift_in_escaping(1), vec![]), List::empty()),
+        );
+
+        PolyFnSig::new(fn_sig, List::from(vars))
+    }
+
+    /// Like [`FnTraitPredicate::to_closure_sig`] but uses the user annotated `closure_sig` (which
+    /// doesn't mention the closure environment) instead of the arguments and output of the predicate.
+    pub fn to_annotated_closure_sig(
+        &self,
+        closure_id: DefId,
+        tys: List<Ty>,
+        closure_sig: &PolyFnSig,
+    ) -> PolyFnSig {
+        let mut vars = closure_sig.vars().to_vec();
+
+        let env_ty = self.closure_env_ty(closure_id, tys, &mut vars);
+        let fn_sig = closure_sig.as_ref().skip_binder();
+        let inputs = std::iter::once(env_ty)
+            .chain(fn_sig.args().iter().cloned())
+            .collect_vec();
 
+        let fn_sig = FnSig::new(fn_sig.requires().clone(), inputs, fn_sig.output().clone());
+
+        PolyFnSig::new(fn_sig, List::from(vars))
+    }
+
+    fn closure_env_ty(
+        &self,
+        closure_id: DefId,
+        tys: List<Ty>,
+        vars: &mut Vec<BoundVariableKind>,
+    ) -> Ty {
         let closure_ty = Ty::closure(closure_id, tys);
-        let env_ty = match self.kind {
+        match self.kind {
             ClosureKind::Fn => {
                 vars.push(BoundVariableKind::Region(BoundRegionKind::BrEnv));
                 let br = BoundRegion {
@@ -562,18 +621,7 @@ impl FnTraitPredicate {
                 Ty::mk_ref(ReLateBound(INNERMOST, br), closure_ty, Mutability::Mut)
             }
             ClosureKind::FnOnce => closure_ty,
-        };
-        let inputs = std::iter::once(env_ty)
-            .chain(self.tupled_args.expect_tuple().iter().cloned())
-            .collect_vec();
-
-        let fn_sig = FnSig::new(
-            vec![],
-            inputs,
-            Binder::new(FnOutput::new(self.output.clone(), vec![]), List::empty()),
-        );
-
-        PolyFnSig::new(fn_sig, List::from(vars))
+        }
     }
 }
 
@@ -1071,6 +1119,21 @@ impl AdtDef {
         self.0.rustc.is_struct()
     }
 
+    pub fn is_union(&self) -> bool {
+        self.0.rustc.is_union()
+    }
+
+    /// The indices of the refined variants of the adt. A union is refined like an enum with one
+    /// variant per field, so its variants are indexed by field.
+    pub fn variant_indices(&self) -> impl Iterator<Item = VariantIdx> {
+        let n = if self.is_union() {
+            self.variant(FIRST_VARIANT).fields.len()
+        } else {
+            self.variants().len()
+        };
+        (0..n).map(VariantIdx::from_usize)
+    }
+
     pub fn variants(&self) -> &IndexSlice<VariantIdx, VariantDef> {
         self.0.rustc.variants()
     }
@@ -1274,6 +1337,10 @@ impl Ty {
         BaseTy::Closure(did, tys.into()).into_ty()
     }
 
+    pub fn fn_ptr(fn_sig: PolyFnSig) -> Ty {
+        BaseTy::FnPtr(fn_sig).into_ty()
+    }
+
     pub fn generator(did: DefId, args: impl Into<List<GenericArg>>) -> Ty {
         BaseTy::Coroutine(did, args.into()).into_ty()
     }
@@ -1345,7 +1412,7 @@ impl TyS {
         }
     }
 
-    pub(crate) fn expect_tuple(&self) -> &[Ty] {
+    pub fn expect_tuple(&self) -> &[Ty] {
         if let TyKind::Indexed(BaseTy::Tuple(tys), _) = self.kind() {
             tys
         } else {
@@ -1494,18 +1561,18 @@ impl BaseTy {
     pub fn sort(&self) -> Sort {
         // CODESYNC(sort-of, 3) sorts should be given consistently
         match self {
-            BaseTy::Int(_) | BaseTy::Uint(_) | BaseTy::Slice(_) => Sort::Int,
+            BaseTy::Int(_) | BaseTy::Uint(_) | BaseTy::Slice(_) | BaseTy::RawPtr(..) => Sort::Int,
             BaseTy::Bool => Sort::Bool,
             BaseTy::Adt(adt_def, _) => adt_def.sort().clone(),
             BaseTy::Param(param_ty) => Sort::Param(*param_ty),
             BaseTy::Float(_)
             | BaseTy::Str
             | BaseTy::Char
-            | BaseTy::RawPtr(..)
             | BaseTy::Ref(..)
             | BaseTy::Tuple(_)
             | BaseTy::Array(_, _)
             | BaseTy::Closure(_, _)
+            | BaseTy::FnPtr(_)
             | BaseTy::Coroutine(_, _)
             | BaseTy::CoroutineWitness(_, _)
             | BaseTy::Never => Sort::unit(),
@@ -2042,6 +2109,7 @@ mod pretty {
                 BaseTy::Closure(did, args) => {
                     w!("Closure {:?}<{:?}>", did, args)
                 }
+                BaseTy::FnPtr(fn_sig) => w!("{:?}", fn_sig),
                 BaseTy::Coroutine(did, args) => {
                     w!("Coroutine({:?})", did)?;
                     let args = args
@@ -12,15 +12,15 @@ use rustc_middle::{
 use rustc_trait_selection::traits::SelectionContext;
 
 use super::{
-    fold::{FallibleTypeFolder, TypeSuperFoldable},
-    AliasKind, AliasTy, BaseTy, BoundRegion, Clause, ClauseKind, Expr, GenericArg,
-    ProjectionPredicate, Region, Ty, TyKind,
+    fold::{FallibleTypeFolder, TypeFoldable, TypeSuperFoldable},
+    AliasKind, AliasReft, AliasTy, BaseTy, BoundRegion, BoundVariableKind, Clause, ClauseKind,
+    Expr, ExprKind, GenericArg, PolyFnSig, ProjectionPredicate, Region, Ty, TyKind,
 };
 use crate::{
     global_env::GlobalEnv,
     queries::{QueryErr, QueryResult},
     rty::fold::TypeVisitable,
-    rustc::ty::FreeRegion,
+    rustc::{lowering, ty::FreeRegion},
 };
 
 pub(crate) struct Normalizer<'sess, 'tcx, 'cx> {
@@ -62,6 +62,7 @@ impl<'sess, 'tcx, 'cx> Normalizer<'sess, 'tcx, 'cx> {
     fn confirm_candidate(&self, candidate: Candidate, obligation: &AliasTy) -> QueryResult<Ty> {
         match candidate {
             Candidate::ParamEnv(pred) | Candidate::TraitDef(pred) => Ok(pred.term),
+            Candidate::Builtin(ty) => Ok(ty),
             Candidate::UserDefinedImpl(impl_def_id) => {
                 // Given a projection obligation
                 //     <IntoIter<{v. i32[v] | v > 0}, Global> as Iterator>::Item
@@ -102,6 +103,52 @@ impl<'sess, 'tcx, 'cx> Normalizer<'sess, 'tcx, 'cx> {
         }
     }
 
+    /// Resolves an associated refinement to its definition in the impl selected for the `Self`
+    /// type, falling back to the default definition in the trait. Returns [`None`] if the impl
+    /// cannot be determined, e.g., because the `Self` type is a type parameter.
+    fn normalize_alias_reft(
+        &mut self,
+        alias: &AliasReft,
+        refine_args: &[Expr],
+    ) -> QueryResult<Option<Expr>> {
+        let tcx = self.tcx();
+        let trait_ref = rustc_middle::ty::TraitRef::new(
+            tcx,
+            alias.trait_id,
+            into_rustc_generic_args(tcx, &alias.args),
+        );
+        let trait_pred = Obligation::with_depth(
+            tcx,
+            ObligationCause::dummy(),
+            5,
+            self.rustc_param_env(),
+            trait_ref,
+        );
+        let Ok(Some(ImplSource::UserDefined(impl_data))) = self.selcx.select(&trait_pred) else {
+            return Ok(None);
+        };
+        let impl_def_id = impl_data.impl_def_id;
+
+        let expr = if let Some(def) = self.genv.assoc_refinement_def(impl_def_id, alias.name)? {
+            // Match the self type of the impl against the `Self` type of the alias to infer the
+            // generic arguments of the impl. See `confirm_candidate`.
+            let rustc_self_ty = tcx
+                .impl_trait_ref(impl_def_id)
+                .unwrap()
+                .skip_binder()
+                .self_ty();
+            let generics = tcx.generics_of(impl_def_id);
+            let args =
+                TVarSubst::mk_subst(tcx, generics, &rustc_self_ty, alias.args[0].expect_type());
+            def.instantiate(&args, &[])
+        } else if let Some(def) = self.genv.assoc_refinement_def(alias.trait_id, alias.name)? {
+            def.instantiate(&alias.args, &[])
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(expr.replace_bound_exprs(refine_args)))
+    }
+
     fn assemble_candidates_from_param_env(
         &self,
         obligation: &AliasTy,
@@ -113,6 +160,9 @@ impl<'sess, 'tcx, 'cx> Normalizer<'sess, 'tcx, 'cx> {
             Candidate::ParamEnv,
             candidates,
         );
+        if self.genv.is_fn_once_output(obligation.def_id) {
+            assemble_candidates_from_fn_trait_predicates(&self.param_env, obligation, candidates);
+        }
     }
 
     fn assemble_candidates_from_trait_def(
@@ -148,12 +198,38 @@ impl<'sess, 'tcx, 'cx> Normalizer<'sess, 'tcx, 'cx> {
             Ok(Some(ImplSource::UserDefined(impl_data))) => {
                 candidates.push(Candidate::UserDefinedImpl(impl_data.impl_def_id));
             }
+            Ok(Some(ImplSource::Builtin(..)))
+                if self.genv.is_discriminant_ty(obligation.def_id) =>
+            {
+                self.assemble_candidates_from_discriminant_kind(obligation, candidates)?;
+            }
             Ok(_) => {}
             Err(e) => bug!("error selecting {trait_pred:?}: {e:?}"),
         }
         Ok(())
     }
 
+    /// Resolves `<T as DiscriminantKind>::Discriminant`, which is used by the code generated by
+    /// `#[derive(PartialEq)]` on enums, when `T` has a known discriminant type.
+    fn assemble_candidates_from_discriminant_kind(
+        &self,
+        obligation: &AliasTy,
+        candidates: &mut Vec<Candidate>,
+    ) -> QueryResult<()> {
+        let tcx = self.tcx();
+        let discr_ty = into_rustc_alias_ty(tcx, obligation)
+            .self_ty()
+            .discriminant_ty(tcx);
+        if !discr_ty.is_integral() {
+            return Ok(());
+        }
+        let discr_ty = lowering::lower_ty(tcx, discr_ty)
+            .unwrap_or_else(|reason| bug!("unexpected discriminant type: {reason:?}"));
+        let generics = self.genv.generics_of(self.def_id)?;
+        candidates.push(Candidate::Builtin(self.genv.refine_default(&generics, &discr_ty)?));
+        Ok(())
+    }
+
     fn tcx(&self) -> TyCtxt<'tcx> {
         self.selcx.tcx()
     }
@@ -178,6 +254,34 @@ fn assemble_candidates_from_predicates(
     }
 }
 
+/// The output of a `Fn*` bound is not stored as a projection predicate, so we resolve
+/// `<F as FnOnce<Args>>::Output` by looking for a `Fn*` bound on `F`. The late bound regions of a
+/// higher-ranked bound are instantiated with `'static`.
+fn assemble_candidates_from_fn_trait_predicates(
+    predicates: &[Clause],
+    obligation: &AliasTy,
+    candidates: &mut Vec<Candidate>,
+) {
+    for predicate in predicates {
+        if let ClauseKind::FnTrait(pred) = predicate.kind()
+            && &pred.self_ty == obligation.self_ty()
+        {
+            let term = predicate
+                .bound_kind()
+                .clone()
+                .rebind(pred.output)
+                .replace_bound_vars(
+                    |_| Region::ReStatic,
+                    |_, _| bug!("unexpected refinement variable in clause"),
+                );
+            candidates.push(Candidate::ParamEnv(ProjectionPredicate {
+                projection_ty: obligation.clone(),
+                term,
+            }));
+        }
+    }
+}
+
 impl FallibleTypeFolder for Normalizer<'_, '_, '_> {
     type Error = QueryErr;
 
@@ -188,6 +292,18 @@ impl FallibleTypeFolder for Normalizer<'_, '_, '_> {
             ty.try_super_fold_with(self)
         }
     }
+
+    fn try_fold_expr(&mut self, expr: &Expr) -> Result<Expr, Self::Error> {
+        let expr = expr.try_super_fold_with(self)?;
+        if let ExprKind::Alias(alias, args) = expr.kind()
+            && let Some(resolved) = self.normalize_alias_reft(alias, args)?
+        {
+            // The definition may mention other associated refinements
+            resolved.try_fold_with(self)
+        } else {
+            Ok(expr)
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -195,6 +311,8 @@ pub enum Candidate {
     UserDefinedImpl(DefId),
     ParamEnv(ProjectionPredicate),
     TraitDef(ProjectionPredicate),
+    /// A projection resolved by a builtin impl, e.g., `<T as DiscriminantKind>::Discriminant`.
+    Builtin(Ty),
 }
 
 fn into_rustc_generic_args<'tcx>(
@@ -292,7 +410,9 @@ fn into_rustc_bty<'tcx>(tcx: TyCtxt<'tcx>, bty: &BaseTy) -> rustc_middle::ty::Ty
         }
         BaseTy::Array(_, _) => todo!(),
         BaseTy::Never => tcx.types.never,
-        BaseTy::Closure(_, _) => todo!(),
+        // The upvar types are irrelevant for trait selection so we use the identity instantiation
+        BaseTy::Closure(def_id, _) => tcx.type_of(*def_id).instantiate_identity(),
+        BaseTy::FnPtr(fn_sig) => ty::Ty::new_fn_ptr(tcx, into_rustc_poly_fn_sig(tcx, fn_sig)),
         BaseTy::Coroutine(def_id, args) => {
             todo!("Generator {:?} {:?}", def_id, args)
             // let args = args.iter().map(|arg| into_rustc_generic_arg(tcx, arg));
@@ -303,6 +423,47 @@ fn into_rustc_bty<'tcx>(tcx: TyCtxt<'tcx>, bty: &BaseTy) -> rustc_middle::ty::Ty
     }
 }
 
+fn into_rustc_poly_fn_sig<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    fn_sig: &PolyFnSig,
+) -> rustc_middle::ty::PolyFnSig<'tcx> {
+    use rustc_middle::ty;
+    // Function pointers only bind late bound regions (refining a function pointer doesn't add
+    // refinement parameters to its binder), so the indices of the bound variables are preserved.
+    let vars = fn_sig.vars().iter().map(|var| {
+        match var {
+            BoundVariableKind::Region(kind) => ty::BoundVariableKind::Region(*kind),
+            BoundVariableKind::Refine(..) => {
+                bug!("unexpected refinement parameter in function pointer `{fn_sig:?}`")
+            }
+        }
+    });
+    let vars = tcx.mk_bound_variable_kinds_from_iter(vars);
+    let fn_sig = fn_sig.as_ref().skip_binder();
+    let inputs = fn_sig.args().iter().map(|ty| into_rustc_ty(tcx, ty));
+    // The return type is inside the binder of the output, so we shift it out to refer to the
+    // variables bound by the signature.
+    let output = into_rustc_ty(
+        tcx,
+        &fn_sig
+            .output()
+            .as_ref()
+            .skip_binder()
+            .ret
+            .shift_out_escaping(1),
+    );
+    // We don't track the safety nor the abi of function pointers, so we assume a safe rust
+    // function as it's the only kind of function pointer we refine.
+    let fn_sig = tcx.mk_fn_sig(
+        inputs,
+        output,
+        false,
+        rustc_hir::Unsafety::Normal,
+        rustc_target::spec::abi::Abi::Rust,
+    );
+    ty::Binder::bind_with_vars(fn_sig, vars)
+}
+
 fn into_rustc_region(tcx: TyCtxt, re: Region) -> rustc_middle::ty::Region {
     match re {
         Region::ReLateBound(debruijn, bound_region) => {
@@ -107,7 +107,7 @@ impl<'a, 'tcx> Refiner<'a, 'tcx> {
         clauses: &[rustc::ty::Clause],
         clause: &rustc::ty::Clause,
     ) -> QueryResult<Option<rty::Clause>> {
-        let kind = match &clause.kind {
+        let kind = match clause.kind.as_ref().skip_binder() {
             rustc::ty::ClauseKind::Trait(trait_pred) => {
                 let trait_ref = &trait_pred.trait_ref;
                 if let Some(kind) = self.genv.tcx.fn_trait_kind_from_def_id(trait_ref.def_id) {
@@ -135,7 +135,7 @@ impl<'a, 'tcx> Refiner<'a, 'tcx> {
                 rty::ClauseKind::TypeOutlives(pred)
             }
         };
-        let kind = rty::Binder::new(kind, List::empty());
+        let kind = rty::Binder::new(kind, refine_bound_variables(clause.kind.vars()));
         Ok(Some(rty::Clause { kind }))
     }
 
@@ -147,7 +147,8 @@ impl<'a, 'tcx> Refiner<'a, 'tcx> {
     ) -> QueryResult<rty::ClauseKind> {
         let mut candidates = vec![];
         for clause in clauses {
-            if let rustc::ty::ClauseKind::Projection(trait_pred) = &clause.kind
+            if let rustc::ty::ClauseKind::Projection(trait_pred) =
+                clause.kind.as_ref().skip_binder()
                 && self.genv.is_fn_once_output(trait_pred.projection_ty.def_id)
                 && trait_pred.projection_ty.self_ty() == trait_ref.self_ty()
             {
@@ -359,7 +360,9 @@ impl<'a, 'tcx> Refiner<'a, 'tcx> {
             rustc::ty::TyKind::Str => rty::BaseTy::Str,
             rustc::ty::TyKind::Slice(ty) => rty::BaseTy::Slice(self.refine_ty(ty)?),
             rustc::ty::TyKind::Char => rty::BaseTy::Char,
-            rustc::ty::TyKind::FnPtr(_) => todo!("refine_ty: FnSig"),
+            rustc::ty::TyKind::FnPtr(fn_sig) => {
+                rty::BaseTy::FnPtr(self.refine_poly_fn_sig(fn_sig)?)
+            }
             rustc::ty::TyKind::RawPtr(ty, mu) => {
                 rty::BaseTy::RawPtr(self.as_default().refine_ty(ty)?, *mu)
             }
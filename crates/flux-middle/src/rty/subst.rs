@@ -300,6 +300,14 @@ impl TypeFolder for GenericsSubstFolder<'_> {
         }
     }
 
+    fn fold_bty(&mut self, bty: &BaseTy) -> BaseTy {
+        if let BaseTy::Array(ty, Const::Param(param)) = bty {
+            BaseTy::Array(ty.fold_with(self), self.const_for_param(*param))
+        } else {
+            bty.super_fold_with(self)
+        }
+    }
+
     fn fold_region(&mut self, re: &Region) -> Region {
         if let ReEarlyBound(ebr) = *re {
             self.region_for_param(ebr)
@@ -309,10 +317,10 @@ impl TypeFolder for GenericsSubstFolder<'_> {
     }
 
     fn fold_expr(&mut self, expr: &Expr) -> Expr {
-        if let ExprKind::Var(Var::EarlyBound(idx)) = expr.kind() {
-            self.expr_for_param(*idx)
-        } else {
-            expr.super_fold_with(self)
+        match expr.kind() {
+            ExprKind::Var(Var::EarlyBound(idx)) => self.expr_for_param(*idx),
+            ExprKind::ConstParam(param) => Expr::from_const(&self.const_for_param(*param)),
+            _ => expr.super_fold_with(self),
         }
     }
 }
@@ -374,6 +382,18 @@ impl GenericsSubstFolder<'_> {
         }
     }
 
+    fn const_for_param(&self, param: ParamConst) -> Const {
+        if let Some(generics) = self.generics {
+            match generics.get(param.index as usize) {
+                Some(GenericArg::Const(c)) => c.clone(),
+                Some(arg) => bug!("expected const for generic parameter, found `{:?}`", arg),
+                None => bug!("const parameter out of range"),
+            }
+        } else {
+            Const::Param(param)
+        }
+    }
+
     fn expr_for_param(&self, idx: u32) -> Expr {
         self.refine[idx as usize].shift_in_escaping(self.current_index.as_u32())
     }
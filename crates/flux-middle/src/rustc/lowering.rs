@@ -1,7 +1,7 @@
 use flux_errors::{FluxSession, ResultExt};
 use itertools::Itertools;
 use rustc_borrowck::consumers::BodyWithBorrowckFacts;
-use rustc_errors::ErrorGuaranteed;
+use rustc_errors::{DiagnosticBuilder, ErrorGuaranteed, Handler, IntoDiagnostic};
 use rustc_hir::def_id::DefId;
 use rustc_infer::traits::Obligation;
 use rustc_middle::{
@@ -18,8 +18,8 @@ use rustc_trait_selection::traits::SelectionContext;
 use super::{
     mir::{
         replicate_infer_ctxt, AggregateKind, AssertKind, BasicBlockData, BinOp, Body, BorrowKind,
-        CallArgs, CastKind, Constant, FakeReadCause, LocalDecl, Operand, Place, PlaceElem,
-        PointerCast, Rvalue, Statement, StatementKind, Terminator, TerminatorKind,
+        CallArgs, CallKind, CastKind, Constant, FakeReadCause, LocalDecl, Operand, Place,
+        PlaceElem, PointerCast, Rvalue, Statement, StatementKind, Terminator, TerminatorKind,
     },
     ty::{
         AdtDef, AdtDefData, AliasKind, Binder, BoundRegion, BoundVariableKind, Clause, ClauseKind,
@@ -34,11 +34,10 @@ use crate::{
     rustc::ty::{AliasTy, ProjectionPredicate, Region},
 };
 
-pub struct LoweringCtxt<'a, 'sess, 'tcx> {
+pub struct LoweringCtxt<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
     param_env: ParamEnv<'tcx>,
     selcx: SelectionContext<'a, 'tcx>,
-    sess: &'sess FluxSession,
     rustc_mir: &'a rustc_mir::Body<'tcx>,
 }
 
@@ -47,17 +46,60 @@ pub struct UnsupportedReason {
     pub(crate) descr: String,
 }
 
-impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
+/// The first unsupported construct found when lowering a MIR body. The error is not emitted
+/// during lowering so callers can decide whether to report it as an error or skip the body.
+#[derive(Debug, Clone)]
+pub enum UnsupportedMirErr {
+    LocalDecl(errors::UnsupportedLocalDecl),
+    Mir(errors::UnsupportedMir),
+}
+
+impl UnsupportedMirErr {
+    pub fn span(&self) -> Span {
+        match self {
+            UnsupportedMirErr::LocalDecl(err) => err.span,
+            UnsupportedMirErr::Mir(err) => err.span,
+        }
+    }
+
+    pub fn reason(&self) -> String {
+        match self {
+            UnsupportedMirErr::LocalDecl(err) => format!("unsupported type `{}`", err.ty),
+            UnsupportedMirErr::Mir(err) => err.reason.descr.clone(),
+        }
+    }
+}
+
+impl<'a> IntoDiagnostic<'a> for UnsupportedMirErr {
+    fn into_diagnostic(self, handler: &'a Handler) -> DiagnosticBuilder<'a, ErrorGuaranteed> {
+        match self {
+            UnsupportedMirErr::LocalDecl(err) => err.into_diagnostic(handler),
+            UnsupportedMirErr::Mir(err) => err.into_diagnostic(handler),
+        }
+    }
+}
+
+impl From<errors::UnsupportedLocalDecl> for UnsupportedMirErr {
+    fn from(err: errors::UnsupportedLocalDecl) -> Self {
+        UnsupportedMirErr::LocalDecl(err)
+    }
+}
+
+impl From<errors::UnsupportedMir> for UnsupportedMirErr {
+    fn from(err: errors::UnsupportedMir) -> Self {
+        UnsupportedMirErr::Mir(err)
+    }
+}
+
+impl<'tcx> LoweringCtxt<'_, 'tcx> {
     pub fn lower_mir_body(
         tcx: TyCtxt<'tcx>,
-        sess: &'sess FluxSession,
         body_with_facts: BodyWithBorrowckFacts<'tcx>,
-    ) -> Result<Body<'tcx>, ErrorGuaranteed> {
+    ) -> Result<Body<'tcx>, UnsupportedMirErr> {
         let infcx = replicate_infer_ctxt(tcx, &body_with_facts);
         let param_env = tcx.param_env(body_with_facts.body.source.def_id());
         let selcx = SelectionContext::new(&infcx);
-        let mut lower =
-            LoweringCtxt { tcx, selcx, param_env, sess, rustc_mir: &body_with_facts.body };
+        let mut lower = LoweringCtxt { tcx, selcx, param_env, rustc_mir: &body_with_facts.body };
 
         let basic_blocks = lower
             .rustc_mir
@@ -80,7 +122,7 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
     fn lower_basic_block_data(
         &mut self,
         data: &rustc_mir::BasicBlockData<'tcx>,
-    ) -> Result<BasicBlockData<'tcx>, ErrorGuaranteed> {
+    ) -> Result<BasicBlockData<'tcx>, UnsupportedMirErr> {
         let data = BasicBlockData {
             statements: data
                 .statements
@@ -100,11 +142,10 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
     fn lower_local_decl(
         &self,
         local_decl: &rustc_mir::LocalDecl<'tcx>,
-    ) -> Result<LocalDecl, ErrorGuaranteed> {
+    ) -> Result<LocalDecl, UnsupportedMirErr> {
         Ok(LocalDecl {
             ty: lower_ty(self.tcx, local_decl.ty)
-                .map_err(|err| errors::UnsupportedLocalDecl::new(local_decl, err))
-                .emit(self.sess)?,
+                .map_err(|err| errors::UnsupportedLocalDecl::new(local_decl, err))?,
             source_info: local_decl.source_info,
         })
     }
@@ -112,42 +153,36 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
     fn lower_statement(
         &self,
         stmt: &rustc_mir::Statement<'tcx>,
-    ) -> Result<Statement, ErrorGuaranteed> {
+    ) -> Result<Statement, UnsupportedMirErr> {
         let span = stmt.source_info.span;
         let kind = match &stmt.kind {
             rustc_mir::StatementKind::Assign(box (place, rvalue)) => {
                 StatementKind::Assign(
                     lower_place(place)
-                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))?,
                     self.lower_rvalue(rvalue)
-                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))?,
                 )
             }
             rustc_mir::StatementKind::SetDiscriminant { place, variant_index } => {
                 StatementKind::SetDiscriminant(
                     lower_place(place)
-                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))?,
                     *variant_index,
                 )
             }
             rustc_mir::StatementKind::FakeRead(box (cause, place)) => {
                 StatementKind::FakeRead(Box::new((
                     self.lower_fake_read_cause(*cause)
-                        .ok_or_else(|| errors::UnsupportedMir::from(stmt))
-                        .emit(self.sess)?,
+                        .ok_or_else(|| errors::UnsupportedMir::from(stmt))?,
                     lower_place(place)
-                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))?,
                 )))
             }
             rustc_mir::StatementKind::PlaceMention(place) => {
                 StatementKind::PlaceMention(
                     lower_place(place)
-                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))?,
                 )
             }
             rustc_mir::StatementKind::Nop
@@ -159,8 +194,7 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
             ) if projs.is_empty() => {
                 StatementKind::AscribeUserType(
                     lower_place(place)
-                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::statement(span, reason))?,
                     *variance,
                 )
             }
@@ -170,7 +204,7 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
             | rustc_mir::StatementKind::Coverage(_)
             | rustc_mir::StatementKind::Intrinsic(_)
             | rustc_mir::StatementKind::ConstEvalCounter => {
-                return Err(errors::UnsupportedMir::from(stmt)).emit(self.sess);
+                return Err(errors::UnsupportedMir::from(stmt).into());
             }
         };
         Ok(Statement { kind, source_info: stmt.source_info })
@@ -191,35 +225,40 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
     fn lower_terminator(
         &mut self,
         terminator: &rustc_mir::Terminator<'tcx>,
-    ) -> Result<Terminator<'tcx>, ErrorGuaranteed> {
+    ) -> Result<Terminator<'tcx>, UnsupportedMirErr> {
         let span = terminator.source_info.span;
         let kind = match &terminator.kind {
             rustc_mir::TerminatorKind::Return => TerminatorKind::Return,
             rustc_mir::TerminatorKind::Call { func, args, destination, target, unwind, .. } => {
-                let (func, generic_args) = match func.ty(self.rustc_mir, self.tcx).kind() {
+                let kind = match func.ty(self.rustc_mir, self.tcx).kind() {
                     rustc_middle::ty::TyKind::FnDef(fn_def, args) => {
                         let lowered = lower_generic_args(self.tcx, args)
-                            .map_err(|_err| errors::UnsupportedMir::from(terminator))
-                            .emit(self.sess)?;
-                        (*fn_def, CallArgs { orig: args, lowered })
+                            .map_err(|_err| errors::UnsupportedMir::from(terminator))?;
+                        let generic_args = CallArgs { orig: args, lowered };
+
+                        let resolved_call =
+                            self.resolve_call(*fn_def, generic_args.orig)
+                                .map_err(|reason| {
+                                    errors::UnsupportedMir::new(span, "terminator call", reason)
+                                })?;
+
+                        CallKind::FnDef { func: *fn_def, generic_args, resolved_call }
+                    }
+                    rustc_middle::ty::TyKind::FnPtr(_) => {
+                        let op = self.lower_operand(func).map_err(|reason| {
+                            errors::UnsupportedMir::new(span, "terminator call", reason)
+                        })?;
+                        CallKind::FnPtr(op)
                     }
-                    _ => Err(errors::UnsupportedMir::from(terminator)).emit(self.sess)?,
+                    _ => Err(errors::UnsupportedMir::from(terminator))?,
                 };
 
-                let destination = lower_place(destination)
-                    .map_err(|reason| {
-                        errors::UnsupportedMir::new(span, "terminator destination", reason)
-                    })
-                    .emit(self.sess)?;
-
-                let resolved_call = self
-                    .resolve_call(func, generic_args.orig)
-                    .map_err(|reason| errors::UnsupportedMir::new(span, "terminator call", reason))
-                    .emit(self.sess)?;
+                let destination = lower_place(destination).map_err(|reason| {
+                    errors::UnsupportedMir::new(span, "terminator destination", reason)
+                })?;
 
                 TerminatorKind::Call {
-                    func,
-                    generic_args,
+                    kind,
                     destination,
                     target: *target,
                     args: args
@@ -229,18 +268,15 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
                                 errors::UnsupportedMir::new(span, "terminator args", reason)
                             })
                         })
-                        .try_collect()
-                        .emit(self.sess)?,
+                        .try_collect()?,
                     unwind: *unwind,
-                    resolved_call,
                 }
             }
             rustc_mir::TerminatorKind::SwitchInt { discr, targets, .. } => {
                 TerminatorKind::SwitchInt {
                     discr: self
                         .lower_operand(discr)
-                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))?,
                     targets: targets.clone(),
                 }
             }
@@ -248,8 +284,7 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
             rustc_mir::TerminatorKind::Drop { place, target, unwind, .. } => {
                 TerminatorKind::Drop {
                     place: lower_place(place)
-                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))?,
                     target: *target,
                     unwind: *unwind,
                 }
@@ -258,14 +293,12 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
                 TerminatorKind::Assert {
                     cond: self
                         .lower_operand(cond)
-                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))?,
                     expected: *expected,
                     target: *target,
                     msg: self
                         .lower_assert_msg(msg)
-                        .ok_or_else(|| errors::UnsupportedMir::from(terminator))
-                        .emit(self.sess)?,
+                        .ok_or_else(|| errors::UnsupportedMir::from(terminator))?,
                 }
             }
             rustc_mir::TerminatorKind::Unreachable => TerminatorKind::Unreachable,
@@ -282,12 +315,10 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
                 TerminatorKind::Yield {
                     value: self
                         .lower_operand(value)
-                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))?,
                     resume: *resume,
                     resume_arg: lower_place(resume_arg)
-                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))
-                        .emit(self.sess)?,
+                        .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))?,
                     drop: *drop,
                 }
             }
@@ -295,7 +326,7 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
             rustc_mir::TerminatorKind::UnwindResume => TerminatorKind::UnwindResume,
             rustc_mir::TerminatorKind::UnwindTerminate(..)
             | rustc_mir::TerminatorKind::InlineAsm { .. } => {
-                return Err(errors::UnsupportedMir::from(terminator)).emit(self.sess);
+                return Err(errors::UnsupportedMir::from(terminator).into());
             }
         };
         Ok(Terminator { kind, source_info: terminator.source_info })
@@ -321,7 +352,10 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
                     .get(&callee_id)?;
                 let assoc_item = tcx.associated_item(assoc_id);
 
-                Some((assoc_item.def_id, impl_data.args))
+                // The method's own generic arguments follow the ones of the trait, e.g., `H` in
+                // `<i32 as Hash>::hash::<H>`, so they have to be kept after the impl's.
+                let args = args.rebase_onto(tcx, trait_id, impl_data.args);
+                Some((assoc_item.def_id, args))
             } else {
                 None
             }
@@ -411,6 +445,9 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
             rustc_adjustment::PointerCoercion::Unsize => {
                 Some(crate::rustc::mir::PointerCast::Unsize)
             }
+            rustc_adjustment::PointerCoercion::ReifyFnPointer => {
+                Some(crate::rustc::mir::PointerCast::ReifyFnPointer)
+            }
             _ => None,
         }
     }
@@ -432,8 +469,13 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
         aggregate_kind: &rustc_mir::AggregateKind<'tcx>,
     ) -> Result<AggregateKind, UnsupportedReason> {
         match aggregate_kind {
-            rustc_mir::AggregateKind::Adt(def_id, variant_idx, args, None, None) => {
-                Ok(AggregateKind::Adt(*def_id, *variant_idx, lower_generic_args(self.tcx, args)?))
+            rustc_mir::AggregateKind::Adt(def_id, variant_idx, args, None, field_idx) => {
+                Ok(AggregateKind::Adt(
+                    *def_id,
+                    *variant_idx,
+                    lower_generic_args(self.tcx, args)?,
+                    *field_idx,
+                ))
             }
             rustc_mir::AggregateKind::Array(ty) => {
                 Ok(AggregateKind::Array(lower_ty(self.tcx, *ty)?))
@@ -523,6 +565,9 @@ impl<'sess, 'tcx> LoweringCtxt<'_, 'sess, 'tcx> {
                 }
             }
             (_, TyKind::Tuple(tys)) if tys.is_empty() => return Ok(Constant::Unit),
+            (_, TyKind::FnDef(def_id, args)) => {
+                Some(Constant::FnDef(*def_id, lower_generic_args(tcx, args)?))
+            }
             (_, _) => Some(Constant::Opaque(lower_ty(tcx, ty)?)),
         }
         .ok_or_else(|| UnsupportedReason::new(format!("unsupported constant `{constant:?}`")))
@@ -814,13 +859,10 @@ fn lower_clause<'tcx>(
     clause: &rustc_ty::Clause<'tcx>,
     span: Span,
 ) -> Result<Clause, ErrorGuaranteed> {
-    let Some(kind) = clause.kind().no_bound_vars() else {
-        return Err(sess.emit_err(errors::UnsupportedGenericBound::new(
-            span,
-            "higher-rank trait bounds are not supported",
-        )));
-    };
-    let kind = match kind {
+    let bound_vars = lower_bound_vars(clause.kind().bound_vars())
+        .map_err(|err| errors::UnsupportedGenericBound::new(span, err.descr))
+        .emit(sess)?;
+    let kind = match clause.kind().skip_binder() {
         rustc_ty::ClauseKind::Trait(trait_pred) => {
             ClauseKind::Trait(TraitPredicate {
                 trait_ref: TraitRef {
@@ -856,14 +898,14 @@ fn lower_clause<'tcx>(
                     .emit(sess)?,
             )
         }
-        _ => {
+        kind => {
             return Err(sess.emit_err(errors::UnsupportedGenericBound::new(
                 span,
                 format!("unsupported clause kind `{kind:?}`"),
             )));
         }
     };
-    Ok(Clause::new(kind))
+    Ok(Clause::new(Binder::bind_with_vars(kind, bound_vars)))
 }
 
 fn lower_type_outlives<'tcx>(
@@ -880,32 +922,29 @@ mod errors {
 
     use super::UnsupportedReason;
 
-    #[derive(Diagnostic)]
+    #[derive(Diagnostic, Debug, Clone)]
     #[diag(middle_unsupported_local_decl, code = "FLUX")]
-    pub(super) struct UnsupportedLocalDecl<'tcx> {
+    pub struct UnsupportedLocalDecl {
         #[primary_span]
         #[label]
-        span: Span,
-        ty: rustc_middle::ty::Ty<'tcx>,
+        pub(crate) span: Span,
+        pub(crate) ty: String,
     }
 
-    impl<'tcx> UnsupportedLocalDecl<'tcx> {
-        pub(super) fn new(
-            local_decl: &rustc_mir::LocalDecl<'tcx>,
-            _err: UnsupportedReason,
-        ) -> Self {
-            Self { span: local_decl.source_info.span, ty: local_decl.ty }
+    impl UnsupportedLocalDecl {
+        pub(super) fn new(local_decl: &rustc_mir::LocalDecl, _err: UnsupportedReason) -> Self {
+            Self { span: local_decl.source_info.span, ty: local_decl.ty.to_string() }
         }
     }
 
-    #[derive(Diagnostic)]
+    #[derive(Diagnostic, Debug, Clone)]
     #[diag(middle_unsupported_mir, code = "FLUX")]
     #[note]
-    pub(super) struct UnsupportedMir {
+    pub struct UnsupportedMir {
         #[primary_span]
-        span: Span,
+        pub(crate) span: Span,
         kind: &'static str,
-        reason: UnsupportedReason,
+        pub(crate) reason: UnsupportedReason,
     }
 
     impl rustc_errors::IntoDiagnosticArg for UnsupportedReason {
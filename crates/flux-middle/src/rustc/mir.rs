@@ -107,17 +107,23 @@ pub struct Instance {
     pub args: GenericArgs,
 }
 
+#[derive(Debug)]
+pub enum CallKind<'tcx> {
+    /// A call to a function item
+    FnDef { func: DefId, generic_args: CallArgs<'tcx>, resolved_call: (DefId, CallArgs<'tcx>) },
+    /// A call through a function pointer
+    FnPtr(Operand),
+}
+
 #[derive(Debug)]
 pub enum TerminatorKind<'tcx> {
     Return,
     Call {
-        func: DefId,
-        generic_args: CallArgs<'tcx>,
+        kind: CallKind<'tcx>,
         args: Vec<Operand>,
         destination: Place,
         target: Option<BasicBlock>,
         unwind: UnwindAction,
-        resolved_call: (DefId, CallArgs<'tcx>),
     },
     SwitchInt {
         discr: Operand,
@@ -212,11 +218,13 @@ pub enum CastKind {
 pub enum PointerCast {
     MutToConstPointer,
     Unsize,
+    ReifyFnPointer,
 }
 
 #[derive(Debug)]
 pub enum AggregateKind {
-    Adt(DefId, VariantIdx, GenericArgs),
+    /// The last component is the index of the initialized field when constructing a union.
+    Adt(DefId, VariantIdx, GenericArgs, Option<FieldIdx>),
     Array(Ty),
     Tuple,
     Closure(DefId, GenericArgs),
@@ -281,6 +289,8 @@ pub enum Constant {
     /// We only support opaque chars, so no data stored here for now
     Char,
     Unit,
+    /// A function item, e.g., the operand of a cast to a function pointer
+    FnDef(DefId, GenericArgs),
     /// General catch-all for constants of a given Ty
     Opaque(Ty),
 }
@@ -454,7 +464,8 @@ impl PlaceTy {
                 Ok(ty.subst(args))
             }
             TyKind::Tuple(tys) => Ok(tys[f.index()].clone()),
-            _ => bug!("extracting field of non-tuple non-adt: {self:?}"),
+            TyKind::Closure(_, args) => Ok(args.as_closure().upvar_tys()[f.index()].clone()),
+            _ => bug!("extracting field of non-tuple non-adt non-closure: {self:?}"),
         }
     }
 }
@@ -552,16 +563,20 @@ impl<'tcx> fmt::Debug for Terminator<'tcx> {
         match &self.kind {
             TerminatorKind::Return => write!(f, "return"),
             TerminatorKind::Unreachable => write!(f, "unreachable"),
-            TerminatorKind::Call { args, destination, target, unwind, resolved_call, .. } => {
-                let (func, generic_args) = resolved_call;
-                let fname = rustc_middle::ty::tls::with(|tcx| {
-                    let path = tcx.def_path(*func);
-                    path.data.iter().join("::")
-                });
-                write!(f, "{destination:?} = call {fname}")?;
-
-                if !generic_args.lowered.is_empty() {
-                    write!(f, "::<{:?}>", generic_args.lowered.iter().format(", "))?;
+            TerminatorKind::Call { kind, args, destination, target, unwind } => {
+                match kind {
+                    CallKind::FnDef { resolved_call: (func, generic_args), .. } => {
+                        let fname = rustc_middle::ty::tls::with(|tcx| {
+                            let path = tcx.def_path(*func);
+                            path.data.iter().join("::")
+                        });
+                        write!(f, "{destination:?} = call {fname}")?;
+
+                        if !generic_args.lowered.is_empty() {
+                            write!(f, "::<{:?}>", generic_args.lowered.iter().format(", "))?;
+                        }
+                    }
+                    CallKind::FnPtr(op) => write!(f, "{destination:?} = call ({op:?})")?,
                 }
 
                 write!(
@@ -663,7 +678,7 @@ impl fmt::Debug for Rvalue {
                 write!(f, "Checked{bin_op:?}({op1:?}, {op2:?})")
             }
             Rvalue::UnaryOp(un_op, op) => write!(f, "{un_op:?}({op:?})"),
-            Rvalue::Aggregate(AggregateKind::Adt(def_id, variant_idx, args), operands) => {
+            Rvalue::Aggregate(AggregateKind::Adt(def_id, variant_idx, args, _), operands) => {
                 let (fname, variant_name) = rustc_middle::ty::tls::with(|tcx| {
                     let variant_name = tcx.adt_def(*def_id).variant(*variant_idx).name;
                     let fname = tcx.def_path(*def_id).data.iter().join("::");
@@ -711,6 +726,7 @@ impl fmt::Debug for PointerCast {
         match self {
             PointerCast::MutToConstPointer => write!(f, "MutToConstPointer"),
             PointerCast::Unsize => write!(f, "Unsize"),
+            PointerCast::ReifyFnPointer => write!(f, "ReifyFnPointer"),
         }
     }
 }
@@ -747,6 +763,14 @@ impl fmt::Debug for Constant {
             Constant::Unit => write!(f, "()"),
             Constant::Str => write!(f, "\"<opaque str>\""),
             Constant::Char => write!(f, "\"<opaque char>\""),
+            Constant::FnDef(def_id, args) => {
+                let fname = rustc_middle::ty::tls::with(|tcx| tcx.def_path_str(*def_id));
+                write!(f, "{fname}")?;
+                if !args.is_empty() {
+                    write!(f, "::<{:?}>", args.iter().format(", "))?;
+                }
+                Ok(())
+            }
             Constant::Opaque(ty) => write!(f, "<opaque {:?}>", ty),
         }
     }
@@ -9,6 +9,9 @@ refineck_assign_error =
 refineck_condition_span_note =
     this is the condition that cannot be proved
 
+refineck_macro_def_note =
+    `{$def_name}` is defined here
+
 refineck_call_span_note =
     inside this call
 
@@ -40,14 +43,67 @@ refineck_unknown_error =
 refineck_overflow_error =
     arithmetic operation may overflow
 
+refineck_ptr_deref_error =
+    dereference of a raw pointer that may not be valid
+
+refineck_ptr_offset_error =
+    pointer offset may be out of bounds
+
+refineck_union_field_error =
+    union field may not be active
+
 refineck_opaque_struct_error =
     cannot access fields of opaque struct `{$struct}`
 
+refineck_unsupported_skipped =
+    `{$name}` was not checked because it uses unsupported features
+    .label = unsupported feature
+    .note = {$reason}
+    .help = callers will trust the signature of `{$name}`
+
+refineck_solver_timeout =
+    fixpoint timed out while checking `{$name}`
+    .note = {$kind ->
+        [total] the time limit for the entire crate was exceeded (`FLUX_SOLVER_TOTAL_TIMEOUT={$secs}`)
+        *[item] the time limit for a single item was exceeded (`FLUX_SOLVER_TIMEOUT={$secs}`)
+    }
+
+refineck_solver_crash =
+    fixpoint failed while checking `{$name}`
+    .note = {$msg}
+
+refineck_solver_task_path =
+    the constraint was saved to `{$path}`
+
+refineck_unresolved_alias =
+    cannot resolve associated refinement `{$alias}` while checking `{$name}`
+    .note = associated refinements are only supported on types implementing the trait and on type parameters
+
 refineck_unsupported_call =
     unsupported type in function call
     .function_definition = function defined here
 
+refineck_vacuous_precondition =
+    the precondition of `{$name}` is unsatisfiable
+    .note = everything in the function is verified vacuously
+
+refineck_vacuous_check =
+    this is only verified because it is unreachable
+    .note = the conditions on the path leading here are contradictory
+
+refineck_suggested_precondition =
+    `{$name}` would verify with the additional precondition `{$precondition}`
+    .suggestion = add the precondition to the signature
+
 # Invariant checking
 
 refineck_invalid_invariant =
     invariant cannot be proven
+
+refineck_vacuous_invariants =
+    the invariants of `{$name}` are contradictory
+    .note = no value satisfies all of them, so every property of the type holds vacuously
+
+refineck_inferred_signature =
+    inferred signature for `{$name}`: `{$sig}`
+    .suggestion = add the signature to the function
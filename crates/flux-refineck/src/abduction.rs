@@ -0,0 +1,188 @@
+//! Suggestion of preconditions for functions that fail to verify, enabled with
+//! [`CheckerConfig::suggest_preconditions`].
+//!
+//! When a call or a return in a function fails to verify, we guard the body of the function with a
+//! fresh kvar over the variables in scope at its entry and ask fixpoint for a solution. Since the
+//! kvar only appears in a guard, fixpoint picks the conjunction of all the qualifiers, which is
+//! typically contradictory. We look for a subset of the conjuncts that is satisfiable and still
+//! makes the body verify, first trying each conjunct on its own and then dropping conjuncts
+//! greedily from the entire solution. Only conjuncts mentioning refinement parameters of the
+//! function are considered because those are the only variables that can be mentioned in a
+//! `requires` clause.
+use flux_errors::{ErrorGuaranteed, ResultExt};
+use flux_middle::{fhir, global_env::GlobalEnv, rty};
+use itertools::Itertools;
+use rustc_hir::def_id::LocalDefId;
+use rustc_span::Span;
+
+use crate::{
+    constraint_gen::{ConstrReason, Tag},
+    fixpoint_encoding::{Conjunct, FixpointCtxt, KVarStore},
+    refine_tree::RefineTree,
+    CheckerConfig,
+};
+
+/// Warns with a precondition that would make the function verify if one can be found. `errors`
+/// are the obligations that failed when checking `refine_tree`, which must not be simplified yet.
+pub(crate) fn suggest_precondition(
+    genv: &GlobalEnv,
+    def_id: LocalDefId,
+    mut refine_tree: RefineTree,
+    mut kvars: KVarStore,
+    errors: &[Tag],
+    config: &CheckerConfig,
+) -> Result<(), ErrorGuaranteed> {
+    if !errors
+        .iter()
+        .any(|err| matches!(err.reason, ConstrReason::Call | ConstrReason::Ret))
+    {
+        return Ok(());
+    }
+    let span = genv.tcx.def_span(def_id);
+    let entry = Tag::new(ConstrReason::Entry, span);
+
+    let mut kvar = None;
+    let found = refine_tree.assume_at(entry, |scope| {
+        let expr = kvars.fresh_precondition(scope);
+        if let rty::ExprKind::KVar(k) = expr.kind() {
+            kvar = Some(k.kvid);
+        }
+        expr
+    });
+    let (true, Some(kvid)) = (found, kvar) else { return Ok(()) };
+    refine_tree.simplify();
+
+    let encode = || {
+        let mut fcx = FixpointCtxt::new(genv, def_id, kvars.clone());
+        let constraint = refine_tree.clone().into_fixpoint(&mut fcx);
+        (fcx, constraint)
+    };
+    let verifies = |preconditions: &[&Conjunct]| {
+        let (fcx, constraint) = encode();
+        fcx.check_precondition(constraint, kvid, preconditions, config)
+            .emit(genv.sess)
+    };
+    let is_satisfiable = |preconditions: &[&Conjunct]| {
+        let (fcx, constraint) = encode();
+        fcx.check_precondition_satisfiable(constraint, kvid, preconditions, entry, config)
+            .emit(genv.sess)
+    };
+
+    let (fcx, constraint) = encode();
+    let Some(solution) = fcx
+        .solve_precondition(constraint, kvid, config)
+        .emit(genv.sess)?
+    else {
+        return Ok(());
+    };
+    let mut candidates = solution
+        .iter()
+        .filter(|precondition| precondition.source.is_some())
+        .collect_vec();
+    if candidates.is_empty() || !verifies(&candidates)? {
+        return Ok(());
+    }
+
+    let mut precondition = None;
+    for candidate in &candidates {
+        if verifies(&[candidate])? && is_satisfiable(&[candidate])? {
+            precondition = Some(vec![*candidate]);
+            break;
+        }
+    }
+    if precondition.is_none() {
+        let mut i = 0;
+        while i < candidates.len() {
+            let mut without = candidates.clone();
+            without.remove(i);
+            if verifies(&without)? {
+                candidates = without;
+            } else {
+                i += 1;
+            }
+        }
+        if is_satisfiable(&candidates)? {
+            precondition = Some(candidates);
+        }
+    }
+    let Some(precondition) = precondition else { return Ok(()) };
+
+    let precondition = precondition
+        .iter()
+        .filter_map(|precondition| precondition.source.as_deref())
+        .join(" && ");
+    let (suggestion_span, suggestion) = match requires_suggestion(genv, def_id, &precondition) {
+        Some((span, suggestion)) => (Some(span), suggestion),
+        None => (None, String::new()),
+    };
+    genv.sess.emit_warning(errors::SuggestedPrecondition {
+        span,
+        name: genv.tcx.def_path_str(def_id.to_def_id()),
+        precondition,
+        suggestion_span,
+        suggestion,
+    });
+    Ok(())
+}
+
+/// Returns where and what to write in the `#[flux::sig]` of the function to add `precondition` to
+/// its `requires` clause, creating the clause if there's none.
+fn requires_suggestion(
+    genv: &GlobalEnv,
+    def_id: LocalDefId,
+    precondition: &str,
+) -> Option<(Span, String)> {
+    if !genv.map().has_fn_sig(def_id) || genv.tcx.asyncness(def_id).is_async() {
+        return None;
+    }
+    let fn_sig = genv.map().get_fn_sig(def_id);
+    if fn_sig.lifted {
+        return None;
+    }
+    let requires = fn_sig.requires.iter().find_map(|constr| {
+        if let fhir::Constraint::Pred(pred) = constr {
+            Some(pred)
+        } else {
+            None
+        }
+    });
+    if let Some(requires) = requires {
+        let snippet = genv
+            .tcx
+            .sess
+            .source_map()
+            .span_to_snippet(requires.span)
+            .ok()?;
+        // `&&` binds tighter than `||`, `=>` and `<=>`
+        let snippet = if snippet.contains("||") || snippet.contains("=>") {
+            format!("({snippet})")
+        } else {
+            snippet
+        };
+        Some((requires.span, format!("{snippet} && {precondition}")))
+    } else {
+        // The `requires` clause goes right after the return type
+        let ret = fn_sig.output.ret.span;
+        fn_sig
+            .span
+            .contains(ret)
+            .then(|| (ret.shrink_to_hi(), format!(" requires {precondition}")))
+    }
+}
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_span::Span;
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_suggested_precondition, code = "FLUX")]
+    pub struct SuggestedPrecondition {
+        #[primary_span]
+        pub span: Span,
+        pub name: String,
+        pub precondition: String,
+        #[suggestion(code = "{suggestion}", applicability = "machine-applicable")]
+        pub suggestion_span: Option<Span>,
+        pub suggestion: String,
+    }
+}
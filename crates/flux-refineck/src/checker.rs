@@ -1,22 +1,27 @@
-use std::{collections::hash_map::Entry, iter};
+use std::{
+    collections::hash_map::Entry,
+    iter,
+    time::{Duration, Instant},
+};
 
 use flux_common::{bug, dbg, index::IndexVec, iter::IterExt, tracked_span_bug};
 use flux_config as config;
 use flux_middle::{
     global_env::GlobalEnv,
     intern::List,
+    queries::QueryResult,
     rty::{
-        self, BaseTy, BinOp, Binder, Bool, Const, Constraint, EarlyBinder, Expr, Float, FnOutput,
-        FnSig, FnTraitPredicate, GeneratorArgs, GeneratorObligPredicate, GenericArg, Generics,
-        HoleKind, Index, Int, IntTy, Mutability, PolyFnSig, Region::ReStatic, Ty, TyKind, Uint,
-        UintTy, VariantIdx,
+        self, fold::TypeFoldable, BaseTy, BinOp, Binder, Bool, Constraint, EarlyBinder, Expr,
+        Float, FnOutput, FnSig, FnTraitPredicate, GeneratorArgs, GeneratorObligPredicate,
+        GenericArg, Generics, HoleKind, Index, Int, IntTy, Mutability, PolyFnSig, Region::ReStatic,
+        Ty, TyKind, Uint, UintTy, VariantIdx,
     },
     rustc::{
         self,
         mir::{
-            self, AggregateKind, AssertKind, BasicBlock, Body, BorrowKind, CastKind, Constant,
-            Location, Operand, Place, Rvalue, Statement, StatementKind, Terminator, TerminatorKind,
-            RETURN_PLACE, START_BLOCK,
+            self, AggregateKind, AssertKind, BasicBlock, Body, BorrowKind, CallKind, CastKind,
+            Constant, Location, Operand, Place, PlaceElem, Rvalue, Statement, StatementKind,
+            Terminator, TerminatorKind, RETURN_PLACE, START_BLOCK,
         },
         ty::GeneratorArgsParts,
     },
@@ -30,14 +35,18 @@ use rustc_hir::{
 };
 use rustc_index::bit_set::BitSet;
 use rustc_infer::infer::NllRegionVariableOrigin;
-use rustc_middle::mir::{SourceInfo, SwitchTargets};
-use rustc_span::Span;
+use rustc_middle::{
+    mir::{SourceInfo, SwitchTargets, VarDebugInfoContents},
+    ty::{self, TyCtxt},
+};
+use rustc_span::{sym, Span};
 
 use self::errors::{CheckerError, ResultExt};
 use crate::{
-    constraint_gen::{ConstrGen, ConstrReason, Obligations},
-    fixpoint_encoding::{self, KVarStore},
+    constraint_gen::{ConstrGen, ConstrReason, Obligations, Tag},
+    fixpoint_encoding::{KVarEncoding, KVarOrigin, KVarStore},
     ghost_statements::{GhostStatement, GhostStatements, Point},
+    local_types::LocalTypesDump,
     queue::WorkQueue,
     refine_tree::{AssumeInvariants, RefineCtxt, RefineSubtree, RefineTree, Snapshot},
     sigs,
@@ -48,8 +57,40 @@ use crate::{
 pub struct CheckerConfig {
     pub check_overflow: bool,
     pub scrape_quals: bool,
+    /// Skip functions using unsupported features with a warning instead of reporting an error.
+    pub skip_unsupported: bool,
+    /// Warn about specifications that are only verified vacuously, see [`crate::vacuity`].
+    pub check_vacuity: bool,
+    /// Suggest a precondition when a function fails to verify, see [`crate::abduction`].
+    pub suggest_preconditions: bool,
+    /// Infer the signature of private functions without one, see [`crate::inference`].
+    pub infer_signatures: bool,
+    /// Check that dereferenced raw pointers are valid and that pointer offsets stay in bounds.
+    pub check_raw_pointers: bool,
+    /// Instant after which calls to fixpoint time out, see [`config::solver_total_timeout`].
+    pub solver_deadline: Option<Instant>,
+    /// Maximum time fixpoint can spend on the constraint of the item, see [`config::solver_timeout`].
+    pub solver_timeout: Option<Duration>,
+    /// Encoding of the kvars generated for holes and join points.
+    pub kvar_encoding: KVarEncoding,
+    /// Dump the constraint and kvar solutions of the item regardless of the global flags.
+    pub verbose: bool,
 }
 
+impl CheckerConfig {
+    pub(crate) fn dump_constraint(&self) -> bool {
+        self.verbose || config::dump_constraint()
+    }
+
+    pub(crate) fn dump_kvar_solutions(&self) -> bool {
+        self.verbose || config::dump_kvar_solutions()
+    }
+}
+
+/// Signatures used instead of the ones in [`GlobalEnv`] for some functions. These are the
+/// templates with kvars of the functions whose signature is being inferred, see [`crate::inference`].
+pub(crate) type SigTemplates = FxHashMap<DefId, EarlyBinder<PolyFnSig>>;
+
 pub(crate) struct Checker<'ck, 'tcx, M> {
     genv: &'ck GlobalEnv<'ck, 'tcx>,
     config: CheckerConfig,
@@ -63,6 +104,7 @@ pub(crate) struct Checker<'ck, 'tcx, M> {
     /// The type used for the `resume` argument of a generator.
     resume_ty: Option<Ty>,
     ghost_stmts: &'ck UnordMap<LocalDefId, GhostStatements>,
+    templates: &'ck SigTemplates,
     output: Binder<FnOutput>,
     mode: &'ck mut M,
     /// A snapshot of the refinement context at the end of the basic block after applying the effects
@@ -98,6 +140,12 @@ pub(crate) trait Mode: Sized {
     ) -> Result<bool, CheckerError>;
 
     fn clear(ck: &mut Checker<Self>, bb: BasicBlock);
+
+    fn record_local_types(ck: &mut Checker<Self>, rcx: &RefineCtxt, env: &TypeEnv, span: Span);
+
+    /// Called with the refinement parameters of the function being checked after they are
+    /// introduced at the root of the tree.
+    fn name_refine_params(&mut self, genv: &GlobalEnv, def_id: LocalDefId, refparams: &[Expr]);
 }
 
 pub(crate) struct ShapeMode {
@@ -107,6 +155,9 @@ pub(crate) struct ShapeMode {
 pub(crate) struct RefineMode {
     bb_envs: FxHashMap<LocalDefId, FxHashMap<BasicBlock, BasicBlockEnv>>,
     kvars: KVarStore,
+    /// Types of named locals recorded for editor tooling, only populated when
+    /// [`config::dump_local_types`] is set.
+    local_types: LocalTypesDump,
 }
 
 /// The result of running the shape phase.
@@ -127,18 +178,21 @@ impl<'a, 'tcx> Checker<'a, 'tcx, ShapeMode> {
         genv: &GlobalEnv<'a, 'tcx>,
         def_id: LocalDefId,
         ghost_stmts: &'a UnordMap<LocalDefId, GhostStatements>,
+        templates: &'a SigTemplates,
         config: CheckerConfig,
     ) -> Result<ShapeResult, CheckerError> {
         dbg::shape_mode_span!(genv.tcx, def_id).in_scope(|| {
             let mut mode = ShapeMode { bb_envs: FxHashMap::default() };
 
-            let fn_sig = genv.fn_sig(def_id).with_span(genv.tcx.def_span(def_id))?;
+            let fn_sig = lookup_fn_sig(genv, templates, def_id.to_def_id())
+                .with_span(genv.tcx.def_span(def_id))?;
 
             Checker::run(
                 genv,
                 RefineTree::new().as_subtree(),
                 def_id,
                 ghost_stmts,
+                templates,
                 &mut mode,
                 fn_sig,
                 None,
@@ -156,27 +210,34 @@ impl<'a, 'tcx> Checker<'a, 'tcx, RefineMode> {
         def_id: LocalDefId,
         ghost_stmts: &'a UnordMap<LocalDefId, GhostStatements>,
         bb_env_shapes: ShapeResult,
+        mut kvars: KVarStore,
+        templates: &'a SigTemplates,
         config: CheckerConfig,
     ) -> Result<(RefineTree, KVarStore), CheckerError> {
-        let fn_sig = genv.fn_sig(def_id).with_span(genv.tcx.def_span(def_id))?;
+        let fn_sig = lookup_fn_sig(genv, templates, def_id.to_def_id())
+            .with_span(genv.tcx.def_span(def_id))?;
 
-        let mut kvars = fixpoint_encoding::KVarStore::new();
         let mut refine_tree = RefineTree::new();
-        let bb_envs = bb_env_shapes.into_bb_envs(&mut kvars);
+        let bb_envs = bb_env_shapes.into_bb_envs(genv, &mut kvars)?;
 
         dbg::refine_mode_span!(genv.tcx, def_id, bb_envs).in_scope(|| {
-            let mut mode = RefineMode { bb_envs, kvars };
+            let mut mode = RefineMode { bb_envs, kvars, local_types: LocalTypesDump::default() };
             Checker::run(
                 genv,
                 refine_tree.as_subtree(),
                 def_id,
                 ghost_stmts,
+                templates,
                 &mut mode,
                 fn_sig,
                 None,
                 config,
             )?;
 
+            if config::dump_local_types() {
+                mode.local_types.dump(genv, def_id).unwrap();
+            }
+
             Ok((refine_tree, mode.kvars))
         })
     }
@@ -189,6 +250,7 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         mut refine_tree: RefineSubtree<'a>,
         def_id: LocalDefId,
         ghost_stmts: &'a UnordMap<LocalDefId, GhostStatements>,
+        templates: &'a SigTemplates,
         mode: &'a mut M,
         poly_sig: EarlyBinder<PolyFnSig>,
         refparams: Option<List<Expr>>,
@@ -204,9 +266,12 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         let exprs = if let Some(exprs) = refparams {
             exprs
         } else {
-            generics
+            assume_const_params(genv.tcx, def_id, &mut rcx);
+            let exprs: List<Expr> = generics
                 .collect_all_refine_params(genv, |param| rcx.define_vars(&param.sort))
-                .with_span(span)?
+                .with_span(span)?;
+            mode.name_refine_params(genv, def_id, &exprs);
+            exprs
         };
 
         let poly_sig = poly_sig.instantiate_identity(&exprs);
@@ -222,6 +287,9 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         );
 
         let env = Self::init(&mut rcx, &body, &fn_sig, config);
+        if config.check_vacuity || config.suggest_preconditions {
+            rcx.check_reachable(Tag::new(ConstrReason::Entry, span));
+        }
 
         // (NOTE:YIELD) per https://doc.rust-lang.org/beta/nightly-rustc/rustc_middle/mir/enum.TerminatorKind.html#variant.Yield
         //   "execution of THIS function continues at the `resume` basic block, with THE SECOND ARGUMENT WRITTEN
@@ -239,6 +307,7 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             body: &body,
             resume_ty,
             ghost_stmts,
+            templates,
             visited: BitSet::new_empty(body.basic_blocks.len()),
             output: fn_sig.output().clone(),
             mode,
@@ -319,6 +388,7 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
                 dbg::statement!("start", stmt, rcx, env);
                 self.check_statement(&mut rcx, &mut env, stmt)?;
                 dbg::statement!("end", stmt, rcx, env);
+                M::record_local_types(self, &rcx, &env, span);
                 Ok(())
             })?;
             if !stmt.is_nop() {
@@ -353,6 +423,27 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         source_info: SourceInfo,
     ) -> Result<(), CheckerError> {
         let ty = rcx.unpack(&ty, AssumeInvariants::yes(self.config.check_overflow));
+        if let [proj @ .., PlaceElem::Field(f)] = &place.projection[..] {
+            let prefix = Place::new(place.local, proj.to_vec());
+            if let Some((def_id, args)) = self.union_of(&prefix).with_src_info(source_info)? {
+                // Writing a field of a union makes it the active one, so instead of updating the
+                // field we construct a new value for the whole union.
+                self.check_place_access(rcx, env, source_info.span, &prefix)?;
+                let variant_idx = VariantIdx::from_usize(f.as_usize());
+                let ty = self.check_constructor(
+                    rcx,
+                    env,
+                    source_info.span,
+                    def_id,
+                    variant_idx,
+                    &args,
+                    &[ty],
+                )?;
+                let gen = &mut self.constr_gen(rcx, source_info.span);
+                return env.assign(rcx, gen, &prefix, ty).with_src_info(source_info);
+            }
+        }
+        self.check_place_access(rcx, env, source_info.span, place)?;
         let gen = &mut self.constr_gen(rcx, source_info.span);
         env.assign(rcx, gen, place, ty).with_src_info(source_info)
     }
@@ -449,44 +540,68 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
                     Ok(Self::check_match(&discr_ty, targets))
                 }
             }
-            TerminatorKind::Call { args, destination, target, resolved_call, .. } => {
-                let actuals = self.check_operands(rcx, env, terminator_span, args)?;
-
-                let (func_id, call_args) = resolved_call;
-                let fn_sig = self
-                    .genv
-                    .fn_sig(*func_id)
-                    .with_src_info(terminator.source_info)?;
-
-                let fn_generics = self
-                    .genv
-                    .generics_of(*func_id)
-                    .with_src_info(terminator.source_info)?;
-
-                let generic_args = call_args
-                    .lowered
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, arg)| {
-                        let param = fn_generics.param_at(idx, self.genv)?;
-                        self.genv
-                            .instantiate_arg_for_fun(&self.generics, &param, arg)
-                    })
-                    .try_collect_vec()
-                    .with_src_info(terminator.source_info)?;
-
-                let ret = self.check_call(
-                    rcx,
-                    env,
-                    terminator_span,
-                    Some(*func_id),
-                    fn_sig,
-                    &generic_args,
-                    &actuals,
-                )?;
+            TerminatorKind::Call { kind, args, destination, target, .. } => {
+                let ret = match kind {
+                    CallKind::FnDef { resolved_call: (func_id, call_args), .. } => {
+                        let actuals = self.check_operands(rcx, env, terminator_span, args)?;
+
+                        let fn_sig = lookup_fn_sig(self.genv, self.templates, *func_id)
+                            .with_src_info(terminator.source_info)?;
+
+                        let fn_generics = self
+                            .genv
+                            .generics_of(*func_id)
+                            .with_src_info(terminator.source_info)?;
+
+                        let generic_args = call_args
+                            .lowered
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, arg)| {
+                                let param = fn_generics.param_at(idx, self.genv)?;
+                                self.genv
+                                    .instantiate_arg_for_fun(&self.generics, &param, arg)
+                            })
+                            .try_collect_vec()
+                            .with_src_info(terminator.source_info)?;
+
+                        let ret = self.check_call(
+                            rcx,
+                            env,
+                            terminator_span,
+                            Some(*func_id),
+                            fn_sig,
+                            &generic_args,
+                            &actuals,
+                        )?;
+                        if self.config.check_raw_pointers && self.is_raw_ptr_offset(*func_id) {
+                            self.check_raw_ptr_offset(rcx, terminator_span, &actuals, ret)
+                        } else {
+                            ret
+                        }
+                    }
+                    CallKind::FnPtr(func) => {
+                        let func_ty = self.check_operand(rcx, env, terminator_span, func)?;
+                        let actuals = self.check_operands(rcx, env, terminator_span, args)?;
+
+                        let TyKind::Indexed(BaseTy::FnPtr(fn_sig), _) = func_ty.kind() else {
+                            tracked_span_bug!("call to non function pointer `{func_ty:?}`")
+                        };
+                        self.check_call(
+                            rcx,
+                            env,
+                            terminator_span,
+                            None,
+                            EarlyBinder(fn_sig.clone()),
+                            &[],
+                            &actuals,
+                        )?
+                    }
+                };
 
                 let ret = rcx.unpack(&ret, AssumeInvariants::No);
                 rcx.assume_invariants(&ret, self.config.check_overflow);
+                self.check_place_access(rcx, env, terminator_span, destination)?;
                 let mut gen = self.constr_gen(rcx, terminator_span);
                 env.assign(rcx, &mut gen, destination, ret)
                     .with_span(terminator_span)?;
@@ -562,6 +677,7 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             refine_tree,
             gen_pred.def_id.expect_local(),
             self.ghost_stmts,
+            self.templates,
             self.mode,
             EarlyBinder(poly_sig),
             Some(self.refparams.clone()),
@@ -573,25 +689,68 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         &mut self,
         rcx: &mut RefineCtxt,
         snapshot: &Snapshot,
-        fn_trait_pred: FnTraitPredicate,
+        fn_trait_pred: Binder<FnTraitPredicate>,
     ) -> Result<(), CheckerError> {
-        if let Some(BaseTy::Closure(def_id, tys)) =
-            fn_trait_pred.self_ty.as_bty_skipping_existentials()
-        {
+        let self_ty = fn_trait_pred.as_ref().skip_binder().self_ty.clone();
+        if let Some(BaseTy::Closure(def_id, tys)) = self_ty.as_bty_skipping_existentials() {
+            let span = self.genv.tcx.def_span(*def_id);
+            let poly_sig = if self.genv.map().has_fn_sig(def_id.expect_local()) {
+                // The closure has a user annotated signature. We check that it refines the
+                // signature expected by the bound and then check the body against it.
+                let closure_sig = self
+                    .genv
+                    .fn_sig(*def_id)
+                    .with_span(span)?
+                    .instantiate_identity(&self.refparams);
+                // The late bound regions of a higher-ranked bound are irrelevant for this check
+                let fn_trait_pred = fn_trait_pred.replace_bound_vars(
+                    |_| ReStatic,
+                    |_, _| tracked_span_bug!("unexpected refinement variable in clause"),
+                );
+                let mut refine_tree = rcx.subtree_at(snapshot).unwrap();
+                let mut rcx = refine_tree.refine_ctxt_at_root();
+                self.constr_gen(&rcx, span)
+                    .check_closure_sig(
+                        &mut rcx,
+                        &closure_sig,
+                        fn_trait_pred.tupled_args.expect_tuple(),
+                        &fn_trait_pred.output,
+                    )
+                    .with_span(span)?;
+                fn_trait_pred.to_annotated_closure_sig(*def_id, tys.clone(), &closure_sig)
+            } else {
+                fn_trait_pred.as_ref().skip_binder().to_closure_sig(
+                    *def_id,
+                    tys.clone(),
+                    fn_trait_pred.vars(),
+                )
+            };
+            // The predicate may mention associated types of the callee's generic arguments,
+            // e.g., `Iterator::filter` requires `P: FnMut(&Self::Item) -> bool`.
+            let poly_sig = poly_sig
+                .normalize_projections(
+                    self.genv,
+                    &self.body.infcx,
+                    self.def_id.to_def_id(),
+                    &self.refparams,
+                )
+                .with_span(span)?;
             let refine_tree = rcx.subtree_at(snapshot).unwrap();
-            let poly_sig = fn_trait_pred.to_closure_sig(*def_id, tys.clone());
             Checker::run(
                 self.genv,
                 refine_tree,
                 def_id.expect_local(),
                 self.ghost_stmts,
+                self.templates,
                 self.mode,
                 EarlyBinder(poly_sig),
                 Some(self.refparams.clone()),
                 self.config,
             )?;
-        } else {
-            panic!("check_oblig_fn_trait_pred: unexpected self_ty {:?}", fn_trait_pred.self_ty);
+        } else if !matches!(self_ty.kind(), TyKind::Param(_)) {
+            // A type parameter satisfies the bound through a bound in the caller's environment,
+            // which has already been checked by rustc and doesn't carry refinements.
+            panic!("check_oblig_fn_trait_pred: unexpected self_ty {self_ty:?}");
         }
         Ok(())
     }
@@ -605,6 +764,7 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         for pred in &obligs.predicates {
             match pred.kind() {
                 rty::ClauseKind::FnTrait(fn_trait_pred) => {
+                    let fn_trait_pred = pred.bound_kind().clone().rebind(fn_trait_pred);
                     self.check_oblig_fn_trait_pred(rcx, &obligs.snapshot, fn_trait_pred)?;
                 }
                 rty::ClauseKind::GeneratorOblig(gen_pred) => {
@@ -639,6 +799,8 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             AssertKind::RemainderByZero => "possible remainder with a divisor of zero",
             AssertKind::Overflow(mir::BinOp::Div) => "possible division with overflow",
             AssertKind::Overflow(mir::BinOp::Rem) => "possible reminder with overflow",
+            // Other overflows, including the shift amount, are checked in the signature of the
+            // operator when `check_overflow` is set.
             AssertKind::Overflow(_) => return Ok(Guard::Pred(pred)),
         };
         self.constr_gen(rcx, terminator_span).check_pred(
@@ -775,29 +937,25 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
                 Ok(Ty::tuple(vec![ty, Ty::bool()]))
             }
             Rvalue::Ref(r, BorrowKind::Mut { .. }, place) => {
+                self.check_place_access(rcx, env, stmt_span, place)?;
                 env.borrow(self.genv, rcx, *r, Mutability::Mut, place)
                     .with_span(stmt_span)
             }
             Rvalue::Ref(r, BorrowKind::Shared, place) => {
+                self.check_place_access(rcx, env, stmt_span, place)?;
                 env.borrow(self.genv, rcx, *r, Mutability::Not, place)
                     .with_span(stmt_span)
             }
             Rvalue::UnaryOp(un_op, op) => self.check_unary_op(rcx, env, stmt_span, *un_op, op),
-            Rvalue::Aggregate(AggregateKind::Adt(def_id, variant_idx, args), operands) => {
+            Rvalue::Aggregate(
+                AggregateKind::Adt(def_id, variant_idx, args, field_idx),
+                operands,
+            ) => {
                 let actuals = self.check_operands(rcx, env, stmt_span, operands)?;
-                let sig = genv
-                    .variant_sig(*def_id, *variant_idx)
-                    .with_span(stmt_span)?
-                    .ok_or_else(|| CheckerError::opaque_struct(*def_id, stmt_span))?
-                    .to_poly_fn_sig();
-                let adt_generics = &genv.generics_of(*def_id).with_span(stmt_span)?;
-                let args = iter::zip(&adt_generics.params, args)
-                    .map(|(param, arg)| {
-                        genv.instantiate_arg_for_constructor(&self.generics, param, arg)
-                    })
-                    .try_collect_vec()
-                    .with_span(stmt_span)?;
-                self.check_call(rcx, env, stmt_span, None, sig, &args, &actuals)
+                // Unions are refined with one variant per field
+                let variant_idx =
+                    field_idx.map_or(*variant_idx, |f| VariantIdx::from_usize(f.as_usize()));
+                self.check_constructor(rcx, env, stmt_span, *def_id, variant_idx, args, &actuals)
             }
             Rvalue::Aggregate(AggregateKind::Array(arr_ty), operands) => {
                 let args = self.check_operands(rcx, env, stmt_span, operands)?;
@@ -834,6 +992,7 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             }
 
             Rvalue::Discriminant(place) => {
+                self.check_place_access(rcx, env, stmt_span, place)?;
                 let ty = env
                     .lookup_place(self.genv, rcx, place)
                     .with_span(stmt_span)?;
@@ -843,6 +1002,17 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             Rvalue::Len(place) => self.check_len(rcx, env, stmt_span, place),
             Rvalue::Cast(kind, op, to) => {
                 let from = self.check_operand(rcx, env, stmt_span, op)?;
+                // A pointer cast to the same type keeps the refinements of the operand
+                if let CastKind::PtrToPtr = kind
+                    && let Operand::Copy(place) | Operand::Move(place) = op
+                    && place
+                        .ty(genv, &self.body.local_decls)
+                        .with_span(stmt_span)?
+                        .ty
+                        == *to
+                {
+                    return Ok(from);
+                }
                 self.check_cast(*kind, &from, to)
             }
         }
@@ -858,7 +1028,9 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
     {
         let tys = self.check_operands(rcx, env, stmt_span, args)?;
         let mut gen = self.constr_gen(rcx, stmt_span);
-        let tys = gen.pack_closure_operands(env, &tys).with_span(stmt_span);
+        let tys = gen
+            .pack_closure_operands(rcx, env, &tys)
+            .with_span(stmt_span);
         Ok(tys)
     }
 
@@ -869,18 +1041,13 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         source_span: Span,
         place: &Place,
     ) -> Result<Ty, CheckerError> {
+        self.check_place_access(rcx, env, source_span, place)?;
         let ty = env
             .lookup_place(self.genv, rcx, place)
             .with_span(source_span)?;
 
         let idx = match ty.kind() {
-            TyKind::Indexed(BaseTy::Array(_, len), _) => {
-                if let Const::Value(value) = len {
-                    Index::from(Expr::constant(rty::Constant::from(value.val)))
-                } else {
-                    tracked_span_bug!("unexpected array length")
-                }
-            }
+            TyKind::Indexed(BaseTy::Array(_, len), _) => Index::from(Expr::from_const(len)),
             TyKind::Indexed(BaseTy::Slice(_), idx) => idx.clone(),
             _ => tracked_span_bug!("expected array or slice type"),
         };
@@ -924,11 +1091,11 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             (TyKind::Indexed(bty1, idx1), TyKind::Indexed(bty2, idx2)) => {
                 let sig = sigs::get_bin_op_sig(bin_op, bty1, bty2, self.config.check_overflow);
                 let (e1, e2) = (idx1.expr.clone(), idx2.expr.clone());
-                if let sigs::Pre::Some(reason, constr) = &sig.pre {
+                for pre in &sig.pre {
                     self.constr_gen(rcx, source_span).check_pred(
                         rcx,
-                        constr([e1.clone(), e2.clone()]),
-                        *reason,
+                        (pre.constr)([e1.clone(), e2.clone()]),
+                        pre.reason,
                     );
                 }
 
@@ -952,9 +1119,12 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             TyKind::Indexed(bty, idx) => {
                 let sig = sigs::get_un_op_sig(un_op, bty, self.config.check_overflow);
                 let e = idx.expr.clone();
-                if let sigs::Pre::Some(reason, constr) = &sig.pre {
-                    self.constr_gen(rcx, source_span)
-                        .check_pred(rcx, constr([e.clone()]), *reason);
+                for pre in &sig.pre {
+                    self.constr_gen(rcx, source_span).check_pred(
+                        rcx,
+                        (pre.constr)([e.clone()]),
+                        pre.reason,
+                    );
                 }
                 Ok(sig.out.to_ty([e]))
             }
@@ -992,19 +1162,32 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             // &mut [T; n] -> &mut [T][n] and &[T; n] -> &[T][n]
             CastKind::Pointer(mir::PointerCast::Unsize) => {
                 if let TyKind::Indexed(BaseTy::Ref(_, src_ty, src_mut), _) = from.kind()
-                    && let TyKind::Indexed(BaseTy::Array(src_arr_ty, Const::Value(src_n)), _) =
-                        src_ty.kind()
+                    && let TyKind::Indexed(BaseTy::Array(src_arr_ty, src_n), _) = src_ty.kind()
                     && let rustc::ty::TyKind::Ref(dst_re, dst_ty, dst_mut) = to.kind()
                     && let rustc::ty::TyKind::Slice(_) = dst_ty.kind()
                     && src_mut == dst_mut
                 {
-                    let dst_ix = Index::from(src_n.clone());
+                    let dst_ix = Index::from(Expr::from_const(src_n));
                     let dst_slice = Ty::indexed(BaseTy::Slice(src_arr_ty.clone()), dst_ix);
                     Ty::mk_ref(*dst_re, dst_slice, *dst_mut)
                 } else {
                     tracked_span_bug!("unsupported Unsize cast")
                 }
             }
+            // A function item already has the type of a function pointer with its signature
+            CastKind::Pointer(mir::PointerCast::ReifyFnPointer) => {
+                if let TyKind::Indexed(BaseTy::FnPtr(_), _) = from.kind() {
+                    from.clone()
+                } else {
+                    tracked_span_bug!("unsupported ReifyFnPointer cast")
+                }
+            }
+            // *mut T -> *const T keeps the number of valid elements
+            CastKind::Pointer(mir::PointerCast::MutToConstPointer)
+                if let TyKind::Indexed(BaseTy::RawPtr(ty, Mutability::Mut), idx) = from.kind() =>
+            {
+                Ty::indexed(BaseTy::RawPtr(ty.clone(), Mutability::Not), idx.clone())
+            }
             CastKind::FloatToInt
             | CastKind::IntToFloat
             | CastKind::PtrToPtr
@@ -1017,6 +1200,31 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         Ok(ty)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn check_constructor(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        env: &mut TypeEnv,
+        span: Span,
+        def_id: DefId,
+        variant_idx: VariantIdx,
+        args: &[rustc::ty::GenericArg],
+        actuals: &[Ty],
+    ) -> Result<Ty, CheckerError> {
+        let genv = self.genv;
+        let sig = genv
+            .variant_sig(def_id, variant_idx)
+            .with_span(span)?
+            .ok_or_else(|| CheckerError::opaque_struct(def_id, span))?
+            .to_poly_fn_sig();
+        let adt_generics = &genv.generics_of(def_id).with_span(span)?;
+        let args = iter::zip(&adt_generics.params, args)
+            .map(|(param, arg)| genv.instantiate_arg_for_constructor(&self.generics, param, arg))
+            .try_collect_vec()
+            .with_span(span)?;
+        self.check_call(rcx, env, span, None, sig, &args, actuals)
+    }
+
     fn check_operands(
         &mut self,
         rcx: &mut RefineCtxt,
@@ -1038,13 +1246,114 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         operand: &Operand,
     ) -> Result<Ty, CheckerError> {
         let ty = match operand {
-            Operand::Copy(p) => env.lookup_place(self.genv, rcx, p).with_span(source_span)?,
-            Operand::Move(p) => env.move_place(self.genv, rcx, p).with_span(source_span)?,
+            Operand::Copy(p) => {
+                self.check_place_access(rcx, env, source_span, p)?;
+                env.lookup_place(self.genv, rcx, p).with_span(source_span)?
+            }
+            Operand::Move(p) => {
+                self.check_place_access(rcx, env, source_span, p)?;
+                env.move_place(self.genv, rcx, p).with_span(source_span)?
+            }
             Operand::Constant(c) => self.check_constant(c)?,
         };
         Ok(rcx.unpack(&ty, AssumeInvariants::yes(self.config.check_overflow)))
     }
 
+    /// Checks the projections of `place` that may be invalid when accessing it. Every union field
+    /// must be the active one and, if raw pointers are checked, every dereferenced raw pointer must
+    /// point to at least one valid element, i.e., its index must be positive.
+    fn check_place_access(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        env: &mut TypeEnv,
+        source_span: Span,
+        place: &Place,
+    ) -> Result<(), CheckerError> {
+        for (i, elem) in place.projection.iter().enumerate() {
+            let prefix = Place::new(place.local, place.projection[..i].to_vec());
+            let needs_check = match elem {
+                PlaceElem::Deref => self.config.check_raw_pointers,
+                PlaceElem::Field(_) => self.union_of(&prefix).with_span(source_span)?.is_some(),
+                _ => false,
+            };
+            if !needs_check {
+                continue;
+            }
+            let ty = env
+                .lookup_place(self.genv, rcx, &prefix)
+                .with_span(source_span)?;
+            let ty = rcx.unpack(&ty, AssumeInvariants::No);
+            match (elem, ty.kind()) {
+                (PlaceElem::Deref, TyKind::Indexed(BaseTy::RawPtr(..), idx)) => {
+                    self.constr_gen(rcx, source_span).check_pred(
+                        rcx,
+                        Expr::gt(&idx.expr, Expr::zero()),
+                        ConstrReason::PtrDeref,
+                    );
+                }
+                (PlaceElem::Field(f), TyKind::Indexed(BaseTy::Adt(adt_def, args), _)) => {
+                    // The collector rejects opaque unions
+                    let rty::Opaqueness::Transparent(variant) = self
+                        .genv
+                        .variant_sig(adt_def.did(), VariantIdx::from_usize(f.as_usize()))
+                        .with_span(source_span)?
+                    else {
+                        tracked_span_bug!("unexpected opaque union `{:?}`", adt_def.did())
+                    };
+                    self.constr_gen(rcx, source_span)
+                        .check_union_field(rcx, &ty, variant, args)
+                        .with_span(source_span)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the union and its generic arguments if `place` has a union type.
+    fn union_of(&self, place: &Place) -> QueryResult<Option<(DefId, rustc::ty::GenericArgs)>> {
+        let ty = place.ty(self.genv, &self.body.local_decls)?.ty;
+        match ty.kind() {
+            rustc::ty::TyKind::Adt(adt_def, args) if adt_def.is_union() => {
+                Ok(Some((adt_def.did(), args.clone())))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether `def_id` is one of the inherent methods `add` or `offset` of raw pointers.
+    fn is_raw_ptr_offset(&self, def_id: DefId) -> bool {
+        let tcx = self.genv.tcx;
+        let Some(impl_id) = tcx.impl_of_method(def_id) else { return false };
+        tcx.trait_id_of_impl(impl_id).is_none()
+            && tcx.type_of(impl_id).skip_binder().is_unsafe_ptr()
+            && matches!(tcx.item_name(def_id), sym::add | sym::offset)
+    }
+
+    /// A call `p.add(k)` or `p.offset(k)` to a pointer `p` with `n` valid elements requires
+    /// `0 <= k <= n` and returns a pointer with `n - k` valid elements. Negative offsets are
+    /// rejected because we do not track the elements before a pointer.
+    fn check_raw_ptr_offset(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        terminator_span: Span,
+        actuals: &[Ty],
+        ret: Ty,
+    ) -> Ty {
+        let [ptr, off] = actuals else { return ret };
+        if let TyKind::Indexed(BaseTy::RawPtr(pointee, mutbl), n) = ptr.kind()
+            && let TyKind::Indexed(BaseTy::Int(_) | BaseTy::Uint(_), k) = off.kind()
+        {
+            let pred = Expr::and([Expr::ge(&k.expr, Expr::zero()), Expr::le(&k.expr, &n.expr)]);
+            self.constr_gen(rcx, terminator_span)
+                .check_pred(rcx, pred, ConstrReason::PtrOffset);
+            let idx = Expr::binary_op(BinOp::Sub, &n.expr, &k.expr, None);
+            Ty::indexed(BaseTy::RawPtr(pointee.clone(), *mutbl), idx)
+        } else {
+            ret
+        }
+    }
+
     fn check_constant(&mut self, c: &Constant) -> Result<Ty, CheckerError> {
         match c {
             Constant::Int(n, int_ty) => {
@@ -1063,6 +1372,12 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
             Constant::Unit => Ok(Ty::unit()),
             Constant::Str => Ok(Ty::mk_ref(ReStatic, Ty::str(), Mutability::Not)),
             Constant::Char => Ok(Ty::char()),
+            Constant::FnDef(def_id, args) => {
+                let fn_sig = self
+                    .fn_item_sig(*def_id, args)
+                    .with_span(self.body.span())?;
+                Ok(Ty::fn_ptr(fn_sig))
+            }
             Constant::Opaque(ty) => {
                 self.genv
                     .refine_default(&self.generics, ty)
@@ -1071,6 +1386,33 @@ impl<'a, 'tcx, M: Mode> Checker<'a, 'tcx, M> {
         }
     }
 
+    /// Returns the signature of a function item as the signature of a function pointer. Generic
+    /// arguments are refined trivially and the refinement parameters of the function, which are
+    /// early bound in the item signature, become late bound in the returned signature.
+    fn fn_item_sig(&self, def_id: DefId, args: &[rustc::ty::GenericArg]) -> QueryResult<PolyFnSig> {
+        let genv = self.genv;
+        let fn_generics = genv.generics_of(def_id)?;
+        let generic_args = args
+            .iter()
+            .enumerate()
+            .map(|(idx, arg)| {
+                let param = fn_generics.param_at(idx, genv)?;
+                let arg = genv.instantiate_arg_for_fun(&self.generics, &param, arg)?;
+                QueryResult::Ok(arg.replace_holes(|_, _| Expr::tt()))
+            })
+            .try_collect_vec()?;
+
+        let fn_sig = lookup_fn_sig(genv, self.templates, def_id)?.skip_binder();
+        let mut vars = fn_sig.vars().to_vec();
+        let refine_args: Vec<_> = fn_generics.collect_all_refine_params(genv, |param| {
+            let idx = vars.len() as u32;
+            vars.push(rty::BoundVariableKind::Refine(param.sort, param.mode));
+            Expr::late_bvar(rty::INNERMOST, idx)
+        })?;
+        let fn_sig = EarlyBinder(fn_sig.skip_binder()).instantiate(&generic_args, &refine_args);
+        Ok(PolyFnSig::new(fn_sig, List::from_vec(vars)))
+    }
+
     fn check_ghost_statements_at(
         &mut self,
         rcx: &mut RefineCtxt,
@@ -1194,6 +1536,10 @@ impl Mode for ShapeMode {
             }
         }
     }
+
+    fn record_local_types(_: &mut Checker<ShapeMode>, _: &RefineCtxt, _: &TypeEnv, _: Span) {}
+
+    fn name_refine_params(&mut self, _: &GlobalEnv, _: LocalDefId, _: &[Expr]) {}
 }
 
 impl Mode for RefineMode {
@@ -1212,7 +1558,10 @@ impl Mode for RefineMode {
             infcx,
             def_id.into(),
             refparams,
-            move |sorts: &[_], encoding| self.kvars.fresh(sorts, &scope, encoding),
+            move |sorts: &[_], encoding| {
+                self.kvars
+                    .fresh(sorts, &scope, encoding, KVarOrigin::Infer(span))
+            },
             span,
         )
     }
@@ -1242,7 +1591,10 @@ impl Mode for RefineMode {
             &ck.body.infcx,
             ck.def_id.into(),
             &ck.refparams,
-            |sorts: &_, encoding| ck.mode.kvars.fresh(sorts, bb_env.scope(), encoding),
+            |sorts: &_, encoding| {
+                let origin = KVarOrigin::Infer(terminator_span);
+                ck.mode.kvars.fresh(sorts, bb_env.scope(), encoding, origin)
+            },
             terminator_span,
         );
         env.check_goto(&mut rcx, gen, bb_env, target)
@@ -1254,6 +1606,29 @@ impl Mode for RefineMode {
     fn clear(_ck: &mut Checker<RefineMode>, _bb: BasicBlock) {
         bug!();
     }
+
+    fn record_local_types(
+        ck: &mut Checker<RefineMode>,
+        rcx: &RefineCtxt,
+        env: &TypeEnv,
+        span: Span,
+    ) {
+        if config::dump_local_types() {
+            ck.mode.local_types.record(ck.genv, ck.body, span, rcx, env);
+        }
+    }
+
+    fn name_refine_params(&mut self, genv: &GlobalEnv, def_id: LocalDefId, refparams: &[Expr]) {
+        let Some(params) = genv.map().get_refine_params(genv.tcx, def_id) else { return };
+        // The parameters of the function come after the ones of its parent
+        let Some(start) = refparams.len().checked_sub(params.len()) else { return };
+        for (param, expr) in iter::zip(params, &refparams[start..]) {
+            if let rty::ExprKind::Var(rty::Var::Free(name)) = expr.kind() {
+                self.kvars
+                    .name_refine_param(*name, param.ident.source_info.name.to_string());
+            }
+        }
+    }
 }
 
 fn bool_int_cast(b: &Expr, int_ty: IntTy) -> Ty {
@@ -1303,21 +1678,68 @@ fn int_bit_width(int_ty: IntTy) -> u64 {
 impl ShapeResult {
     fn into_bb_envs(
         self,
+        genv: &GlobalEnv,
         kvar_store: &mut KVarStore,
-    ) -> FxHashMap<LocalDefId, FxHashMap<BasicBlock, BasicBlockEnv>> {
+    ) -> Result<FxHashMap<LocalDefId, FxHashMap<BasicBlock, BasicBlockEnv>>, CheckerError> {
         self.0
             .into_iter()
             .map(|(def_id, shapes)| {
+                let body = genv.mir(def_id).with_span(genv.tcx.def_span(def_id))?;
+                let rustc_body = body.rustc_body();
+                let local_names = rustc_body
+                    .var_debug_info
+                    .iter()
+                    .filter_map(|info| {
+                        let VarDebugInfoContents::Place(place) = info.value else { return None };
+                        place
+                            .projection
+                            .is_empty()
+                            .then_some((place.local, info.name))
+                    })
+                    .collect();
                 let bb_envs = shapes
                     .into_iter()
-                    .map(|(bb, shape)| (bb, shape.into_bb_env(kvar_store)))
+                    .map(|(bb, shape)| {
+                        let span = rustc_body.source_info(bb.start_location()).span;
+                        (bb, shape.into_bb_env(kvar_store, bb, span, &local_names))
+                    })
                     .collect();
-                (def_id, bb_envs)
+                Ok((def_id, bb_envs))
             })
             .collect()
     }
 }
 
+/// The signature of `def_id`, which is taken from `templates` if present.
+fn lookup_fn_sig(
+    genv: &GlobalEnv,
+    templates: &SigTemplates,
+    def_id: DefId,
+) -> QueryResult<EarlyBinder<PolyFnSig>> {
+    if let Some(template) = templates.get(&def_id) {
+        Ok(template.clone())
+    } else {
+        genv.fn_sig(def_id)
+    }
+}
+
+/// Const generic parameters of an unsigned integer type are assumed to be non-negative
+fn assume_const_params(tcx: TyCtxt, def_id: LocalDefId, rcx: &mut RefineCtxt) {
+    let mut generics = tcx.generics_of(def_id);
+    loop {
+        for param in &generics.params {
+            if let ty::GenericParamDefKind::Const { .. } = param.kind
+                && let ty::Uint(_) = tcx.type_of(param.def_id).instantiate_identity().kind()
+            {
+                let param = ty::ParamConst { index: param.index, name: param.name };
+                rcx.assume_pred(Expr::ge(Expr::const_param(param, None), Expr::zero()));
+            }
+        }
+        let Some(parent) = generics.parent else { break };
+        generics = tcx.generics_of(parent);
+    }
+}
+
 fn snapshot_at_dominator<'a>(
     body: &Body,
     snapshots: &'a IndexVec<BasicBlock, Option<Snapshot>>,
@@ -1352,6 +1774,14 @@ pub(crate) mod errors {
         pub fn opaque_struct(def_id: DefId, span: Span) -> Self {
             Self { kind: CheckerErrKind::OpaqueStruct(def_id), span }
         }
+
+        pub fn unsupported_reason(&self) -> Option<(Span, String)> {
+            if let CheckerErrKind::Query(err) = &self.kind {
+                err.unsupported_reason()
+            } else {
+                None
+            }
+        }
     }
 
     impl<'a> IntoDiagnostic<'a> for CheckerError {
@@ -89,6 +89,15 @@ pub enum ConstrReason {
     Rem,
     Goto(BasicBlock),
     Overflow,
+    /// Dereference of a raw pointer that may not point to a valid element.
+    PtrDeref,
+    /// Offset of a raw pointer with `add` or `offset` that may go past its valid elements.
+    PtrOffset,
+    /// Read of a union field that may not be the active one.
+    UnionField,
+    /// The entry of a function after assuming its precondition, see [`crate::vacuity`] and
+    /// [`crate::abduction`].
+    Entry,
     Other,
 }
 
@@ -130,6 +139,7 @@ impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
 
     pub(crate) fn pack_closure_operands(
         &mut self,
+        rcx: &mut RefineCtxt,
         env: &mut TypeEnv,
         operands: &[Ty],
     ) -> Result<Vec<Ty>, CheckerErrKind> {
@@ -140,6 +150,19 @@ impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
                     let ty = env.get(path);
                     rty::Ty::mk_ref(*region, ty, Mutability::Not)
                 }
+                TyKind::Ptr(PtrKind::Mut(region), path) => {
+                    // A local captured by mutable reference can be updated by the closure. We
+                    // generalize its type with fresh kvars and block it with the generalization,
+                    // so once the closure is dead the local gets the (inferred) updated type.
+                    let mut infcx = self.infcx(rcx, ConstrReason::Assign);
+                    let bound = env.get(path).with_holes().replace_holes(|binders, kind| {
+                        infcx.fresh_infer_var_for_hole(binders, kind)
+                    });
+                    let ty = env.block_with(path, bound.clone());
+                    infcx.subtyping(rcx, &ty, &bound)?;
+                    rcx.replace_evars(&infcx.solve()?);
+                    rty::Ty::mk_ref(*region, bound, Mutability::Mut)
+                }
                 _ => ty.clone(),
             };
             res.push(packed_ty);
@@ -301,6 +324,59 @@ impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
         Ok((output, Obligations::new(obligs, snapshot)))
     }
 
+    /// Checks that the user annotated signature of a closure refines the signature expected by
+    /// a `Fn*` bound, i.e., the arguments of the bound are subtypes of the arguments of the closure
+    /// and the output of the closure is a subtype of the output of the bound.
+    pub(crate) fn check_closure_sig(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        closure_sig: &PolyFnSig,
+        expected_args: &[Ty],
+        expected_ret: &Ty,
+    ) -> Result<(), CheckerErrKind> {
+        let actuals = expected_args
+            .iter()
+            .map(|ty| rcx.unpack(ty, AssumeInvariants::No))
+            .collect_vec();
+
+        let mut infcx = self.infcx(rcx, ConstrReason::Call);
+
+        let closure_sig = closure_sig
+            .replace_bound_vars(|_| rty::ReStatic, |sort, mode| infcx.fresh_infer_var(sort, mode));
+
+        for constr in closure_sig.requires() {
+            if let Constraint::Pred(pred) = constr {
+                infcx.check_pred(rcx, pred);
+            }
+        }
+
+        for (actual, formal) in iter::zip(&actuals, closure_sig.args()) {
+            let (formal, pred) = formal.unconstr();
+            infcx.check_pred(rcx, pred);
+            infcx.subtyping(rcx, actual, &formal)?;
+        }
+
+        let evars_sol = infcx.solve()?;
+        rcx.replace_evars(&evars_sol);
+
+        let output = closure_sig
+            .output()
+            .replace_evars(&evars_sol)
+            .replace_bound_exprs_with(|sort, _| rcx.define_vars(sort));
+        for constr in &output.ensures {
+            if let Constraint::Pred(pred) = constr {
+                rcx.assume_pred(pred);
+            }
+        }
+        let ret = rcx.unpack(&output.ret, AssumeInvariants::No);
+
+        let mut infcx = self.infcx(rcx, ConstrReason::Ret);
+        infcx.subtyping(rcx, &ret, expected_ret)?;
+        rcx.replace_evars(&infcx.solve()?);
+
+        Ok(())
+    }
+
     pub(crate) fn check_ret(
         &mut self,
         rcx: &mut RefineCtxt,
@@ -363,6 +439,31 @@ impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
         Ok(variant.ret().replace_evars(&evars_sol))
     }
 
+    /// Checks that `field` is the active field of a union of type `union_ty`, i.e., that the index
+    /// of the union matches the return index of the field's variant for some instantiation of the
+    /// variant's parameters.
+    pub(crate) fn check_union_field(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        union_ty: &Ty,
+        variant: EarlyBinder<PolyVariant>,
+        generic_args: &[GenericArg],
+    ) -> Result<(), CheckerErrKind> {
+        let mut infcx = self.infcx(rcx, ConstrReason::UnionField);
+
+        let generic_args = infcx.instantiate_generic_args(generic_args);
+
+        let variant = variant
+            .instantiate(&generic_args, &[])
+            .replace_bound_exprs_with(|sort, mode| infcx.fresh_infer_var(sort, mode));
+
+        infcx.subtyping(rcx, union_ty, &variant.ret())?;
+
+        rcx.replace_evars(&infcx.solve()?);
+
+        Ok(())
+    }
+
     pub(crate) fn check_mk_array(
         &mut self,
         rcx: &mut RefineCtxt,
@@ -662,12 +763,77 @@ impl<'a, 'tcx> InferCtxt<'a, 'tcx> {
                 }
                 Ok(())
             }
+            (BaseTy::FnPtr(fn_sig1), BaseTy::FnPtr(fn_sig2)) => {
+                self.fn_sig_subtyping(rcx, fn_sig1, fn_sig2)
+            }
             _ => {
                 panic!("unexpected base types: `{:?}` and `{:?}`", bty1, bty2,);
             }
         }
     }
 
+    /// Checks that a function with signature `fn_sig1` can be used where one with signature
+    /// `fn_sig2` is expected, i.e., that `fn_sig2`'s arguments and precondition imply `fn_sig1`'s
+    /// and that `fn_sig1`'s output implies `fn_sig2`'s.
+    fn fn_sig_subtyping(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        fn_sig1: &PolyFnSig,
+        fn_sig2: &PolyFnSig,
+    ) -> Result<(), CheckerErrKind> {
+        let rcx = &mut rcx.branch();
+
+        let fn_sig2 =
+            fn_sig2.replace_bound_vars(|_| rty::ReStatic, |sort, _| rcx.define_vars(sort));
+        for constr in fn_sig2.requires() {
+            if let Constraint::Pred(pred) = constr {
+                rcx.assume_pred(pred);
+            }
+        }
+        let args2 = fn_sig2
+            .args()
+            .iter()
+            .map(|ty| rcx.unpack(ty, AssumeInvariants::No))
+            .collect_vec();
+
+        self.push_scope(rcx);
+        let fn_sig1 = fn_sig1
+            .replace_bound_vars(|_| rty::ReStatic, |sort, mode| self.fresh_infer_var(sort, mode));
+        for constr in fn_sig1.requires() {
+            match constr {
+                Constraint::Pred(pred) => self.check_pred(rcx, pred),
+                Constraint::Type(..) => {
+                    tracked_span_bug!("unsupported type constraint in function pointer signature");
+                }
+            }
+        }
+        for (ty2, ty1) in iter::zip(&args2, fn_sig1.args()) {
+            self.subtyping(rcx, ty2, ty1)?;
+        }
+        self.pop_scope();
+
+        let output1 = fn_sig1
+            .output()
+            .replace_bound_exprs_with(|sort, _| rcx.define_vars(sort));
+        for constr in &output1.ensures {
+            if let Constraint::Pred(pred) = constr {
+                rcx.assume_pred(pred);
+            }
+        }
+        let ret1 = rcx.unpack(&output1.ret, AssumeInvariants::No);
+
+        let output2 = fn_sig2
+            .output()
+            .replace_bound_exprs_with(|sort, mode| self.fresh_infer_var(sort, mode));
+        self.subtyping(rcx, &ret1, &output2.ret)?;
+        for constr in &output2.ensures {
+            if let Constraint::Pred(pred) = constr {
+                self.check_pred(rcx, pred);
+            }
+        }
+        Ok(())
+    }
+
     fn project_bty(&mut self, self_ty: &Ty, def_id: DefId) -> Result<Ty, CheckerErrKind> {
         let args = vec![GenericArg::Ty(self_ty.clone())];
         let alias_ty = rty::AliasTy::new(def_id, args, List::empty());
@@ -1,6 +1,12 @@
 //! Encoding of the refinement tree into a fixpoint constraint.
 
-use std::{hash::Hash, iter};
+use std::{
+    cell::RefCell,
+    hash::Hash,
+    io::{self, Write},
+    iter,
+    time::{Duration, Instant},
+};
 
 use flux_common::{
     bug,
@@ -10,19 +16,23 @@ use flux_common::{
     span_bug,
 };
 use flux_config as config;
+pub use flux_config::KVarEncoding;
 use flux_fixpoint::FixpointResult;
 // use flux_fixpoint as fixpoint;
 use flux_middle::{
     fhir::FuncKind,
     global_env::GlobalEnv,
     intern::List,
-    queries::QueryResult,
+    queries::QueryErr,
     rty::{self, Constant, ESpan},
+    rustc::mir::BasicBlock,
 };
 use itertools::{self, Itertools};
 use rustc_data_structures::{fx::FxIndexMap, unord::UnordMap};
+use rustc_errors::{DiagnosticBuilder, ErrorGuaranteed, Handler, IntoDiagnostic};
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_index::newtype_index;
+use rustc_infer::traits::util::elaborate;
 use rustc_span::Span;
 use rustc_type_ir::DebruijnIndex;
 
@@ -33,9 +43,17 @@ newtype_index! {
     pub struct TagIdx {}
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct KVarStore {
     kvars: IndexVec<rty::KVid, KVarDecl>,
+    /// Source level names of variables in scope, used to print kvar solutions.
+    names: UnordMap<rty::Name, String>,
+    /// Names of the refinement parameters of the function, which are the only variables that can
+    /// be mentioned in a precondition.
+    refine_params: UnordMap<rty::Name, String>,
+    /// Encoding used for the kvars that would otherwise be encoded as [`KVarEncoding::Conj`],
+    /// see [`CheckerConfig::kvar_encoding`].
+    encoding: KVarEncoding,
 }
 
 #[derive(Clone)]
@@ -43,18 +61,27 @@ struct KVarDecl {
     self_args: usize,
     sorts: Vec<rty::Sort>,
     encoding: KVarEncoding,
+    origin: KVarOrigin,
+    /// Source level name of each (flattened) argument if one is known.
+    arg_names: Vec<Option<String>>,
 }
 
-/// How an [`rty::KVar`] is encoded in the fixpoint constraint
-#[derive(Clone, Copy)]
-pub enum KVarEncoding {
-    /// Generate a single kvar appending the self arguments and the scope, i.e.,
-    /// a kvar `$k(a0, ...)[b0, ...]` becomes `$k(a0, ..., b0, ...)` in the fixpoint constraint.
-    Single,
-    /// Generate a conjunction of kvars, one per argument in [rty::KVar::args].
-    /// Concretely, a kvar `$k(a0, a1, ..., an)[b0, ...]` becomes
-    /// `$k0(a0, a1, ..., an, b0, ...) ∧ $k1(a1, ..., an, b0, ...) ∧ ... ∧ $kn(an, b0, ...)`
-    Conj,
+/// Where a kvar comes from. Used to report solutions.
+#[derive(Clone)]
+pub enum KVarOrigin {
+    /// The kvar stands for the invariant at the entry of a join point. `vars` contains the names
+    /// of the variables bound by the outermost binder, i.e., the refinements of the locals
+    /// generalized at the join point. If `in_ty` is true, the kvar refines a type nested inside
+    /// the type of a local and its innermost binder is named `v`.
+    JoinPoint { bb: BasicBlock, span: Span, vars: Vec<Option<String>>, in_ty: bool },
+    /// The kvar was generated to infer a refinement, e.g., of a generic argument in a call.
+    Infer(Span),
+    /// The kvar stands for a precondition of the function being inferred to suggest it to the
+    /// user, see [`crate::abduction`].
+    Precondition,
+    /// The kvar stands for a refinement in the signature of a function whose signature is being
+    /// inferred, see [`crate::inference`].
+    Signature,
 }
 
 pub mod fixpoint {
@@ -122,6 +149,11 @@ type ConstMap = FxIndexMap<Key, ConstInfo>;
 enum Key {
     Uif(rustc_span::Symbol),
     Const(DefId),
+    /// A const generic parameter indexed by its position in the generics of the item
+    ConstParam(u32),
+    /// An associated refinement of a trait applied to a type parameter (indexed by its position
+    /// in the generics of the item) that cannot be resolved to a definition.
+    Alias(DefId, rustc_span::Symbol, u32),
 }
 
 pub struct FixpointCtxt<'genv, 'tcx, T: Eq + Hash> {
@@ -137,6 +169,9 @@ pub struct FixpointCtxt<'genv, 'tcx, T: Eq + Hash> {
     /// [`DefId`] of the item being checked. This could be a function/method or an adt when checking
     /// invariants.
     def_id: LocalDefId,
+    /// The first associated refinement found during encoding that cannot be resolved, e.g., because
+    /// it's projected from a type without a bound in scope. It's reported instead of checking.
+    unresolved_alias: RefCell<Option<String>>,
 }
 
 struct FixpointKVar {
@@ -194,6 +229,8 @@ impl Env {
 struct ExprCtxt<'a> {
     env: &'a Env,
     const_map: &'a ConstMap,
+    /// Where to record an associated refinement that cannot be resolved. If [`None`], it's a bug.
+    unresolved_alias: Option<&'a RefCell<Option<String>>>,
     /// Used to report bugs
     dbg_span: Span,
 }
@@ -229,7 +266,7 @@ where
     Tag: std::hash::Hash + Eq + Copy,
 {
     pub fn new(genv: &'genv GlobalEnv<'genv, 'tcx>, def_id: LocalDefId, kvars: KVarStore) -> Self {
-        let const_map = fixpoint_const_map(genv);
+        let const_map = fixpoint_const_map(genv, def_id);
         Self {
             comments: vec![],
             kvars,
@@ -241,6 +278,7 @@ where
             tags: IndexVec::new(),
             tags_inv: Default::default(),
             def_id,
+            unresolved_alias: RefCell::default(),
         }
     }
 
@@ -271,18 +309,269 @@ where
         cache: &mut QueryCache,
         constraint: fixpoint::Constraint,
         config: &CheckerConfig,
-    ) -> QueryResult<Vec<Tag>> {
+    ) -> Result<Vec<Tag>, FixpointErr> {
+        self.check_inner(Some(cache), constraint, config)
+    }
+
+    /// Like [`FixpointCtxt::check`] but for auxiliary queries whose results shouldn't be cached
+    /// nor have their kvar solutions dumped.
+    pub fn check_uncached(
+        self,
+        constraint: fixpoint::Constraint,
+        config: &CheckerConfig,
+    ) -> Result<Vec<Tag>, FixpointErr> {
+        self.check_inner(None, constraint, config)
+    }
+
+    fn check_inner(
+        mut self,
+        cache: Option<&mut QueryCache>,
+        constraint: fixpoint::Constraint,
+        config: &CheckerConfig,
+    ) -> Result<Vec<Tag>, FixpointErr> {
+        // The placeholder for an unresolved alias could make the constraint trivial
+        self.check_unresolved_alias()?;
         if !constraint.is_concrete() {
             // skip checking trivial constraints
             return Ok(vec![]);
         }
+        let task = self.build_task(constraint, config)?;
+        if config.dump_constraint() {
+            dbg::dump_item_info(self.genv.tcx, self.def_id, "smt2", &task).unwrap();
+        }
+
+        let task_key = self.genv.tcx.def_path_str(self.def_id);
+        let (timeout, total) = solver_timeout(config);
+
+        let result = match cache {
+            None => task.check_without_cache(timeout),
+            Some(_) if config.dump_kvar_solutions() => {
+                task.check_with_solution(timeout).map(|(result, solution)| {
+                    if let FixpointResult::Safe(_) = result {
+                        self.dump_kvar_solutions(solution).unwrap();
+                    }
+                    result
+                })
+            }
+            Some(cache) => task.check_with_cache(task_key, cache, timeout),
+        };
+
+        match self.solver_result(result, &task, total, config)? {
+            FixpointResult::Unsafe(_, errors) => {
+                Ok(errors
+                    .into_iter()
+                    .map(|err| self.tags[err.tag])
+                    .unique()
+                    .collect_vec())
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Checks `constraint`, which must contain the kvar `kvid` generated for a precondition, and
+    /// returns the solution found for the kvar split into conjuncts. Returns [`None`] if the
+    /// constraint doesn't hold or the solution cannot be parsed.
+    pub(crate) fn solve_precondition(
+        mut self,
+        constraint: fixpoint::Constraint,
+        kvid: rty::KVid,
+        config: &CheckerConfig,
+    ) -> Result<Option<Vec<Conjunct>>, FixpointErr> {
+        let task = self.build_task(constraint, config)?;
+        let (timeout, total) = solver_timeout(config);
+        let (result, solution) = match task.check_with_solution(timeout) {
+            Ok((result, solution)) => (Ok(result), solution),
+            Err(err) => (Err(err), vec![]),
+        };
+        let FixpointResult::Safe(_) = self.solver_result(result, &task, total, config)? else {
+            return Ok(None);
+        };
+        Ok(self.parse_solution(&solution, kvid))
+    }
+
+    /// Checks `constraint` returning the failed obligations and, if there are none, the solutions
+    /// found for the kvars in `kvids` split into conjuncts. Kvars whose solution cannot be parsed
+    /// are missing from the returned map.
+    pub(crate) fn check_with_solutions(
+        mut self,
+        constraint: fixpoint::Constraint,
+        kvids: &[rty::KVid],
+        config: &CheckerConfig,
+    ) -> Result<(Vec<Tag>, KVarSolutions), FixpointErr> {
+        let task = self.build_task(constraint, config)?;
+        if config.dump_constraint() {
+            dbg::dump_item_info(self.genv.tcx, self.def_id, "smt2", &task).unwrap();
+        }
+        let (timeout, total) = solver_timeout(config);
+        let (result, solution) = match task.check_with_solution(timeout) {
+            Ok((result, solution)) => (Ok(result), solution),
+            Err(err) => (Err(err), vec![]),
+        };
+        match self.solver_result(result, &task, total, config)? {
+            FixpointResult::Unsafe(_, errors) => {
+                let errors = errors
+                    .into_iter()
+                    .map(|err| self.tags[err.tag])
+                    .unique()
+                    .collect_vec();
+                Ok((errors, UnordMap::default()))
+            }
+            _ => {
+                let solutions = kvids
+                    .iter()
+                    .filter_map(|kvid| Some((*kvid, self.parse_solution(&solution, *kvid)?)))
+                    .collect();
+                Ok((vec![], solutions))
+            }
+        }
+    }
+
+    /// Parses the solution for the kvar `kvid`, which must be encoded as a single fixpoint kvar,
+    /// splitting it into conjuncts.
+    fn parse_solution(
+        &self,
+        solution: &[flux_fixpoint::KVarBind],
+        kvid: rty::KVid,
+    ) -> Option<Vec<Conjunct>> {
+        let fixpoint_kvid = self.kvid_map.get(&kvid)?[0].to_string();
+        let bind = solution
+            .iter()
+            .find(|bind| bind.kvar.trim_start_matches('$') == fixpoint_kvid)?;
+        let consts: UnordMap<String, fixpoint::GlobalVar> = self
+            .const_map
+            .values()
+            .map(|const_info| (fixpoint::Var::Global(const_info.name).to_string(), const_info.name))
+            .collect();
+        let expr = flux_fixpoint::parse_expr::<ConjunctTypes>(&bind.val, |var| {
+            if let Some(i) = bind.params.iter().position(|param| param == var) {
+                Some(ConjunctVar::Param(i))
+            } else {
+                consts.get(var).copied().map(ConjunctVar::Global)
+            }
+        })
+        .ok()?;
+
+        let names = &self.kvars.get(kvid).arg_names;
+        let mut conjuncts = vec![];
+        split_conjs(expr, &mut conjuncts);
+        let conjuncts = conjuncts
+            .into_iter()
+            .unique_by(|expr| expr.to_string())
+            .map(|expr| {
+                let source: SourceExpr = map_vars(&expr, &|var| {
+                    match var {
+                        ConjunctVar::Param(i) => {
+                            names[*i].clone().unwrap_or_else(|| UNNAMED.to_string())
+                        }
+                        ConjunctVar::Global(name) => {
+                            self.const_map
+                                .values()
+                                .find(|const_info| const_info.name == *name)
+                                .unwrap()
+                                .sym
+                                .to_string()
+                        }
+                    }
+                });
+                let source = if mentions_unnamed(&source) {
+                    None
+                } else if let flux_fixpoint::Expr::BinaryOp(
+                    flux_fixpoint::BinOp::Or
+                    | flux_fixpoint::BinOp::Imp
+                    | flux_fixpoint::BinOp::Iff,
+                    _,
+                ) = source
+                {
+                    // Conjuncts are joined with `&&` which binds tighter
+                    to_flux_syntax(&source).map(|s| format!("({s})"))
+                } else {
+                    to_flux_syntax(&source)
+                };
+                Conjunct { expr, source }
+            })
+            .collect();
+        Some(conjuncts)
+    }
+
+    /// Checks `constraint` replacing the kvar `kvid` generated for a precondition with the
+    /// conjunction of `preconditions`. Returns whether the constraint holds.
+    pub(crate) fn check_precondition(
+        mut self,
+        constraint: fixpoint::Constraint,
+        kvid: rty::KVid,
+        preconditions: &[&Conjunct],
+        config: &CheckerConfig,
+    ) -> Result<bool, FixpointErr> {
+        let constraint = self.replace_precondition(constraint, kvid, preconditions, None);
+        let task = self.build_task(constraint, config)?;
+        let (timeout, total) = solver_timeout(config);
+        let result = self.solver_result(task.check_without_cache(timeout), &task, total, config)?;
+        Ok(matches!(result, FixpointResult::Safe(_)))
+    }
+
+    /// Returns whether the conjunction of `preconditions` is satisfiable in the context where the
+    /// kvar `kvid` generated for a precondition is assumed.
+    pub(crate) fn check_precondition_satisfiable(
+        mut self,
+        constraint: fixpoint::Constraint,
+        kvid: rty::KVid,
+        preconditions: &[&Conjunct],
+        tag: Tag,
+        config: &CheckerConfig,
+    ) -> Result<bool, FixpointErr>
+    where
+        Tag: std::fmt::Debug,
+    {
+        let tag_idx = self.tag_idx(tag);
+        let constraint = self.replace_precondition(constraint, kvid, preconditions, Some(tag_idx));
+        let task = self.build_task(constraint, config)?;
+        let (timeout, total) = solver_timeout(config);
+        match self.solver_result(task.check_without_cache(timeout), &task, total, config)? {
+            FixpointResult::Unsafe(_, errors) => Ok(errors.iter().any(|err| err.tag == tag_idx)),
+            _ => Ok(false),
+        }
+    }
+
+    fn replace_precondition(
+        &self,
+        constraint: fixpoint::Constraint,
+        kvid: rty::KVid,
+        preconditions: &[&Conjunct],
+        head: Option<TagIdx>,
+    ) -> fixpoint::Constraint {
+        let pred = |args: &[fixpoint::Var]| {
+            if preconditions.is_empty() {
+                return fixpoint::Pred::TRUE;
+            }
+            let conjuncts = preconditions
+                .iter()
+                .map(|precondition| {
+                    fixpoint::Pred::Expr(map_vars(&precondition.expr, &|var| {
+                        match var {
+                            ConjunctVar::Param(i) => args[*i],
+                            ConjunctVar::Global(name) => fixpoint::Var::Global(*name),
+                        }
+                    }))
+                })
+                .collect();
+            fixpoint::Pred::And(conjuncts)
+        };
+        replace_kvar_guard(constraint, self.kvid_map[&kvid][0], &pred, head)
+    }
+
+    fn build_task(
+        &mut self,
+        constraint: fixpoint::Constraint,
+        config: &CheckerConfig,
+    ) -> Result<fixpoint::Task, FixpointErr> {
+        self.check_unresolved_alias()?;
         let span = self.def_span();
 
         let kvars = self
             .fixpoint_kvars
-            .into_iter_enumerated()
+            .iter_enumerated()
             .map(|(kvid, kvar)| {
-                fixpoint::KVar::new(kvid, kvar.sorts, format!("orig: {:?}", kvar.orig))
+                fixpoint::KVar::new(kvid, kvar.sorts.clone(), format!("orig: {:?}", kvar.orig))
             })
             .collect_vec();
 
@@ -301,12 +590,12 @@ where
 
         let constants = self
             .const_map
-            .into_values()
+            .values()
             .map(|const_info| {
                 fixpoint::ConstInfo {
                     name: fixpoint::Var::Global(const_info.name),
                     orig: const_info.sym,
-                    sort: const_info.sort,
+                    sort: const_info.sort.clone(),
                 }
             })
             .collect();
@@ -319,33 +608,171 @@ where
             .map(|sort_decl| sort_decl.name.to_string())
             .collect_vec();
 
-        let task = fixpoint::Task::new(
-            self.comments,
+        Ok(fixpoint::Task::new(
+            std::mem::take(&mut self.comments),
             constants,
             kvars,
             closed_constraint,
             qualifiers,
             sorts,
             config.scrape_quals,
-        );
-        if config::dump_constraint() {
-            dbg::dump_item_info(self.genv.tcx, self.def_id, "smt2", &task).unwrap();
+        ))
+    }
+
+    /// Turns failures to run fixpoint into errors. `total` is whether the timeout was given by the
+    /// deadline for the entire crate, see [`solver_timeout`].
+    fn solver_result(
+        &self,
+        result: io::Result<FixpointResult<TagIdx>>,
+        task: &fixpoint::Task,
+        total: bool,
+        config: &CheckerConfig,
+    ) -> Result<FixpointResult<TagIdx>, FixpointErr> {
+        let span = self.def_span();
+        let name = self.genv.tcx.def_path_str(self.def_id);
+        match result {
+            Ok(FixpointResult::Crash(info)) => {
+                Err(FixpointErr::Crash(errors::SolverCrash {
+                    span,
+                    name,
+                    msg: format!("{info:?}"),
+                    path: self.dump_failed_task(task, config),
+                }))
+            }
+            Ok(result) => Ok(result),
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                let (kind, secs) = if total {
+                    ("total", config::solver_total_timeout())
+                } else {
+                    ("item", config.solver_timeout)
+                };
+                Err(FixpointErr::Timeout(errors::SolverTimeout {
+                    span,
+                    name,
+                    kind,
+                    secs: secs.map_or(0, |secs| secs.as_secs()),
+                    path: self.dump_failed_task(task, config),
+                }))
+            }
+            Err(err) => {
+                Err(FixpointErr::Crash(errors::SolverCrash {
+                    span,
+                    name,
+                    msg: format!("failed to run fixpoint: {err}"),
+                    path: self.dump_failed_task(task, config),
+                }))
+            }
         }
+    }
 
-        let task_key = self.genv.tcx.def_path_str(self.def_id);
+    /// Saves a task fixpoint failed to solve such that it can be reproduced and returns the
+    /// path of the file.
+    fn dump_failed_task(&self, task: &fixpoint::Task, config: &CheckerConfig) -> String {
+        // The task is already there if constraints are being dumped
+        if !config.dump_constraint() {
+            let _ = dbg::dump_item_info(self.genv.tcx, self.def_id, "smt2", task);
+        }
+        dbg::item_info_path(self.genv.tcx, self.def_id.to_def_id(), "smt2")
+            .display()
+            .to_string()
+    }
 
-        match task.check_with_cache(task_key, cache) {
-            Ok(FixpointResult::Safe(_)) => Ok(vec![]),
-            Ok(FixpointResult::Unsafe(_, errors)) => {
-                Ok(errors
-                    .into_iter()
-                    .map(|err| self.tags[err.tag])
-                    .unique()
-                    .collect_vec())
+    /// Writes the solutions found by fixpoint for the kvars in this item, mapped back to the
+    /// names of Rust variables. Conjuncts mentioning variables without a source level name are
+    /// omitted, and solutions that fail to parse are written verbatim.
+    fn dump_kvar_solutions(&self, solution: Vec<flux_fixpoint::KVarBind>) -> io::Result<()> {
+        let consts: UnordMap<String, String> = self
+            .const_map
+            .values()
+            .map(|const_info| {
+                (fixpoint::Var::Global(const_info.name).to_string(), const_info.sym.to_string())
+            })
+            .collect();
+
+        // Solutions for the parts of the same kvar are conjoined
+        let mut solved: FxIndexMap<rty::KVid, Vec<Result<SourceExpr, String>>> =
+            FxIndexMap::default();
+        for bind in solution {
+            let Some(kvid) = bind
+                .kvar
+                .trim_start_matches('$')
+                .strip_prefix('k')
+                .and_then(|n| n.parse::<u32>().ok())
+                .map(fixpoint::KVid::from_u32)
+                .filter(|kvid| kvid.index() < self.fixpoint_kvars.len())
+            else {
+                continue;
+            };
+            let orig = self.fixpoint_kvars[kvid].orig;
+            let decl = self.kvars.get(orig);
+            let kvids = &self.kvid_map[&orig];
+            let part = kvids.iter().position(|k| *k == kvid).unwrap();
+            let names = match decl.encoding {
+                _ if decl.sorts.is_empty() => &[][..],
+                KVarEncoding::Single => &decl.arg_names[..],
+                KVarEncoding::Conj => &decl.arg_names[kvids.len() - part - 1..],
+            };
+            let parsed = flux_fixpoint::parse_expr::<SourceTypes>(&bind.val, |var| {
+                if let Some(i) = bind.params.iter().position(|param| param == var) {
+                    Some(
+                        names
+                            .get(i)
+                            .cloned()
+                            .flatten()
+                            .unwrap_or_else(|| UNNAMED.to_string()),
+                    )
+                } else {
+                    consts.get(var).cloned()
+                }
+            });
+            solved
+                .entry(orig)
+                .or_default()
+                .push(parsed.map_err(|_| bind.val));
+        }
+
+        solved.sort_keys();
+        let mut lines = vec![];
+        for (kvid, parts) in solved {
+            let mut conjuncts = vec![];
+            for part in parts {
+                match part {
+                    Ok(expr) => flatten_conjs(expr, &mut conjuncts),
+                    Err(raw) => conjuncts.push(raw),
+                }
+            }
+            let conjuncts = conjuncts
+                .into_iter()
+                .filter(|conj| conj != "true")
+                .unique()
+                .collect_vec();
+            let is_trivial = conjuncts.is_empty();
+            let sol = if is_trivial { "true".to_string() } else { conjuncts.join(" && ") };
+            let source_map = self.genv.tcx.sess.source_map();
+            match &self.kvars.get(kvid).origin {
+                KVarOrigin::JoinPoint { bb, span, in_ty: false, .. } => {
+                    let line = source_map.lookup_char_pos(span.lo()).line;
+                    lines.push(format!("inferred invariant at {bb:?} (line {line}): {sol}"));
+                }
+                KVarOrigin::JoinPoint { bb, span, in_ty: true, .. } if !is_trivial => {
+                    let line = source_map.lookup_char_pos(span.lo()).line;
+                    lines.push(format!("inferred refinement at {bb:?} (line {line}): {sol}"));
+                }
+                KVarOrigin::Infer(span) if !is_trivial => {
+                    let line = source_map.lookup_char_pos(span.lo()).line;
+                    lines.push(format!("inferred refinement at line {line}: {sol}"));
+                }
+                KVarOrigin::JoinPoint { .. }
+                | KVarOrigin::Infer(_)
+                | KVarOrigin::Precondition
+                | KVarOrigin::Signature => {}
             }
-            Ok(FixpointResult::Crash(err)) => span_bug!(span, "fixpoint crash: {err:?}"),
-            Err(err) => span_bug!(span, "failed to run fixpoint: {err:?}"),
         }
+        let mut writer = dbg::writer_for_item(self.genv.tcx, self.def_id.to_def_id(), "sol")?;
+        for line in lines {
+            writeln!(writer, "{line}")?;
+        }
+        Ok(())
     }
 
     pub fn tag_idx(&mut self, tag: Tag) -> TagIdx
@@ -478,8 +905,20 @@ where
         }
     }
 
+    /// Reports the first associated refinement that couldn't be resolved while encoding.
+    fn check_unresolved_alias(&self) -> Result<(), FixpointErr> {
+        if let Some(alias) = self.unresolved_alias.take() {
+            return Err(FixpointErr::UnresolvedAlias(errors::UnresolvedAlias {
+                span: self.def_span(),
+                name: self.genv.tcx.def_path_str(self.def_id),
+                alias,
+            }));
+        }
+        Ok(())
+    }
+
     fn as_expr_cx(&self) -> ExprCtxt<'_> {
-        ExprCtxt::new(&self.env, &self.const_map, self.def_span())
+        ExprCtxt::new(&self.env, &self.const_map, Some(&self.unresolved_alias), self.def_span())
     }
 
     fn def_span(&self) -> Span {
@@ -487,13 +926,302 @@ where
     }
 }
 
+/// Returns the timeout for the next call to fixpoint and whether it is given by the deadline for
+/// the entire crate rather than the timeout for a single item.
+fn solver_timeout(config: &CheckerConfig) -> (Option<Duration>, bool) {
+    let remaining = config
+        .solver_deadline
+        .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+    match (config.solver_timeout, remaining) {
+        (Some(timeout), Some(remaining)) if remaining < timeout => (Some(remaining), true),
+        (None, Some(remaining)) => (Some(remaining), true),
+        (timeout, _) => (timeout, false),
+    }
+}
+
+/// An error checking a constraint with fixpoint
+pub(crate) enum FixpointErr {
+    Query(QueryErr),
+    UnresolvedAlias(errors::UnresolvedAlias),
+    Timeout(errors::SolverTimeout),
+    Crash(errors::SolverCrash),
+}
+
+impl From<QueryErr> for FixpointErr {
+    fn from(err: QueryErr) -> Self {
+        FixpointErr::Query(err)
+    }
+}
+
+impl<'a> IntoDiagnostic<'a> for FixpointErr {
+    fn into_diagnostic(self, handler: &'a Handler) -> DiagnosticBuilder<'a, ErrorGuaranteed> {
+        match self {
+            FixpointErr::Query(err) => err.into_diagnostic(handler),
+            FixpointErr::UnresolvedAlias(err) => err.into_diagnostic(handler),
+            FixpointErr::Timeout(err) => err.into_diagnostic(handler),
+            FixpointErr::Crash(err) => err.into_diagnostic(handler),
+        }
+    }
+}
+
+/// Types used to parse kvar solutions where variables are mapped to source level names.
+struct SourceTypes;
+
+impl flux_fixpoint::Types for SourceTypes {
+    type KVar = String;
+    type Var = String;
+    type Tag = String;
+}
+
+type SourceExpr = flux_fixpoint::Expr<SourceTypes>;
+
+/// Name given to variables without a source level name.
+const UNNAMED: &str = "?";
+
+/// Splits an expression into its top-level conjuncts, dropping the ones mentioning unnamed variables.
+fn flatten_conjs(expr: SourceExpr, conjuncts: &mut Vec<String>) {
+    match expr {
+        flux_fixpoint::Expr::BinaryOp(flux_fixpoint::BinOp::And, box [e1, e2]) => {
+            flatten_conjs(e1, conjuncts);
+            flatten_conjs(e2, conjuncts);
+        }
+        expr if !mentions_unnamed(&expr) => conjuncts.push(expr.to_string()),
+        _ => {}
+    }
+}
+
+fn mentions_unnamed(expr: &SourceExpr) -> bool {
+    use flux_fixpoint::{Expr, Func};
+    match expr {
+        Expr::Var(var) | Expr::App(Func::Var(var), _) if var == UNNAMED => true,
+        Expr::Var(_) | Expr::Constant(_) | Expr::Unit => false,
+        Expr::App(_, args) => args.iter().any(mentions_unnamed),
+        Expr::BinaryOp(_, box [e1, e2]) | Expr::Pair(box [e1, e2]) => {
+            mentions_unnamed(e1) || mentions_unnamed(e2)
+        }
+        Expr::UnaryOp(_, e) | Expr::Proj(e, _) => mentions_unnamed(e),
+        Expr::IfThenElse(box [p, e1, e2]) => {
+            mentions_unnamed(p) || mentions_unnamed(e1) || mentions_unnamed(e2)
+        }
+    }
+}
+
+/// Prints an expression in the syntax of flux specifications or returns [`None`] if the expression
+/// cannot be written in it. Nested binary operations are always parenthesized.
+fn to_flux_syntax(expr: &SourceExpr) -> Option<String> {
+    use flux_fixpoint::{BinOp, Expr, Func, UnOp};
+    fn nested(expr: &SourceExpr) -> Option<String> {
+        let s = to_flux_syntax(expr)?;
+        if matches!(expr, Expr::BinaryOp(..) | Expr::IfThenElse(..)) {
+            Some(format!("({s})"))
+        } else {
+            Some(s)
+        }
+    }
+    let s = match expr {
+        Expr::Var(var) => var.clone(),
+        Expr::Constant(Constant::Int(n)) => format!("{n}"),
+        Expr::Constant(Constant::Bool(b)) => format!("{b}"),
+        Expr::BinaryOp(op, box [e1, e2]) => {
+            let op = match op {
+                BinOp::Iff => "<=>",
+                BinOp::Imp => "=>",
+                BinOp::Or => "||",
+                BinOp::And => "&&",
+                BinOp::Eq => "==",
+                BinOp::Ne => "!=",
+                BinOp::Gt => ">",
+                BinOp::Ge => ">=",
+                BinOp::Lt => "<",
+                BinOp::Le => "<=",
+                BinOp::Add => "+",
+                BinOp::Sub => "-",
+                BinOp::Mul => "*",
+                BinOp::Div => "/",
+                BinOp::Mod => "%",
+            };
+            format!("{} {op} {}", nested(e1)?, nested(e2)?)
+        }
+        Expr::UnaryOp(op, e) => {
+            let op = match op {
+                UnOp::Not => "!",
+                UnOp::Neg => "-",
+            };
+            match e.as_ref() {
+                Expr::Var(_) | Expr::Constant(_) => format!("{op}{}", to_flux_syntax(e)?),
+                _ => format!("{op}({})", to_flux_syntax(e)?),
+            }
+        }
+        Expr::App(Func::Var(func), args) => {
+            let args: Option<Vec<_>> = args.iter().map(to_flux_syntax).collect();
+            format!("{func}({})", args?.join(", "))
+        }
+        Expr::IfThenElse(box [p, e1, e2]) => {
+            format!(
+                "if {} {{ {} }} else {{ {} }}",
+                to_flux_syntax(p)?,
+                to_flux_syntax(e1)?,
+                to_flux_syntax(e2)?
+            )
+        }
+        Expr::Constant(Constant::Real(_))
+        | Expr::App(Func::Itf(_), _)
+        | Expr::Pair(_)
+        | Expr::Proj(..)
+        | Expr::Unit => return None,
+    };
+    Some(s)
+}
+
+/// The solutions found for some kvars split into conjuncts.
+pub(crate) type KVarSolutions = UnordMap<rty::KVid, Vec<Conjunct>>;
+
+/// A conjunct in the solution found by fixpoint for a kvar, see [`FixpointCtxt::solve_precondition`]
+/// and [`FixpointCtxt::check_with_solutions`].
+pub(crate) struct Conjunct {
+    expr: flux_fixpoint::Expr<ConjunctTypes>,
+    /// The conjunct written with source level names or [`None`] if it mentions variables without a name.
+    pub(crate) source: Option<String>,
+}
+
+/// Types used to parse the solution of a kvar, where variables are either parameters
+/// of the kvar or global constants.
+struct ConjunctTypes;
+
+impl flux_fixpoint::Types for ConjunctTypes {
+    type KVar = String;
+    type Var = ConjunctVar;
+    type Tag = String;
+}
+
+#[derive(Hash, Clone, Copy)]
+enum ConjunctVar {
+    /// The parameter of the kvar at the given position
+    Param(usize),
+    Global(fixpoint::GlobalVar),
+}
+
+impl std::fmt::Display for ConjunctVar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConjunctVar::Param(i) => write!(f, "${i}"),
+            ConjunctVar::Global(var) => write!(f, "{}", fixpoint::Var::Global(*var)),
+        }
+    }
+}
+
+/// Splits an expression into its top-level conjuncts, dropping the trivial ones.
+fn split_conjs<T: flux_fixpoint::Types>(
+    expr: flux_fixpoint::Expr<T>,
+    conjuncts: &mut Vec<flux_fixpoint::Expr<T>>,
+) {
+    use flux_fixpoint::{BinOp, Expr};
+    match expr {
+        Expr::BinaryOp(BinOp::And, box [e1, e2]) => {
+            split_conjs(e1, conjuncts);
+            split_conjs(e2, conjuncts);
+        }
+        Expr::Constant(Constant::Bool(true)) => {}
+        expr => conjuncts.push(expr),
+    }
+}
+
+/// Renames the variables in an expression according to `f`.
+fn map_vars<A: flux_fixpoint::Types, B: flux_fixpoint::Types>(
+    expr: &flux_fixpoint::Expr<A>,
+    f: &impl Fn(&A::Var) -> B::Var,
+) -> flux_fixpoint::Expr<B> {
+    use flux_fixpoint::{Expr, Func};
+    match expr {
+        Expr::Var(var) => Expr::Var(f(var)),
+        Expr::Constant(c) => Expr::Constant(*c),
+        Expr::BinaryOp(op, box [e1, e2]) => {
+            Expr::BinaryOp(*op, Box::new([map_vars(e1, f), map_vars(e2, f)]))
+        }
+        Expr::App(func, args) => {
+            let func = match func {
+                Func::Var(var) => Func::Var(f(var)),
+                Func::Itf(sym) => Func::Itf(*sym),
+            };
+            Expr::App(func, args.iter().map(|arg| map_vars(arg, f)).collect())
+        }
+        Expr::UnaryOp(op, e) => Expr::UnaryOp(*op, Box::new(map_vars(e, f))),
+        Expr::Pair(box [e1, e2]) => Expr::Pair(Box::new([map_vars(e1, f), map_vars(e2, f)])),
+        Expr::Proj(e, proj) => Expr::Proj(Box::new(map_vars(e, f)), *proj),
+        Expr::IfThenElse(box [p, e1, e2]) => {
+            Expr::IfThenElse(Box::new([map_vars(p, f), map_vars(e1, f), map_vars(e2, f)]))
+        }
+        Expr::Unit => Expr::Unit,
+    }
+}
+
+/// Replaces the applications of `kvid` in the guards of `constraint` with the predicate returned
+/// by `pred` for the arguments of the application. If `head` is given, the constraint under such
+/// guards is replaced by a check of `false` tagged with `head`.
+fn replace_kvar_guard(
+    constraint: fixpoint::Constraint,
+    kvid: fixpoint::KVid,
+    pred: &impl Fn(&[fixpoint::Var]) -> fixpoint::Pred,
+    head: Option<TagIdx>,
+) -> fixpoint::Constraint {
+    fn replace_in_pred(
+        p: fixpoint::Pred,
+        kvid: fixpoint::KVid,
+        pred: &impl Fn(&[fixpoint::Var]) -> fixpoint::Pred,
+        found: &mut bool,
+    ) -> fixpoint::Pred {
+        match p {
+            fixpoint::Pred::And(preds) => {
+                fixpoint::Pred::And(
+                    preds
+                        .into_iter()
+                        .map(|p| replace_in_pred(p, kvid, pred, found))
+                        .collect(),
+                )
+            }
+            fixpoint::Pred::KVar(k, args) if k == kvid => {
+                *found = true;
+                pred(&args)
+            }
+            p => p,
+        }
+    }
+    match constraint {
+        fixpoint::Constraint::Pred(..) => constraint,
+        fixpoint::Constraint::Conj(constraints) => {
+            fixpoint::Constraint::Conj(
+                constraints
+                    .into_iter()
+                    .map(|c| replace_kvar_guard(c, kvid, pred, head))
+                    .collect(),
+            )
+        }
+        fixpoint::Constraint::Guard(p, body) => {
+            let mut found = false;
+            let p = replace_in_pred(p, kvid, pred, &mut found);
+            let body = match head {
+                Some(tag) if found => {
+                    let ff = fixpoint::Expr::Constant(Constant::Bool(false));
+                    fixpoint::Constraint::Pred(fixpoint::Pred::Expr(ff), Some(tag))
+                }
+                _ => replace_kvar_guard(*body, kvid, pred, head),
+            };
+            fixpoint::Constraint::Guard(p, Box::new(body))
+        }
+        fixpoint::Constraint::ForAll(var, sort, p, body) => {
+            let body = replace_kvar_guard(*body, kvid, pred, head);
+            fixpoint::Constraint::ForAll(var, sort, p, Box::new(body))
+        }
+    }
+}
+
 impl FixpointKVar {
     fn new(sorts: Vec<fixpoint::Sort>, orig: rty::KVid) -> Self {
         Self { sorts, orig }
     }
 }
 
-fn fixpoint_const_map(genv: &GlobalEnv) -> ConstMap {
+fn fixpoint_const_map(genv: &GlobalEnv, def_id: LocalDefId) -> ConstMap {
     let const_name_gen = IndexGen::new();
     let consts = genv
         .map()
@@ -528,12 +1256,75 @@ fn fixpoint_const_map(genv: &GlobalEnv) -> ConstMap {
                 _ => None,
             }
         });
-    itertools::chain(consts, uifs).collect()
+    let mut const_params = vec![];
+    let mut generics = genv.tcx.generics_of(def_id);
+    loop {
+        for param in &generics.params {
+            if let rustc_middle::ty::GenericParamDefKind::Const { .. } = param.kind {
+                let cinfo = ConstInfo {
+                    name: const_name_gen.fresh(),
+                    sym: param.name,
+                    sort: fixpoint::Sort::Int,
+                    val: None,
+                };
+                const_params.push((Key::ConstParam(param.index), cinfo));
+            }
+        }
+        let Some(parent) = generics.parent else { break };
+        generics = genv.tcx.generics_of(parent);
+    }
+    let mut alias_refts = vec![];
+    let predicates = genv
+        .tcx
+        .predicates_of(def_id)
+        .instantiate_identity(genv.tcx);
+    // The bounds are elaborated because a function called with a type parameter bounded by a
+    // subtrait can mention the associated refinements of the supertrait in its signature.
+    for clause in elaborate(genv.tcx, predicates.predicates) {
+        let Some(trait_pred) = clause.as_trait_clause() else { continue };
+        let trait_pred = trait_pred.skip_binder();
+        let rustc_middle::ty::Param(param) = trait_pred.self_ty().kind() else { continue };
+        let Some(trait_id) = trait_pred.def_id().as_local() else { continue };
+        for reft in genv.map().assoc_refinements_of(trait_id) {
+            let Some(fsort) = genv.sort_of_assoc_reft(trait_id.to_def_id(), reft.name) else {
+                continue;
+            };
+            let sort = if fsort.inputs().is_empty() {
+                sort_to_fixpoint(fsort.output())
+            } else {
+                fixpoint::Sort::Func(fixpoint::PolyFuncSort::new(
+                    0,
+                    fsort.inputs().iter().map(sort_to_fixpoint),
+                    sort_to_fixpoint(fsort.output()),
+                ))
+            };
+            let cinfo = ConstInfo { name: const_name_gen.fresh(), sym: reft.name, sort, val: None };
+            alias_refts.push((Key::Alias(trait_id.to_def_id(), reft.name, param.index), cinfo));
+        }
+    }
+    itertools::chain!(consts, uifs, const_params, alias_refts).collect()
 }
 
 impl KVarStore {
-    pub fn new() -> Self {
-        Self { kvars: IndexVec::new() }
+    pub fn new(encoding: KVarEncoding) -> Self {
+        Self {
+            kvars: IndexVec::new(),
+            names: UnordMap::default(),
+            refine_params: UnordMap::default(),
+            encoding,
+        }
+    }
+
+    /// Records the source level name of a variable. The first name recorded for a variable wins.
+    pub(crate) fn name_var(&mut self, name: rty::Name, source_name: String) {
+        self.names.entry(name).or_insert(source_name);
+    }
+
+    /// Records the name of a refinement parameter of the function, overriding any name previously
+    /// recorded for the variable. Parameters are preferred because they can be mentioned in specs.
+    pub(crate) fn name_refine_param(&mut self, name: rty::Name, source_name: String) {
+        self.names.insert(name, source_name.clone());
+        self.refine_params.insert(name, source_name);
     }
 
     fn get(&self, kvid: rty::KVid) -> &KVarDecl {
@@ -554,35 +1345,82 @@ impl KVarStore {
         binders: &[List<rty::Sort>],
         scope: &Scope,
         encoding: KVarEncoding,
+        origin: KVarOrigin,
     ) -> rty::Expr {
+        let encoding = match encoding {
+            KVarEncoding::Conj => self.encoding,
+            KVarEncoding::Single => KVarEncoding::Single,
+        };
         if binders.is_empty() {
-            return self.fresh_inner(0, [], encoding);
+            return self.fresh_inner(0, [], encoding, origin);
         }
+        let outermost = binders.len() - 1;
         let args = itertools::chain(
-            binders.iter().rev().enumerate().flat_map(|(level, sorts)| {
-                let debruijn = DebruijnIndex::from_usize(level);
-                sorts
-                    .iter()
-                    .cloned()
-                    .enumerate()
-                    .map(move |(idx, sort)| (rty::Var::LateBound(debruijn, idx as u32), sort))
-            }),
+            binders
+                .iter()
+                .rev()
+                .enumerate()
+                .flat_map(|(level, sorts)| {
+                    let debruijn = DebruijnIndex::from_usize(level);
+                    let origin = &origin;
+                    sorts.iter().cloned().enumerate().map(move |(idx, sort)| {
+                        let name = match origin {
+                            KVarOrigin::JoinPoint { vars, .. } if level == outermost => {
+                                vars.get(idx).cloned().flatten()
+                            }
+                            _ if sorts.len() == 1 => Some(format!("v{}", "'".repeat(level))),
+                            _ => Some(format!("v{}.{idx}", "'".repeat(level))),
+                        };
+                        (rty::Var::LateBound(debruijn, idx as u32), sort, name)
+                    })
+                })
+                .collect_vec(),
             scope
                 .iter()
-                .map(|(name, sort)| (rty::Var::Free(name), sort)),
-        );
-        self.fresh_inner(binders.last().unwrap().len(), args, encoding)
+                .map(|(name, sort)| (rty::Var::Free(name), sort, self.names.get(&name).cloned())),
+        )
+        .collect_vec();
+        self.fresh_inner(binders.last().unwrap().len(), args, encoding, origin)
+    }
+
+    /// Generates a fresh kvar over all the variables in `scope` standing for a precondition of the
+    /// function, see [`KVarOrigin::Precondition`]. Only refinement parameters are given a name.
+    pub(crate) fn fresh_precondition(&mut self, scope: &Scope) -> rty::Expr {
+        let args = scope
+            .iter()
+            .map(|(name, sort)| {
+                (rty::Var::Free(name), sort, self.refine_params.get(&name).cloned())
+            })
+            .collect_vec();
+        self.fresh_inner(0, args, KVarEncoding::Single, KVarOrigin::Precondition)
+    }
+
+    /// Generates a fresh kvar standing for a refinement in a signature template, see
+    /// [`KVarOrigin::Signature`]. The first `self_args` elements of `args` are the self arguments.
+    pub(crate) fn fresh_signature(
+        &mut self,
+        self_args: usize,
+        args: impl IntoIterator<Item = (rty::Var, rty::Sort, Option<String>)>,
+    ) -> rty::Expr {
+        self.fresh_inner(self_args, args, KVarEncoding::Single, KVarOrigin::Signature)
     }
 
-    fn fresh_inner<A>(&mut self, self_args: usize, args: A, encoding: KVarEncoding) -> rty::Expr
+    fn fresh_inner<A>(
+        &mut self,
+        self_args: usize,
+        args: A,
+        encoding: KVarEncoding,
+        origin: KVarOrigin,
+    ) -> rty::Expr
     where
-        A: IntoIterator<Item = (rty::Var, rty::Sort)>,
+        A: IntoIterator<Item = (rty::Var, rty::Sort, Option<String>)>,
     {
         let mut sorts = vec![];
         let mut exprs = vec![];
+        let mut arg_names = vec![];
 
         let mut flattened_self_args = 0;
-        for (i, (var, sort)) in args.into_iter().enumerate() {
+        for (i, (var, sort, name)) in args.into_iter().enumerate() {
             let is_self_arg = i < self_args;
             let var = var.to_expr();
             sort.walk(|sort, proj| {
@@ -590,13 +1428,21 @@ impl KVarStore {
                     flattened_self_args += is_self_arg as usize;
                     sorts.push(sort.clone());
                     exprs.push(rty::Expr::tuple_projs(&var, proj));
+                    arg_names.push(
+                        name.as_ref()
+                            .map(|name| proj.iter().fold(name.clone(), |s, i| format!("{s}.{i}"))),
+                    );
                 }
             });
         }
 
-        let kvid = self
-            .kvars
-            .push(KVarDecl { self_args: flattened_self_args, sorts, encoding });
+        let kvid = self.kvars.push(KVarDecl {
+            self_args: flattened_self_args,
+            sorts,
+            encoding,
+            origin,
+            arg_names,
+        });
 
         let kvar = rty::KVar::new(kvid, flattened_self_args, exprs);
         rty::Expr::kvar(kvar)
@@ -669,8 +1515,13 @@ fn func_sort_to_fixpoint(fsort: &rty::PolyFuncSort) -> fixpoint::PolyFuncSort {
 }
 
 impl<'a> ExprCtxt<'a> {
-    fn new(env: &'a Env, const_map: &'a ConstMap, dbg_span: Span) -> Self {
-        Self { env, const_map, dbg_span }
+    fn new(
+        env: &'a Env,
+        const_map: &'a ConstMap,
+        unresolved_alias: Option<&'a RefCell<Option<String>>>,
+        dbg_span: Span,
+    ) -> Self {
+        Self { env, const_map, unresolved_alias, dbg_span }
     }
 
     fn expr_to_fixpoint(&self, expr: &rty::Expr) -> fixpoint::Expr {
@@ -700,11 +1551,43 @@ impl<'a> ExprCtxt<'a> {
                 });
                 fixpoint::Expr::Var(const_info.name.into())
             }
+            rty::ExprKind::ConstParam(param) => {
+                let const_info = self
+                    .const_map
+                    .get(&Key::ConstParam(param.index))
+                    .unwrap_or_else(|| {
+                        span_bug!(self.dbg_span, "no entry found in const_map for `{param:?}`")
+                    });
+                fixpoint::Expr::Var(const_info.name.into())
+            }
             rty::ExprKind::App(func, args) => {
                 let func = self.func_to_fixpoint(func);
                 let args = self.exprs_to_fixpoint(args);
                 fixpoint::Expr::App(func, args)
             }
+            rty::ExprKind::Alias(alias, args) => {
+                let Some(const_info) =
+                    alias_self_param(alias.args[0].expect_type()).and_then(|param| {
+                        self.const_map
+                            .get(&Key::Alias(alias.trait_id, alias.name, param.index))
+                    })
+                else {
+                    let Some(unresolved_alias) = self.unresolved_alias else {
+                        span_bug!(self.dbg_span, "unresolved associated refinement: `{expr:?}`")
+                    };
+                    // The constraint is not checked, so any expression works as a placeholder
+                    unresolved_alias
+                        .borrow_mut()
+                        .get_or_insert_with(|| format!("{alias:?}"));
+                    return fixpoint::Expr::Constant(Constant::Bool(true));
+                };
+                if args.is_empty() {
+                    fixpoint::Expr::Var(const_info.name.into())
+                } else {
+                    let func = fixpoint::Func::Var(const_info.name.into());
+                    fixpoint::Expr::App(func, self.exprs_to_fixpoint(args))
+                }
+            }
             rty::ExprKind::IfThenElse(p, e1, e2) => {
                 fixpoint::Expr::IfThenElse(Box::new([
                     self.expr_to_fixpoint(p),
@@ -786,6 +1669,18 @@ impl<'a> ExprCtxt<'a> {
     }
 }
 
+/// The type parameter an unresolved associated refinement is projected from
+fn alias_self_param(ty: &rty::Ty) -> Option<rty::ParamTy> {
+    match ty.kind() {
+        rty::TyKind::Param(param) | rty::TyKind::Indexed(rty::BaseTy::Param(param), _) => {
+            Some(*param)
+        }
+        rty::TyKind::Exists(ty) => alias_self_param(ty.as_ref().skip_binder()),
+        rty::TyKind::Constr(_, ty) => alias_self_param(ty),
+        _ => None,
+    }
+}
+
 fn qualifier_to_fixpoint(
     dbg_span: Span,
     const_map: &ConstMap,
@@ -799,7 +1694,7 @@ fn qualifier_to_fixpoint(
             .map(|(name, var)| ((*name).into(), sort_to_fixpoint(var.expect_sort())))
             .collect();
 
-    let cx = ExprCtxt::new(&env, const_map, dbg_span);
+    let cx = ExprCtxt::new(&env, const_map, None, dbg_span);
     let body = cx.expr_to_fixpoint(qualifier.body.as_ref().skip_binder());
 
     let name = qualifier.name.to_string();
@@ -807,3 +1702,94 @@ fn qualifier_to_fixpoint(
 
     fixpoint::Qualifier { name, args, body, global }
 }
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_span::Span;
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_solver_timeout, code = "FLUX")]
+    #[note]
+    #[note(refineck_solver_task_path)]
+    pub struct SolverTimeout {
+        #[primary_span]
+        pub span: Span,
+        pub name: String,
+        pub kind: &'static str,
+        pub secs: u64,
+        pub path: String,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_unresolved_alias, code = "FLUX")]
+    #[note]
+    pub struct UnresolvedAlias {
+        #[primary_span]
+        pub span: Span,
+        pub name: String,
+        pub alias: String,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_solver_crash, code = "FLUX")]
+    #[note]
+    #[note(refineck_solver_task_path)]
+    pub struct SolverCrash {
+        #[primary_span]
+        pub span: Span,
+        pub name: String,
+        pub msg: String,
+        pub path: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(solver_timeout: Option<u64>, remaining: Option<u64>) -> CheckerConfig {
+        CheckerConfig {
+            check_overflow: false,
+            scrape_quals: false,
+            skip_unsupported: false,
+            check_vacuity: false,
+            suggest_preconditions: false,
+            infer_signatures: false,
+            check_raw_pointers: false,
+            solver_deadline: remaining.map(|secs| Instant::now() + Duration::from_secs(secs)),
+            solver_timeout: solver_timeout.map(Duration::from_secs),
+            kvar_encoding: KVarEncoding::default(),
+            verbose: false,
+        }
+    }
+
+    /// Returns the timeout in whole seconds, rounding up the time remaining until the deadline
+    fn timeout(config: &CheckerConfig) -> (Option<u64>, bool) {
+        let (timeout, total) = solver_timeout(config);
+        (timeout.map(|timeout| timeout.as_secs_f64().ceil() as u64), total)
+    }
+
+    #[test]
+    fn no_timeout() {
+        assert_eq!(timeout(&config(None, None)), (None, false));
+    }
+
+    #[test]
+    fn item_timeout() {
+        assert_eq!(timeout(&config(Some(10), None)), (Some(10), false));
+        assert_eq!(timeout(&config(Some(10), Some(60))), (Some(10), false));
+    }
+
+    #[test]
+    fn total_timeout() {
+        assert_eq!(timeout(&config(None, Some(60))), (Some(60), true));
+        assert_eq!(timeout(&config(Some(60), Some(10))), (Some(10), true));
+    }
+
+    #[test]
+    fn total_timeout_exceeded() {
+        let mut config = config(Some(10), None);
+        config.solver_deadline = Some(Instant::now() - Duration::from_secs(1));
+        assert_eq!(solver_timeout(&config), (Some(Duration::ZERO), true));
+    }
+}
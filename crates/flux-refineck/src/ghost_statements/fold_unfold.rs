@@ -8,8 +8,8 @@ use flux_middle::{
     queries::QueryResult,
     rustc::{
         mir::{
-            BasicBlock, Body, FieldIdx, Local, LocalKind, Location, Operand, Place, PlaceElem,
-            Rvalue, Statement, StatementKind, Terminator, TerminatorKind, VariantIdx,
+            BasicBlock, Body, CallKind, FieldIdx, Local, LocalKind, Location, Operand, Place,
+            PlaceElem, Rvalue, Statement, StatementKind, Terminator, TerminatorKind, VariantIdx,
             FIRST_VARIANT,
         },
         ty::{AdtDef, GenericArgs, Ty, TyKind},
@@ -268,7 +268,10 @@ impl<'a, 'tcx, M: Mode> FoldUnfoldAnalysis<'a, 'tcx, M> {
             TerminatorKind::Return => {
                 M::ret(self, bb, env);
             }
-            TerminatorKind::Call { args, destination, target, .. } => {
+            TerminatorKind::Call { kind, args, destination, target, .. } => {
+                if let CallKind::FnPtr(func) = kind {
+                    self.operand(func, &mut env)?;
+                }
                 for arg in args {
                     self.operand(arg, &mut env)?;
                 }
@@ -416,6 +419,9 @@ impl Env {
         for elem in &place.projection {
             let (n, u) = match *elem {
                 PlaceElem::Deref => node.deref(),
+                // Unions are never unfolded. Accesses to their fields are checked directly
+                // against the active field.
+                PlaceElem::Field(_) if node.is_union() => break,
                 PlaceElem::Field(f) => node.field(genv, f)?,
                 PlaceElem::Downcast(_, idx) => node.downcast(genv, idx)?,
                 PlaceElem::Index(_) => break,
@@ -484,6 +490,16 @@ impl PlaceNode {
         }
     }
 
+    fn is_union(&self) -> bool {
+        if let PlaceNode::Ty(ty) = self
+            && let TyKind::Adt(adt_def, _) = ty.kind()
+        {
+            adt_def.is_union()
+        } else {
+            false
+        }
+    }
+
     fn field(&mut self, genv: &GlobalEnv, f: FieldIdx) -> QueryResult<(&mut PlaceNode, bool)> {
         let (fields, unfolded) = self.fields(genv)?;
         Ok((&mut fields[f.as_usize()], unfolded))
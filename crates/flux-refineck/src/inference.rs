@@ -0,0 +1,592 @@
+//! Inference of signatures for private functions without a `#[flux::sig]`, enabled with
+//! [`CheckerConfig::infer_signatures`].
+//!
+//! The signature of an unannotated function is lifted from its Rust signature, so its callers
+//! learn nothing about its result and its body checks nothing. Instead, for private functions that
+//! are only called directly from code we check, we use a template where integer and boolean
+//! arguments are indexed by fresh refinement parameters, the precondition is a kvar over them and
+//! the return value is refined by another kvar. The function is checked together with all its
+//! callers in a single fixpoint query such that the solution for the kvars is a signature
+//! satisfied by every call and by the body of the function. Functions related through calls
+//! (including recursive ones) are checked in the same query. The solution is reported to the user
+//! as a `#[flux::sig]` that could be added to the function.
+use std::iter;
+
+use flux_errors::ErrorGuaranteed;
+use flux_middle::{
+    global_env::GlobalEnv,
+    intern::List,
+    rty::{
+        self, BaseTy, Binder, BoundVariableKind, Constraint, EarlyBinder, Expr, FnOutput, FnSig,
+        PolyFnSig, Ty, TyKind, INNERMOST,
+    },
+};
+use itertools::Itertools;
+use rustc_data_structures::fx::FxIndexSet;
+use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hir::{
+    self as hir,
+    def::{DefKind, Res},
+    def_id::{DefId, LocalDefId},
+    intravisit::{self, Visitor},
+};
+use rustc_middle::ty::{TyCtxt, TypeckResults};
+use rustc_span::symbol::kw;
+
+use crate::{
+    checker::{Checker, SigTemplates},
+    fixpoint_encoding::{fixpoint, FixpointCtxt, KVarSolutions, KVarStore},
+    generate_refine_tree,
+    ghost_statements::compute_ghost_statements,
+    refine_tree::RefineTree,
+    report_errors, CheckOutcome, CheckerConfig,
+};
+
+/// The template used to infer the signature of a function.
+struct Template {
+    sig: EarlyBinder<PolyFnSig>,
+    /// The names of the arguments indexed by a refinement parameter in the template, [`None`] for
+    /// the ones left unrefined.
+    params: Vec<Option<String>>,
+    /// The kvar standing for the precondition, if some argument is refined.
+    requires: Option<rty::KVid>,
+    /// The kvar refining the return value and the name of the value in it, if the return type is
+    /// refined.
+    ret: Option<(rty::KVid, String)>,
+}
+
+/// Infers signatures for the private functions in `def_ids` without a `#[flux::sig]` and checks
+/// them together with their callers, which must also be in `def_ids` (the functions that will be
+/// checked). Returns the outcome for every function checked this way, which must not be checked
+/// again with [`crate::check_fn`]. The functions without an outcome must be checked with
+/// [`crate::check_fn`] as usual.
+pub fn infer_fn_sigs(
+    genv: &GlobalEnv,
+    def_ids: &[LocalDefId],
+    config: CheckerConfig,
+) -> Vec<(LocalDefId, Result<CheckOutcome, ErrorGuaranteed>)> {
+    let tcx = genv.tcx;
+    let uses = Uses::collect(tcx);
+    let checked: FxHashSet<LocalDefId> = def_ids
+        .iter()
+        .copied()
+        .filter(|def_id| !genv.map().is_trusted(*def_id))
+        .collect();
+
+    let mut kvars = KVarStore::new(config.kvar_encoding);
+    let mut templates = FxHashMap::default();
+    let mut callers = FxHashMap::default();
+    for &def_id in def_ids {
+        let Some(fn_callers) = uses.callers.get(&def_id.to_def_id()) else { continue };
+        if !checked.contains(&def_id)
+            || !is_candidate(genv, def_id)
+            || uses.escaping.contains(&def_id.to_def_id())
+            || fn_callers.iter().all(|caller| *caller == def_id)
+            || fn_callers
+                .iter()
+                .any(|caller| !checked.contains(caller) || has_const_params(tcx, *caller))
+        {
+            continue;
+        }
+        if let Some(template) = template(genv, def_id, &mut kvars) {
+            templates.insert(def_id, template);
+            callers.insert(def_id, fn_callers);
+        }
+    }
+
+    // Functions whose constraint cannot be generated, e.g., because they use unsupported features,
+    // are checked on their own and the functions they call keep their lifted signature.
+    let sig_templates = to_sig_templates(&templates);
+    let failing: FxHashSet<LocalDefId> = callers
+        .iter()
+        .flat_map(|(callee, fn_callers)| iter::once(callee).chain(fn_callers.iter()))
+        .unique()
+        .filter(|def_id| !can_generate(genv, **def_id, &sig_templates, config))
+        .copied()
+        .collect();
+    callers.retain(|callee, fn_callers| {
+        !failing.contains(callee) && fn_callers.iter().all(|caller| !failing.contains(caller))
+    });
+    templates.retain(|def_id, _| callers.contains_key(def_id));
+    let sig_templates = to_sig_templates(&templates);
+
+    let mut outcomes = vec![];
+    for group in groups(def_ids, &callers) {
+        let mut trees = vec![];
+        let mut generated = vec![];
+        for &def_id in &group {
+            match generate_refine_tree(genv, def_id, kvars.clone(), &sig_templates, config) {
+                Ok(Ok((mut refine_tree, group_kvars))) => {
+                    refine_tree.simplify();
+                    trees.push(refine_tree);
+                    generated.push(def_id);
+                    kvars = group_kvars;
+                }
+                Ok(Err(outcome)) => outcomes.push((def_id, Ok(outcome))),
+                Err(err) => outcomes.push((def_id, Err(err))),
+            }
+        }
+        // If the constraint of some function is missing, the templates in the group could be
+        // solved vacuously, e.g., a return kvar without a constraint from the body. The functions
+        // we did generate are left out such that they are checked again with `check_fn`, which
+        // uses the lifted signatures of their callees.
+        if generated.len() != group.len() {
+            continue;
+        }
+        let group_templates = group
+            .iter()
+            .filter_map(|def_id| Some((*def_id, templates.get(def_id)?)))
+            .collect_vec();
+        outcomes.extend(check_group(genv, &generated, trees, &kvars, &group_templates, &config));
+    }
+    outcomes
+}
+
+/// Checks the refinement trees of the functions in `generated` in a single fixpoint query,
+/// reporting the signatures inferred for the functions in `templates` if they all verify.
+fn check_group(
+    genv: &GlobalEnv,
+    generated: &[LocalDefId],
+    trees: Vec<RefineTree>,
+    kvars: &KVarStore,
+    templates: &[(LocalDefId, &Template)],
+    config: &CheckerConfig,
+) -> Vec<(LocalDefId, Result<CheckOutcome, ErrorGuaranteed>)> {
+    // Qualifiers specific to a function are taken from the first one in the group
+    let mut fcx = FixpointCtxt::new(genv, generated[0], kvars.clone());
+    let constraint = fixpoint::Constraint::Conj(
+        trees
+            .into_iter()
+            .map(|tree| tree.into_fixpoint(&mut fcx))
+            .collect(),
+    );
+    let kvids = templates
+        .iter()
+        .flat_map(|(_, template)| {
+            itertools::chain(template.requires, template.ret.as_ref().map(|(kvid, _)| *kvid))
+        })
+        .collect_vec();
+
+    let start = std::time::Instant::now();
+    let (errors, solutions) = match fcx.check_with_solutions(constraint, &kvids, config) {
+        Ok(result) => result,
+        Err(err) => {
+            let err = genv.sess.emit_err(err);
+            return generated.iter().map(|def_id| (*def_id, Err(err))).collect();
+        }
+    };
+    let fixpoint_time = start.elapsed();
+    let checked = |i: usize| {
+        let fixpoint_time = if i == 0 { fixpoint_time } else { Default::default() };
+        Ok(CheckOutcome::Checked { fixpoint_time })
+    };
+
+    if let Err(err) = report_errors(genv, errors.clone()) {
+        let hir = genv.tcx.hir();
+        let body_spans = generated
+            .iter()
+            .map(|def_id| hir.span_with_body(hir.local_def_id_to_hir_id(*def_id)))
+            .collect_vec();
+        let mut failed = FxHashSet::default();
+        for error in &errors {
+            match body_spans
+                .iter()
+                .position(|span| span.contains(error.src_span))
+            {
+                Some(i) => failed.insert(i),
+                // If we cannot tell where the error is, we blame all the functions
+                None => return generated.iter().map(|def_id| (*def_id, Err(err))).collect(),
+            };
+        }
+        return generated
+            .iter()
+            .enumerate()
+            .map(|(i, def_id)| (*def_id, if failed.contains(&i) { Err(err) } else { checked(i) }))
+            .collect();
+    }
+
+    for (def_id, template) in templates {
+        if let Some(sig) = render_sig(genv, *def_id, template, &solutions) {
+            report_sig(genv, *def_id, sig);
+        }
+    }
+    generated
+        .iter()
+        .enumerate()
+        .map(|(i, def_id)| (*def_id, checked(i)))
+        .collect()
+}
+
+fn to_sig_templates(templates: &FxHashMap<LocalDefId, Template>) -> SigTemplates {
+    templates
+        .iter()
+        .map(|(def_id, template)| (def_id.to_def_id(), template.sig.clone()))
+        .collect()
+}
+
+/// Whether we can generate the constraint of `def_id` without errors up to the shape phase, which
+/// is where unsupported features are detected.
+fn can_generate(
+    genv: &GlobalEnv,
+    def_id: LocalDefId,
+    templates: &SigTemplates,
+    config: CheckerConfig,
+) -> bool {
+    compute_ghost_statements(genv, def_id).is_ok_and(|ghost_stmts| {
+        Checker::run_in_shape_mode(genv, def_id, &ghost_stmts, templates, config).is_ok()
+    })
+}
+
+/// Whether the signature of `def_id` can be inferred: it's a private free function or inherent
+/// method without a `#[flux::sig]`.
+fn is_candidate(genv: &GlobalEnv, def_id: LocalDefId) -> bool {
+    let tcx = genv.tcx;
+    let is_inherent = match tcx.def_kind(def_id) {
+        DefKind::Fn => true,
+        DefKind::AssocFn => {
+            tcx.impl_of_method(def_id.to_def_id())
+                .is_some_and(|impl_id| tcx.trait_id_of_impl(impl_id).is_none())
+        }
+        _ => false,
+    };
+    // The signature of a function generated by a macro cannot be written at its definition.
+    is_inherent
+        && !tcx.def_span(def_id).from_expansion()
+        && genv.map().get_fn_sig(def_id).lifted
+        && !tcx.asyncness(def_id).is_async()
+        && !tcx.effective_visibilities(()).is_exported(def_id)
+        && !has_const_params(tcx, def_id)
+}
+
+/// Whether `def_id` or any of its parents has a const generic parameter. Functions checked in the
+/// same query cannot have const generic parameters because they are encoded by position.
+fn has_const_params(tcx: TyCtxt, def_id: LocalDefId) -> bool {
+    let mut generics = tcx.generics_of(def_id);
+    loop {
+        if generics
+            .params
+            .iter()
+            .any(|param| matches!(param.kind, rustc_middle::ty::GenericParamDefKind::Const { .. }))
+        {
+            return true;
+        }
+        let Some(parent) = generics.parent else { return false };
+        generics = tcx.generics_of(parent);
+    }
+}
+
+/// Builds the template used to infer the signature of `def_id` from its lifted signature. Returns
+/// [`None`] if there's nothing to refine.
+fn template(genv: &GlobalEnv, def_id: LocalDefId, kvars: &mut KVarStore) -> Option<Template> {
+    let poly_sig = genv.fn_sig(def_id).ok()?.0;
+    let mut vars = poly_sig.vars().to_vec();
+    let fn_sig = poly_sig.skip_binder();
+    let names = genv.tcx.fn_arg_names(def_id);
+
+    let mut params = vec![];
+    let mut refined = vec![];
+    let args = fn_sig
+        .args()
+        .iter()
+        .zip(names)
+        .map(|(ty, ident)| {
+            if let Some(bty) = refinable(ty)
+                && ![kw::Empty, kw::Underscore, kw::SelfLower].contains(&ident.name)
+            {
+                let idx = vars.len() as u32;
+                let sort = bty.sort();
+                vars.push(BoundVariableKind::Refine(sort.clone(), sort.default_infer_mode()));
+                refined.push((idx, sort, ident.to_string()));
+                params.push(Some(ident.to_string()));
+                Ty::indexed(bty, Expr::late_bvar(INNERMOST, idx))
+            } else {
+                params.push(None);
+                ty.clone()
+            }
+        })
+        .collect_vec();
+
+    let mut requires = fn_sig.requires().to_vec();
+    let requires_kvar = (!refined.is_empty()).then(|| {
+        let kvar = kvars.fresh_signature(
+            0,
+            refined.iter().map(|(idx, sort, name)| {
+                (rty::Var::LateBound(INNERMOST, *idx), sort.clone(), Some(name.clone()))
+            }),
+        );
+        let kvid = expect_kvid(&kvar);
+        requires.push(Constraint::Pred(kvar));
+        kvid
+    });
+
+    let output = fn_sig.output();
+    let FnOutput { ret, ensures } = output.as_ref().skip_binder();
+    let (ret, ret_kvar) = if let Some(bty) = refinable(ret) {
+        let nu = (0..)
+            .map(|i| if i == 0 { "v".to_string() } else { format!("v{i}") })
+            .find(|nu| names.iter().all(|ident| ident.as_str() != nu))
+            .unwrap();
+        let sort = bty.sort();
+        // The arguments are bound two levels up: by the output and by the signature
+        let kvar = kvars.fresh_signature(
+            1,
+            itertools::chain(
+                [(rty::Var::LateBound(INNERMOST, 0), sort.clone(), Some(nu.clone()))],
+                refined.iter().map(|(idx, sort, name)| {
+                    let debruijn = INNERMOST.shifted_in(2);
+                    (rty::Var::LateBound(debruijn, *idx), sort.clone(), Some(name.clone()))
+                }),
+            ),
+        );
+        let kvid = expect_kvid(&kvar);
+        let ret =
+            Ty::exists(Binder::with_sort(Ty::constr(kvar, Ty::indexed(bty, Expr::nu())), sort));
+        (ret, Some((kvid, nu)))
+    } else {
+        (ret.clone(), None)
+    };
+    if requires_kvar.is_none() && ret_kvar.is_none() {
+        return None;
+    }
+
+    let output = Binder::new(FnOutput::new(ret, ensures.clone()), output.vars().clone());
+    let fn_sig = FnSig::new(requires, args, output);
+    Some(Template {
+        sig: EarlyBinder(PolyFnSig::new(fn_sig, List::from_vec(vars))),
+        params,
+        requires: requires_kvar,
+        ret: ret_kvar,
+    })
+}
+
+/// Returns the base type of a lifted integer or boolean type.
+fn refinable(ty: &Ty) -> Option<BaseTy> {
+    if let TyKind::Exists(ty) = ty.kind()
+        && let TyKind::Indexed(bty @ (BaseTy::Int(_) | BaseTy::Uint(_) | BaseTy::Bool), _) =
+            ty.as_ref().skip_binder().kind()
+    {
+        Some(bty.clone())
+    } else {
+        None
+    }
+}
+
+fn expect_kvid(kvar: &Expr) -> rty::KVid {
+    let rty::ExprKind::KVar(kvar) = kvar.kind() else { unreachable!() };
+    kvar.kvid
+}
+
+/// Partitions the functions in `def_ids` related through `callers` into groups that have to be
+/// checked together. Functions not related to any function with a template are left out.
+fn groups(
+    def_ids: &[LocalDefId],
+    callers: &FxHashMap<LocalDefId, &FxIndexSet<LocalDefId>>,
+) -> Vec<Vec<LocalDefId>> {
+    let mut edges: FxHashMap<LocalDefId, Vec<LocalDefId>> = FxHashMap::default();
+    for (callee, fn_callers) in callers {
+        for caller in *fn_callers {
+            edges.entry(*callee).or_default().push(*caller);
+            edges.entry(*caller).or_default().push(*callee);
+        }
+    }
+    let mut visited = FxHashSet::default();
+    let mut groups = vec![];
+    for def_id in def_ids {
+        if !edges.contains_key(def_id) || !visited.insert(*def_id) {
+            continue;
+        }
+        let mut group = FxHashSet::default();
+        let mut stack = vec![*def_id];
+        while let Some(def_id) = stack.pop() {
+            group.insert(def_id);
+            for next in &edges[&def_id] {
+                if visited.insert(*next) {
+                    stack.push(*next);
+                }
+            }
+        }
+        groups.push(
+            def_ids
+                .iter()
+                .copied()
+                .filter(|def_id| group.contains(def_id))
+                .collect(),
+        );
+    }
+    groups
+}
+
+/// Writes the signature inferred for `def_id` in flux syntax. Returns [`None`] if some part of the
+/// precondition cannot be written, in which case we don't report anything.
+fn render_sig(
+    genv: &GlobalEnv,
+    def_id: LocalDefId,
+    template: &Template,
+    solutions: &KVarSolutions,
+) -> Option<String> {
+    let tcx = genv.tcx;
+    let source_map = tcx.sess.source_map();
+    let decl = tcx
+        .hir()
+        .fn_decl_by_hir_id(tcx.hir().local_def_id_to_hir_id(def_id))?;
+
+    let args = decl
+        .inputs
+        .iter()
+        .zip(&template.params)
+        .enumerate()
+        .map(|(i, (input, param))| {
+            let ty = match decl.implicit_self {
+                hir::ImplicitSelfKind::Imm | hir::ImplicitSelfKind::Mut if i == 0 => {
+                    "Self".to_string()
+                }
+                hir::ImplicitSelfKind::ImmRef if i == 0 => "&Self".to_string(),
+                hir::ImplicitSelfKind::MutRef if i == 0 => "&mut Self".to_string(),
+                _ => source_map.span_to_snippet(input.span).ok()?,
+            };
+            Some(match param {
+                Some(name) => format!("{name}: {ty}"),
+                None => ty,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?
+        .join(", ");
+
+    let mut sig = format!("fn({args})");
+    if let hir::FnRetTy::Return(ret) = decl.output {
+        let ret_ty = source_map.span_to_snippet(ret.span).ok()?;
+        let pred = template
+            .ret
+            .as_ref()
+            .and_then(|(kvid, nu)| Some((nu, solutions.get(kvid)?)))
+            .map(|(nu, conjuncts)| {
+                let pred = conjuncts
+                    .iter()
+                    .filter_map(|conjunct| conjunct.source.as_deref())
+                    .join(" && ");
+                (nu, pred)
+            })
+            .filter(|(_, pred)| !pred.is_empty());
+        match pred {
+            Some((nu, pred)) => sig.push_str(&format!(" -> {ret_ty}{{{nu}: {pred}}}")),
+            None => sig.push_str(&format!(" -> {ret_ty}")),
+        }
+    }
+    if let Some(kvid) = template.requires {
+        let conjuncts = solutions.get(&kvid)?;
+        if !conjuncts.is_empty() {
+            let pred = conjuncts
+                .iter()
+                .map(|conjunct| conjunct.source.as_deref())
+                .collect::<Option<Vec<_>>>()?
+                .join(" && ");
+            sig.push_str(&format!(" requires {pred}"));
+        }
+    }
+    Some(sig)
+}
+
+fn report_sig(genv: &GlobalEnv, def_id: LocalDefId, sig: String) {
+    let tcx = genv.tcx;
+    let hir = tcx.hir();
+    let span = tcx.def_span(def_id);
+    let item_span = hir.span(hir.local_def_id_to_hir_id(def_id)).shrink_to_lo();
+    let indent = tcx
+        .sess
+        .source_map()
+        .indentation_before(item_span)
+        .unwrap_or_default();
+    genv.sess.emit_warning(errors::InferredSignature {
+        span,
+        name: tcx.def_path_str(def_id.to_def_id()),
+        suggestion_span: item_span,
+        suggestion: format!("#[flux::sig({sig})]\n{indent}"),
+        sig,
+    });
+}
+
+/// Calls to functions in the bodies of the crate.
+#[derive(Default)]
+struct Uses {
+    /// The functions calling a function. A call inside a closure is attributed to the function
+    /// containing the closure, which is checked together with it.
+    callers: FxHashMap<DefId, FxIndexSet<LocalDefId>>,
+    /// Functions used other than as the callee of a call, e.g., cast to a function pointer.
+    escaping: FxHashSet<DefId>,
+}
+
+impl Uses {
+    fn collect(tcx: TyCtxt) -> Uses {
+        let mut uses = Uses::default();
+        for def_id in tcx.hir().body_owners() {
+            let body = tcx.hir().body(tcx.hir().body_owned_by(def_id));
+            let caller = tcx.typeck_root_def_id(def_id.to_def_id()).expect_local();
+            let mut collector =
+                UsesCollector { typeck: tcx.typeck(def_id), caller, uses: &mut uses };
+            collector.visit_body(body);
+        }
+        uses
+    }
+}
+
+struct UsesCollector<'a, 'tcx> {
+    typeck: &'tcx TypeckResults<'tcx>,
+    caller: LocalDefId,
+    uses: &'a mut Uses,
+}
+
+impl<'tcx> Visitor<'tcx> for UsesCollector<'_, 'tcx> {
+    fn visit_expr(&mut self, expr: &'tcx hir::Expr<'tcx>) {
+        match expr.kind {
+            hir::ExprKind::Call(callee, args)
+                if let hir::ExprKind::Path(qpath) = &callee.kind
+                    && let Res::Def(DefKind::Fn | DefKind::AssocFn, def_id) =
+                        self.typeck.qpath_res(qpath, callee.hir_id) =>
+            {
+                self.uses
+                    .callers
+                    .entry(def_id)
+                    .or_default()
+                    .insert(self.caller);
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+                return;
+            }
+            hir::ExprKind::MethodCall(..)
+                if let Some(def_id) = self.typeck.type_dependent_def_id(expr.hir_id) =>
+            {
+                self.uses
+                    .callers
+                    .entry(def_id)
+                    .or_default()
+                    .insert(self.caller);
+            }
+            hir::ExprKind::Path(qpath)
+                if let Res::Def(DefKind::Fn | DefKind::AssocFn, def_id) =
+                    self.typeck.qpath_res(&qpath, expr.hir_id) =>
+            {
+                self.uses.escaping.insert(def_id);
+            }
+            _ => {}
+        }
+        intravisit::walk_expr(self, expr);
+    }
+}
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_span::Span;
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_inferred_signature, code = "FLUX")]
+    pub struct InferredSignature {
+        #[primary_span]
+        pub span: Span,
+        pub name: String,
+        pub sig: String,
+        #[suggestion(code = "{suggestion}", applicability = "maybe-incorrect")]
+        pub suggestion_span: Span,
+        pub suggestion: String,
+    }
+}
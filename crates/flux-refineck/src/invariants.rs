@@ -1,5 +1,4 @@
 use flux_common::{cache::QueryCache, dbg, iter::IterExt};
-use flux_config as config;
 use flux_errors::{ErrorGuaranteed, ResultExt};
 use flux_middle::{fhir, global_env::GlobalEnv, rty};
 use rustc_hir::def_id::LocalDefId;
@@ -9,7 +8,7 @@ use crate::{
     constraint_gen::{ConstrReason, Tag},
     fixpoint_encoding::{FixpointCtxt, KVarStore},
     refine_tree::RefineTree,
-    CheckerConfig,
+    vacuity, CheckerConfig,
 };
 
 pub fn check_invariants(
@@ -27,7 +26,11 @@ pub fn check_invariants(
         .try_for_each_exhaust(|(idx, invariant)| {
             let span = invariants[idx].span;
             check_invariant(genv, cache, def_id, adt_def, span, invariant, checker_config)
-        })
+        })?;
+    if checker_config.check_vacuity && !adt_def.invariants().is_empty() {
+        vacuity::check_invariants_vacuity(genv, def_id, adt_def, &checker_config)?;
+    }
+    Ok(())
 }
 
 fn check_invariant(
@@ -41,7 +44,7 @@ fn check_invariant(
 ) -> Result<(), ErrorGuaranteed> {
     let mut refine_tree = RefineTree::new();
 
-    for variant_idx in adt_def.variants().indices() {
+    for variant_idx in adt_def.variant_indices() {
         let mut rcx = refine_tree.refine_ctxt_at_root();
 
         let variant = genv
@@ -59,7 +62,7 @@ fn check_invariant(
         rcx.check_pred(pred, Tag::new(ConstrReason::Other, DUMMY_SP));
     }
     let mut fcx = FixpointCtxt::new(genv, def_id, KVarStore::default());
-    if config::dump_constraint() {
+    if checker_config.dump_constraint() {
         dbg::dump_item_info(genv.tcx, def_id, "fluxc", &refine_tree).unwrap();
     }
 
@@ -17,6 +17,9 @@
 )]
 
 extern crate rustc_data_structures;
+// Tests are linked as executables so they need the compiler's shared library
+#[cfg(test)]
+extern crate rustc_driver as _;
 extern crate rustc_errors;
 extern crate rustc_hash;
 extern crate rustc_hir;
@@ -28,91 +31,171 @@ extern crate rustc_serialize;
 extern crate rustc_span;
 extern crate rustc_type_ir;
 
+mod abduction;
 mod checker;
 mod constraint_gen;
 mod fixpoint_encoding;
 mod ghost_statements;
+pub mod inference;
 pub mod invariants;
+mod local_types;
 mod queue;
 mod refine_tree;
 mod sigs;
 mod type_env;
+mod vacuity;
+
+use std::time::{Duration, Instant};
 
-use checker::Checker;
 pub use checker::CheckerConfig;
+use checker::{Checker, SigTemplates};
 use constraint_gen::{ConstrReason, Tag};
 use flux_common::{cache::QueryCache, dbg};
-use flux_config as config;
 use flux_errors::ResultExt;
 use flux_macros::fluent_messages;
-use flux_middle::{
-    global_env::GlobalEnv,
-    rty::{self, ESpan},
-};
+use flux_middle::{global_env::GlobalEnv, rty};
 use itertools::Itertools;
-use rustc_errors::{DiagnosticMessage, ErrorGuaranteed, SubdiagnosticMessage};
+use rustc_errors::{DiagnosticMessage, ErrorGuaranteed, IntoDiagnostic, SubdiagnosticMessage};
 use rustc_hir::def_id::LocalDefId;
 use rustc_span::Span;
 
-use crate::ghost_statements::compute_ghost_statements;
+use crate::{
+    fixpoint_encoding::KVarStore, ghost_statements::compute_ghost_statements,
+    refine_tree::RefineTree,
+};
 
 fluent_messages! { "../locales/en-US.ftl" }
 
+/// The outcome of checking a function that didn't report errors.
+#[derive(Debug)]
+pub enum CheckOutcome {
+    /// The function was checked. `fixpoint_time` is the time spent solving its constraint.
+    Checked { fixpoint_time: Duration },
+    /// The function is trusted and it was not checked.
+    Trusted,
+    /// The function was skipped because it uses features we don't support. This is only produced
+    /// when [`CheckerConfig::skip_unsupported`] is set. `span` points to the unsupported feature.
+    Unsupported { span: Span, reason: String },
+}
+
 pub fn check_fn(
     genv: &GlobalEnv,
     cache: &mut QueryCache,
     def_id: LocalDefId,
     config: CheckerConfig,
-) -> Result<(), ErrorGuaranteed> {
+) -> Result<CheckOutcome, ErrorGuaranteed> {
     dbg::check_fn_span!(genv.tcx, def_id).in_scope(|| {
-        if genv.map().is_trusted(def_id) {
-            return Ok(());
-        }
-
-        // HACK(nilehmann) this will ignore any code generated by a macro. This is
-        // a temporary workaround to allow `#[derive(PartialEq, Eq)]` and should be
-        // removed.
-        if genv.tcx.def_span(def_id).ctxt() > rustc_span::SyntaxContext::root() {
-            return Ok(());
-        }
-        let ghost_stmts = compute_ghost_statements(genv, def_id).emit(genv.sess)?;
+        let (mut refine_tree, kvars) = match generate_refine_tree(
+            genv,
+            def_id,
+            KVarStore::new(config.kvar_encoding),
+            &SigTemplates::default(),
+            config,
+        )? {
+            Ok(generated) => generated,
+            Err(outcome) => return Ok(outcome),
+        };
 
-        // PHASE 1: infer shape of `TypeEnv` at the entry of join points
-        let shape_result =
-            Checker::run_in_shape_mode(genv, def_id, &ghost_stmts, config).emit(genv.sess)?;
-        tracing::info!("check_fn::shape");
-
-        // PHASE 2: generate refinement tree constraint
-        let (mut refine_tree, kvars) =
-            Checker::run_in_refine_mode(genv, def_id, &ghost_stmts, shape_result, config)
-                .emit(genv.sess)?;
-        tracing::info!("check_fn::refine");
+        let vacuity = config
+            .check_vacuity
+            .then(|| (refine_tree.vacuity_tree(), kvars.clone()));
+        let abduction = config
+            .suggest_preconditions
+            .then(|| (refine_tree.clone(), kvars.clone()));
 
         // PHASE 3: invoke fixpoint on the constraint
         refine_tree.simplify();
-        if config::dump_constraint() {
+        if config.dump_constraint() {
             dbg::dump_item_info(genv.tcx, def_id, "fluxc", &refine_tree).unwrap();
         }
         let mut fcx = fixpoint_encoding::FixpointCtxt::new(genv, def_id, kvars);
         let constraint = refine_tree.into_fixpoint(&mut fcx);
+        let start = Instant::now();
         let errors = fcx.check(cache, constraint, &config).emit(genv.sess)?;
+        let fixpoint_time = start.elapsed();
 
         tracing::info!("check_fn::fixpoint");
         if errors.is_empty() {
-            Ok(())
+            if let Some(((tree, tags), kvars)) = vacuity {
+                vacuity::check_fn_vacuity(genv, def_id, tree, tags, kvars, &config)?;
+            }
+            Ok(CheckOutcome::Checked { fixpoint_time })
         } else {
-            report_errors(genv, errors)
+            let result = report_errors(genv, errors.clone());
+            if let Some((tree, kvars)) = abduction {
+                abduction::suggest_precondition(genv, def_id, tree, kvars, &errors, &config)?;
+            }
+            result.map(|_| CheckOutcome::Checked { fixpoint_time })
         }
     })
 }
 
-fn call_error(genv: &GlobalEnv, span: Span, dst_span: Option<ESpan>) -> ErrorGuaranteed {
-    genv.sess
-        .emit_err(errors::RefineError::call(span, dst_span))
+/// Generates the refinement tree of `def_id` adding the kvars it needs to `kvars`. Signatures in
+/// `templates` are used instead of the ones in `genv`. Returns the outcome of the check in the
+/// inner [`Err`] if the function is not checked, e.g., because it's trusted.
+fn generate_refine_tree(
+    genv: &GlobalEnv,
+    def_id: LocalDefId,
+    kvars: KVarStore,
+    templates: &SigTemplates,
+    config: CheckerConfig,
+) -> Result<Result<(RefineTree, KVarStore), CheckOutcome>, ErrorGuaranteed> {
+    if genv.map().is_trusted(def_id) {
+        return Ok(Err(CheckOutcome::Trusted));
+    }
+    let ghost_stmts = match compute_ghost_statements(genv, def_id) {
+        Ok(ghost_stmts) => ghost_stmts,
+        Err(err) => {
+            return skip_or_emit(genv, def_id, config, err.unsupported_reason(), err).map(Err)
+        }
+    };
+
+    // PHASE 1: infer shape of `TypeEnv` at the entry of join points
+    let shape_result =
+        match Checker::run_in_shape_mode(genv, def_id, &ghost_stmts, templates, config) {
+            Ok(shape_result) => shape_result,
+            Err(err) => {
+                return skip_or_emit(genv, def_id, config, err.unsupported_reason(), err).map(Err)
+            }
+        };
+    tracing::info!("check_fn::shape");
+
+    // PHASE 2: generate refinement tree constraint
+    let generated = Checker::run_in_refine_mode(
+        genv,
+        def_id,
+        &ghost_stmts,
+        shape_result,
+        kvars,
+        templates,
+        config,
+    )
+    .emit(genv.sess)?;
+    tracing::info!("check_fn::refine");
+    Ok(Ok(generated))
 }
 
-fn ret_error(genv: &GlobalEnv, span: Span, dst_span: Option<ESpan>) -> ErrorGuaranteed {
-    genv.sess.emit_err(errors::RefineError::ret(span, dst_span))
+/// Emits `err` unless it was caused by an `unsupported` feature and [`CheckerConfig::skip_unsupported`]
+/// is set, in which case the function is skipped with a warning.
+fn skip_or_emit<'a>(
+    genv: &'a GlobalEnv,
+    def_id: LocalDefId,
+    config: CheckerConfig,
+    unsupported: Option<(Span, String)>,
+    err: impl IntoDiagnostic<'a>,
+) -> Result<CheckOutcome, ErrorGuaranteed> {
+    match unsupported {
+        Some((span, reason)) if config.skip_unsupported => {
+            genv.sess.emit_warning(errors::UnsupportedSkipped {
+                span: genv.tcx.def_span(def_id),
+                unsupported_span: span,
+                name: genv.tcx.def_path_str(def_id.to_def_id()),
+                reason: reason.clone(),
+            });
+            Ok(CheckOutcome::Unsupported { span, reason })
+        }
+        _ => Err(genv.sess.emit_err(err)),
+    }
 }
 
 fn report_errors(genv: &GlobalEnv, errors: Vec<Tag>) -> Result<(), ErrorGuaranteed> {
@@ -120,16 +203,23 @@ fn report_errors(genv: &GlobalEnv, errors: Vec<Tag>) -> Result<(), ErrorGuarante
     for err in errors {
         let span = err.src_span;
         e = Some(match err.reason {
-            ConstrReason::Call => call_error(genv, span, err.dst_span),
-            ConstrReason::Assign => genv.sess.emit_err(errors::AssignError { span }),
-            ConstrReason::Ret => ret_error(genv, span, err.dst_span),
-            ConstrReason::Div => genv.sess.emit_err(errors::DivError { span }),
-            ConstrReason::Rem => genv.sess.emit_err(errors::RemError { span }),
-            ConstrReason::Goto(_) => genv.sess.emit_err(errors::GotoError { span }),
-            ConstrReason::Assert(msg) => genv.sess.emit_err(errors::AssertError { span, msg }),
-            ConstrReason::Fold => genv.sess.emit_err(errors::FoldError { span }),
-            ConstrReason::Overflow => genv.sess.emit_err(errors::OverflowError { span }),
-            ConstrReason::Other => genv.sess.emit_err(errors::UnknownError { span }),
+            ConstrReason::Call => {
+                emit_err(genv, span, errors::RefineError::call(span, err.dst_span))
+            }
+            ConstrReason::Assign => emit_err(genv, span, errors::AssignError { span }),
+            ConstrReason::Ret => emit_err(genv, span, errors::RefineError::ret(span, err.dst_span)),
+            ConstrReason::Div => emit_err(genv, span, errors::DivError { span }),
+            ConstrReason::Rem => emit_err(genv, span, errors::RemError { span }),
+            ConstrReason::Goto(_) => emit_err(genv, span, errors::GotoError { span }),
+            ConstrReason::Assert(msg) => emit_err(genv, span, errors::AssertError { span, msg }),
+            ConstrReason::Fold => emit_err(genv, span, errors::FoldError { span }),
+            ConstrReason::Overflow => emit_err(genv, span, errors::OverflowError { span }),
+            ConstrReason::PtrDeref => emit_err(genv, span, errors::PtrDerefError { span }),
+            ConstrReason::PtrOffset => emit_err(genv, span, errors::PtrOffsetError { span }),
+            ConstrReason::UnionField => emit_err(genv, span, errors::UnionFieldError { span }),
+            ConstrReason::Entry | ConstrReason::Other => {
+                emit_err(genv, span, errors::UnknownError { span })
+            }
         });
     }
 
@@ -140,10 +230,31 @@ fn report_errors(genv: &GlobalEnv, errors: Vec<Tag>) -> Result<(), ErrorGuarante
     }
 }
 
+/// Emits an error at `span`. If `span` comes from the expansion of a macro defined in the current
+/// crate, the error also points to the definition of the macro.
+fn emit_err<'a>(genv: &'a GlobalEnv, span: Span, err: impl IntoDiagnostic<'a>) -> ErrorGuaranteed {
+    genv.sess
+        .emit_err(errors::InExpansion::new(genv, span, err))
+}
+
 mod errors {
     use flux_macros::{Diagnostic, Subdiagnostic};
-    use flux_middle::rty::ESpan;
-    use rustc_span::Span;
+    use flux_middle::{global_env::GlobalEnv, rty::ESpan};
+    use rustc_errors::{DiagnosticBuilder, ErrorGuaranteed, Handler, IntoDiagnostic};
+    use rustc_span::{hygiene::ExpnKind, Span};
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_unsupported_skipped, code = "FLUX")]
+    #[note]
+    #[help]
+    pub struct UnsupportedSkipped {
+        #[primary_span]
+        pub span: Span,
+        #[label]
+        pub unsupported_span: Span,
+        pub name: String,
+        pub reason: String,
+    }
 
     #[derive(Diagnostic)]
     #[diag(refineck_goto_error, code = "FLUX")]
@@ -159,6 +270,47 @@ mod errors {
         pub span: Span,
     }
 
+    /// Wraps an error whose span comes from the expansion of a local macro to add a note pointing
+    /// to the definition of the macro. The expansion site is already labeled by rustc.
+    pub struct InExpansion<D> {
+        err: D,
+        macro_def_note: Option<MacroDefNote>,
+    }
+
+    impl<D> InExpansion<D> {
+        pub fn new(genv: &GlobalEnv, span: Span, err: D) -> Self {
+            let macro_def_note = span
+                .macro_backtrace()
+                .find(|expn_data| matches!(expn_data.kind, ExpnKind::Macro(..)))
+                .filter(|expn_data| {
+                    !expn_data.def_site.is_dummy()
+                        && !genv.tcx.sess.source_map().is_imported(expn_data.def_site)
+                })
+                .map(|expn_data| {
+                    MacroDefNote { span: expn_data.def_site, def_name: expn_data.kind.descr() }
+                });
+            InExpansion { err, macro_def_note }
+        }
+    }
+
+    impl<'a, D: IntoDiagnostic<'a>> IntoDiagnostic<'a> for InExpansion<D> {
+        fn into_diagnostic(self, handler: &'a Handler) -> DiagnosticBuilder<'a, ErrorGuaranteed> {
+            let mut diag = self.err.into_diagnostic(handler);
+            if let Some(note) = self.macro_def_note {
+                diag.subdiagnostic(note);
+            }
+            diag
+        }
+    }
+
+    #[derive(Subdiagnostic)]
+    #[note(refineck_macro_def_note)]
+    pub(crate) struct MacroDefNote {
+        #[primary_span]
+        pub span: Span,
+        pub def_name: String,
+    }
+
     #[derive(Subdiagnostic)]
     #[note(refineck_condition_span_note)]
     pub(crate) struct ConditionSpanNote {
@@ -243,6 +395,27 @@ mod errors {
         pub span: Span,
     }
 
+    #[derive(Diagnostic)]
+    #[diag(refineck_ptr_deref_error, code = "FLUX")]
+    pub struct PtrDerefError {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_ptr_offset_error, code = "FLUX")]
+    pub struct PtrOffsetError {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_union_field_error, code = "FLUX")]
+    pub struct UnionFieldError {
+        #[primary_span]
+        pub span: Span,
+    }
+
     #[derive(Diagnostic)]
     #[diag(refineck_unknown_error, code = "FLUX")]
     pub struct UnknownError {
@@ -0,0 +1,120 @@
+//! Snapshots of the refined types of user-visible locals.
+//!
+//! When `FLUX_DUMP_LOCAL_TYPES` is set, the checker records, after every statement checked in
+//! refine mode, the type of each named local in scope together with the refinement context. The
+//! snapshots are dumped as json next to the other debugging information so editor tooling
+//! (e.g., `flux-lsp`) can display them.
+use std::io;
+
+use flux_common::dbg;
+use flux_middle::{
+    global_env::GlobalEnv,
+    rty::{Loc, Path},
+    rustc::mir::Body,
+};
+use rustc_hir::def_id::LocalDefId;
+use rustc_middle::mir::VarDebugInfoContents;
+use rustc_span::{FileName, Span};
+use serde::Serialize;
+
+use crate::{refine_tree::RefineCtxt, type_env::TypeEnv};
+
+// CODESYNC(local-types) the format must be kept in sync with `flux-lsp`
+#[derive(Serialize, Default)]
+pub(crate) struct LocalTypesDump {
+    /// Span of the item being checked, including its signature.
+    body_span: Option<SpanData>,
+    snapshots: Vec<TypeSnapshot>,
+}
+
+#[derive(Serialize)]
+struct TypeSnapshot {
+    span: SpanData,
+    ctxt: String,
+    locals: Vec<LocalType>,
+}
+
+#[derive(Serialize)]
+struct LocalType {
+    name: String,
+    ty: String,
+}
+
+/// A source range. Lines are 1-based and columns are 0-based character offsets.
+#[derive(Serialize)]
+struct SpanData {
+    file: String,
+    lo_line: usize,
+    lo_col: usize,
+    hi_line: usize,
+    hi_col: usize,
+}
+
+impl LocalTypesDump {
+    pub(crate) fn record(
+        &mut self,
+        genv: &GlobalEnv,
+        body: &Body,
+        span: Span,
+        rcx: &RefineCtxt,
+        env: &TypeEnv,
+    ) {
+        if span.from_expansion() {
+            return;
+        }
+        let rustc_body = body.rustc_body();
+        let mut locals: Vec<LocalType> = vec![];
+        for info in &rustc_body.var_debug_info {
+            let VarDebugInfoContents::Place(place) = info.value else { continue };
+            if !place.projection.is_empty() {
+                continue;
+            }
+            let scope_span = rustc_body.source_scopes[info.source_info.scope].span;
+            if !scope_span.contains(span) {
+                continue;
+            }
+            let ty = env.get(&Path::from(Loc::Local(place.local)));
+            if ty.is_uninit() {
+                continue;
+            }
+            let name = info.name.to_string();
+            // Later entries correspond to inner scopes and shadow earlier ones.
+            locals.retain(|local| local.name != name);
+            locals.push(LocalType { name, ty: format!("{ty:?}") });
+        }
+        if locals.is_empty() {
+            return;
+        }
+        let Some(span) = SpanData::new(genv, span) else { return };
+        self.snapshots
+            .push(TypeSnapshot { span, ctxt: format!("{rcx:?}"), locals });
+    }
+
+    pub(crate) fn dump(mut self, genv: &GlobalEnv, def_id: LocalDefId) -> io::Result<()> {
+        if self.snapshots.is_empty() {
+            return Ok(());
+        }
+        let hir = genv.tcx.hir();
+        let body_span = hir.span_with_body(hir.local_def_id_to_hir_id(def_id));
+        self.body_span = SpanData::new(genv, body_span);
+        let writer = dbg::writer_for_item(genv.tcx, def_id.to_def_id(), "locals.json")?;
+        serde_json::to_writer(writer, &self).map_err(io::Error::from)
+    }
+}
+
+impl SpanData {
+    fn new(genv: &GlobalEnv, span: Span) -> Option<SpanData> {
+        let source_map = genv.tcx.sess.source_map();
+        let lo = source_map.lookup_char_pos(span.lo());
+        let hi = source_map.lookup_char_pos(span.hi());
+        let FileName::Real(file) = &lo.file.name else { return None };
+        let file = file.local_path_if_available().display().to_string();
+        Some(SpanData {
+            file,
+            lo_line: lo.line,
+            lo_col: lo.col.0,
+            hi_line: hi.line,
+            hi_col: hi.col.0,
+        })
+    }
+}
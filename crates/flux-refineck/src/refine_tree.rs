@@ -11,7 +11,7 @@ use flux_middle::rty::{
     fold::{
         TypeFoldable, TypeFolder, TypeSuperFoldable, TypeSuperVisitable, TypeVisitable, TypeVisitor,
     },
-    BaseTy, Expr, GenericArg, Mutability, Name, Sort, Ty, TyKind,
+    BaseTy, Expr, ExprKind, GenericArg, Mutability, Name, Sort, Ty, TyKind,
 };
 use itertools::Itertools;
 
@@ -104,6 +104,7 @@ struct Node {
 struct NodePtr(Rc<RefCell<Node>>);
 struct WeakNodePtr(Weak<RefCell<Node>>);
 
+#[derive(Clone)]
 enum NodeKind {
     Conj,
     Comment(String),
@@ -128,6 +129,73 @@ impl RefineTree {
         self.root.borrow_mut().simplify();
     }
 
+    /// Returns a copy of the tree where every head without kvars checks `false` instead, together
+    /// with the tags of the replaced heads. A head in the copy holds iff the path leading to it is
+    /// infeasible, i.e., if the original head can only be proven vacuously. Heads mentioning kvars
+    /// are preserved such that kvars get the same solution as in the original tree.
+    pub(crate) fn vacuity_tree(&self) -> (RefineTree, Vec<Tag>) {
+        let mut tags = vec![];
+        let tree = self.map_nodes(|kind| {
+            match kind {
+                NodeKind::Head(pred, tag) if !has_kvars(pred) => {
+                    tags.push(*tag);
+                    NodeKind::Head(Expr::ff(), *tag)
+                }
+                kind => kind.clone(),
+            }
+        });
+        (tree, tags)
+    }
+
+    /// Returns a deep copy of the tree where the kind of every node is mapped with `f`.
+    fn map_nodes(&self, mut f: impl FnMut(&NodeKind) -> NodeKind) -> RefineTree {
+        fn go(
+            ptr: &NodePtr,
+            parent: Option<WeakNodePtr>,
+            f: &mut impl FnMut(&NodeKind) -> NodeKind,
+        ) -> NodePtr {
+            let node = ptr.borrow();
+            let copy =
+                Node { kind: f(&node.kind), nbindings: node.nbindings, parent, children: vec![] };
+            let copy = NodePtr(Rc::new(RefCell::new(copy)));
+            let children = node
+                .children
+                .iter()
+                .map(|child| go(child, Some(NodePtr::downgrade(&copy)), f))
+                .collect();
+            copy.borrow_mut().children = children;
+            copy
+        }
+        RefineTree { root: go(&self.root, None, &mut f) }
+    }
+
+    /// Finds the node where [`RefineCtxt::check_reachable`] was called with `tag` and guards all
+    /// its children with the predicate returned by `f`, which is given the scope at the node.
+    /// Returns false if there's no such node.
+    pub(crate) fn assume_at(&mut self, tag: Tag, f: impl FnOnce(&Scope) -> Expr) -> bool {
+        fn find(ptr: &NodePtr, tag: Tag) -> Option<NodePtr> {
+            let node = ptr.borrow();
+            node.children.iter().find_map(|child| {
+                if let NodeKind::Head(_, t) = &child.borrow().kind
+                    && *t == tag
+                {
+                    Some(NodePtr::clone(ptr))
+                } else {
+                    find(child, tag)
+                }
+            })
+        }
+        let Some(ptr) = find(&self.root, tag) else { return false };
+        let scope = Snapshot { ptr: NodePtr::downgrade(&ptr) }.scope().unwrap();
+        let children = std::mem::take(&mut ptr.borrow_mut().children);
+        let guard = NodePtr::clone(&ptr).push_node(NodeKind::Guard(f(&scope)));
+        for child in &children {
+            child.borrow_mut().parent = Some(NodePtr::downgrade(&guard));
+        }
+        guard.borrow_mut().children = children;
+        true
+    }
+
     pub(crate) fn into_fixpoint(self, cx: &mut FixpointCtxt<Tag>) -> fixpoint::Constraint {
         self.root
             .borrow()
@@ -140,6 +208,12 @@ impl RefineTree {
     }
 }
 
+impl Clone for RefineTree {
+    fn clone(&self) -> Self {
+        self.map_nodes(NodeKind::clone)
+    }
+}
+
 impl<'a> RefineSubtree<'a> {
     pub(crate) fn refine_ctxt_at_root(&mut self) -> RefineCtxt {
         RefineCtxt { ptr: NodePtr(Rc::clone(&self.root)), tree: self.tree }
@@ -218,6 +292,13 @@ impl<'rcx> RefineCtxt<'rcx> {
         }
     }
 
+    /// Pushes a head that always holds. The head is only relevant in the tree returned by
+    /// [`RefineTree::vacuity_tree`] where it checks that the current node is reachable, or to
+    /// find the node again with [`RefineTree::assume_at`].
+    pub(crate) fn check_reachable(&mut self, tag: Tag) {
+        self.ptr.push_node(NodeKind::Head(Expr::tt(), tag));
+    }
+
     pub(crate) fn check_impl(&mut self, pred1: impl Into<Expr>, pred2: impl Into<Expr>, tag: Tag) {
         self.ptr
             .push_node(NodeKind::Guard(pred1.into()))
@@ -584,6 +665,22 @@ impl Node {
     }
 }
 
+fn has_kvars(expr: &Expr) -> bool {
+    struct HasKVars;
+
+    impl TypeVisitor for HasKVars {
+        type BreakTy = ();
+
+        fn visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+            if let ExprKind::KVar(_) = expr.kind() {
+                return ControlFlow::Break(());
+            }
+            expr.super_visit_with(self)
+        }
+    }
+    expr.visit_with(&mut HasKVars).is_break()
+}
+
 fn children_to_fixpoint(
     cx: &mut FixpointCtxt<Tag>,
     children: &[NodePtr],
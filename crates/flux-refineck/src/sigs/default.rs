@@ -1,12 +1,13 @@
 use std::sync::LazyLock;
 
+use flux_common::bug;
 use flux_middle::{
-    rty::{BaseTy, BinOp, Expr, INT_TYS, UINT_TYS},
+    rty::{BaseTy, BinOp, Constant, Expr, ExprKind, INT_TYS, UINT_TYS},
     rustc::mir,
 };
 use itertools::iproduct;
 
-use super::{Sig, SigTable};
+use super::{Pre, Sig, SigTable};
 use crate::{
     constraint_gen::ConstrReason,
     sigs::{define_btys, s},
@@ -19,7 +20,7 @@ pub(super) static BIN_OPS: LazyLock<SigTable<mir::BinOp, 2>> = LazyLock::new(||
 
     table.extend(mk_signed_bin_ops());
     table.extend(mk_unsigned_bin_ops());
-    table.extend(mk_shift_ops());
+    table.extend(mk_shift_ops(false));
     table.extend(mk_bool_bin_ops());
 
     table
@@ -60,8 +61,8 @@ fn mk_unsigned_bin_ops() -> impl Iterator<Item = (mir::BinOp, Sig<2>)> {
                          requires E::ne(b, 0) => ConstrReason::Rem),
                 ),
                 // BIT
-                (BitAnd, s!(fn(a: Uint, b: Uint) -> Uint{v: E::tt()})),
-                (BitOr,  s!(fn(a: Uint, b: Uint) -> Uint{v: E::tt()})),
+                (BitAnd, s!(fn(a: Uint, b: Uint) -> Uint{v: uint_bit_and(v, a, b)})),
+                (BitOr,  s!(fn(a: Uint, b: Uint) -> Uint{v: uint_bit_or(v, a, b)})),
                 // CMP
                 (Eq, s!(fn(a: Uint, b: Uint) -> bool[E::eq(a, b)])),
                 (Ne, s!(fn(a: Uint, b: Uint) -> bool[E::ne(a, b)])),
@@ -88,17 +89,15 @@ fn mk_signed_bin_ops() -> impl Iterator<Item = (mir::BinOp, Sig<2>)> {
                 (Add, s!(fn(a: Int, b: Int) -> Int[a + b])),
                 (Sub, s!(fn(a: Int, b: Int) -> Int[a - b])),
                 (Mul, s!(fn(a: Int, b: Int) -> Int[a * b])),
-                (Div, s!(fn(a: Int, b: Int) -> Int[a / b]
+                (Div, s!(fn(a: Int, b: Int) -> Int[trunc_div(&a, &b)]
                             requires E::ne(b, 0) => ConstrReason::Div),
                 ),
-                (Rem, s!(fn(a:Int , b: Int) -> Int{v: E::implies(
-                                                          E::and([E::ge(&a, 0), E::ge(&b, 0)]),
-                                                          E::eq(v, E::binary_op(BinOp::Mod, a, b, None))) }
+                (Rem, s!(fn(a: Int, b: Int) -> Int[trunc_rem(&a, &b)]
                             requires E::ne(b, 0) => ConstrReason::Rem),
                 ),
                 // BIT
-                (BitAnd, s!(fn(a: Int, b: Int) -> Int{v: E::tt()})),
-                (BitOr,  s!(fn(a: Int, b: Int) -> Int{v: E::tt()})),
+                (BitAnd, s!(fn(a: Int, b: Int) -> Int{v: int_bit_and(v, a, b)})),
+                (BitOr,  s!(fn(a: Int, b: Int) -> Int{v: int_bit_or(v, a, b)})),
                 // CMP
                 (Eq, s!(fn(a: Int, b: Int) -> bool[E::eq(a, b)])),
                 (Ne, s!(fn(a: Int, b: Int) -> bool[E::ne(a, b)])),
@@ -110,6 +109,31 @@ fn mk_signed_bin_ops() -> impl Iterator<Item = (mir::BinOp, Sig<2>)> {
         })
 }
 
+/// Division in the logic is euclidean but integer division in Rust rounds towards zero, so we
+/// divide the absolute values and fix the sign of the result.
+pub(super) fn trunc_div(a: &Expr, b: &Expr) -> Expr {
+    let pos_a = E::ge(a, 0);
+    let pos_b = E::ge(b, 0);
+    E::ite(
+        pos_a,
+        E::ite(&pos_b, a / b, (a / &b.neg()).neg(), None),
+        E::ite(pos_b, (&a.neg() / b).neg(), a.neg() / b.neg(), None),
+        None,
+    )
+}
+
+/// The remainder in Rust takes the sign of the dividend, e.g., `-7 % 3 == -1`, whereas in the logic
+/// it is always non-negative.
+pub(super) fn trunc_rem(a: &Expr, b: &Expr) -> Expr {
+    let abs_b = E::ite(E::ge(b, 0), b, b.neg(), None);
+    E::ite(
+        E::ge(a, 0),
+        E::binary_op(BinOp::Mod, a, &abs_b, None),
+        E::binary_op(BinOp::Mod, a.neg(), abs_b, None).neg(),
+        None,
+    )
+}
+
 #[rustfmt::skip]
 pub(crate) fn mk_bool_bin_ops() -> impl IntoIterator<Item = (mir::BinOp, Sig<2>)> {
     use mir::BinOp::*;
@@ -128,29 +152,136 @@ pub(crate) fn mk_bool_bin_ops() -> impl IntoIterator<Item = (mir::BinOp, Sig<2>)
     ]
 }
 
+/// Signatures for shifts. The amount can be of any integer type so we need a signature for every
+/// combination of types. When `check_overflow` is set, shifting by an amount greater than or equal
+/// to the bit width of the type is considered an overflow.
 #[rustfmt::skip]
-pub(crate) fn mk_shift_ops() -> impl IntoIterator<Item = (mir::BinOp, Sig<2>)> {
+pub(crate) fn mk_shift_ops(check_overflow: bool) -> impl Iterator<Item = (mir::BinOp, Sig<2>)> {
     use mir::BinOp::*;
-    iproduct!(INT_TYS, UINT_TYS)
-        .flat_map(|(int_ty, uint_ty)|{
+    let int_btys = || {
+        INT_TYS.into_iter().map(BaseTy::Int).chain(UINT_TYS.into_iter().map(BaseTy::Uint))
+    };
+    iproduct!(int_btys(), int_btys())
+        .flat_map(move |(bty1, bty2)| {
             define_btys! {
-                let Int = BaseTy::Int(int_ty);
-                let Uint = BaseTy::Uint(uint_ty);
+                let A = bty1.clone();
+                let B = bty2.clone();
             }
-            [
-                (Shl, s!(fn(a: Int, b: Int) -> Int{ v: E::tt() })),
-                (Shl, s!(fn(a: Int, b: Uint) -> Int{ v: E::tt() })),
-                (Shr, s!(fn(a: Int, b: Int) -> Int{ v: E::tt() })),
-                (Shr, s!(fn(a: Int, b: Uint) -> Int{ v: E::tt() })),
-
-                (Shl, s!(fn(a: Uint, b: Int) -> Uint{ v: E::tt() })),
-                (Shl, s!(fn(a: Uint, b: Uint) -> Uint{ v: E::tt() })),
-                (Shr, s!(fn(a: Uint, b: Int) -> Uint{ v: E::tt() })),
-                (Shr, s!(fn(a: Uint, b: Uint) -> Uint{ v: E::tt() })),
-            ]
+            let bits = bit_width(&bty1);
+            let (shl_bty, shr_bty) = (bty1.clone(), bty1.clone());
+            let mut sigs = [
+                (Shl, s!(fn(a: A, b: B) -> A{v: shl(&shl_bty, v, a, b)})),
+                (Shr, s!(fn(a: A, b: B) -> A{v: shr(&shr_bty, v, a, b)})),
+            ];
+            if check_overflow {
+                for (_, sig) in &mut sigs {
+                    sig.pre.push(Pre {
+                        reason: ConstrReason::Overflow,
+                        constr: Box::new(move |[_, b]| E::and([E::ge(&b, 0), E::lt(b, E::constant(Constant::from(bits as usize)))])),
+                    });
+                }
+            }
+            sigs
         })
 }
 
+/// `a << k` for a constant `k` is equal to `a * 2^k` as long as no bits are shifted out
+fn shl(bty: &BaseTy, v: Expr, a: Expr, b: Expr) -> Expr {
+    if let Some(k) = const_shift_amount(bty, &b) {
+        let res = a * pow2(k);
+        E::implies(in_range(bty, &res), E::eq(v, res))
+    } else {
+        E::tt()
+    }
+}
+
+/// `a >> k` for a constant `k` is equal to `a / 2^k`. Division in the logic rounds towards negative
+/// infinity (for a positive divisor), which matches the arithmetic shift of signed integers.
+fn shr(bty: &BaseTy, v: Expr, a: Expr, b: Expr) -> Expr {
+    if let Some(k) = const_shift_amount(bty, &b) {
+        return E::eq(v, a / pow2(k));
+    }
+    match bty {
+        BaseTy::Uint(_) => E::le(v, a),
+        _ => {
+            E::and([
+                E::implies(E::ge(&a, 0), E::and([E::ge(&v, 0), E::le(&v, &a)])),
+                E::implies(E::lt(&a, 0), E::and([E::ge(&v, &a), E::lt(v, 0)])),
+            ])
+        }
+    }
+}
+
+fn const_shift_amount(bty: &BaseTy, b: &Expr) -> Option<u32> {
+    if let ExprKind::Constant(Constant::Int(n)) = b.kind()
+        && let Some(k) = n.to_u32()
+        && k < bit_width(bty)
+    {
+        Some(k)
+    } else {
+        None
+    }
+}
+
+fn pow2(k: u32) -> Expr {
+    E::constant(Constant::from(1u128 << k))
+}
+
+fn in_range(bty: &BaseTy, e: &Expr) -> Expr {
+    match bty {
+        BaseTy::Int(int_ty) => {
+            E::and([E::ge(e, E::int_min(*int_ty)), E::le(e, E::int_max(*int_ty))])
+        }
+        BaseTy::Uint(uint_ty) => E::le(e, E::uint_max(*uint_ty)),
+        _ => bug!("unexpected base type for shift `{bty:?}`"),
+    }
+}
+
+fn bit_width(bty: &BaseTy) -> u32 {
+    let bit_width = match bty {
+        BaseTy::Int(int_ty) => int_ty.bit_width(),
+        BaseTy::Uint(uint_ty) => uint_ty.bit_width(),
+        _ => bug!("unexpected base type for shift `{bty:?}`"),
+    };
+    bit_width
+        .unwrap_or(flux_config::pointer_width().bits())
+        .try_into()
+        .unwrap()
+}
+
+/// The result of a bitwise and on unsigned integers cannot be greater than any of the operands.
+pub(super) fn uint_bit_and(v: Expr, a: Expr, b: Expr) -> Expr {
+    E::and([E::le(&v, a), E::le(v, b)])
+}
+
+/// The result of a bitwise or on unsigned integers is at least as large as both operands and at
+/// most their sum.
+pub(super) fn uint_bit_or(v: Expr, a: Expr, b: Expr) -> Expr {
+    E::and([E::ge(&v, &a), E::ge(&v, &b), E::le(v, a + b)])
+}
+
+/// Bitwise and on signed integers behaves as in the unsigned case if one of the operands is
+/// non-negative. If both operands are negative the sign bit is kept.
+pub(super) fn int_bit_and(v: Expr, a: Expr, b: Expr) -> Expr {
+    E::and([
+        E::implies(E::ge(&a, 0), E::and([E::ge(&v, 0), E::le(&v, &a)])),
+        E::implies(E::ge(&b, 0), E::and([E::ge(&v, 0), E::le(&v, &b)])),
+        E::implies(E::and([E::lt(&a, 0), E::lt(&b, 0)]), E::lt(v, 0)),
+    ])
+}
+
+/// Bitwise or on signed integers behaves as in the unsigned case if both operands are
+/// non-negative. If one of them is negative the sign bit is set.
+pub(super) fn int_bit_or(v: Expr, a: Expr, b: Expr) -> Expr {
+    E::and([
+        E::implies(
+            E::and([E::ge(&a, 0), E::ge(&b, 0)]),
+            E::and([E::ge(&v, &a), E::ge(&v, &b), E::le(&v, &a + &b)]),
+        ),
+        E::implies(E::or([E::lt(a, 0), E::lt(b, 0)]), E::lt(v, 0)),
+    ])
+}
+
 #[rustfmt::skip]
 fn mk_neg() -> impl Iterator<Item = (mir::UnOp, Sig<1>)> {
     use mir::UnOp::*;
@@ -13,18 +13,19 @@ use crate::constraint_gen::ConstrReason;
 
 pub(crate) struct Sig<const N: usize> {
     pub args: [BaseTy; N],
-    pub pre: Pre<N>,
+    pub pre: Vec<Pre<N>>,
     pub out: Output<N>,
 }
 
-pub(crate) enum Pre<const N: usize> {
-    None,
-    Some(ConstrReason, Box<dyn Fn([Expr; N]) -> Expr + Sync + Send>),
+/// A precondition of a signature together with the reason reported when it cannot be proven
+pub(crate) struct Pre<const N: usize> {
+    pub reason: ConstrReason,
+    pub constr: Box<dyn Fn([Expr; N]) -> Expr + Sync + Send>,
 }
 
 pub(crate) enum Output<const N: usize> {
-    Indexed(BaseTy, fn([Expr; N]) -> Expr),
-    Exists(BaseTy, fn(Expr, [Expr; N]) -> Expr),
+    Indexed(BaseTy, Box<dyn Fn([Expr; N]) -> Expr + Sync + Send>),
+    Exists(BaseTy, Box<dyn Fn(Expr, [Expr; N]) -> Expr + Sync + Send>),
 }
 
 struct SigTable<T: Eq + Hash, const N: usize> {
@@ -84,7 +85,7 @@ macro_rules! _sig {
         #[allow(unused_variables)]
         let pre = s!(($($args),+) $($rest)*);
         #[allow(unused_variables)]
-        let out = $crate::sigs::Output::Indexed(bty, |[$($args),+]| $out);
+        let out = $crate::sigs::Output::Indexed(bty, Box::new(move |[$($args),+]| $out));
         (pre, out)
     }};
     (($($args:ident),+) $bty:ident{$v:ident : $out:expr} $($rest:tt)*) => {{
@@ -92,14 +93,17 @@ macro_rules! _sig {
         #[allow(unused_variables)]
         let pre = s!(($($args),+) $($rest)*);
         #[allow(unused_variables)]
-        let out = $crate::sigs::Output::Exists(bty, |$v, [$($args),+]| $out);
+        let out = $crate::sigs::Output::Exists(bty, Box::new(move |$v, [$($args),+]| $out));
         (pre, out)
     }};
+    (@pre ($($args:ident),+) $pre:expr => $tag:path) => {
+        $crate::sigs::Pre { reason: $tag, constr: Box::new(move |[$($args),+]| $pre) }
+    };
     (($($args:ident),+)) => {
-        $crate::sigs::Pre::None
+        vec![]
     };
-    (($($args:ident),+) requires $pre:expr => $tag:path) => {
-        $crate::sigs::Pre::Some($tag, Box::new(move |[$($args),+]| $pre))
+    ($args:tt $(requires $pre:expr => $tag:path),+) => {
+        vec![$(s!(@pre $args $pre => $tag)),+]
     };
 }
 use crate::_sig as s;
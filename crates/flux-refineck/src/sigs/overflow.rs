@@ -5,7 +5,10 @@ use flux_middle::{
     rustc::mir,
 };
 
-use super::{Sig, SigTable};
+use super::{
+    default::{int_bit_and, int_bit_or, trunc_div, trunc_rem, uint_bit_and, uint_bit_or},
+    Sig, SigTable,
+};
 use crate::{
     constraint_gen::ConstrReason,
     sigs::{define_btys, s},
@@ -18,7 +21,7 @@ pub(super) static BIN_OPS: LazyLock<SigTable<mir::BinOp, 2>> = LazyLock::new(||
 
     table.extend(mk_signed_bin_ops());
     table.extend(mk_unsigned_bin_ops());
-    table.extend(super::default::mk_shift_ops());
+    table.extend(super::default::mk_shift_ops(true));
     table.extend(super::default::mk_bool_bin_ops());
 
     table
@@ -66,8 +69,8 @@ fn mk_unsigned_bin_ops() -> impl Iterator<Item = (mir::BinOp, Sig<2>)> {
                          requires E::ne(b, 0) => ConstrReason::Rem),
                 ),
                 // BIT
-                (BitAnd, s!(fn(a: Uint, b: Uint) -> Uint{v: E::tt()})),
-                (BitOr,  s!(fn(a: Uint, b: Uint) -> Uint{v: E::tt()})),
+                (BitAnd, s!(fn(a: Uint, b: Uint) -> Uint{v: uint_bit_and(v, a, b)})),
+                (BitOr,  s!(fn(a: Uint, b: Uint) -> Uint{v: uint_bit_or(v, a, b)})),
                 // CMP
                 (Eq, s!(fn(a: Uint, b: Uint) -> bool[E::eq(a, b)])),
                 (Ne, s!(fn(a: Uint, b: Uint) -> bool[E::ne(a, b)])),
@@ -109,17 +112,17 @@ fn mk_signed_bin_ops() -> impl Iterator<Item = (mir::BinOp, Sig<2>)> {
                                          E::ge(a - b, E::int_min(int_ty))
                                      ]) => ConstrReason::Overflow)
                 ),
-                (Div, s!(fn(a: Int, b: Int) -> Int[a / b]
-                            requires E::ne(b, 0) => ConstrReason::Div),
+                (Div, s!(fn(a: Int, b: Int) -> Int[trunc_div(&a, &b)]
+                            requires E::ne(&b, 0) => ConstrReason::Div,
+                            requires E::implies(E::eq(a, E::int_min(int_ty)), E::ne(b, -1)) => ConstrReason::Overflow),
                 ),
-                (Rem, s!(fn(a:Int , b: Int) -> Int{v: E::implies(
-                                                          E::and([E::ge(&a, 0), E::ge(&b, 0)]),
-                                                          E::eq(v, E::binary_op(BinOp::Mod, a, b, None))) }
-                            requires E::ne(b, 0) => ConstrReason::Rem),
+                (Rem, s!(fn(a: Int, b: Int) -> Int[trunc_rem(&a, &b)]
+                            requires E::ne(&b, 0) => ConstrReason::Rem,
+                            requires E::implies(E::eq(a, E::int_min(int_ty)), E::ne(b, -1)) => ConstrReason::Overflow),
                 ),
                 // BIT
-                (BitAnd, s!(fn(a: Int, b: Int) -> Int{v: E::tt()})),
-                (BitOr,  s!(fn(a: Int, b: Int) -> Int{v: E::tt()})),
+                (BitAnd, s!(fn(a: Int, b: Int) -> Int{v: int_bit_and(v, a, b)})),
+                (BitOr,  s!(fn(a: Int, b: Int) -> Int{v: int_bit_or(v, a, b)})),
                 // CMP
                 (Eq, s!(fn(a: Int, b: Int) -> bool[E::eq(a, b)])),
                 (Ne, s!(fn(a: Int, b: Int) -> bool[E::ne(a, b)])),
@@ -11,20 +11,22 @@ use flux_middle::{
         evars::EVarSol,
         fold::{FallibleTypeFolder, TypeFoldable, TypeFolder, TypeVisitable, TypeVisitor},
         subst::RegionSubst,
-        BaseTy, Binder, BoundVariableKind, Expr, ExprKind, GenericArg, HoleKind, Mutability, Path,
-        PtrKind, Region, Ty, TyKind, INNERMOST,
+        BaseTy, Binder, BoundVariableKind, Expr, ExprKind, FnOutput, FnSig, GenericArg, HoleKind,
+        Mutability, Path, PolyFnSig, PtrKind, Region, Ty, TyKind, Var, INNERMOST,
     },
     rustc::mir::{BasicBlock, Local, LocalDecls, Place, PlaceElem},
 };
 use itertools::{izip, Itertools};
+use rustc_data_structures::unord::UnordMap;
 use rustc_middle::ty::TyCtxt;
+use rustc_span::{Span, Symbol};
 
 use self::projection::{LocKind, PlacesTree};
 use super::rty::{Loc, Sort};
 use crate::{
     checker::errors::CheckerErrKind,
     constraint_gen::{ConstrGen, ConstrReason},
-    fixpoint_encoding::{KVarEncoding, KVarStore},
+    fixpoint_encoding::{KVarEncoding, KVarOrigin, KVarStore},
     refine_tree::{AssumeInvariants, RefineCtxt, Scope},
     rty::VariantIdx,
     CheckerConfig,
@@ -355,6 +357,7 @@ impl BasicBlockEnvShape {
             | BaseTy::Char
             | BaseTy::Never
             | BaseTy::Closure(_, _)
+            | BaseTy::FnPtr(_)
             | BaseTy::Coroutine(_, _)
             | BaseTy::CoroutineWitness(_, _) => bty.clone(),
         }
@@ -495,6 +498,11 @@ impl BasicBlockEnvShape {
                 debug_assert_eq!(len1, len2);
                 BaseTy::Array(self.join_ty(ty1, ty2), len1.clone())
             }
+            // Different function pointers flowing into the same place are generalized by
+            // inferring a signature for the pointer.
+            (BaseTy::FnPtr(fn_sig1), BaseTy::FnPtr(fn_sig2)) if fn_sig1 != fn_sig2 => {
+                BaseTy::FnPtr(generalize_fn_sig(fn_sig1))
+            }
             _ => {
                 debug_assert_eq!(bty1, bty2);
                 bty1.clone()
@@ -516,13 +524,50 @@ impl BasicBlockEnvShape {
         }
     }
 
-    pub fn into_bb_env(self, kvar_store: &mut KVarStore) -> BasicBlockEnv {
+    /// Generalizes the shape into a [`BasicBlockEnv`] with fresh kvars for the invariant at the
+    /// entry of `bb`. The names in `local_names` are used to give source level names to the kvars'
+    /// arguments.
+    pub fn into_bb_env(
+        self,
+        kvar_store: &mut KVarStore,
+        bb: BasicBlock,
+        span: Span,
+        local_names: &UnordMap<Local, Symbol>,
+    ) -> BasicBlockEnv {
         let mut bindings = self.bindings;
 
+        let path_name = |path: &Path| {
+            let Loc::Local(local) = path.loc else { return None };
+            let name = local_names.get(&local)?.to_string();
+            Some(
+                path.projection()
+                    .iter()
+                    .fold(name, |name, field| format!("{name}.{}", field.as_u32())),
+            )
+        };
+        bindings.iter_flatten(|path, _, ty| {
+            if let TyKind::Indexed(_, idx) = ty.kind()
+                && let ExprKind::Var(Var::Free(name)) = idx.expr.kind()
+                && let Some(source_name) = path_name(&path)
+            {
+                kvar_store.name_var(*name, source_name);
+            }
+        });
+
         let mut generalizer = Generalizer::new();
         bindings.fmap_mut(|ty| generalizer.generalize(ty));
         let (vars, preds) = generalizer.into_parts();
 
+        let mut var_names = vec![None; vars.len()];
+        bindings.iter_flatten(|path, _, ty| {
+            if let TyKind::Indexed(_, idx) = ty.kind()
+                && let ExprKind::Var(Var::LateBound(INNERMOST, idx)) = idx.expr.kind()
+            {
+                var_names[*idx as usize] = path_name(&path);
+            }
+        });
+        let origin = |in_ty| KVarOrigin::JoinPoint { bb, span, vars: var_names.clone(), in_ty };
+
         // Replace all holes with a single fresh kvar on all parameters
         let mut constrs = preds
             .into_iter()
@@ -531,7 +576,12 @@ impl BasicBlockEnvShape {
 
         let outter_sorts = vars.to_sort_list();
 
-        let kvar = kvar_store.fresh(&[outter_sorts.clone()], &self.scope, KVarEncoding::Conj);
+        let kvar = kvar_store.fresh(
+            &[outter_sorts.clone()],
+            &self.scope,
+            KVarEncoding::Conj,
+            origin(false),
+        );
         constrs.push(kvar);
 
         // Replace remaning holes by fresh kvars
@@ -540,7 +590,7 @@ impl BasicBlockEnvShape {
             let sorts = std::iter::once(outter_sorts.clone())
                 .chain(sorts.iter().cloned())
                 .collect_vec();
-            kvar_store.fresh(&sorts, &self.scope, KVarEncoding::Conj)
+            kvar_store.fresh(&sorts, &self.scope, KVarEncoding::Conj, origin(true))
         };
         bindings.fmap_mut(|binding| binding.replace_holes(&mut kvar_gen));
 
@@ -706,3 +756,21 @@ mod pretty {
         BasicBlockEnv => "basic_block_env"
     }
 }
+
+/// Generalizes a function signature by replacing all refinements in its inputs and output with
+/// holes and dropping its refinement parameters and constraints.
+fn generalize_fn_sig(fn_sig: &PolyFnSig) -> PolyFnSig {
+    let vars = fn_sig
+        .vars()
+        .iter()
+        .filter(|var| matches!(var, BoundVariableKind::Region(_)))
+        .cloned()
+        .collect_vec();
+    let fn_sig = fn_sig.as_ref().skip_binder();
+    let args = fn_sig.args().iter().map(Ty::with_holes).collect_vec();
+    let output = fn_sig
+        .output()
+        .as_ref()
+        .map(|output| FnOutput::new(output.ret.with_holes(), vec![]));
+    PolyFnSig::new(FnSig::new(vec![], args, output), List::from_vec(vars))
+}
@@ -95,6 +95,14 @@ pub(crate) trait LookupMode {
         args: &[GenericArg],
         idx: &Index,
     ) -> Result<Vec<Ty>, Self::Error>;
+
+    fn downcast_union(
+        &mut self,
+        adt: &AdtDef,
+        args: &[GenericArg],
+        field: FieldIdx,
+        idx: &Index,
+    ) -> Result<Ty, Self::Error>;
 }
 
 struct Unfold<'a, 'rcx, 'tcx>(&'a GlobalEnv<'a, 'tcx>, &'a mut RefineCtxt<'rcx>);
@@ -117,6 +125,16 @@ impl LookupMode for Unfold<'_, '_, '_> {
     ) -> Result<Vec<Ty>, Self::Error> {
         downcast_struct(self.0, adt, args, idx)
     }
+
+    fn downcast_union(
+        &mut self,
+        adt: &AdtDef,
+        args: &[GenericArg],
+        field: FieldIdx,
+        idx: &Index,
+    ) -> Result<Ty, Self::Error> {
+        downcast_union(self.0, self.1, adt, args, field, idx)
+    }
 }
 
 struct NoUnfold;
@@ -126,6 +144,16 @@ impl LookupMode for NoUnfold {
         tracked_span_bug!("cannot unfold in `NoUnfold` mode")
     }
 
+    fn downcast_union(
+        &mut self,
+        _: &AdtDef,
+        _: &[GenericArg],
+        _: FieldIdx,
+        _: &Index,
+    ) -> Result<Ty, !> {
+        tracked_span_bug!("cannot unfold in `NoUnfold` mode")
+    }
+
     fn unpack(&mut self, ty: &Ty) -> Ty {
         ty.clone()
     }
@@ -188,6 +216,10 @@ impl PlacesTree {
                         TyKind::Downcast(.., fields) => {
                             ty = fields[f.as_usize()].clone();
                         }
+                        TyKind::Indexed(BaseTy::Adt(adt, args), idx) if adt.is_union() => {
+                            is_strg = false;
+                            ty = mode.downcast_union(adt, args, f, idx)?;
+                        }
                         TyKind::Indexed(BaseTy::Adt(adt, args), idx) => {
                             ty = mode.downcast_struct(adt, args, idx)?[f.as_usize()].clone();
                         }
@@ -276,7 +308,7 @@ impl PlacesTree {
         self.map.iter()
     }
 
-    fn iter_flatten(&self, mut f: impl FnMut(Path, &LocKind, &Ty)) {
+    pub(crate) fn iter_flatten(&self, mut f: impl FnMut(Path, &LocKind, &Ty)) {
         fn go(
             loc: &Loc,
             kind: &LocKind,
@@ -479,6 +511,8 @@ impl<'a, 'rcx, 'tcx> Unfolder<'a, 'rcx, 'tcx> {
                 fields[f.as_usize()] = fields[f.as_usize()].try_fold_with(self)?;
                 Ty::indexed(BaseTy::Closure(*def_id, fields.into()), idx.clone())
             }
+            // Unions are never unfolded, their fields are accessed with `downcast_union`
+            TyKind::Indexed(BaseTy::Adt(adt, _), _) if adt.is_union() => ty.clone(),
             TyKind::Indexed(BaseTy::Adt(adt, args), idx) => {
                 let mut fields = downcast_struct(self.genv, adt, args, idx)?
                     .into_iter()
@@ -777,6 +811,23 @@ fn downcast_enum(
     Ok(variant_def.fields.to_vec())
 }
 
+/// Unions are refined as enums with one variant per field. Accessing the field of a union `x : U[i..]`
+/// works like a `downcast` on `enum` to the variant of the field: we instantiate the variant with
+/// fresh names and assume the constraint `i == j'...`. The checker separately proves that the field
+/// is active before accessing it, see [`crate::constraint_gen::ConstrGen::check_union_field`].
+fn downcast_union(
+    genv: &GlobalEnv,
+    rcx: &mut RefineCtxt,
+    adt: &AdtDef,
+    args: &[GenericArg],
+    field: FieldIdx,
+    idx: &Index,
+) -> CheckerResult<Ty> {
+    let variant_idx = VariantIdx::from_usize(field.as_usize());
+    let mut fields = downcast_enum(genv, rcx, adt, variant_idx, args, idx)?;
+    Ok(fields.remove(0))
+}
+
 fn fold(
     bindings: &mut PlacesTree,
     rcx: &mut RefineCtxt,
@@ -0,0 +1,131 @@
+//! Detection of specifications that are verified vacuously, enabled with
+//! [`CheckerConfig::check_vacuity`].
+//!
+//! A function whose precondition is unsatisfiable, or an obligation generated in a path whose
+//! condition is false, is trivially proven. We detect these by issuing additional fixpoint queries
+//! asking whether `false` can be proven at those points, see [`RefineTree::vacuity_tree`].
+use flux_errors::{ErrorGuaranteed, ResultExt};
+use flux_middle::{global_env::GlobalEnv, rty};
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+use rustc_hir::def_id::LocalDefId;
+use rustc_span::Span;
+
+use crate::{
+    constraint_gen::{ConstrReason, Tag},
+    fixpoint_encoding::{FixpointCtxt, KVarStore},
+    refine_tree::RefineTree,
+    CheckerConfig,
+};
+
+/// Warns if the precondition of the function is unsatisfiable or, otherwise, about the obligations
+/// that are only proven because they are unreachable. `refine_tree` and `tags` are the result of
+/// [`RefineTree::vacuity_tree`] on the tree generated for the function.
+pub(crate) fn check_fn_vacuity(
+    genv: &GlobalEnv,
+    def_id: LocalDefId,
+    mut refine_tree: RefineTree,
+    tags: Vec<Tag>,
+    kvars: KVarStore,
+    config: &CheckerConfig,
+) -> Result<(), ErrorGuaranteed> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+    refine_tree.simplify();
+    let mut fcx = FixpointCtxt::new(genv, def_id, kvars);
+    let constraint = refine_tree.into_fixpoint(&mut fcx);
+    // The heads that fail are the ones we can reach
+    let reachable: FxHashSet<(ConstrReason, Span)> = fcx
+        .check_uncached(constraint, config)
+        .emit(genv.sess)?
+        .into_iter()
+        .map(|tag| (tag.reason, tag.src_span))
+        .collect();
+    let vacuous = tags
+        .into_iter()
+        .filter(|tag| !reachable.contains(&(tag.reason, tag.src_span)))
+        .collect_vec();
+
+    // Closures checked as part of the function have their own entry
+    let span = genv.tcx.def_span(def_id);
+    if vacuous
+        .iter()
+        .any(|tag| tag.reason == ConstrReason::Entry && tag.src_span == span)
+    {
+        genv.sess.emit_warning(errors::VacuousPrecondition {
+            span,
+            name: genv.tcx.def_path_str(def_id.to_def_id()),
+        });
+        return Ok(());
+    }
+    for span in vacuous
+        .into_iter()
+        .map(|tag| tag.src_span)
+        .filter(|span| !span.is_dummy())
+        .unique()
+        .sorted()
+    {
+        genv.sess.emit_warning(errors::VacuousCheck { span });
+    }
+    Ok(())
+}
+
+/// Warns if no value satisfies all the invariants of an adt.
+pub(crate) fn check_invariants_vacuity(
+    genv: &GlobalEnv,
+    def_id: LocalDefId,
+    adt_def: &rty::AdtDef,
+    config: &CheckerConfig,
+) -> Result<(), ErrorGuaranteed> {
+    let span = genv.tcx.def_span(def_id);
+    let mut refine_tree = RefineTree::new();
+    let mut rcx = refine_tree.refine_ctxt_at_root();
+    let idx = rcx.define_vars(adt_def.sort());
+    for invariant in adt_def.invariants() {
+        rcx.assume_pred(invariant.pred.replace_bound_expr(&idx));
+    }
+    rcx.check_pred(rty::Expr::ff(), Tag::new(ConstrReason::Other, span));
+
+    let mut fcx = FixpointCtxt::new(genv, def_id, KVarStore::default());
+    let constraint = refine_tree.into_fixpoint(&mut fcx);
+    let errors = fcx.check_uncached(constraint, config).emit(genv.sess)?;
+    if errors.is_empty() {
+        genv.sess.emit_warning(errors::VacuousInvariants {
+            span,
+            name: genv.tcx.def_path_str(def_id.to_def_id()),
+        });
+    }
+    Ok(())
+}
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_span::Span;
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_vacuous_precondition, code = "FLUX")]
+    #[note]
+    pub struct VacuousPrecondition {
+        #[primary_span]
+        pub span: Span,
+        pub name: String,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_vacuous_check, code = "FLUX")]
+    #[note]
+    pub struct VacuousCheck {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(refineck_vacuous_invariants, code = "FLUX")]
+    #[note]
+    pub struct VacuousInvariants {
+        #[primary_span]
+        pub span: Span,
+        pub name: String,
+    }
+}
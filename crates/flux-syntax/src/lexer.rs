@@ -37,6 +37,7 @@ pub enum Token {
     Iff,
     FatArrow,
     Mut,
+    Const,
     Where,
     Impl,
     Requires,
@@ -64,6 +65,7 @@ pub enum Token {
     BitVec,
     As,
     Refine,
+    Reft,
 }
 
 pub(crate) struct Cursor<'t> {
@@ -85,6 +87,7 @@ struct Symbols {
     local: Symbol,
     bitvec: Symbol,
     refine: Symbol,
+    reft: Symbol,
 }
 
 struct Frame<'t> {
@@ -113,6 +116,7 @@ impl<'t> Cursor<'t> {
                 opaque: Symbol::intern("opaque"),
                 local: Symbol::intern("local"),
                 refine: Symbol::intern("refine"),
+                reft: Symbol::intern("reft"),
             },
         }
     }
@@ -154,7 +158,9 @@ impl<'t> Cursor<'t> {
             TokenKind::Ident(symb, _) if symb == self.symbs.local => Token::Local,
             TokenKind::Ident(symb, _) if symb == self.symbs.bitvec => Token::BitVec,
             TokenKind::Ident(symb, _) if symb == self.symbs.refine => Token::Refine,
+            TokenKind::Ident(symb, _) if symb == self.symbs.reft => Token::Reft,
             TokenKind::Ident(symb, _) if symb == kw::Mut => Token::Mut,
+            TokenKind::Ident(symb, _) if symb == kw::Const => Token::Const,
             TokenKind::Ident(symb, _) if symb == kw::Where => Token::Where,
             TokenKind::Ident(symb, _) if symb == kw::Impl => Token::Impl,
             TokenKind::Ident(symb, _) if symb == kw::Type => Token::Type,
@@ -206,6 +212,12 @@ impl<'t> Cursor<'t> {
                 }
                 self.map_token(token);
             }
+            // Fragments captured by a macro (e.g., a `$e:expr`) are wrapped in invisible delimiters
+            // when they are flattened. We lex their tokens as if they were not delimited.
+            Some(TokenTree::Delimited(_, Delimiter::Invisible, tokens)) => {
+                self.stack
+                    .push(Frame { cursor: tokens.trees().peekable(), close: None });
+            }
             Some(TokenTree::Delimited(span, delim, tokens)) => {
                 let close = (
                     Location(span.close.lo() - self.offset),
@@ -217,7 +229,11 @@ impl<'t> Cursor<'t> {
                 let token = token::Token { kind: TokenKind::OpenDelim(*delim), span: span.open };
                 self.map_token(&token);
             }
-            None => self.tokens.push_back(self.stack.pop()?.close?),
+            None => {
+                if let Some(close) = self.stack.pop()?.close {
+                    self.tokens.push_back(close);
+                }
+            }
         }
         Some(())
     }
@@ -227,8 +243,8 @@ impl Iterator for Cursor<'_> {
     type Item = (Location, Token, Location);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.tokens.is_empty() {
-            self.advance();
+        while self.tokens.is_empty() {
+            self.advance()?;
         }
         self.tokens.pop_front()
     }
@@ -81,6 +81,14 @@ impl ParseSess {
         parse!(self, grammar::ItemsParser, tokens, span)
     }
 
+    pub fn parse_assoc_refts(
+        &mut self,
+        tokens: &TokenStream,
+        span: Span,
+    ) -> ParseResult<Vec<surface::AssocReft>> {
+        parse!(self, grammar::AssocReftsParser, tokens, span)
+    }
+
     pub fn parse_type(&mut self, tokens: &TokenStream, span: Span) -> ParseResult<surface::Ty> {
         parse!(self, grammar::TyParser, tokens, span)
     }
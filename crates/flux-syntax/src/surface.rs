@@ -55,6 +55,19 @@ pub struct FuncDef {
     pub body: Option<Expr>,
 }
 
+/// An associated refinement, i.e., a refinement level function declared in a trait which each
+/// implementation must define. In a trait, an associated refinement is declared without a body,
+/// e.g., `reft is_valid(x: int) -> bool;`, and in an impl it must be given one, e.g.,
+/// `reft is_valid(x: int) -> bool { x > 0 }`.
+#[derive(Debug)]
+pub struct AssocReft {
+    pub name: Ident,
+    pub params: Vec<RefineParam>,
+    pub output: Sort,
+    pub body: Option<Expr>,
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub struct Generics {
     pub params: Vec<GenericParam>,
@@ -105,6 +118,7 @@ impl StructDef {
 #[derive(Debug)]
 pub struct EnumDef {
     pub refined_by: Option<RefinedBy>,
+    /// The variants of the enum. For a union, there is one variant per field.
     pub variants: Vec<Option<VariantDef>>,
     pub invariants: Vec<Expr>,
     pub node_id: NodeId,
@@ -276,6 +290,8 @@ pub enum TyKind {
     Constr(Expr, Box<Ty>),
     Tuple(Vec<Ty>),
     Array(Box<Ty>, ArrayLen),
+    /// A function pointer type, e.g., `fn(i32{v: v > 0}) -> i32`
+    BareFn(Vec<Ty>, Box<Ty>),
     /// The `NodeId` is used to resolve the type to a corresponding `OpaqueTy`
     ImplTrait(NodeId, GenericBounds),
 }
@@ -290,6 +306,7 @@ impl Ty {
             TyKind::Ref(_, _)
             | TyKind::Tuple(_)
             | TyKind::Array(_, _)
+            | TyKind::BareFn(_, _)
             | TyKind::ImplTrait(_, _) => None,
         }
     }
@@ -321,14 +338,24 @@ pub struct BaseTy {
 pub enum BaseTyKind {
     Path(Path),
     Slice(Box<Ty>),
+    /// A raw pointer `*const T` or `*mut T`. The pointee must be a base type so that `*const T[n]`
+    /// is parsed as a pointer indexed by `n`.
+    RawPtr(Box<Ty>, Mutability),
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct ArrayLen {
-    pub val: usize,
+    pub kind: ArrayLenKind,
     pub span: Span,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum ArrayLenKind {
+    Lit(usize),
+    /// A const generic parameter, e.g., the `N` in `[i32; N]`
+    Param(Ident),
+}
+
 #[derive(Debug)]
 pub struct Indices {
     pub indices: Vec<RefineArg>,
@@ -378,9 +405,20 @@ pub enum ExprKind {
     BinaryOp(BinOp, Box<[Expr; 2]>),
     UnaryOp(UnOp, Box<Expr>),
     App(Ident, Vec<Expr>),
+    /// Application of an associated refinement, e.g., `<T as Trait>::f(x)`
+    Alias(AliasReft, Vec<Expr>),
     IfThenElse(Box<[Expr; 3]>),
 }
 
+/// A path to an associated refinement qualified with a type, e.g., `<T as Trait>::f`
+#[derive(Debug, Clone)]
+pub struct AliasReft {
+    pub qself: Ident,
+    pub trait_path: QPathExpr,
+    pub name: Ident,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct QPathExpr {
     pub segments: Vec<Ident>,
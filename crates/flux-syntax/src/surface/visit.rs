@@ -346,6 +346,10 @@ pub fn walk_ty<V: Visitor>(vis: &mut V, ty: &Ty) {
             vis.visit_array_len(len);
             vis.visit_ty(ty);
         }
+        TyKind::BareFn(inputs, output) => {
+            walk_list!(vis, visit_ty, inputs);
+            vis.visit_ty(output);
+        }
         TyKind::ImplTrait(_node_id, trait_ref) => {
             walk_list!(vis, visit_trait_ref, trait_ref);
         }
@@ -355,7 +359,7 @@ pub fn walk_ty<V: Visitor>(vis: &mut V, ty: &Ty) {
 pub fn walk_bty<V: Visitor>(vis: &mut V, bty: &BaseTy) {
     match &bty.kind {
         BaseTyKind::Path(path) => vis.visit_path(path),
-        BaseTyKind::Slice(ty) => vis.visit_ty(ty),
+        BaseTyKind::Slice(ty) | BaseTyKind::RawPtr(ty, _) => vis.visit_ty(ty),
     }
 }
 
@@ -385,6 +389,12 @@ pub fn walk_expr<V: Visitor>(vis: &mut V, expr: &Expr) {
             vis.visit_ident(*fun);
             walk_list!(vis, visit_expr, exprs);
         }
+        ExprKind::Alias(alias, exprs) => {
+            vis.visit_ident(alias.qself);
+            vis.visit_qpath_expr(&alias.trait_path);
+            vis.visit_ident(alias.name);
+            walk_list!(vis, visit_expr, exprs);
+        }
         ExprKind::IfThenElse(box exprs) => {
             walk_list!(vis, visit_expr, exprs);
         }
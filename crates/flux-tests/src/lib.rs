@@ -9,6 +9,11 @@ pub fn find_flux_path() -> PathBuf {
     find_file_in_target_dir(executable_name)
 }
 
+pub fn find_cargo_flux_path() -> PathBuf {
+    let executable_name = if cfg!(windows) { "cargo-flux.exe" } else { "cargo-flux" };
+    find_file_in_target_dir(executable_name)
+}
+
 /// Rustc flags to pass Flux when running tests
 pub fn rustc_flags() -> Vec<String> {
     vec!["--crate-type=rlib".to_string(), "--edition=2021".to_string()]
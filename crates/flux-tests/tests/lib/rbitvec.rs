@@ -37,3 +37,30 @@ impl std::ops::BitAnd<Bv32> for Bv32 {
         Bv32(self.0 & other.0)
     }
 }
+
+impl std::ops::BitOr<Bv32> for Bv32 {
+    type Output = Bv32;
+    #[flux::trusted]
+    #[flux::sig(fn (x:Bv32, y:Bv32) -> Bv32[bv_or(x,y)])]
+    fn bitor(self, other: Bv32) -> Bv32 {
+        Bv32(self.0 | other.0)
+    }
+}
+
+impl std::ops::Shl<Bv32> for Bv32 {
+    type Output = Bv32;
+    #[flux::trusted]
+    #[flux::sig(fn (x:Bv32, y:Bv32) -> Bv32[bv_shl(x,y)])]
+    fn shl(self, other: Bv32) -> Bv32 {
+        Bv32(self.0 << other.0)
+    }
+}
+
+impl std::ops::Shr<Bv32> for Bv32 {
+    type Output = Bv32;
+    #[flux::trusted]
+    #[flux::sig(fn (x:Bv32, y:Bv32) -> Bv32[bv_lshr(x,y)])]
+    fn shr(self, other: Bv32) -> Bv32 {
+        Bv32(self.0 >> other.0)
+    }
+}
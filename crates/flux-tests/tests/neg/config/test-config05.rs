@@ -0,0 +1,20 @@
+#![flux::cfg(skip_unsupported = true)]
+
+use std::arch::asm;
+
+// `nop` is not checked but callers still use its signature
+#[flux::sig(fn(x: i32) -> i32{v: v == x})]
+pub fn nop(x: i32) -> i32 { //~ WARNING was not checked
+    unsafe { asm!("nop") };
+    x
+}
+
+#[flux::sig(fn() -> i32{v: v == 3})]
+pub fn ok() -> i32 {
+    nop(3)
+}
+
+#[flux::sig(fn() -> i32{v: v == 4})]
+pub fn bad() -> i32 {
+    nop(3) //~ ERROR refinement type error
+}
@@ -0,0 +1,33 @@
+// Settings given on a module are inherited by the items inside it and can be overridden
+
+#[flux::cfg(check_overflow = true)]
+mod checked {
+    #[flux::sig(fn(a: u32, b: u32) -> u32{v: v == a + b})]
+    pub fn add(a: u32, b: u32) -> u32 {
+        a + b //~ ERROR overflow
+    }
+
+    pub struct Counter {
+        pub n: u32,
+    }
+
+    impl Counter {
+        #[flux::sig(fn(&mut Counter))]
+        pub fn incr(&mut self) {
+            self.n += 1; //~ ERROR overflow
+        }
+    }
+
+    #[flux::cfg(check_overflow = false)]
+    mod legacy {
+        #[flux::sig(fn(a: u32, b: u32) -> u32{v: v == a + b})]
+        pub fn add(a: u32, b: u32) -> u32 {
+            a + b
+        }
+    }
+}
+
+#[flux::sig(fn(a: u32, b: u32) -> u32{v: v == a + b})]
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
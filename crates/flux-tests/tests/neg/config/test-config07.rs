@@ -0,0 +1,5 @@
+#[flux::cfg(skip_unsupported = true)] //~ ERROR invalid flux configuration: invalid item cfg keyword `skip_unsupported`
+#[flux::sig(fn(x: i32, y: i32) -> i32)]
+pub fn test(x: i32, y: i32) -> i32 {
+    x / y
+}
@@ -0,0 +1,18 @@
+// rustc-env:FLUX_SOLVER_TOTAL_TIMEOUT=0
+
+// The time limit for the crate is exceeded before fixpoint is called on the first item so every
+// item that needs fixpoint times out.
+
+#[flux::sig(fn(n: usize) -> usize[n])]
+pub fn count(n: usize) -> usize { //~ ERROR fixpoint timed out while checking `count`
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+#[flux::sig(fn(x: i32{x > 0}) -> i32{v: v > 1})]
+pub fn incr(x: i32) -> i32 { //~ ERROR fixpoint timed out while checking `incr`
+    x + 1
+}
@@ -0,0 +1,33 @@
+// A `solver_timeout` given on an item only applies to the items in scope
+
+#[flux::cfg(solver_timeout = 0)]
+#[flux::sig(fn(n: usize) -> usize[n])]
+pub fn count(n: usize) -> usize { //~ ERROR fixpoint timed out while checking `count`
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+#[flux::sig(fn(n: usize) -> usize[n])]
+pub fn count_no_timeout(n: usize) -> usize {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+#[flux::cfg(solver_timeout = 0)]
+mod timeout {
+    // The setting is inherited from the module
+    #[flux::sig(fn(n: usize) -> usize[n])]
+    pub fn count(n: usize) -> usize { //~ ERROR fixpoint timed out while checking `timeout::count`
+        let mut i = 0;
+        while i < n {
+            i += 1;
+        }
+        i
+    }
+}
@@ -0,0 +1,13 @@
+#![flux::cfg(infer_signatures = true)]
+
+// Functions with a `flux::cfg` are not part of signature inference and are checked with their own
+// configuration
+#[flux::cfg(check_overflow = true)]
+fn inc(x: i32) -> i32 {
+    x + 1 //~ ERROR overflow
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32)]
+pub fn test(n: i32) -> i32 {
+    inc(n)
+}
@@ -0,0 +1,13 @@
+// rustc-env:FLUX_POINTER_WIDTH=32
+// ignore-32bit
+// error-pattern: the configured pointer width (32) differs from the pointer width of the target
+// error-pattern: overflow
+
+// The warning about the mismatch has no span so it can't be matched with an annotation.
+
+#![flux::cfg(check_overflow = true)]
+
+#[flux::sig(fn(x: usize{x <= 4294967295}) -> usize)]
+pub fn inc(x: usize) -> usize {
+    x + 1
+}
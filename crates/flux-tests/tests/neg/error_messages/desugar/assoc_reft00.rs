@@ -0,0 +1,8 @@
+#[flux::assoc(reft is_valid(x: int) -> bool;)]
+pub trait Validate {}
+
+#[flux::sig(fn(x: i32{<T as Validate>::is_valid(x)}))] //~ ERROR cannot resolve associated refinement
+pub fn test00<T>(x: i32) {}
+
+#[flux::sig(fn(x: i32{<U as Validate>::is_valid(x)}))] //~ ERROR cannot resolve associated refinement
+pub fn test01<T: Validate>(x: i32) {}
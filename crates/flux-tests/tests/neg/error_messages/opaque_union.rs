@@ -0,0 +1,9 @@
+#[flux::opaque]
+union U { //~ ERROR unions can't be opaque
+    i: i32,
+    f: f32,
+}
+
+fn read(u: U) -> i32 {
+    unsafe { u.i }
+}
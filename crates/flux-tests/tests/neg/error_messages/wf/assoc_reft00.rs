@@ -0,0 +1,22 @@
+#[flux::assoc(reft is_valid(x: int) -> bool;)]
+pub trait Validate {}
+
+pub struct A;
+
+#[flux::assoc(reft is_valid(x: bool) -> bool { x })] //~ ERROR associated refinement `is_valid` is incompatible
+impl Validate for A {}
+
+pub struct B;
+
+#[flux::assoc(reft is_small(x: int) -> bool { x < 10 })] //~ ERROR associated refinement `is_small` is not declared
+impl Validate for B {}
+
+pub struct C;
+
+impl Validate for C {} //~ ERROR missing definition for associated refinement `is_valid`
+
+#[flux::sig(fn(x: i32{<T as Validate>::is_valid(x, x)}))] //~ ERROR this associated refinement takes 1 refinement argument
+pub fn test00<T: Validate>(x: i32) {}
+
+#[flux::sig(fn(x: i32{<T as Validate>::is_valid(x) + 1 > 0}))] //~ ERROR mismatched sorts
+pub fn test01<T: Validate>(x: i32) {}
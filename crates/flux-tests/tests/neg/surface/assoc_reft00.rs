@@ -0,0 +1,27 @@
+#[flux::assoc(reft is_valid(x: int) -> bool;)]
+pub trait Validate {
+    fn validate(x: i32) -> bool;
+}
+
+pub struct Pos;
+
+#[flux::assoc(reft is_valid(x: int) -> bool { x > 0 })]
+impl Validate for Pos {
+    fn validate(x: i32) -> bool {
+        x > 0
+    }
+}
+
+#[flux::sig(fn(x: i32{<T as Validate>::is_valid(x)}) -> i32{v: <T as Validate>::is_valid(v)})]
+pub fn keep<T: Validate>(x: i32) -> i32 {
+    x
+}
+
+#[flux::sig(fn(x: i32) -> i32{v: <T as Validate>::is_valid(v)})]
+pub fn bad<T: Validate>(x: i32) -> i32 {
+    x //~ ERROR refinement type
+}
+
+pub fn test00() -> i32 {
+    keep::<Pos>(0) //~ ERROR refinement type
+}
@@ -0,0 +1,18 @@
+#[flux::assoc(reft is_valid(x: int) -> bool;)]
+pub trait Validate {
+    #[flux::sig(fn(x: i32{<Self as Validate>::is_valid(x)}) -> i32{v: <Self as Validate>::is_valid(v)})]
+    fn keep(x: i32) -> i32 {
+        x
+    }
+}
+
+pub struct Wrap<T>(T);
+
+// The associated refinement is projected from a type that is not a type parameter and whose impl
+// comes from a bound, so it cannot be resolved.
+pub fn test00<T>(x: i32) -> i32 //~ ERROR cannot resolve associated refinement
+where
+    Wrap<T>: Validate,
+{
+    Wrap::<T>::keep(x)
+}
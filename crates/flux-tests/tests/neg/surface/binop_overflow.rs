@@ -31,3 +31,18 @@ pub fn int_add1(a: i32) -> i32 {
 pub fn uint_add1(a: u32) -> u32 {
     a + 1 //~ ERROR overflow
 }
+
+#[flux::sig(fn(a: i32, b: i32{b != 0}) -> i32)]
+pub fn int_div(a: i32, b: i32) -> i32 {
+    a / b //~ ERROR overflow
+}
+
+#[flux::sig(fn(a: i32, b: i32{b != 0}) -> i32)]
+pub fn int_rem(a: i32, b: i32) -> i32 {
+    a % b //~ ERROR overflow
+}
+
+#[flux::sig(fn(x: u32, k: u32) -> u32)]
+pub fn uint_shl(x: u32, k: u32) -> u32 {
+    x << k //~ ERROR overflow
+}
@@ -0,0 +1,20 @@
+#[flux::sig(fn(u32) -> u32{v: v < 255})]
+pub fn mask(x: u32) -> u32 {
+    x & 0xFF //~ ERROR refinement type
+}
+
+#[flux::sig(fn(x: i32) -> i32{v: v >= 0})]
+pub fn or_one(x: i32) -> i32 {
+    x | 1 //~ ERROR refinement type
+}
+
+#[flux::sig(fn(n: i32) -> i32{v: v >= 0})]
+pub fn half(n: i32) -> i32 {
+    n >> 1 //~ ERROR refinement type
+}
+
+// The multiplication only holds if no bits are shifted out
+#[flux::sig(fn(n: u32) -> u32[n * 2])]
+pub fn double(n: u32) -> u32 {
+    n << 1 //~ ERROR refinement type
+}
@@ -0,0 +1,25 @@
+#![feature(stmt_expr_attributes)]
+
+#[flux::sig(fn(c: Option<i32>) -> Option<i32>)]
+pub fn test0(c: Option<i32>) -> Option<i32> {
+    c.map(#[flux::sig(fn(x: i32{x > 0}) -> i32)] |x| x + 1) //~ ERROR refinement type
+}
+
+#[flux::sig(fn(c: Option<i32{v: v >= 0}>) -> Option<i32>)]
+pub fn test1(c: Option<i32>) -> Option<i32> {
+    #[flux::sig(fn(x: i32{x >= 0}) -> i32{v: v > 10})]
+    let f = |x| x + 10; //~ ERROR refinement type
+    c.map(f)
+}
+
+#[flux::trusted]
+fn apply<F: FnMut(i32)>(mut f: F) {
+    f(1)
+}
+
+#[flux::sig(fn() -> i32{v: v >= 0})]
+pub fn test2() -> i32 {
+    let mut x = 0;
+    apply(|y| x = y);
+    x //~ ERROR refinement type
+}
@@ -0,0 +1,19 @@
+#[flux::sig(fn(arr: &[i32; N], usize{v: v < N}) -> i32)]
+pub fn get<const N: usize>(arr: &[i32; N], i: usize) -> i32 {
+    arr[i]
+}
+
+pub fn test00() -> i32 {
+    let arr = [1, 2, 3];
+    get(&arr, 3) //~ ERROR refinement type
+}
+
+#[flux::sig(fn(&[i32; N]) -> usize[N])]
+pub fn len<const N: usize>(arr: &[i32; N]) -> usize {
+    arr.len() - 1 //~ ERROR refinement type
+}
+
+#[flux::sig(fn(&[i32; N]) -> i32)]
+pub fn first<const N: usize>(arr: &[i32; N]) -> i32 {
+    arr[0] //~ ERROR refinement type
+}
@@ -0,0 +1,5 @@
+#[derive(Default)] //~ ERROR refinement type
+pub struct Pos {
+    #[flux::field(i32{v: v > 0})]
+    val: i32,
+}
@@ -0,0 +1,25 @@
+use flux_rs::flux;
+
+flux! {
+    pub trait Counter {
+        fn start() -> i32{v: v >= 0};
+    }
+
+    pub struct Neg;
+
+    impl Counter for Neg {
+        fn start() -> i32{v: v >= 0} {
+            -1 //~ ERROR refinement type
+        }
+    }
+
+    pub const LIMIT: usize{v: v > 0} = 10;
+
+    pub const WRONG_LIMIT: usize{v: v > 20} = 10; //~ ERROR refinement type
+
+    pub static WRONG_NAME: (i32{v: v < 0}, &str) = (1, "neg"); //~ ERROR refinement type
+
+    fn limit() -> usize{v: v == 20} {
+        LIMIT //~ ERROR refinement type
+    }
+}
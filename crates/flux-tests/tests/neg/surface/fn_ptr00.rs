@@ -0,0 +1,24 @@
+#[flux::sig(fn(i32{v: v >= 0}) -> i32{v: v > 0})]
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+#[flux::sig(fn(i32) -> i32)]
+fn dec(x: i32) -> i32 {
+    x - 1
+}
+
+#[flux::sig(fn(f: fn(i32{v: v > 0}) -> i32{v: v > 0}) -> i32{v: v > 0})]
+fn call(f: fn(i32) -> i32) -> i32 {
+    f(0) //~ ERROR refinement type
+}
+
+pub fn test00() -> i32 {
+    call(dec as fn(i32) -> i32) //~ ERROR refinement type
+}
+
+#[flux::sig(fn() -> i32[6])]
+pub fn test01() -> i32 {
+    let f = inc as fn(i32) -> i32;
+    f(5) //~ ERROR refinement type
+}
@@ -0,0 +1,21 @@
+#[flux::sig(fn(bool[true]))]
+fn assert(_: bool) {}
+
+#[flux::sig(fn(&[i32{v: v > 0}], F))]
+fn for_each<F>(xs: &[i32], mut f: F)
+where
+    F: for<'a> FnMut(&'a i32),
+{
+    let mut i = 0;
+    while i < xs.len() {
+        f(&xs[i]);
+        i += 1;
+    }
+}
+
+#[flux::sig(fn(&[i32{v: v > 0}], k: i32))]
+pub fn test00(xs: &[i32], k: i32) {
+    // The bound on `F` is not refined so the closure cannot assume the elements are positive
+    for_each(xs, |x| assert(*x > 0)); //~ ERROR refinement type
+    for_each(xs, |_| assert(k > 0)); //~ ERROR refinement type
+}
@@ -0,0 +1,10 @@
+#![flux::cfg(infer_signatures = true)]
+
+fn dec(x: i32) -> i32 {
+    x - 1
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32{v: v > 0})]
+pub fn test(n: i32) -> i32 {
+    dec(n) //~ ERROR refinement type
+}
@@ -0,0 +1,18 @@
+#![flux::cfg(infer_signatures = true)]
+
+// The inferred signature must satisfy all the callers, so it cannot require more than what every
+// caller provides.
+
+fn id(x: i32) -> i32 {
+    x
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32{v: v > 0})]
+pub fn test00(n: i32) -> i32 {
+    id(n)
+}
+
+#[flux::sig(fn(n: i32) -> i32{v: v > 0})]
+pub fn test01(n: i32) -> i32 {
+    id(n) //~ ERROR refinement type
+}
@@ -0,0 +1,17 @@
+#![flux::cfg(infer_signatures = true)]
+
+// A function used as a function pointer is not inferred and keeps its lifted signature.
+
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+pub fn apply(x: i32) -> i32 {
+    let f: fn(i32) -> i32 = inc;
+    f(x)
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32{v: v > 1})]
+pub fn test(n: i32) -> i32 {
+    inc(n) //~ ERROR refinement type
+}
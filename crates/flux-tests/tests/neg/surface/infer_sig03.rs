@@ -0,0 +1,18 @@
+#![flux::cfg(infer_signatures = true)]
+
+// A function called from a function that is not checked is not inferred and keeps its lifted
+// signature.
+
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+#[flux::trusted]
+pub fn unchecked(x: i32) -> i32 {
+    inc(x)
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32{v: v > 1})]
+pub fn test(n: i32) -> i32 {
+    inc(n) //~ ERROR refinement type
+}
@@ -0,0 +1,23 @@
+macro_rules! sub_fn {
+    ($name:ident, $n:literal) => {
+        #[flux::sig(fn(x: i32{x >= 0}) -> i32{v: v >= 0})]
+        pub fn $name(x: i32) -> i32 {
+            x - $n //~ ERROR refinement type
+        }
+    };
+}
+
+sub_fn!(sub1, 1);
+
+#[flux::sig(fn(i32{v: v > 0}))]
+fn assert_pos(_x: i32) {}
+
+macro_rules! check_pos {
+    ($e:expr) => {
+        assert_pos($e) //~ ERROR refinement type
+    };
+}
+
+pub fn test() {
+    check_pos!(0);
+}
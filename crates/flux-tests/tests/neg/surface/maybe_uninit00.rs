@@ -0,0 +1,31 @@
+use std::mem::MaybeUninit;
+
+use flux_rs::extern_spec;
+
+#[extern_spec(std::mem)]
+#[flux::refined_by(init: bool)]
+struct MaybeUninit<T>;
+
+#[extern_spec(std::mem)]
+impl<T> MaybeUninit<T> {
+    #[flux::sig(fn() -> MaybeUninit<T>[false])]
+    fn uninit() -> MaybeUninit<T>;
+
+    #[flux::sig(fn(T) -> MaybeUninit<T>[true])]
+    fn new(val: T) -> MaybeUninit<T>;
+
+    #[flux::sig(fn(MaybeUninit<T>[true]) -> T)]
+    unsafe fn assume_init(this: MaybeUninit<T>) -> T;
+}
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 0})]
+pub fn init_missing(n: i32) -> i32 {
+    let x = MaybeUninit::uninit();
+    unsafe { x.assume_init() } //~ ERROR refinement type
+}
+
+pub fn init_overwritten() -> u8 {
+    let mut x = MaybeUninit::new(0);
+    x = MaybeUninit::uninit();
+    unsafe { x.assume_init() } //~ ERROR refinement type
+}
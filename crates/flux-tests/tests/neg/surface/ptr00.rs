@@ -0,0 +1,25 @@
+#![flux::cfg(check_raw_pointers = true)]
+
+pub unsafe fn read(p: *const i32) -> i32 {
+    *p //~ ERROR dereference of a raw pointer
+}
+
+#[flux::sig(fn(p: *const i32[@n], usize[@k]) -> i32 requires k <= n)]
+pub unsafe fn read_at(p: *const i32, k: usize) -> i32 {
+    *p.add(k) //~ ERROR dereference of a raw pointer
+}
+
+#[flux::sig(fn(p: *const i32[3]) -> *const i32)]
+pub unsafe fn past_end(p: *const i32) -> *const i32 {
+    p.add(4) //~ ERROR pointer offset may be out of bounds
+}
+
+#[flux::sig(fn(p: *mut i32[@n], i: usize{i <= n}, v: i32))]
+pub unsafe fn write_at(p: *mut i32, i: usize, v: i32) {
+    *p.add(i) = v; //~ ERROR dereference of a raw pointer
+}
+
+#[flux::sig(fn(p: *const i32[2]) -> i32)]
+pub unsafe fn backwards(p: *const i32) -> i32 {
+    *p.offset(-1) //~ ERROR pointer offset may be out of bounds
+}
@@ -0,0 +1,22 @@
+// Specs for the standard library are bundled with flux and loaded automatically
+
+#[flux::sig(fn(bool[true]))]
+fn assert(_: bool) {}
+
+pub fn test_vec(i: usize) {
+    let mut v = Vec::new();
+    v.push(0);
+    v.push(1);
+    v.push(2);
+    v.remove(i); //~ ERROR refinement type
+    assert(v.len() == 3); //~ ERROR refinement type
+}
+
+pub fn test_slice(s: &mut [i32]) {
+    s.swap(0, 1); //~ ERROR refinement type
+}
+
+#[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a - b + 1]>)]
+pub fn test_checked_sub(a: usize, b: usize) -> Option<usize> {
+    a.checked_sub(b) //~ ERROR refinement type
+}
@@ -0,0 +1,12 @@
+#![flux::cfg(suggest_preconditions = true)]
+
+#[flux::sig(fn(x: i32{x > 0}) -> i32{v: v > 0})]
+pub fn pos(x: i32) -> i32 {
+    x
+}
+
+#[flux::sig(fn(n: i32) -> i32[n])]
+pub fn id(n: i32) -> i32 { //~ WARNING would verify with the additional precondition `n > 0`
+    pos(n); //~ ERROR refinement type
+    n
+}
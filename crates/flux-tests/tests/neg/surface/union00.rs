@@ -0,0 +1,27 @@
+#[flux::refined_by(is_int: bool)]
+pub union IntOrFloat {
+    #[flux::variant((i32{v: v > 0}) -> IntOrFloat[true])]
+    i: i32,
+    #[flux::variant((f32) -> IntOrFloat[false])]
+    f: f32,
+}
+
+#[flux::sig(fn(IntOrFloat) -> i32)]
+pub fn get_int(x: IntOrFloat) -> i32 {
+    unsafe { x.i } //~ ERROR union field may not be active
+}
+
+#[flux::sig(fn(&IntOrFloat[true]) -> f32)]
+pub fn get_float(x: &IntOrFloat) -> f32 {
+    unsafe { x.f } //~ ERROR union field may not be active
+}
+
+pub fn write_then_read() -> i32 {
+    let mut x = IntOrFloat { i: 1 };
+    x.f = 1.0;
+    unsafe { x.i } //~ ERROR union field may not be active
+}
+
+pub fn bad_write(x: &mut IntOrFloat) {
+    x.i = 0; //~ ERROR refinement type
+}
@@ -0,0 +1,22 @@
+#![flux::cfg(check_vacuity = true)]
+
+#[flux::sig(fn(bool[true]))]
+pub fn assert(_b: bool) {}
+
+#[flux::sig(fn(x: i32{x > 0 && x < 0}) -> i32{v: v > 0})]
+pub fn unsat(x: i32) -> i32 { //~ WARNING precondition of `unsat` is unsatisfiable
+    x
+}
+
+#[flux::sig(fn(x: i32{x > 0}))]
+pub fn dead_branch(x: i32) {
+    assert(x > 0);
+    if x < 0 {
+        assert(x > 5); //~ WARNING only verified because it is unreachable
+    }
+}
+
+#[flux::sig(fn(x: i32{x > 0}))]
+pub fn live(x: i32) {
+    assert(x > 5); //~ ERROR refinement type
+}
@@ -0,0 +1,27 @@
+#![flux::cfg(check_vacuity = true)]
+
+// The invariants hold for every value of the fields, but only because no value satisfies the
+// refinements of the fields.
+#[flux::refined_by(n: int)]
+#[flux::invariant(n > 0)]
+#[flux::invariant(n < 0)]
+pub struct S { //~ WARNING the invariants of `S` are contradictory
+    #[flux::field({i32[n] | n > 0 && n < 0})]
+    x: i32,
+}
+
+#[flux::refined_by(n: int)]
+#[flux::invariant(n > 5)]
+#[flux::invariant(n < 0)]
+pub enum E { //~ WARNING the invariants of `E` are contradictory
+    #[flux::variant({{i32[@n] | n > 5 && n < 0}} -> E[n])]
+    A(i32),
+}
+
+#[flux::refined_by(n: int)]
+#[flux::invariant(n > 0)]
+#[flux::invariant(n < 10)]
+pub struct Ok {
+    #[flux::field({i32[n] | n > 0 && n < 10})]
+    x: i32,
+}
@@ -0,0 +1,19 @@
+// rustc-env:FLUX_POINTER_WIDTH=32
+// ignore-32bit
+// normalize-stderr-test "target `[^`]*`" -> "target `TARGET`"
+
+// The configured pointer width overrides the one of the target so the bounds of `usize` and `isize`
+// in specifications and overflow checks are the 32-bit ones. Constants in the code are still
+// evaluated by rustc for the actual target, which is why a mismatch is reported with a warning.
+
+#![flux::cfg(check_overflow = true)]
+
+#[flux::sig(fn() -> (usize[usize::MAX], isize[isize::MIN], isize[isize::MAX]))]
+pub fn bounds() -> (usize, isize, isize) {
+    (4294967295, -2147483648, 2147483647)
+}
+
+#[flux::sig(fn(x: usize) -> bool[true])]
+pub fn fits_in_u32(x: usize) -> bool {
+    x <= 4294967295
+}
@@ -0,0 +1,63 @@
+#[flux::assoc(reft is_valid(x: int) -> bool;)]
+pub trait Validate {
+    fn validate(x: i32) -> bool;
+}
+
+pub struct Pos;
+
+#[flux::assoc(reft is_valid(x: int) -> bool { x > 0 })]
+impl Validate for Pos {
+    fn validate(x: i32) -> bool {
+        x > 0
+    }
+}
+
+pub struct Small;
+
+#[flux::assoc(reft is_valid(x: int) -> bool { x < 10 })]
+impl Validate for Small {
+    fn validate(x: i32) -> bool {
+        x < 10
+    }
+}
+
+#[flux::sig(fn(x: i32{<T as Validate>::is_valid(x)}) -> i32{v: <T as Validate>::is_valid(v)})]
+pub fn keep<T: Validate>(x: i32) -> i32 {
+    x
+}
+
+#[flux::sig(fn() -> i32{v: v > 0})]
+pub fn test00() -> i32 {
+    keep::<Pos>(5)
+}
+
+#[flux::sig(fn() -> i32{v: v < 10})]
+pub fn test01() -> i32 {
+    keep::<Small>(5)
+}
+
+#[flux::sig(fn(x: i32{<T as Validate>::is_valid(x)}) -> i32{v: <T as Validate>::is_valid(v)})]
+pub fn keep_twice<T: Validate>(x: i32) -> i32 {
+    keep::<T>(keep::<T>(x))
+}
+
+#[flux::assoc(
+    reft max() -> int;
+    reft in_range(x: int) -> bool { 0 <= x && x <= <Self as Bounded>::max() }
+)]
+pub trait Bounded {}
+
+pub struct Byte;
+
+#[flux::assoc(reft max() -> int { 255 })]
+impl Bounded for Byte {}
+
+#[flux::sig(fn(x: i32{<T as Bounded>::in_range(x)}) -> i32{v: <T as Bounded>::in_range(v)})]
+pub fn check<T: Bounded>(x: i32) -> i32 {
+    x
+}
+
+#[flux::sig(fn() -> i32{v: v <= 255})]
+pub fn test02() -> i32 {
+    check::<Byte>(100)
+}
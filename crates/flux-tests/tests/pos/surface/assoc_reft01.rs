@@ -0,0 +1,43 @@
+#[flux::assoc(reft is_valid(x: int) -> bool;)]
+pub trait Validate {
+    #[flux::sig(fn(x: i32{<Self as Validate>::is_valid(x)}) -> i32{v: <Self as Validate>::is_valid(v)})]
+    fn keep(x: i32) -> i32 {
+        x
+    }
+
+    #[flux::sig(fn(x: i32) -> bool[<Self as Validate>::is_valid(x)])]
+    fn validate(x: i32) -> bool;
+}
+
+pub struct Pos;
+
+#[flux::assoc(reft is_valid(x: int) -> bool { x > 0 })]
+impl Validate for Pos {
+    #[flux::sig(fn(x: i32) -> bool[x > 0])]
+    fn validate(x: i32) -> bool {
+        x > 0
+    }
+}
+
+#[flux::sig(fn() -> i32{v: v > 0})]
+pub fn test00() -> i32 {
+    Pos::keep(5)
+}
+
+#[flux::sig(fn() -> i32{v: v > 0})]
+pub fn test01() -> i32 {
+    <Pos as Validate>::keep(5)
+}
+
+pub trait Strict: Validate {}
+
+impl Strict for Pos {}
+
+// The associated refinements of a supertrait are available through a bound on the subtrait
+pub fn test02<T: Strict>(x: i32) -> i32 {
+    if T::validate(x) {
+        T::keep(x)
+    } else {
+        0
+    }
+}
@@ -13,3 +13,18 @@ pub fn uint_sub(a: u32, b: u32) -> u32 {
 pub fn int_add(a: i32, b: i32) -> i32 {
     a + b
 }
+
+#[flux::sig(fn(a: i32, b: i32{b > 0}) -> i32)]
+pub fn int_div(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[flux::sig(fn(a: i32{a > i32::MIN}, b: i32{b != 0}) -> i32)]
+pub fn int_rem(a: i32, b: i32) -> i32 {
+    a % b
+}
+
+#[flux::sig(fn(x: u32, k: u32{k < 32}) -> u32)]
+pub fn uint_shr(x: u32, k: u32) -> u32 {
+    x >> k
+}
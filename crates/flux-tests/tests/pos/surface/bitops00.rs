@@ -0,0 +1,49 @@
+#[flux::sig(fn(u32) -> u32{v: v < 256})]
+pub fn mask(x: u32) -> u32 {
+    x & 0xFF
+}
+
+#[flux::sig(fn(&[i32][256], usize) -> i32)]
+pub fn mask_index(s: &[i32], x: usize) -> i32 {
+    s[x & 0xFF]
+}
+
+#[flux::sig(fn(a: u32, b: u32) -> u32{v: v >= a && v >= b})]
+pub fn or(a: u32, b: u32) -> u32 {
+    a | b
+}
+
+#[flux::sig(fn(x: i32) -> i32{v: 0 <= v && v <= 15})]
+pub fn mask_signed(x: i32) -> i32 {
+    x & 15
+}
+
+#[flux::sig(fn(x: i32{x < 0}, y: i32) -> i32{v: v < 0})]
+pub fn or_neg(x: i32, y: i32) -> i32 {
+    x | y
+}
+
+#[flux::sig(fn(n: u32) -> u32{v: v <= n})]
+pub fn half(n: u32) -> u32 {
+    n >> 1
+}
+
+#[flux::sig(fn(n: u32) -> u32[n / 8])]
+pub fn div8(n: u32) -> u32 {
+    n >> 3
+}
+
+#[flux::sig(fn(n: i32{n < 0}) -> i32{v: v < 0})]
+pub fn half_neg(n: i32) -> i32 {
+    n >> 1
+}
+
+#[flux::sig(fn(n: u32{n < 1000}) -> u32[n * 4])]
+pub fn mul4(n: u32) -> u32 {
+    n << 2
+}
+
+#[flux::sig(fn(x: u64, k: u32) -> u64{v: v <= x})]
+pub fn shr_var(x: u64, k: u32) -> u64 {
+    x >> k
+}
@@ -0,0 +1,13 @@
+#[path = "../../lib/rbitvec.rs"]
+mod rbitvec;
+use rbitvec::Bv32;
+
+#[flux::sig(fn(x: Bv32) -> Bv32[bv_shl(x, bv_int_to_bv32(1))])]
+pub fn shl1(x: Bv32) -> Bv32 {
+    x << Bv32::to_bv(1)
+}
+
+#[flux::sig(fn(x: Bv32, y: Bv32) -> Bv32[bv_or(y, x)])]
+pub fn or_comm(x: Bv32, y: Bv32) -> Bv32 {
+    x | y
+}
@@ -0,0 +1,41 @@
+pub fn from_array<const N: usize>(items: [i32; N]) -> Vec<i32> {
+    items.to_vec()
+}
+
+pub fn test00() -> Vec<i32> {
+    from_array([1, 2, 3])
+}
+
+#[flux::sig(fn(arr: &[i32; N], usize{v: v < N}) -> i32)]
+pub fn get<const N: usize>(arr: &[i32; N], i: usize) -> i32 {
+    arr[i]
+}
+
+pub fn test01() -> i32 {
+    let arr = [1, 2, 3];
+    get(&arr, 2)
+}
+
+#[flux::sig(fn(&[i32; N]) -> usize[N])]
+pub fn len<const N: usize>(arr: &[i32; N]) -> usize {
+    arr.len()
+}
+
+#[flux::sig(fn() -> usize[3])]
+pub fn test02() -> usize {
+    len(&[1, 2, 3])
+}
+
+pub struct RingBuffer<const N: usize> {
+    #[flux::field([i32; N])]
+    data: [i32; N],
+    #[flux::field(usize{v: v < N})]
+    head: usize,
+}
+
+impl<const N: usize> RingBuffer<N> {
+    #[flux::sig(fn(&RingBuffer<N>) -> i32)]
+    pub fn peek(&self) -> i32 {
+        self.data[self.head]
+    }
+}
@@ -0,0 +1,21 @@
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Kind {
+    #[default]
+    Unknown,
+    Tcp(i32),
+    Udp { port: u16 },
+}
+
+#[flux::refined_by(n: int)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Wrapper {
+    #[flux::field(i32[n])]
+    val: i32,
+}
+
+#[flux::trusted_derives(Debug, Clone)]
+#[derive(Debug, Clone)]
+pub struct Pos {
+    #[flux::field(i32{v: v > 0})]
+    val: i32,
+}
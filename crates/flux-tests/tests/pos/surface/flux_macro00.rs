@@ -0,0 +1,33 @@
+use flux_rs::flux;
+
+flux! {
+    pub trait Counter {
+        fn start() -> i32{v: v >= 0};
+
+        fn bump(x: i32{x >= 0}) -> i32{v: v > x} {
+            x + 1
+        }
+    }
+
+    pub struct Nat;
+
+    impl Counter for Nat {
+        fn start() -> i32{v: v >= 0} {
+            0
+        }
+    }
+
+    pub const LIMIT: usize{v: v > 0} = 10;
+
+    static NAME: &str = "nat";
+
+    fn limit() -> usize{v: v == 10} {
+        LIMIT
+    }
+}
+
+pub fn test() -> i32 {
+    let _ = NAME;
+    let _ = limit();
+    Nat::bump(Nat::start())
+}
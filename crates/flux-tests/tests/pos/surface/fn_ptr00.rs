@@ -0,0 +1,31 @@
+#[flux::sig(fn(i32{v: v >= 0}) -> i32{v: v > 0})]
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+#[flux::sig(fn(x: i32) -> i32[x])]
+fn id(x: i32) -> i32 {
+    x
+}
+
+#[flux::sig(fn(f: fn(i32{v: v > 0}) -> i32{v: v > 0}) -> i32{v: v > 0})]
+fn call(f: fn(i32) -> i32) -> i32 {
+    f(1)
+}
+
+#[flux::sig(fn() -> i32{v: v > 0})]
+pub fn test00() -> i32 {
+    call(inc as fn(i32) -> i32) + call(id as fn(i32) -> i32)
+}
+
+#[flux::sig(fn(bool) -> i32{v: v > 0})]
+pub fn test01(b: bool) -> i32 {
+    let f: fn(i32) -> i32 = if b { inc } else { id };
+    f(5)
+}
+
+#[flux::sig(fn() -> i32[5])]
+pub fn test02() -> i32 {
+    let f = id as fn(i32) -> i32;
+    f(5)
+}
@@ -0,0 +1,33 @@
+// Projections whose arguments contain a refined function pointer
+
+pub trait Apply {
+    type Output;
+
+    fn apply(&self, x: i32) -> Self::Output;
+}
+
+impl Apply for fn(i32) -> i32 {
+    type Output = i32;
+
+    fn apply(&self, x: i32) -> i32 {
+        self(x)
+    }
+}
+
+fn apply<T: Apply>(f: &T, x: i32) -> T::Output {
+    f.apply(x)
+}
+
+#[flux::sig(fn(i32{v: v >= 0}) -> i32{v: v > 0})]
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+#[flux::sig(fn(f: fn(i32{v: v >= 0}) -> i32{v: v > 0}) -> i32)]
+pub fn test00(f: fn(i32) -> i32) -> i32 {
+    apply(&f, 0)
+}
+
+pub fn test01() -> i32 {
+    test00(inc as fn(i32) -> i32)
+}
@@ -0,0 +1,36 @@
+#[flux::sig(fn(bool[true]))]
+fn assert(_: bool) {}
+
+fn for_each<F>(xs: &[i32], mut f: F)
+where
+    for<'a> F: FnMut(&'a i32),
+{
+    let mut i = 0;
+    while i < xs.len() {
+        f(&xs[i]);
+        i += 1;
+    }
+}
+
+fn count<F: for<'a> Fn(&'a i32) -> bool>(xs: &[i32], f: F) -> usize {
+    let mut n = 0;
+    for_each(xs, |x| {
+        if f(x) {
+            n += 1;
+        }
+    });
+    n
+}
+
+#[flux::sig(fn(&[i32], k: i32{k > 0}))]
+pub fn test00(xs: &[i32], k: i32) {
+    for_each(xs, |_| assert(k > 0));
+}
+
+pub fn test01(xs: &[i32]) -> usize {
+    count(xs, |x| *x > 0)
+}
+
+pub fn test02(xs: &[i32]) -> usize {
+    xs.iter().filter(|x| **x > 0).count()
+}
@@ -0,0 +1,12 @@
+// normalize-stderr-test "`: `fn\(.*`" -> "`: `SIG`"
+// normalize-stderr-test "#\[flux::sig\(.*\)\]" -> "#[flux::sig(SIG)]"
+#![flux::cfg(infer_signatures = true)]
+
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32{v: v > 1})]
+pub fn test(n: i32) -> i32 {
+    inc(n)
+}
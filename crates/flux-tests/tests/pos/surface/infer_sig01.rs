@@ -0,0 +1,26 @@
+// normalize-stderr-test "`: `fn\(.*`" -> "`: `SIG`"
+// normalize-stderr-test "#\[flux::sig\(.*\)\]" -> "#[flux::sig(SIG)]"
+#![flux::cfg(infer_signatures = true)]
+
+// Mutually recursive functions are inferred together with their callers.
+
+fn even_steps(n: i32) -> i32 {
+    if n <= 0 {
+        0
+    } else {
+        odd_steps(n - 1) + 1
+    }
+}
+
+fn odd_steps(n: i32) -> i32 {
+    if n <= 0 {
+        0
+    } else {
+        even_steps(n - 1) + 1
+    }
+}
+
+#[flux::sig(fn(n: i32) -> i32{v: v >= 0})]
+pub fn test(n: i32) -> i32 {
+    even_steps(n)
+}
@@ -0,0 +1,40 @@
+// Settings given with `flux::cfg` on modules, impls and functions
+
+#[flux::cfg(scrape_quals = true)]
+mod scraped {
+    // The qualifier for the loop invariant is scraped from the signature
+    #[flux::sig(fn(lo: usize, hi:usize{lo<=hi}) -> usize[hi-lo] )]
+    pub fn test_ix(lo: usize, hi: usize) -> usize {
+        let mut i = lo;
+        let mut res = 0;
+        while i < hi {
+            res += 1;
+            i += 1;
+        }
+        res
+    }
+}
+
+#[flux::refined_by(n: int)]
+pub struct Counter {
+    #[flux::field(u32[n])]
+    n: u32,
+}
+
+#[flux::cfg(check_overflow = true, solver_timeout = 60)]
+impl Counter {
+    #[flux::sig(fn(Counter[@n]) -> Counter[n + 1] requires n < 100)]
+    pub fn incr(self) -> Counter {
+        Counter { n: self.n + 1 }
+    }
+}
+
+#[flux::cfg(kvar_encoding = "single")]
+#[flux::sig(fn(n: usize) -> usize[n])]
+pub fn count(n: usize) -> usize {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
@@ -0,0 +1,25 @@
+macro_rules! add_fn {
+    ($name:ident, $n:literal) => {
+        #[flux::sig(fn(x: i32{x >= 0}) -> i32{v: v >= $n})]
+        pub fn $name(x: i32) -> i32 {
+            x + $n
+        }
+    };
+}
+
+add_fn!(add1, 1);
+add_fn!(add2, 2);
+
+#[flux::sig(fn(i32{v: v > 0}))]
+fn assert_pos(_x: i32) {}
+
+macro_rules! check_pos {
+    ($e:expr) => {
+        assert_pos($e)
+    };
+}
+
+pub fn test() {
+    check_pos!(add1(0));
+    check_pos!(add2(0));
+}
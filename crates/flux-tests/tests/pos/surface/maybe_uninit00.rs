@@ -0,0 +1,34 @@
+use std::mem::MaybeUninit;
+
+use flux_rs::extern_spec;
+
+#[extern_spec(std::mem)]
+#[flux::refined_by(init: bool)]
+struct MaybeUninit<T>;
+
+#[extern_spec(std::mem)]
+impl<T> MaybeUninit<T> {
+    #[flux::sig(fn() -> MaybeUninit<T>[false])]
+    fn uninit() -> MaybeUninit<T>;
+
+    #[flux::sig(fn(T) -> MaybeUninit<T>[true])]
+    fn new(val: T) -> MaybeUninit<T>;
+
+    #[flux::sig(fn(this: &strg MaybeUninit<T>, T) -> &mut T ensures this: MaybeUninit<T>[true])]
+    fn write(this: &mut MaybeUninit<T>, val: T) -> &mut T;
+
+    #[flux::sig(fn(MaybeUninit<T>[true]) -> T)]
+    unsafe fn assume_init(this: MaybeUninit<T>) -> T;
+}
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 0})]
+pub fn init_later(n: i32) -> i32 {
+    let mut x = MaybeUninit::uninit();
+    x.write(n);
+    unsafe { x.assume_init() }
+}
+
+#[flux::sig(fn() -> MaybeUninit<u8>[true])]
+pub fn init_now() -> MaybeUninit<u8> {
+    MaybeUninit::new(0)
+}
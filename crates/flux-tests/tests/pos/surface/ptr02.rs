@@ -0,0 +1,26 @@
+#![flux::cfg(check_raw_pointers = true)]
+
+#[flux::sig(fn(p: *const i32[@n]) -> i32 requires n > 0)]
+pub unsafe fn read(p: *const i32) -> i32 {
+    *p
+}
+
+#[flux::sig(fn(p: *const i32[@n], usize[@k]) -> *const i32[n - k] requires k <= n)]
+pub unsafe fn advance(p: *const i32, k: usize) -> *const i32 {
+    p.add(k)
+}
+
+#[flux::sig(fn(p: *mut i32[@n], i: usize{i < n}, v: i32))]
+pub unsafe fn write_at(p: *mut i32, i: usize, v: i32) {
+    *p.add(i) = v;
+}
+
+#[flux::sig(fn(p: *const i32[3]) -> i32)]
+pub unsafe fn sum3(p: *const i32) -> i32 {
+    *p + *p.add(1) + *p.offset(2)
+}
+
+#[flux::sig(fn(p: *mut i32[@n]) -> *const i32[n])]
+pub unsafe fn as_const(p: *mut i32) -> *const i32 {
+    p as *const i32
+}
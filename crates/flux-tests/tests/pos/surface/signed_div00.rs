@@ -0,0 +1,36 @@
+// Division and remainder on signed integers truncate towards zero
+
+#[flux::sig(fn(i32[-7], i32[3]) -> i32[-2])]
+pub fn div_neg_pos(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[flux::sig(fn(i32[7], i32[-3]) -> i32[-2])]
+pub fn div_pos_neg(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[flux::sig(fn(i32[-7], i32[-3]) -> i32[2])]
+pub fn div_neg_neg(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[flux::sig(fn(i32[-7], i32[3]) -> i32[-1])]
+pub fn rem_neg_pos(a: i32, b: i32) -> i32 {
+    a % b
+}
+
+#[flux::sig(fn(i32[7], i32[-3]) -> i32[1])]
+pub fn rem_pos_neg(a: i32, b: i32) -> i32 {
+    a % b
+}
+
+#[flux::sig(fn(i32{v: v < 0}) -> i32{v: v <= 0})]
+pub fn div_neg(a: i32) -> i32 {
+    a / 2
+}
+
+#[flux::sig(fn(i32{v: v < 0}) -> i32{v: -2 < v && v <= 0})]
+pub fn rem_neg(a: i32) -> i32 {
+    a % 2
+}
@@ -0,0 +1,49 @@
+// Specs for the standard library are bundled with flux and loaded automatically
+
+#[flux::sig(fn(bool[true]))]
+fn assert(_: bool) {}
+
+pub fn test_vec() {
+    let mut v = Vec::new();
+    v.push(1);
+    v.push(2);
+    assert(v.len() == 2);
+    v.insert(2, 3);
+    v.remove(0);
+    v.swap_remove(1);
+    v.pop();
+    assert(v.is_empty());
+    v.pop();
+    assert(v.is_empty());
+    v.push(0);
+    v.clear();
+    assert(v.as_slice().len() == 0);
+}
+
+#[flux::sig(fn(&mut [i32][@n]) requires n > 2)]
+pub fn test_slice(s: &mut [i32]) {
+    s.swap(0, 2);
+    let (a, b) = s.split_at(1);
+    assert(a.len() == 1);
+    assert(b.len() == s.len() - 1);
+}
+
+pub fn test_array() {
+    let a = [1, 2, 3];
+    assert(a.as_slice().len() == 3);
+}
+
+#[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a + b]>)]
+pub fn test_checked_add(a: usize, b: usize) -> Option<usize> {
+    a.checked_add(b)
+}
+
+#[flux::sig(fn(i32{v: v > 0}, i32{v: v > 0}) -> i32{v: v > 0})]
+pub fn test_min(a: i32, b: i32) -> i32 {
+    std::cmp::min(a, b)
+}
+
+#[flux::sig(fn(Option<i32{v: v > 0}>) -> i32{v: v > 0})]
+pub fn test_unwrap_or(x: Option<i32>) -> i32 {
+    x.unwrap_or(1)
+}
@@ -0,0 +1,47 @@
+#[flux::refined_by(is_int: bool)]
+pub union IntOrFloat {
+    #[flux::variant((i32{v: v > 0}) -> IntOrFloat[true])]
+    i: i32,
+    #[flux::variant((f32) -> IntOrFloat[false])]
+    f: f32,
+}
+
+#[flux::sig(fn(i32{v: v > 0}) -> IntOrFloat[true])]
+pub fn mk_int(n: i32) -> IntOrFloat {
+    IntOrFloat { i: n }
+}
+
+#[flux::sig(fn(IntOrFloat[true]) -> i32{v: v > 0})]
+pub fn get_int(x: IntOrFloat) -> i32 {
+    unsafe { x.i }
+}
+
+#[flux::sig(fn(&IntOrFloat[true]) -> i32{v: v > 0})]
+pub fn get_int_ref(x: &IntOrFloat) -> i32 {
+    unsafe { x.i }
+}
+
+#[flux::sig(fn(x: &strg IntOrFloat) ensures x: IntOrFloat[false])]
+pub fn set_float(x: &mut IntOrFloat) {
+    x.f = 1.0;
+}
+
+pub fn roundtrip() -> i32 {
+    let mut x = mk_int(5);
+    set_float(&mut x);
+    x.i = 3;
+    get_int(x)
+}
+
+#[flux::refined_by(tag: int)]
+pub union Tagged {
+    #[flux::variant((u8[@n]) -> Tagged[n])]
+    small: u8,
+    #[flux::variant((u32) -> Tagged[-1])]
+    big: u32,
+}
+
+#[flux::sig(fn(Tagged[3]) -> u8[3])]
+pub fn get_small3(x: Tagged) -> u8 {
+    unsafe { x.small }
+}
@@ -0,0 +1,41 @@
+pub union Raw {
+    a: u32,
+    b: [u8; 4],
+}
+
+pub struct Header {
+    len: usize,
+    raw: Raw,
+}
+
+pub fn read(h: &Header) -> u32 {
+    unsafe { h.raw.a }
+}
+
+pub fn byte0(h: &Header) -> u8 {
+    unsafe { h.raw.b[0] }
+}
+
+pub fn write(h: &mut Header) {
+    h.raw.a = 5;
+    h.len = 3;
+}
+
+pub fn mk() -> Header {
+    Header { len: 0, raw: Raw { a: 0 } }
+}
+
+pub union Generic<T: Copy> {
+    x: T,
+    y: u8,
+}
+
+pub fn gen(g: Generic<i32>) -> i32 {
+    unsafe { g.x }
+}
+
+pub fn swap(r: &mut Raw) -> u32 {
+    let old = unsafe { r.a };
+    *r = Raw { b: [0, 0, 0, 0] };
+    old
+}
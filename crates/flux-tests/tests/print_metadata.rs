@@ -0,0 +1,106 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{self, Command},
+};
+
+use flux_tests::{find_cargo_flux_path, find_flux_path, FLUX_SYSROOT};
+
+const MANIFEST: &str = r#"
+[package]
+name = "mylib"
+version = "0.1.0"
+edition = "2021"
+
+[package.metadata.flux]
+enabled = true
+"#;
+
+const LIB: &str = r#"
+#[flux::refined_by(n: int)]
+pub struct Nat(#[flux::field(i32[n])] i32);
+
+#[flux::trusted]
+#[flux::sig(fn(x: i32{x > 0}) -> i32{v: v > x})]
+pub fn inc(x: i32) -> i32 {
+    x + 1
+}
+"#;
+
+/// A package checked with `cargo flux` in a temporary directory, removed when dropped
+struct Package {
+    dir: PathBuf,
+}
+
+impl Package {
+    fn check() -> Package {
+        let dir = env::temp_dir().join(format!("flux-tests-print-metadata-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("Cargo.toml"), MANIFEST).unwrap();
+        fs::write(dir.join("src/lib.rs"), LIB).unwrap();
+        let package = Package { dir };
+
+        // The paths to the binaries are relative to the current directory
+        let output = Command::new(fs::canonicalize(find_cargo_flux_path()).unwrap())
+            .arg("flux")
+            .current_dir(&package.dir)
+            .env(FLUX_SYSROOT, sysroot())
+            .env("CARGO_TARGET_DIR", package.dir.join("target"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        package
+    }
+
+    fn metadata_file(&self) -> PathBuf {
+        fs::read_dir(self.dir.join("target/flux/debug/deps"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|ext| ext == "fluxmeta"))
+            .unwrap()
+    }
+}
+
+impl Drop for Package {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn sysroot() -> PathBuf {
+    fs::canonicalize(find_flux_path().parent().unwrap()).unwrap()
+}
+
+fn print_metadata(path: &PathBuf) -> process::Output {
+    Command::new(find_flux_path())
+        .arg("--print-metadata")
+        .arg(path)
+        .env(FLUX_SYSROOT, sysroot())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn prints_metadata() {
+    let package = Package::check();
+    let output = print_metadata(&package.metadata_file());
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<_> = stdout.lines().collect();
+    assert!(lines[0].starts_with("// flux metadata of `mylib` produced by flux"));
+    assert!(lines.iter().any(|line| line.starts_with("fn mylib::inc: ")));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("variant mylib::Nat: ")));
+    assert!(lines.contains(&"refined_by mylib::Nat: {n: int}"));
+}
+
+#[test]
+fn rejects_files_that_are_not_metadata() {
+    let output = print_metadata(&PathBuf::from("mylib.rs"));
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("expected a file named `lib<crate name>-<hash>.fluxmeta`"));
+}
@@ -25,6 +25,9 @@ pub enum Item {
     Fn(ItemFn),
     Impl(ItemImpl),
     Mod(ItemMod),
+    Trait(ItemTrait),
+    Const(ItemConst),
+    Static(ItemStatic),
 }
 
 #[derive(Debug)]
@@ -332,6 +335,62 @@ pub struct IndexParams {
     pub params: Punctuated<ExistsParam, Token![,]>,
 }
 
+#[derive(Debug)]
+pub struct ItemConst {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub const_token: Token![const],
+    pub ident: Ident,
+    pub colon_token: Token![:],
+    pub ty: Box<Type>,
+    pub eq_token: Token![=],
+    pub expr: Box<syn::Expr>,
+    pub semi_token: Token![;],
+}
+
+#[derive(Debug)]
+pub struct ItemStatic {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub static_token: Token![static],
+    pub mutability: Option<Token![mut]>,
+    pub ident: Ident,
+    pub colon_token: Token![:],
+    pub ty: Box<Type>,
+    pub eq_token: Token![=],
+    pub expr: Box<syn::Expr>,
+    pub semi_token: Token![;],
+}
+
+#[derive(Debug)]
+pub struct ItemTrait {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub unsafety: Option<Token![unsafe]>,
+    pub trait_token: Token![trait],
+    pub ident: Ident,
+    pub generics: Generics,
+    pub colon_token: Option<Token![:]>,
+    pub supertraits: Punctuated<syn::TypeParamBound, Token![+]>,
+    pub brace_token: token::Brace,
+    pub items: Vec<TraitItem>,
+}
+
+#[derive(Debug)]
+pub enum TraitItem {
+    Fn(TraitItemFn),
+    Type(syn::TraitItemType),
+    Const(syn::TraitItemConst),
+}
+
+#[derive(Debug)]
+pub struct TraitItemFn {
+    pub attrs: Vec<Attribute>,
+    pub sig: Signature,
+    pub default: Option<Block>,
+    pub semi_token: Option<Token![;]>,
+}
+
 #[derive(Debug)]
 pub struct ItemImpl {
     pub attrs: Vec<Attribute>,
@@ -348,6 +407,7 @@ pub struct ItemImpl {
 pub enum ImplItem {
     Fn(ImplItemFn),
     Type(syn::ImplItemType),
+    Const(syn::ImplItemConst),
 }
 
 #[derive(Debug)]
@@ -584,7 +644,7 @@ impl Parse for Items {
     }
 }
 
-const FLUX_ATTRS: &[&str] = &["opaque", "invariant", "trusted"];
+const FLUX_ATTRS: &[&str] = &["opaque", "invariant", "trusted", "assoc"];
 
 impl Parse for Item {
     fn parse(input: ParseStream) -> Result<Self> {
@@ -607,6 +667,14 @@ impl Parse for Item {
             Item::Use(input.parse()?)
         } else if lookahead.peek(Token![type]) {
             Item::Type(input.parse()?)
+        } else if lookahead.peek(Token![trait])
+            || (ahead.peek(Token![unsafe]) && ahead.peek2(Token![trait]))
+        {
+            Item::Trait(input.parse()?)
+        } else if lookahead.peek(Token![const]) {
+            Item::Const(input.parse()?)
+        } else if lookahead.peek(Token![static]) {
+            Item::Static(input.parse()?)
         } else {
             return Err(lookahead.error());
         };
@@ -1019,6 +1087,131 @@ impl Parse for ItemType {
     }
 }
 
+impl Parse for ItemConst {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(ItemConst {
+            attrs: input.call(Attribute::parse_outer)?,
+            vis: input.parse()?,
+            const_token: input.parse()?,
+            ident: input.call(Ident::parse_any)?,
+            colon_token: input.parse()?,
+            ty: input.parse()?,
+            eq_token: input.parse()?,
+            expr: input.parse()?,
+            semi_token: input.parse()?,
+        })
+    }
+}
+
+impl Parse for ItemStatic {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(ItemStatic {
+            attrs: input.call(Attribute::parse_outer)?,
+            vis: input.parse()?,
+            static_token: input.parse()?,
+            mutability: input.parse()?,
+            ident: input.parse()?,
+            colon_token: input.parse()?,
+            ty: input.parse()?,
+            eq_token: input.parse()?,
+            expr: input.parse()?,
+            semi_token: input.parse()?,
+        })
+    }
+}
+
+impl Parse for ItemTrait {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let unsafety = input.parse()?;
+        let trait_token = input.parse()?;
+        let ident = input.parse()?;
+        let mut generics: Generics = input.parse()?;
+
+        let colon_token: Option<Token![:]> = input.parse()?;
+        let mut supertraits = Punctuated::new();
+        if colon_token.is_some() {
+            loop {
+                if input.peek(Token![where]) || input.peek(token::Brace) {
+                    break;
+                }
+                supertraits.push_value(input.parse()?);
+                if input.peek(Token![where]) || input.peek(token::Brace) {
+                    break;
+                }
+                supertraits.push_punct(input.parse()?);
+            }
+        }
+        generics.where_clause = input.parse()?;
+
+        Ok(ItemTrait {
+            attrs,
+            vis,
+            unsafety,
+            trait_token,
+            ident,
+            generics,
+            colon_token,
+            supertraits,
+            brace_token: braced!(content in input),
+            items: {
+                let mut items = Vec::new();
+                while !content.is_empty() {
+                    items.push(content.parse()?);
+                }
+                items
+            },
+        })
+    }
+}
+
+impl TraitItem {
+    fn replace_attrs(&mut self, new: Vec<Attribute>) -> Vec<Attribute> {
+        match self {
+            TraitItem::Fn(TraitItemFn { attrs, .. })
+            | TraitItem::Type(syn::TraitItemType { attrs, .. })
+            | TraitItem::Const(syn::TraitItemConst { attrs, .. }) => mem::replace(attrs, new),
+        }
+    }
+}
+
+impl Parse for TraitItem {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        flux_tool_attrs(&mut attrs, FLUX_ATTRS);
+        let lookahead = input.lookahead1();
+        let mut item = if lookahead.peek(Token![fn]) {
+            TraitItem::Fn(input.parse()?)
+        } else if lookahead.peek(Token![type]) {
+            TraitItem::Type(input.parse()?)
+        } else if lookahead.peek(Token![const]) {
+            TraitItem::Const(input.parse()?)
+        } else {
+            return Err(lookahead.error());
+        };
+        item.replace_attrs(attrs);
+        Ok(item)
+    }
+}
+
+impl Parse for TraitItemFn {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let sig = input.parse()?;
+        let lookahead = input.lookahead1();
+        let (default, semi_token) = if lookahead.peek(token::Brace) {
+            (Some(input.parse()?), None)
+        } else if lookahead.peek(Token![;]) {
+            (None, Some(input.parse()?))
+        } else {
+            return Err(lookahead.error());
+        };
+        Ok(TraitItemFn { attrs, sig, default, semi_token })
+    }
+}
+
 fn parse_index_params(input: ParseStream) -> Result<Option<IndexParams>> {
     if input.peek(token::Bracket) {
         let content;
@@ -1089,7 +1282,8 @@ impl ImplItem {
     fn replace_attrs(&mut self, new: Vec<Attribute>) -> Vec<Attribute> {
         match self {
             ImplItem::Fn(ImplItemFn { attrs, .. })
-            | ImplItem::Type(syn::ImplItemType { attrs, .. }) => mem::replace(attrs, new),
+            | ImplItem::Type(syn::ImplItemType { attrs, .. })
+            | ImplItem::Const(syn::ImplItemConst { attrs, .. }) => mem::replace(attrs, new),
         }
     }
 }
@@ -1105,6 +1299,8 @@ impl Parse for ImplItem {
             ImplItem::Fn(input.parse()?)
         } else if lookahead.peek(Token![type]) {
             ImplItem::Type(input.parse()?)
+        } else if lookahead.peek(Token![const]) {
+            ImplItem::Const(input.parse()?)
         } else {
             return Err(lookahead.error());
         };
@@ -1151,6 +1347,7 @@ fn parse_requires(input: ParseStream) -> Result<Option<Requires>> {
                 || input.peek(kw::ensures)
                 || input.peek(token::Brace)
                 || input.peek(Token![,])
+                || input.peek(Token![;])
             {
                 break;
             }
@@ -1163,10 +1360,18 @@ fn parse_requires(input: ParseStream) -> Result<Option<Requires>> {
 
 fn parse_ensures(input: ParseStream) -> Result<Option<Ensures>> {
     if input.peek(kw::ensures) {
-        Ok(Some(Ensures {
-            ensures_token: input.parse()?,
-            constraints: parse_until(input, Constraint::parse, Token![,], token::Brace)?,
-        }))
+        let ensures_token = input.parse()?;
+        // The signature ends at the body or, for required trait methods, at a semicolon.
+        let at_end = |input: ParseStream| input.peek(token::Brace) || input.peek(Token![;]);
+        let mut constraints = Punctuated::new();
+        while !at_end(input) {
+            constraints.push_value(input.parse()?);
+            if at_end(input) {
+                break;
+            }
+            constraints.push_punct(input.parse()?);
+        }
+        Ok(Some(Ensures { ensures_token, constraints }))
     } else {
         Ok(None)
     }
@@ -1487,7 +1692,10 @@ impl Item {
             | Item::Enum(ItemEnum { attrs, .. })
             | Item::Struct(ItemStruct { attrs, .. })
             | Item::Use(syn::ItemUse { attrs, .. })
-            | Item::Type(ItemType { attrs, .. }) => mem::replace(attrs, new),
+            | Item::Type(ItemType { attrs, .. })
+            | Item::Trait(ItemTrait { attrs, .. })
+            | Item::Const(ItemConst { attrs, .. })
+            | Item::Static(ItemStatic { attrs, .. }) => mem::replace(attrs, new),
         }
     }
 }
@@ -1508,6 +1716,9 @@ impl ToTokens for Item {
             Item::Use(item_use) => item_use.to_tokens(tokens),
             Item::Type(item_type) => item_type.to_tokens(tokens),
             Item::Mod(item_mod) => item_mod.to_tokens(tokens),
+            Item::Trait(item_trait) => item_trait.to_tokens(tokens),
+            Item::Const(item_const) => item_const.to_tokens(tokens),
+            Item::Static(item_static) => item_static.to_tokens(tokens),
         }
     }
 }
@@ -1755,6 +1966,7 @@ impl ToTokens for ImplItem {
         match self {
             ImplItem::Fn(impl_item_fn) => impl_item_fn.to_tokens(tokens),
             ImplItem::Type(impl_item_ty) => impl_item_ty.to_tokens(tokens),
+            ImplItem::Const(impl_item_const) => impl_item_const.to_tokens(tokens),
         }
     }
 }
@@ -1776,6 +1988,118 @@ impl ToTokens for ImplItemFn {
     }
 }
 
+impl ToTokens for ItemTrait {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(&self.attrs);
+        self.vis.to_tokens(tokens);
+        self.unsafety.to_tokens(tokens);
+        self.trait_token.to_tokens(tokens);
+        self.ident.to_tokens(tokens);
+        self.generics.to_tokens(tokens, Mode::Rust);
+        if !self.supertraits.is_empty() {
+            tokens_or_default(self.colon_token.as_ref(), tokens);
+            self.supertraits.to_tokens(tokens);
+        }
+        self.generics.where_clause.to_tokens(tokens);
+        self.brace_token
+            .surround(tokens, |tokens| tokens.append_all(&self.items));
+    }
+}
+
+impl ToTokens for TraitItem {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            TraitItem::Fn(trait_item_fn) => trait_item_fn.to_tokens(tokens),
+            TraitItem::Type(trait_item_ty) => trait_item_ty.to_tokens(tokens),
+            TraitItem::Const(trait_item_const) => trait_item_const.to_tokens(tokens),
+        }
+    }
+}
+
+impl ToTokens for TraitItemFn {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let TraitItemFn { attrs, sig, default, semi_token } = self;
+        #[cfg(flux_sysroot)]
+        {
+            let flux_sig = ToTokensFlux(sig);
+            quote!(#[flux_tool::sig(#flux_sig)]).to_tokens(tokens);
+        }
+        let rust_sig = ToTokensRust(sig);
+        quote! {
+            #(#attrs)*
+            #rust_sig #default #semi_token
+        }
+        .to_tokens(tokens);
+    }
+}
+
+impl ToTokens for ItemConst {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        #[cfg(flux_sysroot)]
+        refinement_check(&self.ty, &self.expr, tokens);
+        tokens.append_all(&self.attrs);
+        // Flux computes the exact value of integral constants, so uses of the constant know more
+        // than what the refinement says.
+        #[cfg(flux_sysroot)]
+        if self.ty.is_refined() {
+            quote!(#[flux_tool::constant]).to_tokens(tokens);
+        }
+        self.vis.to_tokens(tokens);
+        self.const_token.to_tokens(tokens);
+        self.ident.to_tokens(tokens);
+        self.colon_token.to_tokens(tokens);
+        self.ty.to_tokens_inner(tokens, Mode::Rust);
+        self.eq_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+        self.semi_token.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for ItemStatic {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        #[cfg(flux_sysroot)]
+        refinement_check(&self.ty, &self.expr, tokens);
+        tokens.append_all(&self.attrs);
+        self.vis.to_tokens(tokens);
+        self.static_token.to_tokens(tokens);
+        self.mutability.to_tokens(tokens);
+        self.ident.to_tokens(tokens);
+        self.colon_token.to_tokens(tokens);
+        self.ty.to_tokens_inner(tokens, Mode::Rust);
+        self.eq_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+        self.semi_token.to_tokens(tokens);
+    }
+}
+
+/// Flux doesn't check the initializer of `const` and `static` items, so if their type has
+/// refinements we generate a function returning the initializer with the refined type as its
+/// return type. The function is hidden inside an anonymous constant so it doesn't pollute the
+/// namespace. References in the type of an item are implicitly `'static`, the unused argument
+/// gives them a lifetime when they appear in the return type.
+#[cfg(flux_sysroot)]
+fn refinement_check(ty: &Type, expr: &syn::Expr, tokens: &mut TokenStream) {
+    let mut flux_ty = TokenStream::new();
+    ty.to_tokens_inner(&mut flux_ty, Mode::Flux);
+    let mut rust_ty = TokenStream::new();
+    ty.to_tokens_inner(&mut rust_ty, Mode::Rust);
+    if flux_ty.to_string() == rust_ty.to_string() {
+        return;
+    }
+    // Use the span of the initializer so errors point to it
+    let span = syn::spanned::Spanned::span(expr);
+    quote_spanned! {span=>
+        const _: () = {
+            #[flux_tool::sig(fn(&()) -> #flux_ty)]
+            #[allow(dead_code)]
+            fn check(_: &()) -> #rust_ty {
+                #expr
+            }
+        };
+    }
+    .to_tokens(tokens);
+}
+
 struct ToTokensFlux<T>(T);
 
 impl ToTokens for ToTokensFlux<&Signature> {
@@ -1920,6 +2244,15 @@ impl PatIdent {
 }
 
 impl Type {
+    /// Whether the type carries a refinement at the top level, e.g., `i32[n]` or `i32{v: v > 0}`.
+    #[cfg(flux_sysroot)]
+    fn is_refined(&self) -> bool {
+        matches!(
+            self,
+            Type::Indexed(_) | Type::Exists(_) | Type::GeneralExists(_) | Type::Constraint(_)
+        )
+    }
+
     fn to_tokens_inner(&self, tokens: &mut TokenStream, mode: Mode) {
         match self {
             Type::Base(bty) => bty.to_tokens_inner(tokens, mode),
@@ -212,6 +212,10 @@ fn create_dummy_ident(dummy_prefix: &mut String, ty: &syn::Type) -> syn::Result<
             dummy_prefix.push_str("Slice");
             create_dummy_ident(dummy_prefix, ty_slice.elem.as_ref())
         }
+        Array(ty_array) => {
+            dummy_prefix.push_str("Array");
+            create_dummy_ident(dummy_prefix, ty_array.elem.as_ref())
+        }
         Path(ty_path) => create_dummy_ident_from_path(dummy_prefix, &ty_path.path),
         _ => {
             Err(syn::Error::new(
@@ -266,16 +270,16 @@ fn create_dummy_struct(
     };
     let mut dummy_struct = item_struct.clone();
     let ident = item_struct.ident;
-    let generics = item_struct.generics;
+    let (_, ty_generics, _) = item_struct.generics.split_for_impl();
     dummy_struct.ident = format_ident!("__FluxExternStruct{}", ident);
     dummy_struct.semi_token = None;
     let dummy_field: syn::FieldsUnnamed = if let Some(mod_path) = mod_path {
         parse_quote_spanned! {item_struct_span =>
-                              ( #mod_path :: #ident #generics )
+                              ( #mod_path :: #ident #ty_generics )
         }
     } else {
         parse_quote_spanned! {item_struct_span =>
-                              ( #ident #generics )
+                              ( #ident #ty_generics )
         }
     };
     dummy_struct.fields = syn::Fields::Unnamed(dummy_field);
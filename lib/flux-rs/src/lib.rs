@@ -44,6 +44,11 @@ pub fn trusted(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     attr_impl::trusted(attr, tokens)
 }
 
+#[proc_macro_attribute]
+pub fn assoc(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    attr_impl::assoc(attr, tokens)
+}
+
 #[proc_macro]
 pub fn flux(tokens: TokenStream) -> TokenStream {
     flux_attrs::flux(tokens.into()).into()
@@ -85,7 +90,7 @@ mod attr_sysroot {
         };
     }
 
-    flux_tool_attrs!(alias, sig, qualifiers, constant, invariant, opaque, trusted);
+    flux_tool_attrs!(alias, sig, qualifiers, constant, invariant, opaque, trusted, assoc);
 }
 
 #[cfg(not(flux_sysroot))]
@@ -100,6 +105,11 @@ mod attr_dummy {
         TokenStream::new()
     }
 
+    /// Extern specs are only meaningful to flux and are not valid Rust on their own
+    pub fn extern_spec(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+        TokenStream::new()
+    }
+
     macro_rules! no_op {
         ($($name:ident),+ $(,)?) => {
             $(
@@ -110,5 +120,5 @@ mod attr_dummy {
         };
     }
 
-    no_op!(alias, sig, qualifiers, invariant, constant, opaque, trusted, extern_spec);
+    no_op!(alias, sig, qualifiers, invariant, constant, opaque, trusted, assoc);
 }
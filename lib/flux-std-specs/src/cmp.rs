@@ -0,0 +1,11 @@
+use flux_rs::extern_spec;
+
+// The result is always one of the arguments, so any refinement shared by both is preserved.
+
+#[extern_spec(std::cmp)]
+#[flux::sig(fn(T, T) -> T)]
+fn min<T: Ord>(v1: T, v2: T) -> T;
+
+#[extern_spec(std::cmp)]
+#[flux::sig(fn(T, T) -> T)]
+fn max<T: Ord>(v1: T, v2: T) -> T;
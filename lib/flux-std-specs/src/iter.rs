@@ -0,0 +1,16 @@
+use flux_rs::extern_spec;
+
+// Adaptors and sources are generic over the items they produce, so refinements on the items are
+// preserved.
+
+#[extern_spec(std::iter)]
+#[flux::sig(fn(T) -> std::iter::Once<T>)]
+fn once<T>(value: T) -> std::iter::Once<T>;
+
+#[extern_spec(std::iter)]
+#[flux::sig(fn(T) -> std::iter::Repeat<T>)]
+fn repeat<T: Clone>(elt: T) -> std::iter::Repeat<T>;
+
+#[extern_spec(std::iter)]
+#[flux::sig(fn() -> std::iter::Empty<T>)]
+fn empty<T>() -> std::iter::Empty<T>;
@@ -0,0 +1,19 @@
+//! Refined specifications for the standard library.
+//!
+//! This crate only contains [extern specs]. It is compiled by `flux` when building the sysroot and
+//! the specs are exported through flux metadata. The flux driver loads them automatically when
+//! checking a crate, so users don't need to depend on this crate directly. A spec defined locally in
+//! a crate takes precedence over the one bundled here.
+//!
+//! During normal compilation extern specs expand to nothing and this crate is empty.
+//!
+//! [extern specs]: https://flux-rs.github.io/flux/guide/specs.html#extern-specs
+#![feature(allocator_api)]
+
+mod cmp;
+mod iter;
+mod num;
+mod option;
+mod result;
+mod slice;
+mod vec;
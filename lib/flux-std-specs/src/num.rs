@@ -0,0 +1,28 @@
+use flux_rs::extern_spec;
+
+// Checked operations return `None` on overflow, so when they return a value it is the exact
+// result of the operation.
+
+#[extern_spec]
+impl usize {
+    #[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a + b]>)]
+    fn checked_add(a: usize, b: usize) -> Option<usize>;
+
+    #[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a - b]>)]
+    fn checked_sub(a: usize, b: usize) -> Option<usize>;
+
+    #[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a * b]>)]
+    fn checked_mul(a: usize, b: usize) -> Option<usize>;
+}
+
+#[extern_spec]
+impl i32 {
+    #[flux::sig(fn(i32[@a], i32[@b]) -> Option<i32[a + b]>)]
+    fn checked_add(a: i32, b: i32) -> Option<i32>;
+
+    #[flux::sig(fn(i32[@a], i32[@b]) -> Option<i32[a - b]>)]
+    fn checked_sub(a: i32, b: i32) -> Option<i32>;
+
+    #[flux::sig(fn(i32[@a], i32[@b]) -> Option<i32[a * b]>)]
+    fn checked_mul(a: i32, b: i32) -> Option<i32>;
+}
@@ -0,0 +1,19 @@
+use flux_rs::extern_spec;
+
+// `Option` is not refined, but these signatures make sure refinements on the wrapped value flow
+// through the most common combinators.
+
+#[extern_spec(std::option)]
+impl<T> Option<T> {
+    #[flux::sig(fn(Option<T>, T) -> T)]
+    fn unwrap_or(o: Option<T>, default: T) -> T;
+
+    #[flux::sig(fn(Option<T>, E) -> Result<T, E>)]
+    fn ok_or<E>(o: Option<T>, err: E) -> Result<T, E>;
+
+    #[flux::sig(fn(&Option<T>) -> Option<&T>)]
+    fn as_ref(o: &Option<T>) -> Option<&T>;
+
+    #[flux::sig(fn(&mut Option<T>) -> Option<T>)]
+    fn take(o: &mut Option<T>) -> Option<T>;
+}
@@ -0,0 +1,19 @@
+use flux_rs::extern_spec;
+
+// `Result` is not refined, but these signatures make sure refinements on the wrapped values flow
+// through the most common combinators.
+
+#[extern_spec(std::result)]
+impl<T, E> Result<T, E> {
+    #[flux::sig(fn(Result<T, E>) -> Option<T>)]
+    fn ok(r: Result<T, E>) -> Option<T>;
+
+    #[flux::sig(fn(Result<T, E>) -> Option<E>)]
+    fn err(r: Result<T, E>) -> Option<E>;
+
+    #[flux::sig(fn(Result<T, E>, T) -> T)]
+    fn unwrap_or(r: Result<T, E>, default: T) -> T;
+
+    #[flux::sig(fn(&Result<T, E>) -> Result<&T, &E>)]
+    fn as_ref(r: &Result<T, E>) -> Result<&T, &E>;
+}
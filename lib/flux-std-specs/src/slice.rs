@@ -0,0 +1,29 @@
+use flux_rs::extern_spec;
+
+#[extern_spec]
+impl<T> [T] {
+    #[flux::sig(fn(&[T][@n]) -> usize[n])]
+    fn len(s: &[T]) -> usize;
+
+    #[flux::sig(fn(&[T][@n]) -> bool[n == 0])]
+    fn is_empty(s: &[T]) -> bool;
+
+    #[flux::sig(fn(&mut [T][@n], usize{i: i < n}, usize{j: j < n}))]
+    fn swap(s: &mut [T], a: usize, b: usize);
+
+    #[flux::sig(fn(&[T][@n], usize[@m]) -> (&[T][m], &[T][n - m]) requires m <= n)]
+    fn split_at(s: &[T], mid: usize) -> (&[T], &[T]);
+
+    #[flux::sig(fn(&mut [T][@n], usize[@m]) -> (&mut [T][m], &mut [T][n - m]) requires m <= n)]
+    fn split_at_mut(s: &mut [T], mid: usize) -> (&mut [T], &mut [T]);
+}
+
+// Methods on arrays that are not already available through unsizing to a slice.
+#[extern_spec]
+impl<T, const N: usize> [T; N] {
+    #[flux::sig(fn(&[T; N]) -> &[T][N])]
+    fn as_slice(a: &[T; N]) -> &[T];
+
+    #[flux::sig(fn(&mut [T; N]) -> &mut [T][N])]
+    fn as_mut_slice(a: &mut [T; N]) -> &mut [T];
+}
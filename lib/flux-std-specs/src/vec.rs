@@ -0,0 +1,55 @@
+use flux_rs::extern_spec;
+
+#[extern_spec(std::vec)]
+#[flux::refined_by(len: int)]
+#[flux::invariant(0 <= len)]
+struct Vec<T, A: std::alloc::Allocator = std::alloc::Global>;
+
+#[extern_spec(std::vec)]
+impl<T, A: std::alloc::Allocator> Vec<T, A> {
+    #[flux::sig(fn(&Vec<T, A>[@n]) -> usize[n])]
+    fn len(v: &Vec<T, A>) -> usize;
+
+    #[flux::sig(fn(&Vec<T, A>[@n]) -> bool[n == 0])]
+    fn is_empty(v: &Vec<T, A>) -> bool;
+
+    #[flux::sig(fn(v: &strg Vec<T, A>[@n], T) ensures v: Vec<T, A>[n + 1])]
+    fn push(v: &mut Vec<T, A>, value: T);
+
+    #[flux::sig(fn(v: &strg Vec<T, A>[@n]) -> Option<T>
+                ensures v: Vec<T, A>[if n > 0 { n - 1 } else { 0 }])]
+    fn pop(v: &mut Vec<T, A>) -> Option<T>;
+
+    #[flux::sig(fn(v: &strg Vec<T, A>[@n], usize{i: i <= n}, T) ensures v: Vec<T, A>[n + 1])]
+    fn insert(v: &mut Vec<T, A>, index: usize, element: T);
+
+    #[flux::sig(fn(v: &strg Vec<T, A>[@n], usize{i: i < n}) -> T ensures v: Vec<T, A>[n - 1])]
+    fn remove(v: &mut Vec<T, A>, index: usize) -> T;
+
+    #[flux::sig(fn(v: &strg Vec<T, A>[@n], usize{i: i < n}) -> T ensures v: Vec<T, A>[n - 1])]
+    fn swap_remove(v: &mut Vec<T, A>, index: usize) -> T;
+
+    #[flux::sig(fn(v: &strg Vec<T, A>) ensures v: Vec<T, A>[0])]
+    fn clear(v: &mut Vec<T, A>);
+
+    #[flux::sig(fn(&Vec<T, A>[@n]) -> &[T][n])]
+    fn as_slice(v: &Vec<T, A>) -> &[T];
+
+    #[flux::sig(fn(&mut Vec<T, A>[@n]) -> &mut [T][n])]
+    fn as_mut_slice(v: &mut Vec<T, A>) -> &mut [T];
+}
+
+// Constructors are only defined for the global allocator. They live in a separate module because
+// each extern impl generates a dummy struct named after the self type.
+mod global {
+    use flux_rs::extern_spec;
+
+    #[extern_spec(std::vec)]
+    impl<T> Vec<T> {
+        #[flux::sig(fn() -> Vec<T>[0])]
+        fn new() -> Vec<T>;
+
+        #[flux::sig(fn(usize) -> Vec<T>[0])]
+        fn with_capacity(capacity: usize) -> Vec<T>;
+    }
+}
@@ -119,6 +119,7 @@ fn run_inner(
 
 fn install(sh: &Shell, args: &Install) -> anyhow::Result<()> {
     cmd!(sh, "cargo install --path crates/flux-bin --force").run()?;
+    cmd!(sh, "cargo install --path crates/flux-lsp --force").run()?;
     install_driver(sh, args)?;
     install_libs(sh, args)?;
 
@@ -140,11 +141,14 @@ fn install_libs(sh: &Shell, args: &Install) -> anyhow::Result<()> {
     let profile = args.profile();
     let out_dir = default_sysroot_dir();
     cmd!(sh, "cargo build -Zunstable-options {profile} -p flux-rs --out-dir {out_dir}").run()?;
+    // `rustc-flux` was installed in the cargo bin directory and uses the default sysroot
+    build_std_specs(sh, Path::new("rustc-flux"), &out_dir)?;
     Ok(())
 }
 
 fn uninstall(sh: &Shell) -> anyhow::Result<()> {
     cmd!(sh, "cargo uninstall -p flux-bin").run()?;
+    cmd!(sh, "cargo uninstall -p flux-lsp").run()?;
     println!("$ rm -rf ~/.flux");
     std::fs::remove_dir_all(default_sysroot_dir())?;
     Ok(())
@@ -170,9 +174,31 @@ fn project_root() -> PathBuf {
 }
 
 fn build_sysroot(sh: &Shell) -> anyhow::Result<()> {
-    // CODESYNC(build-sysroot, 5)
-    let _env = sh.push_env("FLUX_BUILD_SYSROOT", "1");
-    cmd!(sh, "cargo build -p flux-rs").run()?;
+    {
+        // CODESYNC(build-sysroot, 5)
+        let _env = sh.push_env("FLUX_BUILD_SYSROOT", "1");
+        cmd!(sh, "cargo build -p flux-rs").run()?;
+    }
+    cmd!(sh, "cargo build -p flux-bin -p flux-driver").run()?;
+    let flux_path = find_flux_path();
+    let sysroot = flux_path.parent().unwrap();
+    let _env = sh.push_env(FLUX_SYSROOT, sysroot);
+    build_std_specs(sh, &flux_path, sysroot)
+}
+
+/// Compile the specs for the standard library with flux, producing the library together with
+/// its flux metadata in `out_dir`.
+fn build_std_specs(sh: &Shell, rustc_flux: &Path, out_dir: &Path) -> anyhow::Result<()> {
+    // CODESYNC(flux-cargo) Make the flux-driver do a full compilation and read the manifest of
+    // `flux-std-specs` to decide whether to verify it.
+    let _env = sh.push_env("FLUX_CARGO", "1");
+    let _manifest = sh.push_env("CARGO_MANIFEST_DIR", project_root().join("lib/flux-std-specs"));
+    let rustc_flags = flux_tests::rustc_flags();
+    cmd!(
+        sh,
+        "{rustc_flux} {rustc_flags...} --crate-name flux_std_specs --emit=metadata,link --out-dir {out_dir} lib/flux-std-specs/src/lib.rs"
+    )
+    .run()?;
     Ok(())
 }
 